@@ -0,0 +1,94 @@
+//! Optional client-side value cache. Entries are never served blind: every read revalidates
+//! with `If-None-Match`, and a `304 Not Modified` answer reuses the cached bytes.
+
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use transdb_common::Version;
+
+/// Value cache bounds; see [`ClientConfig::with_cache`](crate::ClientConfig).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheConfig {
+    /// Most keys held at once.
+    pub max_entries: usize,
+    /// Most value bytes held at once; a single value larger than this is never cached.
+    pub max_bytes: usize,
+}
+
+#[derive(Debug)]
+struct CachedValue {
+    version: Version,
+    value: Bytes,
+    last_used: u64,
+}
+
+/// Least-recently-used map from key to the last value read and its version.
+#[derive(Debug)]
+pub struct ValueCache {
+    config: CacheConfig,
+    entries: HashMap<String, CachedValue>,
+    /// `last_used` tick → key, oldest first.
+    recency: BTreeMap<u64, String>,
+    bytes: usize,
+    tick: u64,
+}
+
+impl ValueCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self { config, entries: HashMap::new(), recency: BTreeMap::new(), bytes: 0, tick: 0 }
+    }
+
+    /// The cached version and value for `key`, marking it most recently used.
+    pub fn get(&mut self, key: &str) -> Option<(Version, Bytes)> {
+        self.tick += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.recency.remove(&entry.last_used);
+        entry.last_used = self.tick;
+        self.recency.insert(self.tick, key.to_string());
+        Some((entry.version, entry.value.clone()))
+    }
+
+    /// Cache `value` at `version`, evicting least recently used keys to stay within bounds.
+    pub fn insert(&mut self, key: &str, version: Version, value: Bytes) {
+        self.remove(key);
+        if self.config.max_entries == 0 || value.len() > self.config.max_bytes {
+            return;
+        }
+        while self.entries.len() >= self.config.max_entries || self.bytes + value.len() > self.config.max_bytes {
+            let Some((_, oldest)) = self.recency.pop_first() else { break };
+            if let Some(evicted) = self.entries.remove(&oldest) {
+                self.bytes -= evicted.value.len();
+            }
+        }
+        self.tick += 1;
+        self.bytes += value.len();
+        self.recency.insert(self.tick, key.to_string());
+        self.entries.insert(key.to_string(), CachedValue { version, value, last_used: self.tick });
+    }
+
+    /// Drop `key`, e.g. because it was written or deleted through this client.
+    pub fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+            self.bytes -= entry.value.len();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+        self.bytes = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Total size of the cached values.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
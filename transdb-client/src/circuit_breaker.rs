@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Circuit breaker settings; see [`ClientConfig::with_circuit_breaker`](crate::ClientConfig).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive connection/timeout failures that open the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a single probe request is let through.
+    pub cool_down: Duration,
+}
+
+/// Observable state of a [`CircuitBreaker`] at a given instant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests flow normally.
+    Closed,
+    /// Requests fail fast until the cool-down elapses.
+    Open,
+    /// Cool-down elapsed; the next request is a probe that decides between Closed and Open.
+    HalfOpen,
+}
+
+/// Answer to [`CircuitBreaker::try_acquire`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Admission {
+    /// Circuit closed; send the request.
+    Allowed,
+    /// Circuit half-open and this request is the single probe; its outcome decides the state.
+    Probe,
+    /// Fail fast without sending.
+    Rejected,
+}
+
+/// Lock-free breaker for a single target node.
+///
+/// Every method takes the current time explicitly so the state machine can be driven
+/// deterministically in tests.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    /// Reference point for `opened_at_nanos`.
+    epoch: Instant,
+    consecutive_failures: AtomicU32,
+    /// Nanoseconds after `epoch` at which the circuit opened, plus one; 0 means closed.
+    opened_at_nanos: AtomicU64,
+    probe_in_flight: AtomicBool,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self::with_epoch(config, Instant::now())
+    }
+
+    /// Create a breaker whose internal clock starts at `epoch`; `now` arguments must not precede it.
+    pub fn with_epoch(config: CircuitBreakerConfig, epoch: Instant) -> Self {
+        Self {
+            config,
+            epoch,
+            consecutive_failures: AtomicU32::new(0),
+            opened_at_nanos: AtomicU64::new(0),
+            probe_in_flight: AtomicBool::new(false),
+        }
+    }
+
+    pub fn state(&self, now: Instant) -> CircuitState {
+        match self.opened_at_nanos.load(Ordering::Acquire) {
+            0 => CircuitState::Closed,
+            opened if (self.nanos_since_epoch(now) + 1).saturating_sub(opened) < self.config.cool_down.as_nanos() as u64 => {
+                CircuitState::Open
+            }
+            _ => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Ask to send a request. Rejected while open, and while half-open if another request is
+    /// already probing.
+    pub fn try_acquire(&self, now: Instant) -> Admission {
+        match self.state(now) {
+            CircuitState::Closed => Admission::Allowed,
+            CircuitState::Open => Admission::Rejected,
+            CircuitState::HalfOpen => {
+                match self.probe_in_flight.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire) {
+                    Ok(_) => Admission::Probe,
+                    Err(_) => Admission::Rejected,
+                }
+            }
+        }
+    }
+
+    /// The target answered; close the circuit.
+    pub fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::Release);
+        self.opened_at_nanos.store(0, Ordering::Release);
+        self.probe_in_flight.store(false, Ordering::Release);
+    }
+
+    /// The probe was cancelled before completing; release the slot without changing state.
+    pub fn abandon(&self) {
+        self.probe_in_flight.store(false, Ordering::Release);
+    }
+
+    /// The target could not be reached (connection error or timeout).
+    /// A failed probe re-opens the circuit for a fresh cool-down.
+    pub fn record_failure(&self, now: Instant) {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::AcqRel) + 1;
+        let was_probe = self.probe_in_flight.swap(false, Ordering::AcqRel);
+        if was_probe || failures >= self.config.failure_threshold {
+            self.opened_at_nanos.store(self.nanos_since_epoch(now) + 1, Ordering::Release);
+        }
+    }
+
+    fn nanos_since_epoch(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.epoch).as_nanos() as u64
+    }
+}
@@ -0,0 +1,186 @@
+//! In-memory stand-in for [`Client`](crate::Client), built with the `test-util` feature. Code
+//! written against [`KvClient`] can be unit-tested with a [`FakeClient`] instead of a server.
+//!
+//! The fake follows the client's observable contract for the [`KvClient`] operations: keys and
+//! values are checked against the same [`Limits`], every write takes the next version of one
+//! counter, a deleted key reads as `KeyDeleted` until its tombstone lapses, and an entry whose
+//! TTL has passed on the fake's [`Clock`] reads as `KeyNotFound`.
+
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use transdb_common::{Limits, Result, TransDbError, Version};
+
+use crate::{GetResult, KvClient};
+
+/// How long a tombstone is kept, as on the server; after that the key reads as never written.
+pub const TOMBSTONE_TTL_SECS: u64 = 3600;
+
+/// Source of the current time that TTLs are compared against.
+pub trait Clock: Send + Sync {
+    fn unix_now_secs(&self) -> u64;
+}
+
+/// Wall-clock time, the default for [`FakeClient::new`].
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn unix_now_secs(&self) -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+    }
+}
+
+/// A clock that only moves when told to, for testing expiry.
+#[derive(Debug, Default)]
+pub struct ManualClock(AtomicU64);
+
+impl ManualClock {
+    pub fn new(unix_secs: u64) -> Self {
+        Self(AtomicU64::new(unix_secs))
+    }
+
+    pub fn set(&self, unix_secs: u64) {
+        self.0.store(unix_secs, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, secs: u64) {
+        self.0.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn unix_now_secs(&self) -> u64 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Option<Bytes>, // None = tombstone
+    version: u64,
+    expires_at: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Store {
+    entries: HashMap<String, Entry>,
+    next_version: u64,
+}
+
+/// A [`KvClient`] backed by a map in process memory.
+pub struct FakeClient {
+    limits: Limits,
+    clock: Arc<dyn Clock>,
+    store: Mutex<Store>,
+}
+
+impl Default for FakeClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeClient {
+    /// An empty fake on the system clock with [`Limits::default`].
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// An empty fake whose TTLs are evaluated against `clock`.
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self { limits: Limits::default(), clock, store: Mutex::default() }
+    }
+
+    /// Enforce `limits` instead of the defaults, as [`ClientConfig::limits`](crate::ClientConfig) would.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// A panic while the store was held cannot have left a torn entry behind, so a poisoned
+    /// lock is taken over rather than propagated.
+    fn store(&self) -> MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn validate_key(&self, key: &str) -> Result<()> {
+        if key.is_empty() {
+            return Err(TransDbError::InvalidKey { key: String::new(), reason: "must not be empty".to_string() });
+        }
+        if key.len() > self.limits.max_key_size {
+            return Err(TransDbError::KeyTooLarge(self.limits.max_key_size));
+        }
+        Ok(())
+    }
+
+    fn is_expired(&self, entry: &Entry) -> bool {
+        entry.expires_at.is_some_and(|ts| self.clock.unix_now_secs() >= ts)
+    }
+
+    fn write(&self, key: &str, value: &[u8], ttl: Option<u64>) -> Result<Version> {
+        self.validate_key(key)?;
+        if value.len() > self.limits.max_value_size {
+            return Err(TransDbError::ValueTooLarge(self.limits.max_value_size));
+        }
+        let mut store = self.store();
+        store.next_version += 1;
+        let version = store.next_version;
+        let entry = Entry { value: Some(Bytes::copy_from_slice(value)), version, expires_at: ttl };
+        store.entries.insert(key.to_string(), entry);
+        Ok(Version(version))
+    }
+
+    fn get_now(&self, key: &str) -> Result<GetResult> {
+        self.validate_key(key)?;
+        let store = self.store();
+        match store.entries.get(key).filter(|entry| !self.is_expired(entry)) {
+            None => Err(TransDbError::KeyNotFound(key.to_string())),
+            Some(Entry { value: None, version, .. }) => {
+                Err(TransDbError::KeyDeleted { key: key.to_string(), version: *version })
+            }
+            Some(Entry { value: Some(value), version, expires_at }) => Ok(GetResult {
+                value: value.clone(),
+                version: Version(*version),
+                expired: false,
+                expires_at: *expires_at,
+                sequence: store.next_version,
+            }),
+        }
+    }
+
+    fn delete_now(&self, key: &str) -> Result<Option<Version>> {
+        self.validate_key(key)?;
+        let now = self.clock.unix_now_secs();
+        let mut store = self.store();
+        // Like the server, an expired value is still replaced by a tombstone.
+        if store.entries.get(key).is_none_or(|entry| entry.value.is_none()) {
+            return Ok(None);
+        }
+        store.next_version += 1;
+        let version = store.next_version;
+        let tombstone = Entry { value: None, version, expires_at: Some(now + TOMBSTONE_TTL_SECS) };
+        store.entries.insert(key.to_string(), tombstone);
+        Ok(Some(Version(version)))
+    }
+}
+
+impl KvClient for FakeClient {
+    fn get(&self, key: &str) -> impl Future<Output = Result<GetResult>> + Send {
+        std::future::ready(self.get_now(key))
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> impl Future<Output = Result<Version>> + Send {
+        std::future::ready(self.write(key, value, None))
+    }
+
+    fn put_with_ttl(&self, key: &str, value: &[u8], ttl: u64) -> impl Future<Output = Result<Version>> + Send {
+        std::future::ready(self.write(key, value, Some(ttl)))
+    }
+
+    fn delete(&self, key: &str) -> impl Future<Output = Result<Option<Version>>> + Send {
+        std::future::ready(self.delete_now(key))
+    }
+}
@@ -1,164 +1,1036 @@
-use transdb_common::{ErrorResponse, Result, Topology, TransDbError, MAX_KEY_SIZE, MAX_VALUE_SIZE};
+use bytes::Bytes;
+use futures_util::future::Either;
+use futures_util::{stream, Stream, StreamExt};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use transdb_common::{
+    signing, validate_namespace, BulkTtlRequest, BulkTtlResult, ErrorResponse, KeyList, Limits, MutexStatus,
+    Result, StoreStats, Topology, TransDbError, Version, ERR_CONTENT_REJECTED, ERR_EMPTY_KEY,
+    ERR_IDEMPOTENCY_KEY_TOO_LARGE, ERR_IDEMPOTENCY_MISMATCH, ERR_INVALID_SIGNATURE,
+    ERR_KEY_NOT_FOUND, ERR_KEY_TOO_LARGE, ERR_OVERLOADED, ERR_PRECONDITION_FAILED, ERR_READ_ONLY,
+    ERR_STORAGE_FULL, ERR_VALUE_TOO_LARGE, ERR_VERSION_CONFLICT, MUTEX_KEY_PREFIX,
+    NDJSON_CONTENT_TYPE,
+};
+use transdb_common::batch::{
+    Base64Value, MultiCasConflict, MultiCasRead, MultiCasRequest, MultiCasResponse, MultiCasWrite,
+};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+pub mod cache;
+pub mod circuit_breaker;
+#[cfg(feature = "test-util")]
+pub mod fake;
+#[cfg(feature = "tracing")]
+pub mod trace;
+use cache::{CacheConfig, ValueCache};
+use circuit_breaker::{Admission, CircuitBreaker, CircuitBreakerConfig, CircuitState};
+
+/// User-Agent sent when none is configured.
+pub const DEFAULT_USER_AGENT: &str = concat!("transdb-client/", env!("CARGO_PKG_VERSION"));
+
+/// Headers the client sets per request; they cannot be configured as default headers.
+pub const RESERVED_HEADERS: [&str; 6] =
+    ["idempotency-key", "if-match", "x-ttl", "x-request-timeout-ms", "x-signature", "x-timestamp"];
+
+/// Topology for [`Client::from_env`]: a path to a topology JSON file, or the JSON itself.
+pub const TOPOLOGY_ENV: &str = "TRANSDB_TOPOLOGY";
+/// Initial target for [`Client::from_env`]: `primary` (the default) or `replica`.
+pub const TARGET_ENV: &str = "TRANSDB_TARGET";
+
+/// Default for [`ClientConfig::bulk_concurrency`].
+pub const DEFAULT_BULK_CONCURRENCY: usize = 16;
+
 /// TransDB client configuration
 #[derive(Debug, Clone)]
 pub struct ClientConfig {
     pub topology: Topology,
+    /// Per-target circuit breaker; `None` (the default) disables it.
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Value cache revalidated with `If-None-Match`; `None` (the default) disables it.
+    pub cache: Option<CacheConfig>,
+    /// Poll the current target's `GET /topology` at this interval; `None` (the default) only
+    /// refreshes on [`Client::refresh_topology`].
+    pub topology_refresh: Option<Duration>,
+    /// Requests [`Client::delete_many`] keeps in flight at once.
+    pub bulk_concurrency: usize,
+    /// Idle connections kept open per node; `None` keeps reqwest's default (unbounded).
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Close pooled connections idle for this long; `None` keeps reqwest's default (90 s).
+    pub pool_idle_timeout: Option<Duration>,
+    /// TCP keepalive probe interval; `None` (the default) disables keepalive.
+    pub tcp_keepalive: Option<Duration>,
+    /// Speak HTTP/2 without negotiation (h2c). Requests are multiplexed over one connection per
+    /// node instead of one connection each.
+    pub http2_prior_knowledge: bool,
+    /// Speak HTTPS to every node, for servers started with a TLS certificate.
+    pub tls: bool,
+    /// Reject keys containing `/`, `?`, `#`, whitespace or control characters with
+    /// [`TransDbError::InvalidKey`] instead of percent-encoding them. Off by default.
+    pub strict_keys: bool,
+    /// Checked before a request is sent; should match the server's. Defaults to
+    /// [`Limits::default`].
+    pub limits: Limits,
+    /// Key operations address `/{namespace}/keys/...` instead of the server's default key
+    /// space; set through [`ClientConfig::with_namespace`]. Mutexes, batches and stats are not
+    /// namespaced.
+    namespace: Option<String>,
+    /// Receives the parsed `Server-Timing` of every response that carries one (servers started
+    /// with `--server-timing`); set through [`ClientConfig::with_server_timing_sink`].
+    pub server_timing_sink: Option<mpsc::Sender<ServerTiming>>,
+    /// Trusted in addition to the system roots; see [`ClientConfig::with_root_certificate`].
+    root_certificates: Vec<reqwest::Certificate>,
+    /// Sent on every request; populated through [`ClientConfig::header`].
+    default_headers: HeaderMap,
+    user_agent: String,
+}
+
+impl ClientConfig {
+    pub fn new(topology: Topology) -> Self {
+        Self {
+            topology,
+            circuit_breaker: None,
+            cache: None,
+            topology_refresh: None,
+            bulk_concurrency: DEFAULT_BULK_CONCURRENCY,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            http2_prior_knowledge: false,
+            tls: false,
+            strict_keys: false,
+            limits: Limits::default(),
+            namespace: None,
+            server_timing_sink: None,
+            root_certificates: Vec::new(),
+            default_headers: HeaderMap::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+        }
+    }
+
+    /// A config for the topology JSON file at `path`; see [`Client::from_topology_file`].
+    pub fn from_topology_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Topology::from_file(path)?))
+    }
+
+    /// Refresh the topology from the server in the background every `interval`.
+    pub fn with_topology_refresh(mut self, interval: Duration) -> Self {
+        self.topology_refresh = Some(interval);
+        self
+    }
+
+    /// Cap the number of concurrent requests issued by bulk operations (at least 1).
+    pub fn with_bulk_concurrency(mut self, concurrency: usize) -> Self {
+        self.bulk_concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Keep at most `max` idle connections per node. On loopback a value near the number of
+    /// concurrent callers avoids reconnecting; on a WAN, fewer idle sockets holds less state.
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Close connections that sat idle in the pool for `timeout`. Keep it below any load
+    /// balancer's idle timeout so the client never reuses a connection the balancer dropped.
+    pub fn with_pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Send TCP keepalive probes every `interval`, so NATs and firewalls on a WAN path keep
+    /// idle pooled connections open. Unnecessary on loopback.
+    pub fn with_tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Use HTTP/2 without negotiation. Many concurrent requests then share one connection per
+    /// node, which avoids ephemeral port exhaustion in long high-throughput runs. Only for
+    /// servers reached directly, not through an HTTP/1-only proxy.
+    pub fn with_http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self
+    }
+
+    /// Connect over HTTPS instead of plain HTTP.
+    pub fn with_tls(mut self) -> Self {
+        self.tls = true;
+        self
+    }
+
+    /// Trust the PEM certificate `pem` when verifying servers, e.g. a self-signed certificate
+    /// or a private CA. Returns `InvalidConfig` if it does not parse.
+    pub fn with_root_certificate(mut self, pem: &[u8]) -> Result<Self> {
+        let certificate = reqwest::Certificate::from_pem(pem)
+            .map_err(|e| TransDbError::InvalidConfig(format!("invalid root certificate: {e}")))?;
+        self.root_certificates.push(certificate);
+        Ok(self)
+    }
+
+    /// Refuse keys that only reach the server intact because of percent-encoding, e.g. for a
+    /// server behind a proxy that decodes or normalizes paths.
+    pub fn with_strict_keys(mut self, strict: bool) -> Self {
+        self.strict_keys = strict;
+        self
+    }
+
+    /// Check requests against `limits`, for a server configured with other than the defaults.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Scope key operations to `namespace`, which the server creates on the first write. Returns
+    /// `InvalidConfig` for a name the server would reject (see [`validate_namespace`]).
+    pub fn with_namespace(mut self, namespace: &str) -> Result<Self> {
+        validate_namespace(namespace).map_err(TransDbError::InvalidConfig)?;
+        self.namespace = Some(namespace.to_string());
+        Ok(self)
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// Forward each response's `Server-Timing` to `sink`, e.g. to collect lock wait times in a
+    /// load test. Sends to a dropped receiver are ignored.
+    pub fn with_server_timing_sink(mut self, sink: mpsc::Sender<ServerTiming>) -> Self {
+        self.server_timing_sink = Some(sink);
+        self
+    }
+
+    /// Add a header to every request the client makes (e.g. `X-Service-Name` for proxy tracing).
+    /// Returns `InvalidConfig` for a malformed header or one in [`RESERVED_HEADERS`].
+    pub fn header(mut self, name: &str, value: &str) -> Result<Self> {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|_| TransDbError::InvalidConfig(format!("invalid header name: {name}")))?;
+        if RESERVED_HEADERS.contains(&name.as_str()) || name == reqwest::header::USER_AGENT {
+            return Err(TransDbError::InvalidConfig(format!(
+                "{name} is set by the client and cannot be a default header"
+            )));
+        }
+        let value = HeaderValue::from_str(value)
+            .map_err(|_| TransDbError::InvalidConfig(format!("invalid value for header {name}")))?;
+        self.default_headers.append(name, value);
+        Ok(self)
+    }
+
+    /// Replace the default `transdb-client/<version>` User-Agent.
+    pub fn user_agent(mut self, user_agent: &str) -> Result<Self> {
+        HeaderValue::from_str(user_agent)
+            .map_err(|_| TransDbError::InvalidConfig(format!("invalid User-Agent: {user_agent}")))?;
+        self.user_agent = user_agent.to_string();
+        Ok(self)
+    }
+
+    /// Headers added to every request by [`ClientConfig::header`].
+    pub fn default_headers(&self) -> &HeaderMap {
+        &self.default_headers
+    }
+
+    /// Fail fast with `CircuitOpen` after repeated connection/timeout failures to a target.
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(config);
+        self
+    }
+
+    /// Keep recently read values and revalidate them with `If-None-Match`, so an unchanged value
+    /// is answered with an empty `304` instead of being downloaded again.
+    pub fn with_cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
 }
 
 /// Result returned by a successful GET
 #[derive(Debug, Clone, PartialEq)]
 pub struct GetResult {
-    pub value: Vec<u8>,
-    pub version: u64,
+    /// The value as received from the server; cloning is cheap (reference-counted, no copy).
+    pub value: Bytes,
+    pub version: Version,
     /// `true` when the server returned `X-Expired: true` (entry exists but TTL has elapsed).
     pub expired: bool,
+    /// The entry's TTL as absolute Unix seconds, from `X-Expires-At`; `None` if it has none.
+    pub expires_at: Option<u64>,
+    /// The server's global version high-water mark (`X-Sequence`) when it answered. Versions of
+    /// all keys come from that one counter, so comparing versions orders writes across keys.
+    pub sequence: u64,
+}
+
+/// Result of a successful [`Client::get_range`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialGetResult {
+    /// The requested bytes, truncated at the end of the value.
+    pub data: Bytes,
+    /// Length of the whole value.
+    pub total_bytes: u64,
+    pub version: Version,
+}
+
+/// Result of a successful PUT, as returned by [`Client::put_detailed`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PutOutcome {
+    /// The version assigned by this write.
+    pub version: Version,
+    /// Version of the live value this write replaced; `None` if the key was new, deleted,
+    /// or expired.
+    pub previous: Option<Version>,
+    /// `X-Version-Diff`: writes to the store, to any key, between `previous` and `version`.
+    /// 0 for a new key, or from a server that does not send the header.
+    pub version_diff: u64,
+    /// The server answered from its idempotency cache (`X-Idempotent-Replay: true`): this
+    /// Idempotency-Key had already been executed, and nothing was written this time.
+    pub replayed: bool,
+}
+
+/// A server's build and role, as reported by `GET /version` to [`Client::server_info`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ServerInfo {
+    /// The server crate's version, e.g. `0.1.0`.
+    pub server_version: String,
+    /// Revision of the HTTP API the server speaks; see [`transdb_common::PROTOCOL_VERSION`].
+    pub protocol_version: String,
+    /// `primary` or `replica`.
+    pub role: String,
+    /// Short hash of the commit the server was built from, or `unknown`.
+    pub git_hash: String,
+    /// The day the server was built, `YYYY-MM-DD`, or `unknown`.
+    pub build_date: String,
+}
+
+/// A server's `Server-Timing: lock;dur=<ms>, work;dur=<ms>`, as sent to
+/// [`ClientConfig::server_timing_sink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerTiming {
+    /// Time spent waiting for the store lock (for a batched write, the whole wait for its batch).
+    pub lock: Duration,
+    /// Time spent holding the lock; `None` when the server reported only `lock`.
+    pub work: Option<Duration>,
+}
+
+impl ServerTiming {
+    /// Parse a `Server-Timing` header value; `None` if it has no `lock` metric.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut lock = None;
+        let mut work = None;
+        for metric in value.split(',') {
+            let mut params = metric.split(';').map(str::trim);
+            let name = params.next();
+            let dur = params
+                .find_map(|p| p.strip_prefix("dur="))
+                .and_then(|ms| ms.parse::<f64>().ok())
+                .filter(|ms| ms.is_finite() && *ms >= 0.0)
+                .map(|ms| Duration::from_secs_f64(ms / 1000.0));
+            match name {
+                Some("lock") => lock = dur,
+                Some("work") => work = dur,
+                _ => {}
+            }
+        }
+        Some(Self { lock: lock?, work })
+    }
+}
+
+/// Full HTTP response returned by [`Client::raw_request`].
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    pub status: reqwest::StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// Per-call options for the `_ext` operation variants.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    /// Give up after this long, returning `TransDbError::Timeout`. Also sent to the server as
+    /// `X-Request-Timeout-Ms` so it can abandon the work. `None` waits indefinitely.
+    pub deadline: Option<Duration>,
 }
 
 /// TransDB Client
 pub struct Client {
+    /// Configuration as passed to [`Client::new`]. `config.topology` is the seed topology and is
+    /// not updated by refreshes; see [`Client::topology`].
     pub config: ClientConfig,
+    http_client: reqwest::Client,
+    /// Shared with the background refresh task, if any.
+    routing: Arc<RwLock<Routing>>,
+    /// Periodic `/topology` poller; aborted when the client is dropped.
+    refresh_task: Option<JoinHandle<()>>,
+    /// HMAC key every request is signed with; see [`Client::with_signing_key`].
+    signing_key: Option<Arc<[u8]>>,
+    cache: Option<Mutex<ValueCache>>,
+}
+
+/// The latest known topology and where requests go within it.
+struct Routing {
+    topology: Topology,
     /// Current target address (`host:port`); all requests go to this node.
-    /// Defaults to `config.topology.primary_addr`.
+    /// Defaults to `topology.primary_addr`.
     target: String,
-    http_client: reqwest::Client,
+    breaker_config: Option<CircuitBreakerConfig>,
+    /// One breaker per target address, created on first use; empty when disabled.
+    breakers: HashMap<String, Arc<CircuitBreaker>>,
+    /// Breaker for the current target.
+    breaker: Option<Arc<CircuitBreaker>>,
+}
+
+impl Routing {
+    fn retarget(&mut self, addr: &str) {
+        self.target = addr.to_string();
+        self.breaker = self.breaker_config.as_ref().map(|config| {
+            self.breakers
+                .entry(addr.to_string())
+                .or_insert_with(|| Arc::new(CircuitBreaker::new(config.clone())))
+                .clone()
+        });
+    }
+
+    /// Swap in `topology`, moving the target to whichever node now holds the role it had
+    /// (falling back to the primary when the replica was removed).
+    fn apply_topology(&mut self, topology: Topology) {
+        let was_replica = self.topology.replica_addr.as_deref() == Some(self.target.as_str());
+        let was_primary = self.topology.primary_addr == self.target;
+        let target = match (was_primary, was_replica) {
+            (true, _) => Some(topology.primary_addr.clone()),
+            (false, true) => Some(topology.replica_addr.clone().unwrap_or_else(|| topology.primary_addr.clone())),
+            (false, false) => None,
+        };
+        self.topology = topology;
+        if let Some(target) = target {
+            self.retarget(&target);
+        }
+    }
 }
 
 impl Client {
-    /// Create a new client with the given configuration
+    /// Create a new client with the given configuration.
+    /// With [`ClientConfig::with_topology_refresh`] set, this must be called inside a Tokio runtime.
     pub fn new(config: ClientConfig) -> Self {
-        let target = config.topology.primary_addr.clone();
-        Self {
-            config,
-            target,
-            http_client: reqwest::Client::new(),
+        let mut builder = reqwest::Client::builder()
+            .default_headers(config.default_headers.clone())
+            .user_agent(config.user_agent.as_str())
+            .tcp_keepalive(config.tcp_keepalive);
+        if let Some(max) = config.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = config.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if config.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
         }
+        for certificate in &config.root_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+        // Headers and User-Agent were validated by the `ClientConfig` builders, so this only fails
+        // if the TLS backend cannot initialize — the same condition under which
+        // `reqwest::Client::new` panics.
+        let http_client = builder.build().expect("failed to build HTTP client");
+        let mut routing = Routing {
+            topology: config.topology.clone(),
+            target: String::new(),
+            breaker_config: config.circuit_breaker.clone(),
+            breakers: HashMap::new(),
+            breaker: None,
+        };
+        routing.retarget(&config.topology.primary_addr);
+        let routing = Arc::new(RwLock::new(routing));
+        let refresh_task = config.topology_refresh.map(|interval| {
+            tokio::spawn(refresh_periodically(http_client.clone(), routing.clone(), interval, config.tls))
+        });
+        let cache = config.cache.clone().map(|config| Mutex::new(ValueCache::new(config)));
+        Self { config, http_client, routing, refresh_task, signing_key: None, cache }
+    }
+
+    /// Create a client from a topology JSON file, as passed to the server's `--topology`.
+    /// Returns `ConfigError` if the file cannot be read or parsed, `InvalidTopology` if it fails
+    /// [`Topology::validate`].
+    pub fn from_topology_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(ClientConfig::from_topology_file(path)?))
+    }
+
+    /// Create a client from [`TOPOLOGY_ENV`] (a file path, or inline JSON starting with `{`),
+    /// targeting the node named by [`TARGET_ENV`]. Returns `ConfigError` when a variable is
+    /// missing or invalid, or the topology cannot be loaded.
+    pub fn from_env() -> Result<Self> {
+        let topology = Topology::from_env(TOPOLOGY_ENV)?;
+        let target = match std::env::var(TARGET_ENV).as_deref() {
+            Err(_) | Ok("primary") => topology.primary_addr.clone(),
+            Ok("replica") => topology.replica_addr.clone().ok_or_else(|| {
+                TransDbError::ConfigError(format!("{TARGET_ENV}=replica but the topology has no replica_addr"))
+            })?,
+            Ok(other) => {
+                return Err(TransDbError::ConfigError(format!(
+                    "{TARGET_ENV} must be primary or replica, got {other:?}"
+                )))
+            }
+        };
+        let mut client = Self::new(ClientConfig::new(topology));
+        client.set_target(&target)?;
+        Ok(client)
+    }
+
+    /// Sign every request with `key` (`X-Signature` + `X-Timestamp`), for servers started with a
+    /// signature key. Requests are buffered in memory, so signing applies to the whole body.
+    pub fn with_signing_key(mut self, key: &str) -> Self {
+        self.signing_key = Some(Arc::from(key.as_bytes()));
+        self
     }
 
     /// Override the target node for all subsequent requests.
-    /// Pass a bare `host:port` address matching an entry in the topology.
-    pub fn set_target(&mut self, addr: &str) {
-        self.target = addr.to_string();
+    /// `addr` is a bare `host:port` and must be the primary or replica of the current topology;
+    /// otherwise returns `InvalidConfig` and the target is unchanged.
+    pub fn set_target(&mut self, addr: &str) -> Result<()> {
+        let mut routing = self.routing_mut();
+        let topology = &routing.topology;
+        if topology.primary_addr != addr && topology.replica_addr.as_deref() != Some(addr) {
+            return Err(TransDbError::InvalidConfig(format!("{addr} is not a member of the current topology")));
+        }
+        routing.retarget(addr);
+        Ok(())
+    }
+
+    /// Address (`host:port`) that requests are currently sent to.
+    pub fn target(&self) -> String {
+        self.routing().target.clone()
+    }
+
+    /// `http://` or `https://` (per [`ClientConfig::tls`]) followed by the current target.
+    fn base_url(&self) -> String {
+        base_url(self.config.tls, &self.target())
+    }
+
+    /// The latest known topology: the seed from `ClientConfig`, or the last successful refresh.
+    pub fn topology(&self) -> Topology {
+        self.routing().topology.clone()
+    }
+
+    /// Fetch `GET /topology` from the current target and swap it in. A client targeting the
+    /// primary (or replica) keeps targeting the primary (or replica) at its new address.
+    pub async fn refresh_topology(&self) -> Result<Topology> {
+        let options = RequestOptions::default();
+        let topology = self.call(&options, fetch_topology(&self.http_client, &self.base_url())).await?;
+        self.routing_mut().apply_topology(topology.clone());
+        Ok(topology)
+    }
+
+    /// State of the current target's circuit breaker, or `None` if the breaker is disabled.
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        self.routing().breaker.as_ref().map(|b| b.state(Instant::now()))
+    }
+
+    fn routing(&self) -> RwLockReadGuard<'_, Routing> {
+        self.routing.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn routing_mut(&self) -> RwLockWriteGuard<'_, Routing> {
+        self.routing.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn cache(&self) -> Option<MutexGuard<'_, ValueCache>> {
+        self.cache.as_ref().map(|cache| cache.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Drop `key` from the cache before this client writes it, for read-your-writes.
+    fn invalidate(&self, key: &str) {
+        if let Some(mut cache) = self.cache() {
+            cache.remove(key);
+        }
+    }
+
+    /// Number of values currently held by the cache (0 when caching is disabled).
+    pub fn cached_len(&self) -> usize {
+        self.cache().map_or(0, |cache| cache.len())
+    }
+
+    /// Check `key` before any network I/O: its size always, and under
+    /// [`ClientConfig::strict_keys`] the characters a URL path would mangle.
+    fn validate_key(&self, key: &str) -> Result<()> {
+        if key.is_empty() {
+            return Err(TransDbError::InvalidKey { key: String::new(), reason: "must not be empty".to_string() });
+        }
+        if key.len() > self.config.limits.max_key_size {
+            return Err(TransDbError::KeyTooLarge(self.config.limits.max_key_size));
+        }
+        if !self.config.strict_keys {
+            return Ok(());
+        }
+        let reason = key.chars().find_map(|c| match c {
+            '/' | '?' | '#' => Some(format!("contains '{}'", c)),
+            c if c.is_whitespace() => Some(format!("contains whitespace {:?}", c)),
+            c if c.is_control() => Some(format!("contains control character {:?}", c)),
+            _ => None,
+        });
+        match reason {
+            Some(reason) => Err(TransDbError::InvalidKey { key: key.to_string(), reason }),
+            None => Ok(()),
+        }
     }
 
-    /// Build the URL for a key operation against the current target.
+    /// Build the URL for a key operation against the current target. The key is
+    /// percent-encoded, so `a/b` becomes `/keys/a%2Fb`; a namespace is prepended as
+    /// `/{namespace}/keys/...`.
     pub fn build_key_url(&self, key: &str) -> String {
-        format!("http://{}/keys/{}", self.target, key)
+        match &self.config.namespace {
+            Some(namespace) => format!("{}/{}/keys/{}", self.base_url(), namespace, encode_path_segment(key)),
+            None => format!("{}/keys/{}", self.base_url(), encode_path_segment(key)),
+        }
     }
 
     /// Get a value by key (strong guarantee).
-    /// Returns `KeyNotFound` if the key does not exist **or** if it exists but has expired.
+    /// Returns `KeyNotFound` if the key does not exist **or** if it exists but has expired,
+    /// and `KeyDeleted` (carrying the tombstone version) if it was deleted.
+    /// Prefer [`Client::get_optional`] unless the tombstone version is needed.
     pub async fn get(&self, key: &str) -> Result<GetResult> {
-        let result = self.get_allowing_expired(key).await?;
+        self.get_ext(key, &RequestOptions::default()).await
+    }
+
+    /// [`Client::get`] with per-call options.
+    pub async fn get_ext(&self, key: &str, options: &RequestOptions) -> Result<GetResult> {
+        let result = self.call(options, self.get_impl(key, options, false)).await?;
         if result.expired {
             return Err(TransDbError::KeyNotFound(key.to_string()));
         }
         Ok(result)
     }
 
+    /// Like [`Client::get`], but sends `X-Consistency: eventual`, so a server with a read
+    /// snapshot answers from it without waiting on its store lock. The value may then be up to
+    /// one snapshot interval stale, with `sequence` the high-water mark when the snapshot was
+    /// taken. Servers without a snapshot answer as for [`Client::get`]. Bypasses the value
+    /// cache, so a stale answer never replaces a fresher cached one.
+    pub async fn get_eventual(&self, key: &str) -> Result<GetResult> {
+        let options = RequestOptions::default();
+        let result = self.call(&options, self.get_impl(key, &options, true)).await?;
+        if result.expired {
+            return Err(TransDbError::KeyNotFound(key.to_string()));
+        }
+        Ok(result)
+    }
+
+    /// Like [`Client::get`], but an absent, deleted or expired key is `Ok(None)`, leaving `Err`
+    /// for genuine failures (network, 5xx, malformed responses).
+    pub async fn get_optional(&self, key: &str) -> Result<Option<GetResult>> {
+        absent_as_none(self.get(key).await)
+    }
+
+    /// Like [`Client::get_allowing_expired`], but an absent or deleted key is `Ok(None)`.
+    pub async fn get_allowing_expired_optional(&self, key: &str) -> Result<Option<GetResult>> {
+        absent_as_none(self.get_allowing_expired(key).await)
+    }
+
+    /// Get bytes `start..=end` of a value via `Range`, so only that slice is transferred. An `end`
+    /// past the value is truncated; a `start` past it returns `RangeNotSatisfiable`. Expired and
+    /// missing keys fail as for [`Client::get`].
+    pub async fn get_range(&self, key: &str, start: u64, end: u64) -> Result<PartialGetResult> {
+        if start > end {
+            return Err(TransDbError::RangeNotSatisfiable { key: key.to_string(), total_bytes: None });
+        }
+        self.call(&RequestOptions::default(), self.get_range_impl(key, start, end)).await
+    }
+
+    async fn get_range_impl(&self, key: &str, start: u64, end: u64) -> Result<PartialGetResult> {
+        self.validate_key(key)?;
+
+        let request = self.http_client.get(self.build_key_url(key)).header("Range", format!("bytes={}-{}", start, end));
+        let response = self.send(request).await?;
+
+        let status = response.status();
+        // "bytes 0-99/4096" on a 206, "bytes */4096" on a 416.
+        let total_bytes = response
+            .headers()
+            .get("content-range")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit_once('/'))
+            .and_then(|(_, total)| total.parse::<u64>().ok());
+        if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+            return Err(TransDbError::RangeNotSatisfiable { key: key.to_string(), total_bytes });
+        }
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, key, response).await);
+        }
+
+        let version = parse_etag(&response).ok_or(TransDbError::MissingETag)?;
+        if response.headers().get("x-expired").and_then(|v| v.to_str().ok()) == Some("true") {
+            return Err(TransDbError::KeyNotFound(key.to_string()));
+        }
+        let body = response.bytes().await.map_err(|e| TransDbError::NetworkError(e.to_string()))?;
+
+        if status == reqwest::StatusCode::PARTIAL_CONTENT {
+            let missing = || TransDbError::NetworkError("206 response without Content-Range".to_string());
+            let total_bytes = total_bytes.ok_or_else(missing)?;
+            return Ok(PartialGetResult { data: body, total_bytes, version });
+        }
+        // A server that ignores `Range` sends the whole value; slice it here.
+        let total_bytes = body.len() as u64;
+        if start >= total_bytes {
+            return Err(TransDbError::RangeNotSatisfiable { key: key.to_string(), total_bytes: Some(total_bytes) });
+        }
+        let data = body.slice(start as usize..=end.min(total_bytes - 1) as usize);
+        Ok(PartialGetResult { data, total_bytes, version })
+    }
+
     /// Get a value by key, returning it even if its TTL has elapsed (soft guarantee).
     /// Check `GetResult::expired` to determine whether the value is stale.
     pub async fn get_allowing_expired(&self, key: &str) -> Result<GetResult> {
-        if key.len() > MAX_KEY_SIZE {
-            return Err(TransDbError::KeyTooLarge(MAX_KEY_SIZE));
-        }
+        let options = RequestOptions::default();
+        self.call(&options, self.get_impl(key, &options, false)).await
+    }
+
+    /// An `eventual` read asks for the server's read snapshot and leaves the value cache alone.
+    async fn get_impl(&self, key: &str, options: &RequestOptions, eventual: bool) -> Result<GetResult> {
+        self.validate_key(key)?;
 
         let url = self.build_key_url(key);
 
-        let response = self
-            .http_client
-            .get(&url)
-            .send()
-            .await
-            .map_err(|e| TransDbError::NetworkError(e.to_string()))?;
+        let cache = || if eventual { None } else { self.cache() };
+        let cached = cache().and_then(|mut cache| cache.get(key));
+        let mut request = apply_options(self.http_client.get(&url), options);
+        if let Some((version, _)) = &cached {
+            request = request.header("If-None-Match", version.to_etag());
+        }
+        if eventual {
+            request = request.header("X-Consistency", "eventual");
+        }
+        let response = self.send(request).await?;
 
         let status = response.status();
+        let sequence = parse_sequence(&response);
+        let expires_at = parse_expires_at(&response);
+        if let (reqwest::StatusCode::NOT_MODIFIED, Some((version, value))) = (status, cached) {
+            // The server only answers 304 for an unexpired value.
+            let sequence = sequence.unwrap_or(version.get());
+            return Ok(GetResult { value, version, expired: false, expires_at, sequence });
+        }
         if !status.is_success() {
-            return Err(parse_error_response(status, key, response).await);
+            if !eventual {
+                self.invalidate(key);
+            }
+            return Err(parse_error_response(&self.config.limits, status, key, response).await);
         }
 
         let version = parse_etag(&response).ok_or(TransDbError::MissingETag)?;
+        // A server predating `X-Sequence` has written at least up to this version.
+        let sequence = sequence.unwrap_or(version.get());
         let expired = response
             .headers()
             .get("x-expired")
             .and_then(|v| v.to_str().ok())
             == Some("true");
 
-        let bytes = response
+        let value = response
             .bytes()
             .await
             .map_err(|e| TransDbError::NetworkError(e.to_string()))?;
 
-        Ok(GetResult { value: bytes.to_vec(), version, expired })
+        // Expired values are not cached, so a strict `get` never revalidates into one.
+        if let Some(mut cache) = cache() {
+            if expired {
+                cache.remove(key);
+            } else {
+                cache.insert(key, version, value.clone());
+            }
+        }
+        Ok(GetResult { value, version, expired, expires_at, sequence })
     }
 
     /// Store a value under the given key; returns the version assigned by this write.
-    pub async fn put(&self, key: &str, value: &[u8]) -> Result<u64> {
-        self.put_impl(key, value, None).await
+    pub async fn put(&self, key: &str, value: &[u8]) -> Result<Version> {
+        self.put_ext(key, value, &RequestOptions::default()).await
+    }
+
+    /// [`Client::put`] with per-call options.
+    pub async fn put_ext(&self, key: &str, value: &[u8], options: &RequestOptions) -> Result<Version> {
+        let idempotency_key = Uuid::new_v4().to_string();
+        self.call(options, self.put_impl(key, value, OCTET_STREAM, None, &idempotency_key, options))
+            .await
+            .map(|o| o.version)
+    }
+
+    /// Like [`Client::put`], but also reports the version of the value that was overwritten,
+    /// so callers can tell a create from a clobber.
+    pub async fn put_detailed(&self, key: &str, value: &[u8]) -> Result<PutOutcome> {
+        let options = RequestOptions::default();
+        self.call(&options, self.put_impl(key, value, OCTET_STREAM, None, &Uuid::new_v4().to_string(), &options)).await
     }
 
     /// Store a value under the given key with an absolute Unix epoch TTL (seconds).
     /// Returns the version assigned by this write.
-    pub async fn put_with_ttl(&self, key: &str, value: &[u8], ttl: u64) -> Result<u64> {
-        self.put_impl(key, value, Some(ttl)).await
+    pub async fn put_with_ttl(&self, key: &str, value: &[u8], ttl: u64) -> Result<Version> {
+        let options = RequestOptions::default();
+        self.call(&options, self.put_impl(key, value, OCTET_STREAM, Some(ttl), &Uuid::new_v4().to_string(), &options))
+            .await
+            .map(|o| o.version)
+    }
+
+    /// Bulk-load `items`, keeping up to `concurrency` PUTs in flight. Returns each key with its
+    /// [`Client::put`] result, in completion order. With `stop_on_fatal`, the first
+    /// non-retryable error (see [`TransDbError::is_retryable`]) stops the load: remaining items
+    /// are not sent, and PUTs still in flight are abandoned and may or may not have been applied.
+    pub async fn put_stream<I>(
+        &self,
+        items: I,
+        concurrency: usize,
+        stop_on_fatal: bool,
+    ) -> Vec<(String, Result<Version>)>
+    where
+        I: IntoIterator<Item = (String, Bytes)>,
+    {
+        let mut puts = stream::iter(items)
+            .map(|(key, value)| async move {
+                let result = self.put(&key, &value).await;
+                (key, result)
+            })
+            .buffer_unordered(concurrency.max(1));
+
+        let mut results = Vec::new();
+        while let Some((key, result)) = puts.next().await {
+            let fatal = result.as_ref().is_err_and(|e| !e.is_retryable());
+            results.push((key, result));
+            if fatal && stop_on_fatal {
+                break;
+            }
+        }
+        results
+    }
+
+    /// Store a value using a caller-chosen Idempotency-Key, so a retry of the same logical write
+    /// replays the original version instead of writing twice.
+    /// Returns `IdempotencyConflict` if the key was already used for a different key or method.
+    pub async fn put_with_idempotency_key(&self, key: &str, value: &[u8], idempotency_key: &str) -> Result<Version> {
+        let options = RequestOptions::default();
+        self.call(&options, self.put_impl(key, value, OCTET_STREAM, None, idempotency_key, &options))
+            .await
+            .map(|o| o.version)
+    }
+
+    /// [`Client::put_with_idempotency_key`] for callers that persist `token` and may retry long
+    /// after the first attempt. [`PutOutcome::replayed`] tells a replay of the original write
+    /// (`true`, with its original version) from a fresh one (`false`). A `false` on a retry
+    /// means the server no longer remembered the token, e.g. because it was restarted or its
+    /// idempotency cache was cleared, so the value was written again under a new version.
+    /// Returns `IdempotencyConflict` if the token was already used for a different key or method.
+    pub async fn put_idempotent(&self, key: &str, value: &[u8], token: &str) -> Result<PutOutcome> {
+        let options = RequestOptions::default();
+        self.call(&options, self.put_impl(key, value, OCTET_STREAM, None, token, &options)).await
     }
 
-    async fn put_impl(&self, key: &str, value: &[u8], ttl: Option<u64>) -> Result<u64> {
-        if key.len() > MAX_KEY_SIZE {
-            return Err(TransDbError::KeyTooLarge(MAX_KEY_SIZE));
+    /// Store `value` encoded as JSON, sent with `Content-Type: application/json`; returns the
+    /// version assigned by this write. Fails with `SerializationError` if `value` cannot be
+    /// encoded, in which case nothing is sent.
+    pub async fn put_json<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<Version> {
+        let body = to_json(value)?;
+        let options = RequestOptions::default();
+        self.call(&options, self.put_impl(key, &body, JSON, None, &Uuid::new_v4().to_string(), &options))
+            .await
+            .map(|o| o.version)
+    }
+
+    /// [`Client::put_json`] with an absolute Unix epoch TTL (seconds), as for [`Client::put_with_ttl`].
+    pub async fn put_json_with_ttl<T: Serialize + ?Sized>(&self, key: &str, value: &T, ttl: u64) -> Result<Version> {
+        let body = to_json(value)?;
+        let options = RequestOptions::default();
+        self.call(&options, self.put_impl(key, &body, JSON, Some(ttl), &Uuid::new_v4().to_string(), &options))
+            .await
+            .map(|o| o.version)
+    }
+
+    /// Store `value` encoded as JSON only if `key` is absent, deleted or expired, and return the
+    /// new version. Sent as a one-key [`Client::multi_cas`] (`POST /multi-cas`) that read the key
+    /// at version 0, so if it holds a live value nothing is written and `VersionConflict` is
+    /// returned. Unlike [`Client::put_json`], the value goes without `Content-Type:
+    /// application/json`, since multi-cas writes carry no content type. A reply with no version
+    /// for the write is malformed and fails with `DeserializationError`.
+    pub async fn put_json_if_absent<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<Version> {
+        let body = to_json(value)?;
+        let versions = self.multi_cas(&[(key, Version(0))], &[(key, &body)]).await?;
+        versions.first().copied().ok_or_else(|| {
+            TransDbError::DeserializationError("multi-cas response has no version for the write".to_string())
+        })
+    }
+
+    /// [`Client::get`] the value under `key` and decode it from JSON as a `T`. Fails with
+    /// `DeserializationError` if the stored value is not valid JSON for `T`.
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
+        from_json(&self.get(key).await?.value)
+    }
+
+    /// [`Client::get_json`] that also returns values whose TTL has elapsed, as for
+    /// [`Client::get_allowing_expired`].
+    pub async fn get_json_allowing_expired<T: DeserializeOwned>(&self, key: &str) -> Result<T> {
+        from_json(&self.get_allowing_expired(key).await?.value)
+    }
+
+    async fn put_impl(
+        &self,
+        key: &str,
+        value: &[u8],
+        content_type: &str,
+        ttl: Option<u64>,
+        idempotency_key: &str,
+        options: &RequestOptions,
+    ) -> Result<PutOutcome> {
+        self.validate_key(key)?;
+        let limits = &self.config.limits;
+        if value.len() > limits.max_value_size {
+            return Err(TransDbError::ValueTooLarge(limits.max_value_size));
         }
-        if value.len() > MAX_VALUE_SIZE {
-            return Err(TransDbError::ValueTooLarge(MAX_VALUE_SIZE));
+        if idempotency_key.len() > limits.max_idempotency_key_size {
+            return Err(TransDbError::IdempotencyKeyTooLarge(limits.max_idempotency_key_size));
         }
+        self.invalidate(key);
 
         let url = self.build_key_url(key);
 
-        let mut request = self
-            .http_client
-            .put(&url)
-            .header("Content-Type", "application/octet-stream")
-            .header("Idempotency-Key", Uuid::new_v4().to_string())
+        let mut request = apply_options(self.http_client.put(&url), options)
+            .header("Content-Type", content_type)
+            .header("Idempotency-Key", idempotency_key)
             .body(value.to_vec());
 
         if let Some(ts) = ttl {
             request = request.header("X-TTL", ts.to_string());
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| TransDbError::NetworkError(e.to_string()))?;
+        let response = self.send(request).await?;
 
         let status = response.status();
         if !status.is_success() {
-            return Err(parse_error_response(status, key, response).await);
+            return Err(parse_error_response(&self.config.limits, status, key, response).await);
+        }
+
+        let version = parse_etag(&response).ok_or(TransDbError::MissingETag)?;
+        let previous = response
+            .headers()
+            .get("x-previous-version")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Version);
+        let version_diff = response
+            .headers()
+            .get("x-version-diff")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(0);
+        if version_diff > 0 {
+            log::debug!("PUT {key}: {version_diff} other write(s) since the version it replaced");
         }
+        let replayed = response.headers().get("x-idempotent-replay").is_some_and(|v| v == "true");
+        Ok(PutOutcome { version, previous, version_diff, replayed })
+    }
+
+    /// Append `suffix` to the value stored under `key` via `PATCH /keys/:key`, creating the key
+    /// if it is absent, deleted or expired, and return the new version. The value keeps its TTL.
+    /// Fails with `ValueTooLarge` if the combined value would exceed the `max_value_size` limit.
+    pub async fn append(&self, key: &str, suffix: &[u8]) -> Result<Version> {
+        let idempotency_key = Uuid::new_v4().to_string();
+        self.call(&RequestOptions::default(), self.append_impl(key, suffix, &idempotency_key)).await
+    }
+
+    async fn append_impl(&self, key: &str, suffix: &[u8], idempotency_key: &str) -> Result<Version> {
+        self.validate_key(key)?;
+        if suffix.len() > self.config.limits.max_value_size {
+            return Err(TransDbError::ValueTooLarge(self.config.limits.max_value_size));
+        }
+        self.invalidate(key);
+
+        let request = self
+            .http_client
+            .patch(self.build_key_url(key))
+            .header("Content-Type", OCTET_STREAM)
+            .header("Idempotency-Key", idempotency_key)
+            .body(suffix.to_vec());
+        let response = self.send(request).await?;
 
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, key, response).await);
+        }
         parse_etag(&response).ok_or(TransDbError::MissingETag)
     }
 
     /// Delete the value stored under the given key.
     /// Returns `Some(version)` when a tombstone was written (`200 OK` + ETag),
     /// or `None` when the key was absent or already deleted (`204 No Content`).
-    pub async fn delete(&self, key: &str) -> Result<Option<u64>> {
-        if key.len() > MAX_KEY_SIZE {
-            return Err(TransDbError::KeyTooLarge(MAX_KEY_SIZE));
+    pub async fn delete(&self, key: &str) -> Result<Option<Version>> {
+        self.delete_ext(key, &RequestOptions::default()).await
+    }
+
+    /// [`Client::delete`] with per-call options.
+    pub async fn delete_ext(&self, key: &str, options: &RequestOptions) -> Result<Option<Version>> {
+        let idempotency_key = Uuid::new_v4().to_string();
+        self.call(options, self.delete_impl(key, None, &idempotency_key, options)).await
+    }
+
+    /// Delete using a caller-chosen Idempotency-Key; see [`Client::put_with_idempotency_key`].
+    pub async fn delete_with_idempotency_key(&self, key: &str, idempotency_key: &str) -> Result<Option<Version>> {
+        let options = RequestOptions::default();
+        self.call(&options, self.delete_impl(key, None, idempotency_key, &options)).await
+    }
+
+    /// Delete many keys, keeping up to [`ClientConfig::bulk_concurrency`] DELETEs in flight.
+    /// Returns each key with its own [`Client::delete`] result, in input order, so callers can
+    /// retry just the failures. Every key is validated before anything is sent.
+    pub async fn delete_many(&self, keys: &[&str]) -> Result<Vec<(String, Result<Option<Version>>)>> {
+        for key in keys {
+            self.validate_key(key)?;
         }
 
+        let results = stream::iter(keys)
+            .map(|key| async move { (key.to_string(), self.delete(key).await) })
+            .buffered(self.config.bulk_concurrency)
+            .collect()
+            .await;
+        Ok(results)
+    }
+
+    /// Delete only if the key is currently at `version` (`If-Match`), returning the tombstone
+    /// version. If `version` is already the key's tombstone the delete is re-asserted without
+    /// writing. Returns `PreconditionFailed` if the key is absent or at any other version; the
+    /// error carries the version it is at.
+    pub async fn delete_if_match(&self, key: &str, version: Version) -> Result<Version> {
+        let options = RequestOptions::default();
+        let idempotency_key = Uuid::new_v4().to_string();
+        let tombstone = self.call(&options, self.delete_impl(key, Some(version), &idempotency_key, &options)).await?;
+        // The server never answers a conditional delete with 204.
+        tombstone.ok_or(TransDbError::MissingETag)
+    }
+
+    async fn delete_impl(
+        &self,
+        key: &str,
+        if_match: Option<Version>,
+        idempotency_key: &str,
+        options: &RequestOptions,
+    ) -> Result<Option<Version>> {
+        self.validate_key(key)?;
+        let max = self.config.limits.max_idempotency_key_size;
+        if idempotency_key.len() > max {
+            return Err(TransDbError::IdempotencyKeyTooLarge(max));
+        }
+        self.invalidate(key);
+
         let url = self.build_key_url(key);
 
-        let response = self
-            .http_client
-            .delete(&url)
-            .header("Idempotency-Key", Uuid::new_v4().to_string())
-            .send()
-            .await
-            .map_err(|e| TransDbError::NetworkError(e.to_string()))?;
+        let mut request = apply_options(self.http_client.delete(&url), options).header("Idempotency-Key", idempotency_key);
+        if let Some(version) = if_match {
+            request = request.header("If-Match", version.to_etag());
+        }
+
+        let response = self.send(request).await?;
 
         let status = response.status();
         if !status.is_success() {
-            return Err(parse_error_response(status, key, response).await);
+            return Err(parse_error_response(&self.config.limits, status, key, response).await);
         }
 
         if status == reqwest::StatusCode::NO_CONTENT {
@@ -168,32 +1040,716 @@ impl Client {
         // 200 OK — a tombstone was written; ETag carries the version.
         parse_etag(&response).map(Some).ok_or(TransDbError::MissingETag)
     }
+
+    /// Number of live (non-deleted, non-expired) keys on the target, via `HEAD /keys`.
+    pub async fn key_count(&self) -> Result<usize> {
+        self.call(&RequestOptions::default(), self.key_count_impl()).await
+    }
+
+    async fn key_count_impl(&self) -> Result<usize> {
+        let url = format!("{}/keys", self.base_url());
+
+        let response = self.send(self.http_client.head(&url)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, "", response).await);
+        }
+
+        response
+            .headers()
+            .get("x-key-count")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|s| s.parse::<usize>().ok())
+            .ok_or_else(|| TransDbError::HttpError(status.as_u16(), "Missing or invalid X-Key-Count header".to_string()))
+    }
+
+    /// Live keys on the target starting with `prefix` (every key for `None`), sorted, via
+    /// `GET /keys`. The list arrives as one JSON document; for large key spaces prefer
+    /// [`Client::list_keys_streaming`].
+    pub async fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>> {
+        self.call(&RequestOptions::default(), self.list_keys_impl(prefix)).await
+    }
+
+    async fn list_keys_impl(&self, prefix: Option<&str>) -> Result<Vec<String>> {
+        let response = self.list_keys_request(prefix, None).await?;
+        response.json::<KeyList>().await.map(|list| list.keys).map_err(|e| TransDbError::NetworkError(e.to_string()))
+    }
+
+    /// As [`Client::list_keys`], but asks for NDJSON and yields each key as its line arrives, so
+    /// the list is never held whole. The deadline and circuit breaker cover the initial request
+    /// only. The stream ends after the first error.
+    pub fn list_keys_streaming<'a>(&'a self, prefix: Option<&'a str>) -> impl Stream<Item = Result<String>> + 'a {
+        let request = self.list_keys_request(prefix, Some(NDJSON_CONTENT_TYPE));
+        let response = async move { self.call(&RequestOptions::default(), request).await };
+        stream::once(response).flat_map(|response| match response {
+            Ok(response) => Either::Left(ndjson_lines(response)),
+            Err(e) => Either::Right(stream::iter([Err(e)])),
+        })
+    }
+
+    async fn list_keys_request(&self, prefix: Option<&str>, accept: Option<&str>) -> Result<reqwest::Response> {
+        let url = format!("{}/keys", self.base_url());
+        let mut request = self.http_client.get(&url);
+        if let Some(prefix) = prefix {
+            request = request.query(&[("prefix", prefix)]);
+        }
+        if let Some(accept) = accept {
+            request = request.header(reqwest::header::ACCEPT, accept);
+        }
+
+        let response = self.send(request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, "", response).await);
+        }
+        Ok(response)
+    }
+
+    /// Send an arbitrary request to `/keys/{key}` on the current target and return the full
+    /// response, for callers that need status codes or headers the typed methods hide.
+    /// Skips client-side validation; non-2xx statuses are returned, not mapped to errors.
+    /// Default headers, User-Agent and the circuit breaker apply as for typed calls.
+    pub async fn raw_request(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        headers: HeaderMap,
+        body: Option<Bytes>,
+    ) -> Result<RawResponse> {
+        self.call(&RequestOptions::default(), self.raw_request_impl(method, key, headers, body)).await
+    }
+
+    async fn raw_request_impl(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        headers: HeaderMap,
+        body: Option<Bytes>,
+    ) -> Result<RawResponse> {
+        let mut request = self.http_client.request(method, self.build_key_url(key)).headers(headers);
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        let response = self.send(request).await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await.map_err(|e| TransDbError::NetworkError(e.to_string()))?;
+        Ok(RawResponse { status, headers, body })
+    }
+
+    /// Set the TTL of every listed key to `expires_at` (absolute Unix epoch seconds) in one
+    /// atomic request. Values and versions are unchanged; keys that are absent, deleted, or
+    /// already expired come back in `BulkTtlResult::not_found`.
+    pub async fn extend_ttl_many(&self, keys: &[&str], expires_at: u64) -> Result<BulkTtlResult> {
+        self.call(&RequestOptions::default(), self.extend_ttl_many_impl(keys, expires_at)).await
+    }
+
+    async fn extend_ttl_many_impl(&self, keys: &[&str], expires_at: u64) -> Result<BulkTtlResult> {
+        for key in keys {
+            self.validate_key(key)?;
+        }
+
+        let url = format!("{}/batch/ttl", self.base_url());
+        let request = BulkTtlRequest { keys: keys.iter().map(|k| k.to_string()).collect(), expires_at };
+
+        let request = self
+            .http_client
+            .patch(&url)
+            .header("Idempotency-Key", Uuid::new_v4().to_string())
+            .json(&request);
+        let response = self.send(request).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, "", response).await);
+        }
+
+        response
+            .json::<BulkTtlResult>()
+            .await
+            .map_err(|e| TransDbError::NetworkError(e.to_string()))
+    }
+
+    /// Write every `(key, value)` in `writes` atomically, provided each `(key, version)` in `reads`
+    /// is still current, via `POST /multi-cas`; `Version(0)` stands for a key that is absent.
+    /// Returns the new version of each write, in order. If any read key has moved on, nothing is
+    /// written and the error is [`TransDbError::VersionConflict`] naming those keys.
+    pub async fn multi_cas(&self, reads: &[(&str, Version)], writes: &[(&str, &[u8])]) -> Result<Vec<Version>> {
+        self.call(&RequestOptions::default(), self.multi_cas_impl(reads, writes)).await
+    }
+
+    async fn multi_cas_impl(&self, reads: &[(&str, Version)], writes: &[(&str, &[u8])]) -> Result<Vec<Version>> {
+        for key in reads.iter().map(|(k, _)| k).chain(writes.iter().map(|(k, _)| k)) {
+            self.validate_key(key)?;
+        }
+        if writes.iter().any(|(_, value)| value.len() > self.config.limits.max_value_size) {
+            return Err(TransDbError::ValueTooLarge(self.config.limits.max_value_size));
+        }
+
+        let url = format!("{}/multi-cas", self.base_url());
+        let request = MultiCasRequest {
+            reads: reads
+                .iter()
+                .map(|(key, version)| MultiCasRead { key: key.to_string(), version: *version })
+                .collect(),
+            writes: writes
+                .iter()
+                .map(|(key, value)| MultiCasWrite { key: key.to_string(), value_b64: Base64Value::from_bytes(value) })
+                .collect(),
+            idempotency_key: Uuid::new_v4().to_string(),
+        };
+        let response = self.send(self.http_client.post(&url).json(&request)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, "", response).await);
+        }
+
+        response
+            .json::<MultiCasResponse>()
+            .await
+            .map(|r| r.versions)
+            .map_err(|e| TransDbError::NetworkError(e.to_string()))
+    }
+
+    /// The target's version, build and role, via `GET /version`. Both roles answer it.
+    pub async fn server_info(&self) -> Result<ServerInfo> {
+        self.call(&RequestOptions::default(), self.server_info_impl()).await
+    }
+
+    async fn server_info_impl(&self) -> Result<ServerInfo> {
+        let url = format!("{}/version", self.base_url());
+
+        let response = self.send(self.http_client.get(&url)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, "", response).await);
+        }
+
+        response
+            .json::<ServerInfo>()
+            .await
+            .map_err(|e| TransDbError::NetworkError(e.to_string()))
+    }
+
+    /// Entry counts (live, expired-but-not-collected, tombstoned) on the target, via `GET /stats`.
+    pub async fn stats(&self) -> Result<StoreStats> {
+        self.call(&RequestOptions::default(), self.stats_impl()).await
+    }
+
+    async fn stats_impl(&self) -> Result<StoreStats> {
+        let url = format!("{}/stats", self.base_url());
+
+        let response = self.send(self.http_client.get(&url)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, "", response).await);
+        }
+
+        response
+            .json::<StoreStats>()
+            .await
+            .map_err(|e| TransDbError::NetworkError(e.to_string()))
+    }
+
+    /// Names of the namespaces on the target, sorted, via `GET /admin/namespaces`.
+    pub async fn namespaces(&self) -> Result<Vec<String>> {
+        self.call(&RequestOptions::default(), self.namespaces_impl()).await
+    }
+
+    async fn namespaces_impl(&self) -> Result<Vec<String>> {
+        let url = format!("{}/admin/namespaces", self.base_url());
+
+        let response = self.send(self.http_client.get(&url)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, "", response).await);
+        }
+
+        response
+            .json::<Vec<String>>()
+            .await
+            .map_err(|e| TransDbError::NetworkError(e.to_string()))
+    }
+
+    /// Tombstone every live key and clear the server's idempotency cache via
+    /// `DELETE /keys?confirm=yes`, returning how many keys were deleted. Only servers built with
+    /// the `test-admin` feature serve this; meant for resetting a store between test cases.
+    #[cfg(feature = "test-admin")]
+    pub async fn delete_all(&self) -> Result<u64> {
+        self.call(&RequestOptions::default(), self.delete_all_impl()).await
+    }
+
+    #[cfg(feature = "test-admin")]
+    async fn delete_all_impl(&self) -> Result<u64> {
+        let url = format!("{}/keys?confirm=yes", self.base_url());
+
+        let response = self.send(self.http_client.delete(&url)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(parse_error_response(&self.config.limits, status, "", response).await);
+        }
+        if let Some(mut cache) = self.cache() {
+            cache.clear();
+        }
+
+        response
+            .json::<transdb_common::DeleteAllResult>()
+            .await
+            .map(|result| result.deleted_count)
+            .map_err(|e| TransDbError::NetworkError(e.to_string()))
+    }
+
+    /// Take the advisory mutex `name` for `owner` with a lease of `ttl_secs`, via
+    /// `POST /mutex/:name/acquire`. Losing the race is not an error: the returned status has
+    /// `acquired: false` and names the current holder. Re-acquiring a held mutex resets its lease.
+    pub async fn acquire_mutex(&self, name: &str, owner: &str, ttl_secs: u64) -> Result<MutexStatus> {
+        let url = format!("{}/mutex/{}/acquire", self.base_url(), encode_path_segment(name));
+        self.call(&RequestOptions::default(), self.mutex_lease_impl(url, name, owner, ttl_secs)).await
+    }
+
+    /// Extend `owner`'s lease on mutex `name` to `ttl_secs` from now. Returns `acquired: false`
+    /// if the lease already lapsed or another owner holds the mutex.
+    pub async fn renew_mutex(&self, name: &str, owner: &str, ttl_secs: u64) -> Result<MutexStatus> {
+        let url = format!("{}/mutex/{}/renew", self.base_url(), encode_path_segment(name));
+        self.call(&RequestOptions::default(), self.mutex_lease_impl(url, name, owner, ttl_secs)).await
+    }
+
+    /// Release mutex `name` if `owner` holds it. Returns `false` if it did not (lease lapsed or
+    /// held by someone else), in which case nothing changes.
+    pub async fn release_mutex(&self, name: &str, owner: &str) -> Result<bool> {
+        self.call(&RequestOptions::default(), self.release_mutex_impl(name, owner)).await
+    }
+
+    async fn mutex_lease_impl(&self, url: String, name: &str, owner: &str, ttl_secs: u64) -> Result<MutexStatus> {
+        check_mutex_name(name, &self.config.limits)?;
+        let request = self.http_client.post(&url).query(&[("owner", owner), ("ttl_secs", &ttl_secs.to_string())]);
+        let response = self.send(request).await?;
+
+        let status = response.status();
+        if !status.is_success() && status != reqwest::StatusCode::PRECONDITION_FAILED {
+            return Err(parse_error_response(&self.config.limits, status, name, response).await);
+        }
+
+        response
+            .json::<MutexStatus>()
+            .await
+            .map_err(|e| TransDbError::NetworkError(e.to_string()))
+    }
+
+    async fn release_mutex_impl(&self, name: &str, owner: &str) -> Result<bool> {
+        check_mutex_name(name, &self.config.limits)?;
+        let url = format!("{}/mutex/{}", self.base_url(), encode_path_segment(name));
+        let response = self.send(self.http_client.delete(&url).query(&[("owner", owner)])).await?;
+
+        match response.status() {
+            reqwest::StatusCode::PRECONDITION_FAILED => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => Err(parse_error_response(&self.config.limits, status, name, response).await),
+        }
+    }
+
+    /// Send a request, first adding signature headers when a signing key is configured. With the
+    /// `tracing` feature it is sent in a span, as the `trace` module describes.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let mut request = request.build().map_err(|e| TransDbError::NetworkError(e.to_string()))?;
+        if let Some(key) = &self.signing_key {
+            let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let body = request.body().and_then(|b| b.as_bytes()).unwrap_or_default();
+            let url = request.url();
+            let path_and_query = match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            };
+            let signature = signing::sign(key, request.method().as_str(), &path_and_query, body, timestamp);
+            let headers = request.headers_mut();
+            headers.insert("x-timestamp", HeaderValue::from(timestamp));
+            headers.insert("x-signature", HeaderValue::from_str(&signature).expect("base64 is a valid header value"));
+        }
+        #[cfg(feature = "tracing")]
+        let (span, started) = (trace::start(&mut request), Instant::now());
+        let execute = self.http_client.execute(request);
+        #[cfg(feature = "tracing")]
+        let execute = tracing::Instrument::instrument(execute, span.clone());
+        let result = execute.await.map_err(transport_error);
+        #[cfg(feature = "tracing")]
+        trace::finish(&span, &result, started.elapsed());
+        let response = result?;
+        if let Some(sink) = &self.config.server_timing_sink {
+            let timing = response.headers().get("server-timing").and_then(|v| v.to_str().ok());
+            if let Some(timing) = timing.and_then(ServerTiming::parse) {
+                let _ = sink.send(timing);
+            }
+        }
+        Ok(response)
+    }
+
+    /// Run one operation against the current target, applying the per-call deadline and the
+    /// target's circuit breaker (if enabled). Only connection failures and timeouts count
+    /// against the breaker; any answer from the server, even an error status, closes it.
+    async fn call<T>(&self, options: &RequestOptions, operation: impl Future<Output = Result<T>>) -> Result<T> {
+        let Some(breaker) = self.routing().breaker.clone() else {
+            return with_deadline(options.deadline, operation).await;
+        };
+        let admission = breaker.try_acquire(Instant::now());
+        if admission == Admission::Rejected {
+            return Err(TransDbError::CircuitOpen { target: self.target() });
+        }
+
+        // Frees the half-open probe slot if this future is dropped before it completes.
+        let probe = (admission == Admission::Probe).then_some(ProbeGuard(&breaker));
+        let result = with_deadline(options.deadline, operation).await;
+        std::mem::forget(probe);
+
+        match &result {
+            Err(TransDbError::NetworkError(_) | TransDbError::ConnectError(_) | TransDbError::Timeout(_)) => {
+                breaker.record_failure(Instant::now())
+            }
+            _ => breaker.record_success(),
+        }
+        result
+    }
+}
+
+/// The basic key-value operations of [`Client`], for code that should run against either the
+/// real client or the in-memory `fake::FakeClient` (built with the `test-util` feature). Each
+/// method behaves as the [`Client`] method of the same name.
+pub trait KvClient: Send + Sync {
+    fn get(&self, key: &str) -> impl Future<Output = Result<GetResult>> + Send;
+    fn put(&self, key: &str, value: &[u8]) -> impl Future<Output = Result<Version>> + Send;
+    fn put_with_ttl(&self, key: &str, value: &[u8], ttl: u64) -> impl Future<Output = Result<Version>> + Send;
+    fn delete(&self, key: &str) -> impl Future<Output = Result<Option<Version>>> + Send;
+}
+
+impl KvClient for Client {
+    fn get(&self, key: &str) -> impl Future<Output = Result<GetResult>> + Send {
+        Client::get(self, key)
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> impl Future<Output = Result<Version>> + Send {
+        Client::put(self, key, value)
+    }
+
+    fn put_with_ttl(&self, key: &str, value: &[u8], ttl: u64) -> impl Future<Output = Result<Version>> + Send {
+        Client::put_with_ttl(self, key, value, ttl)
+    }
+
+    fn delete(&self, key: &str) -> impl Future<Output = Result<Option<Version>>> + Send {
+        Client::delete(self, key)
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if let Some(task) = &self.refresh_task {
+            task.abort();
+        }
+    }
+}
+
+/// Background loop behind [`ClientConfig::with_topology_refresh`]. A failed fetch keeps the last
+/// known topology and is retried on the next tick.
+async fn refresh_periodically(
+    http_client: reqwest::Client,
+    routing: Arc<RwLock<Routing>>,
+    interval: Duration,
+    tls: bool,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await; // the seed topology is current at startup
+    loop {
+        ticker.tick().await;
+        let target = routing.read().unwrap_or_else(PoisonError::into_inner).target.clone();
+        if let Ok(topology) = fetch_topology(&http_client, &base_url(tls, &target)).await {
+            routing.write().unwrap_or_else(PoisonError::into_inner).apply_topology(topology);
+        }
+    }
+}
+
+fn base_url(tls: bool, target: &str) -> String {
+    format!("{}://{}", if tls { "https" } else { "http" }, target)
+}
+
+async fn fetch_topology(http_client: &reqwest::Client, base_url: &str) -> Result<Topology> {
+    let url = format!("{}/topology", base_url);
+
+    let response = http_client.get(&url).send().await.map_err(transport_error)?;
+
+    let status = response.status();
+    if !status.is_success() {
+        // Not routed through `parse_error_response`: a 404 here means "no topology", not a key.
+        let message = match response.json::<ErrorResponse>().await {
+            Ok(body) => with_request_id(body.error, body.request_id),
+            Err(_) => format!("Server returned status: {}", status),
+        };
+        return Err(TransDbError::HttpError(status.as_u16(), message));
+    }
+
+    response
+        .json::<Topology>()
+        .await
+        .map_err(|e| TransDbError::NetworkError(e.to_string()))
+}
+
+/// Drop guard for a probe in flight; see [`CircuitBreaker::abandon`].
+struct ProbeGuard<'a>(&'a CircuitBreaker);
+
+impl Drop for ProbeGuard<'_> {
+    fn drop(&mut self) {
+        self.0.abandon();
+    }
+}
+
+
+/// Attach the server-visible parts of `options` to a request.
+fn apply_options(request: reqwest::RequestBuilder, options: &RequestOptions) -> reqwest::RequestBuilder {
+    match options.deadline {
+        Some(deadline) => request.header("X-Request-Timeout-Ms", deadline.as_millis().to_string()),
+        None => request,
+    }
+}
+
+/// Run `operation` to completion, or fail with `Timeout` once `deadline` elapses.
+async fn with_deadline<T>(deadline: Option<Duration>, operation: impl Future<Output = Result<T>>) -> Result<T> {
+    match deadline {
+        None => operation.await,
+        Some(deadline) => tokio::time::timeout(deadline, operation)
+            .await
+            .unwrap_or(Err(TransDbError::Timeout(deadline))),
+    }
+}
+
+/// Parse the ETag header as a version; returns `None` if absent or not a version's tag.
+fn parse_etag(response: &reqwest::Response) -> Option<Version> {
+    response.headers().get("etag").and_then(|v| v.to_str().ok()).and_then(Version::from_etag)
+}
+
+fn parse_expires_at(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get("x-expires-at")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok())
 }
 
-/// Parse the ETag header as a `u64` version; returns `None` if absent or unparseable.
-fn parse_etag(response: &reqwest::Response) -> Option<u64> {
+fn parse_sequence(response: &reqwest::Response) -> Option<u64> {
     response
         .headers()
-        .get("etag")
+        .get("x-sequence")
         .and_then(|v| v.to_str().ok())
-        .map(|s| s.trim_matches('"'))
         .and_then(|s| s.parse::<u64>().ok())
 }
 
+/// Classify a failure to get a response: connect, redirect and TLS handshake failures get their
+/// own variants, so callers can retry the first but not the others; anything else is a
+/// `NetworkError`. A failed handshake is also a connect error to reqwest, so it is looked for
+/// first, as a TLS backend error among the error's sources.
+fn transport_error(e: reqwest::Error) -> TransDbError {
+    let mut source = std::error::Error::source(&e);
+    while let Some(cause) = source {
+        if cause.is::<native_tls::Error>() {
+            // reqwest's own message only names the URL; the backend's says what failed.
+            return TransDbError::TlsError(format!("{e}: {cause}"));
+        }
+        source = cause.source();
+    }
+    if e.is_redirect() {
+        TransDbError::RedirectError(e.to_string())
+    } else if e.is_connect() {
+        TransDbError::ConnectError(e.to_string())
+    } else {
+        TransDbError::NetworkError(e.to_string())
+    }
+}
+
+/// The JSON strings of an NDJSON body, parsed a chunk at a time. A line split across chunks is
+/// held back until its newline arrives; a read failure or a malformed line ends the stream.
+fn ndjson_lines(response: reqwest::Response) -> impl Stream<Item = Result<String>> {
+    stream::unfold(Some((response, Vec::new())), |state| async move {
+        let (mut response, mut buffer) = state?;
+        let (lines, finished) = match response.chunk().await {
+            Ok(Some(chunk)) => {
+                buffer.extend_from_slice(&chunk);
+                let end = buffer.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+                let rest = buffer.split_off(end);
+                (std::mem::replace(&mut buffer, rest), false)
+            }
+            Ok(None) => (std::mem::take(&mut buffer), true),
+            Err(e) => return Some((vec![Err(transport_error(e))], None)),
+        };
+        let mut keys = Vec::new();
+        for line in lines.split(|&b| b == b'\n').filter(|line| !line.trim_ascii().is_empty()) {
+            match serde_json::from_slice::<String>(line) {
+                Ok(key) => keys.push(Ok(key)),
+                Err(e) => {
+                    keys.push(Err(TransDbError::NetworkError(format!("Invalid NDJSON line: {}", e))));
+                    return Some((keys, None));
+                }
+            }
+        }
+        Some((keys, (!finished).then_some((response, buffer))))
+    })
+    .flat_map(stream::iter)
+}
+
+/// Fold the "key is absent" errors into `Ok(None)`.
+fn absent_as_none(result: Result<GetResult>) -> Result<Option<GetResult>> {
+    match result {
+        Ok(result) => Ok(Some(result)),
+        Err(e) if e.is_not_found() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Content types of PUT bodies: raw bytes, or a value written by the `*_json` methods.
+const OCTET_STREAM: &str = "application/octet-stream";
+const JSON: &str = "application/json";
+
+fn to_json<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|e| TransDbError::SerializationError(e.to_string()))
+}
+
+fn from_json<T: DeserializeOwned>(value: &[u8]) -> Result<T> {
+    serde_json::from_slice(value).map_err(|e| TransDbError::DeserializationError(e.to_string()))
+}
+
+/// Everything but RFC 3986 unreserved characters, so a key is always a single path segment.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+fn encode_path_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, PATH_SEGMENT).to_string()
+}
+
+/// Mutex `name` is stored under the key `_mutex/{name}`, which must fit in `max_key_size`.
+fn check_mutex_name(name: &str, limits: &Limits) -> Result<()> {
+    if MUTEX_KEY_PREFIX.len() + name.len() > limits.max_key_size {
+        return Err(TransDbError::KeyTooLarge(limits.max_key_size));
+    }
+    Ok(())
+}
+
+/// Map an error response to a `TransDbError`, keying off the envelope's `error_type` when the
+/// server provides one and falling back to the status code otherwise. Size errors report the
+/// client's `limits`, which are meant to match the server's.
 async fn parse_error_response(
+    limits: &Limits,
     status: reqwest::StatusCode,
     key: &str,
     response: reqwest::Response,
 ) -> TransDbError {
+    let deleted_version = response
+        .headers()
+        .get("x-deleted-version")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+    if status == reqwest::StatusCode::NOT_FOUND {
+        if let Some(version) = deleted_version {
+            return TransDbError::KeyDeleted { key: key.to_string(), version };
+        }
+    }
+    let retry_after_secs = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.trim().parse::<u64>().ok());
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return TransDbError::RateLimited { retry_after_secs };
+    }
+    // On 412 the server reports the version the key is actually at as its ETag.
+    let current_version = parse_etag(&response);
+
+    let body = response.json::<ErrorResponse>().await.ok();
+
+    match body.as_ref().map(|b| b.error_type.as_str()) {
+        Some(ERR_KEY_NOT_FOUND) => return TransDbError::KeyNotFound(key.to_string()),
+        Some(ERR_EMPTY_KEY) => {
+            return TransDbError::InvalidKey { key: String::new(), reason: "must not be empty".to_string() }
+        }
+        Some(ERR_KEY_TOO_LARGE) => return TransDbError::KeyTooLarge(limits.max_key_size),
+        Some(ERR_VALUE_TOO_LARGE) => return TransDbError::ValueTooLarge(limits.max_value_size),
+        Some(ERR_IDEMPOTENCY_KEY_TOO_LARGE) => {
+            return TransDbError::IdempotencyKeyTooLarge(limits.max_idempotency_key_size)
+        }
+        Some(ERR_OVERLOADED) => return TransDbError::Overloaded { retry_after_secs },
+        _ => {}
+    }
+
     if status == reqwest::StatusCode::NOT_FOUND {
         return TransDbError::KeyNotFound(key.to_string());
     }
 
-    let error_msg = response
-        .json::<ErrorResponse>()
-        .await
+    if status == reqwest::StatusCode::PRECONDITION_FAILED
+        || body.as_ref().map(|b| b.error_type.as_str()) == Some(ERR_PRECONDITION_FAILED)
+    {
+        return TransDbError::PreconditionFailed { key: key.to_string(), current_version };
+    }
+
+    if status == reqwest::StatusCode::UNAUTHORIZED
+        || body.as_ref().map(|b| b.error_type.as_str()) == Some(ERR_INVALID_SIGNATURE)
+    {
+        let message = body.map(|r| r.error).unwrap_or_else(|| format!("Server returned status: {}", status));
+        return TransDbError::Unauthorized(message);
+    }
+
+    if status == reqwest::StatusCode::INSUFFICIENT_STORAGE
+        || body.as_ref().map(|b| b.error_type.as_str()) == Some(ERR_STORAGE_FULL)
+    {
+        let message = body.map(|r| r.error).unwrap_or_else(|| format!("Server returned status: {}", status));
+        return TransDbError::StorageFull(message);
+    }
+
+    if body.as_ref().map(|b| b.error_type.as_str()) == Some(ERR_READ_ONLY) {
+        let message = body.map(|r| r.error).unwrap_or_else(|| format!("Server returned status: {}", status));
+        return TransDbError::ReadOnly(message);
+    }
+
+    if let Some(conflict) = body.as_ref().filter(|b| b.error_type == ERR_VERSION_CONFLICT) {
+        let keys = conflict
+            .details
+            .clone()
+            .and_then(|d| serde_json::from_value::<MultiCasConflict>(d).ok())
+            .map(|c| c.mismatched)
+            .unwrap_or_default();
+        return TransDbError::VersionConflict { keys };
+    }
+
+    if let Some(rejected) = body.as_ref().filter(|b| b.error_type == ERR_CONTENT_REJECTED) {
+        let detail = rejected
+            .details
+            .as_ref()
+            .and_then(|d| d.get("detail"))
+            .and_then(|d| d.as_str())
+            .map_or_else(|| rejected.error.clone(), str::to_string);
+        return TransDbError::ContentRejected { key: key.to_string(), detail };
+    }
+
+    let is_conflict = body.as_ref().map(|b| b.error_type.as_str()) == Some(ERR_IDEMPOTENCY_MISMATCH)
+        || status == reqwest::StatusCode::UNPROCESSABLE_ENTITY;
+
+    let request_id = body.as_ref().and_then(|b| b.request_id.clone());
+    let error_msg = body
         .map(|r| r.error)
-        .unwrap_or_else(|_| format!("Server returned status: {}", status));
+        .unwrap_or_else(|| format!("Server returned status: {}", status));
+
+    if is_conflict {
+        return TransDbError::IdempotencyConflict { message: error_msg };
+    }
 
-    TransDbError::HttpError(status.as_u16(), error_msg)
+    TransDbError::HttpError(status.as_u16(), with_request_id(error_msg, request_id))
+}
+
+/// `message` with the server's `request_id` appended as `(request_id=...)`, so the error can
+/// be looked up server-side; unchanged for servers that do not send one.
+fn with_request_id(message: String, request_id: Option<String>) -> String {
+    match request_id {
+        Some(id) => format!("{message} (request_id={id})"),
+        None => message,
+    }
 }
@@ -0,0 +1,75 @@
+//! `tracing` spans around the client's HTTP requests, built with the `tracing` feature.
+//!
+//! Every request gets a `transdb_client.request` span recording its method, key (for
+//! `/keys/:key` routes), target address, status and latency. The request also carries an
+//! `X-Request-Id` and a W3C `traceparent`, each recorded on the span too. The server echoes the
+//! request ID and puts it in error bodies, so its side of the exchange can be found from the span.
+
+use percent_encoding::percent_decode_str;
+use reqwest::header::HeaderValue;
+use std::time::Duration;
+use tracing::field::Empty;
+use tracing::Span;
+use transdb_common::Result;
+use uuid::Uuid;
+
+/// Name of the span every request is sent in.
+pub const SPAN_NAME: &str = "transdb_client.request";
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Open the span for `request`, first giving it an `X-Request-Id` and a `traceparent` unless the
+/// caller already set them (e.g. as default headers).
+pub(crate) fn start(request: &mut reqwest::Request) -> Span {
+    let headers = request.headers_mut();
+    if !headers.contains_key(REQUEST_ID_HEADER) {
+        let id = HeaderValue::from_str(&Uuid::new_v4().to_string()).expect("a UUID is a valid header value");
+        headers.insert(REQUEST_ID_HEADER, id);
+    }
+    if !headers.contains_key(TRACEPARENT_HEADER) {
+        // A fresh trace per request: version 00, 128-bit trace ID, 64-bit parent ID, sampled.
+        let (trace_id, parent_id) = (Uuid::new_v4().simple(), Uuid::new_v4().as_u64_pair().0);
+        let traceparent = format!("00-{trace_id}-{parent_id:016x}-01");
+        headers.insert(TRACEPARENT_HEADER, HeaderValue::from_str(&traceparent).expect("hex is a valid header value"));
+    }
+
+    let header = |name| request.headers().get(name).and_then(|v| v.to_str().ok()).unwrap_or_default();
+    let url = request.url();
+    let target = format!("{}:{}", url.host_str().unwrap_or_default(), url.port_or_known_default().unwrap_or(0));
+    let span = tracing::info_span!(
+        SPAN_NAME,
+        method = %request.method(),
+        key = Empty,
+        target = %target,
+        request_id = header(REQUEST_ID_HEADER),
+        traceparent = header(TRACEPARENT_HEADER),
+        status = Empty,
+        latency_ms = Empty,
+        error = Empty,
+    );
+    if let Some(key) = key_of(url.path()) {
+        span.record("key", key.as_str());
+    }
+    span
+}
+
+/// Record how the request ended: the response status, or the error if none arrived.
+pub(crate) fn finish(span: &Span, result: &Result<reqwest::Response>, latency: Duration) {
+    span.record("latency_ms", latency.as_secs_f64() * 1000.0);
+    match result {
+        Ok(response) => span.record("status", response.status().as_u16()),
+        Err(e) => span.record("error", tracing::field::display(e)),
+    };
+}
+
+/// The key a `/keys/:key` or `/:namespace/keys/:key` path addresses, decoded.
+fn key_of(path: &str) -> Option<String> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    let key = match (segments.next(), segments.next(), segments.next()) {
+        (Some("keys"), Some(key), None) => key,
+        (Some(_), Some("keys"), Some(key)) if segments.next().is_none() => key,
+        _ => return None,
+    };
+    Some(percent_decode_str(key).decode_utf8_lossy().into_owned())
+}
@@ -0,0 +1,45 @@
+use bytes::Bytes;
+use transdb_client::cache::{CacheConfig, ValueCache};
+use transdb_common::Version;
+
+fn cache(max_entries: usize, max_bytes: usize) -> ValueCache {
+    ValueCache::new(CacheConfig { max_entries, max_bytes })
+}
+
+#[test]
+fn test_get_returns_inserted_version_and_value() {
+    let mut cache = cache(4, 100);
+    assert_eq!(cache.get("k"), None);
+    cache.insert("k", Version(3), Bytes::from("abc"));
+    assert_eq!(cache.get("k"), Some((Version(3), Bytes::from("abc"))));
+
+    // Re-inserting replaces the entry and its size.
+    cache.insert("k", Version(4), Bytes::from("abcdef"));
+    assert_eq!(cache.get("k"), Some((Version(4), Bytes::from("abcdef"))));
+    assert_eq!((cache.len(), cache.bytes()), (1, 6));
+
+    cache.remove("k");
+    assert!(cache.is_empty());
+    assert_eq!(cache.bytes(), 0);
+}
+
+#[test]
+fn test_evicts_least_recently_used_by_count_and_bytes() {
+    let mut cache = cache(2, 10);
+    cache.insert("a", Version(1), Bytes::from("1"));
+    cache.insert("b", Version(1), Bytes::from("2"));
+    cache.get("a");
+    cache.insert("c", Version(1), Bytes::from("3"));
+    assert_eq!(cache.get("b"), None, "b was least recently used");
+    assert!(cache.get("a").is_some() && cache.get("c").is_some());
+
+    // A 9-byte value needs room: only the least recently used 1-byte entry goes.
+    cache.insert("big", Version(1), Bytes::from("123456789"));
+    assert_eq!((cache.len(), cache.bytes()), (2, 10));
+    assert_eq!(cache.get("a"), None);
+
+    // A value over the byte bound is never cached.
+    cache.insert("huge", Version(1), Bytes::from("12345678901"));
+    assert_eq!(cache.get("huge"), None);
+    assert!(cache.get("big").is_some());
+}
@@ -0,0 +1,68 @@
+use std::time::{Duration, Instant};
+use transdb_client::circuit_breaker::{Admission, CircuitBreaker, CircuitBreakerConfig, CircuitState};
+
+const COOL_DOWN: Duration = Duration::from_secs(10);
+
+fn breaker(epoch: Instant) -> CircuitBreaker {
+    CircuitBreaker::with_epoch(CircuitBreakerConfig { failure_threshold: 3, cool_down: COOL_DOWN }, epoch)
+}
+
+#[test]
+fn test_opens_after_threshold_consecutive_failures() {
+    let t0 = Instant::now();
+    let b = breaker(t0);
+
+    b.record_failure(t0);
+    b.record_failure(t0);
+    assert_eq!(b.state(t0), CircuitState::Closed);
+    assert_eq!(b.try_acquire(t0), Admission::Allowed);
+
+    // A success in between resets the streak.
+    b.record_success();
+    b.record_failure(t0);
+    b.record_failure(t0);
+    assert_eq!(b.state(t0), CircuitState::Closed);
+
+    b.record_failure(t0);
+    assert_eq!(b.state(t0), CircuitState::Open);
+    assert_eq!(b.try_acquire(t0 + COOL_DOWN - Duration::from_millis(1)), Admission::Rejected);
+}
+
+#[test]
+fn test_half_open_admits_one_probe_then_closes_or_reopens() {
+    let t0 = Instant::now();
+    let b = breaker(t0);
+    for _ in 0..3 {
+        b.record_failure(t0);
+    }
+
+    let t1 = t0 + COOL_DOWN;
+    assert_eq!(b.state(t1), CircuitState::HalfOpen);
+    assert_eq!(b.try_acquire(t1), Admission::Probe);
+    assert_eq!(b.try_acquire(t1), Admission::Rejected, "only one probe at a time");
+
+    // Failed probe: open again for a fresh cool-down measured from the failure.
+    b.record_failure(t1);
+    assert_eq!(b.state(t1 + COOL_DOWN - Duration::from_millis(1)), CircuitState::Open);
+
+    let t2 = t1 + COOL_DOWN;
+    assert_eq!(b.try_acquire(t2), Admission::Probe);
+    b.record_success();
+    assert_eq!(b.state(t2), CircuitState::Closed);
+    assert_eq!(b.try_acquire(t2), Admission::Allowed);
+}
+
+#[test]
+fn test_abandoned_probe_frees_the_slot() {
+    let t0 = Instant::now();
+    let b = breaker(t0);
+    for _ in 0..3 {
+        b.record_failure(t0);
+    }
+
+    let t1 = t0 + COOL_DOWN;
+    assert_eq!(b.try_acquire(t1), Admission::Probe);
+    b.abandon();
+    assert_eq!(b.state(t1), CircuitState::HalfOpen);
+    assert_eq!(b.try_acquire(t1), Admission::Probe);
+}
@@ -1,32 +1,56 @@
-use transdb_client::{Client, ClientConfig};
-use transdb_common::{Topology, TransDbError, MAX_KEY_SIZE, MAX_VALUE_SIZE};
+use bytes::Bytes;
+use futures_util::StreamExt;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+use transdb_client::cache::CacheConfig;
+use transdb_client::circuit_breaker::{CircuitBreakerConfig, CircuitState};
+use transdb_client::{
+    Client, ClientConfig, PartialGetResult, PutOutcome, RequestOptions, ServerInfo, ServerTiming,
+    DEFAULT_BULK_CONCURRENCY, DEFAULT_USER_AGENT, TARGET_ENV, TOPOLOGY_ENV,
+};
+use transdb_common::{
+    BulkTtlResult, Limits, MutexStatus, StoreStats, Topology, TransDbError, Version,
+    MAX_NAMESPACE_SIZE,
+};
 
 // Helper: build a ClientConfig aimed at the given mockito server URL (strips the http:// prefix).
 fn primary_config(server_url: &str) -> ClientConfig {
     let addr = server_url.trim_start_matches("http://").to_string();
-    ClientConfig { topology: Topology { primary_addr: addr, replica_addr: None } }
+    ClientConfig::new(Topology { primary_addr: addr, replica_addr: None, primary_id: None, replica_id: None })
 }
 
 // Helper: a client pointed at localhost:8080 for tests that never actually connect.
 fn localhost_client() -> Client {
-    Client::new(ClientConfig {
-        topology: Topology { primary_addr: "127.0.0.1:8080".to_string(), replica_addr: None },
-    })
+    Client::new(ClientConfig::new(Topology {
+        primary_addr: "127.0.0.1:8080".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    }))
 }
 
 #[test]
 fn test_client_config_custom() {
-    let config = ClientConfig {
-        topology: Topology { primary_addr: "localhost:9000".to_string(), replica_addr: None },
-    };
+    let config = ClientConfig::new(Topology {
+        primary_addr: "localhost:9000".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    });
     assert_eq!(config.topology.primary_addr, "localhost:9000");
 }
 
 #[test]
 fn test_client_creation_with_config() {
-    let config = ClientConfig {
-        topology: Topology { primary_addr: "example.com:3000".to_string(), replica_addr: None },
-    };
+    let config = ClientConfig::new(Topology {
+        primary_addr: "example.com:3000".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    });
     let client = Client::new(config);
     assert_eq!(client.config.topology.primary_addr, "example.com:3000");
 }
@@ -42,9 +66,12 @@ fn test_build_key_url() {
 
 #[test]
 fn test_build_key_url_with_custom_base() {
-    let config = ClientConfig {
-        topology: Topology { primary_addr: "localhost:9000".to_string(), replica_addr: None },
-    };
+    let config = ClientConfig::new(Topology {
+        primary_addr: "localhost:9000".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    });
     let client = Client::new(config);
     assert_eq!(
         client.build_key_url("my_key"),
@@ -68,27 +95,133 @@ fn test_build_key_url_special_characters() {
     assert_eq!(url, "http://127.0.0.1:8080/keys/key-with-dashes");
 }
 
+#[test]
+fn test_build_key_url_percent_encodes_unsafe_characters() {
+    let client = localhost_client();
+    let cases = [
+        ("a/b", "a%2Fb"),
+        ("key?version=1", "key%3Fversion%3D1"),
+        ("key#fragment", "key%23fragment"),
+        ("100%", "100%25"),
+        ("two words", "two%20words"),
+        ("キー", "%E3%82%AD%E3%83%BC"),
+        ("a-b.c_d~e", "a-b.c_d~e"),
+    ];
+    for (key, encoded) in cases {
+        assert_eq!(client.build_key_url(key), format!("http://127.0.0.1:8080/keys/{}", encoded));
+    }
+}
+
+#[test]
+fn test_namespace_prefixes_key_urls_and_is_validated() {
+    let topology = Topology {
+        primary_addr: "127.0.0.1:8080".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
+    let config = ClientConfig::new(topology.clone()).with_namespace("tenant-1").unwrap();
+    assert_eq!(config.namespace(), Some("tenant-1"));
+    let client = Client::new(config);
+    assert_eq!(client.build_key_url("a/b"), "http://127.0.0.1:8080/tenant-1/keys/a%2Fb");
+
+    let too_long = "n".repeat(MAX_NAMESPACE_SIZE + 1);
+    for namespace in ["", "a/b", "a b", "keys", "admin", too_long.as_str()] {
+        let result = ClientConfig::new(topology.clone()).with_namespace(namespace);
+        assert!(matches!(result, Err(TransDbError::InvalidConfig(_))), "{namespace:?} was accepted");
+    }
+}
+
 // --- set_target ---
 
 #[test]
 fn test_set_target_changes_url() {
-    let config = ClientConfig {
-        topology: Topology {
+    let config = ClientConfig::new(Topology {
             primary_addr: "127.0.0.1:3000".to_string(),
             replica_addr: Some("127.0.0.1:3001".to_string()),
-        },
-    };
+            primary_id: None,
+            replica_id: None,
+        });
     let mut client = Client::new(config);
     // Initially routes to primary
     assert_eq!(client.build_key_url("k"), "http://127.0.0.1:3000/keys/k");
 
     // After set_target, routes to replica
-    client.set_target("127.0.0.1:3001");
+    client.set_target("127.0.0.1:3001").unwrap();
     assert_eq!(client.build_key_url("k"), "http://127.0.0.1:3001/keys/k");
 
     // Resetting to primary restores the original URL
-    client.set_target("127.0.0.1:3000");
+    client.set_target("127.0.0.1:3000").unwrap();
     assert_eq!(client.build_key_url("k"), "http://127.0.0.1:3000/keys/k");
+
+    // Addresses outside the topology are rejected and leave the target unchanged
+    assert!(matches!(client.set_target("127.0.0.1:3002"), Err(TransDbError::InvalidConfig(_))));
+    assert_eq!(client.target(), "127.0.0.1:3000");
+}
+
+// --- from_topology_file / from_env ---
+
+const TOPOLOGY_JSON: &str = r#"{"primary_addr":"127.0.0.1:3000","replica_addr":"127.0.0.1:3001"}"#;
+
+fn topology_file(contents: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(contents.as_bytes()).unwrap();
+    file
+}
+
+#[test]
+fn test_from_topology_file() {
+    let file = topology_file(TOPOLOGY_JSON);
+    let client = Client::from_topology_file(file.path()).unwrap();
+    assert_eq!(client.target(), "127.0.0.1:3000");
+    assert_eq!(client.topology().replica_addr.as_deref(), Some("127.0.0.1:3001"));
+
+    let missing = file.path().with_extension("missing");
+    let err = Client::from_topology_file(&missing).err().unwrap();
+    assert!(matches!(&err, TransDbError::ConfigError(msg) if msg.contains("cannot read topology file")), "{err:?}");
+
+    let malformed = topology_file("{\"primary_addr\":");
+    let err = Client::from_topology_file(malformed.path()).err().unwrap();
+    assert!(matches!(&err, TransDbError::ConfigError(msg) if msg.contains("malformed topology JSON")), "{err:?}");
+
+    let invalid = topology_file(r#"{"primary_addr":"no-port"}"#);
+    let err = Client::from_topology_file(invalid.path()).err().unwrap();
+    assert!(matches!(err, TransDbError::InvalidTopology(_)), "{err:?}");
+}
+
+// The only test touching TRANSDB_TOPOLOGY / TRANSDB_TARGET, so mutating them cannot race another test.
+#[test]
+fn test_from_env() {
+    let file = topology_file(TOPOLOGY_JSON);
+    std::env::remove_var(TARGET_ENV);
+
+    std::env::remove_var(TOPOLOGY_ENV);
+    let err = Client::from_env().err().unwrap();
+    assert!(matches!(&err, TransDbError::ConfigError(msg) if msg.contains(TOPOLOGY_ENV)), "{err:?}");
+
+    // A path, defaulting to the primary.
+    std::env::set_var(TOPOLOGY_ENV, file.path());
+    assert_eq!(Client::from_env().unwrap().target(), "127.0.0.1:3000");
+
+    // Inline JSON, targeting the replica.
+    std::env::set_var(TOPOLOGY_ENV, TOPOLOGY_JSON);
+    std::env::set_var(TARGET_ENV, "replica");
+    assert_eq!(Client::from_env().unwrap().target(), "127.0.0.1:3001");
+
+    std::env::set_var(TARGET_ENV, "leader");
+    assert!(matches!(Client::from_env(), Err(TransDbError::ConfigError(_))));
+
+    // No replica to target.
+    std::env::set_var(TOPOLOGY_ENV, r#"{"primary_addr":"127.0.0.1:3000"}"#);
+    std::env::set_var(TARGET_ENV, "replica");
+    assert!(matches!(Client::from_env(), Err(TransDbError::ConfigError(_))));
+
+    std::env::set_var(TOPOLOGY_ENV, "{not json");
+    std::env::remove_var(TARGET_ENV);
+    let err = Client::from_env().err().unwrap();
+    assert!(matches!(&err, TransDbError::ConfigError(msg) if msg.contains("malformed topology JSON")), "{err:?}");
+
+    std::env::remove_var(TOPOLOGY_ENV);
 }
 
 #[tokio::test]
@@ -105,6 +238,22 @@ async fn test_get_returns_key_not_found_on_404() {
     assert!(matches!(client.get_allowing_expired("missing_key").await, Err(TransDbError::KeyNotFound(k)) if k == "missing_key"));
 }
 
+#[tokio::test]
+async fn test_get_returns_key_deleted_on_tombstone_404() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/gone")
+        .with_status(404)
+        .with_header("X-Deleted-Version", "12")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+
+    let expected = TransDbError::KeyDeleted { key: "gone".to_string(), version: 12 };
+    assert_eq!(client.get("gone").await, Err(expected.clone()));
+    assert_eq!(client.get_allowing_expired("gone").await, Err(expected));
+}
+
 #[tokio::test]
 async fn test_get_returns_bytes_on_200() {
     let mut server = mockito::Server::new_async().await;
@@ -118,7 +267,7 @@ async fn test_get_returns_bytes_on_200() {
     let client = Client::new(primary_config(&server.url()));
     let result = client.get("my_key").await;
 
-    assert_eq!(result.unwrap().value, b"hello");
+    assert_eq!(result.unwrap().value.as_ref(), b"hello");
 }
 
 #[tokio::test]
@@ -134,8 +283,8 @@ async fn test_get_returns_version_from_etag() {
     let client = Client::new(primary_config(&server.url()));
     let result = client.get("my_key").await.unwrap();
 
-    assert_eq!(result.version, 5);
-    assert_eq!(result.value, b"hello");
+    assert_eq!(result.version, Version(5));
+    assert_eq!(result.value.as_ref(), b"hello");
 }
 
 #[tokio::test]
@@ -180,7 +329,7 @@ async fn test_get_returns_empty_bytes_on_200() {
     let client = Client::new(primary_config(&server.url()));
     let result = client.get("empty_key").await;
 
-    assert_eq!(result.unwrap().value, b"");
+    assert_eq!(result.unwrap().value.as_ref(), b"");
 }
 
 #[tokio::test]
@@ -197,7 +346,7 @@ async fn test_get_returns_binary_data_on_200() {
     let client = Client::new(primary_config(&server.url()));
     let result = client.get("binary_key").await;
 
-    assert_eq!(result.unwrap().value, binary_data);
+    assert_eq!(result.unwrap().value.as_ref(), binary_data);
 }
 
 #[tokio::test]
@@ -255,7 +404,77 @@ async fn test_put_returns_version_from_etag() {
     let client = Client::new(primary_config(&server.url()));
     let version = client.put("my_key", b"hello").await.unwrap();
 
-    assert_eq!(version, 3);
+    assert_eq!(version, Version(3));
+}
+
+#[tokio::test]
+async fn test_put_detailed_reports_previous_version() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("PUT", "/keys/new")
+        .with_status(200)
+        .with_header("ETag", "\"5\"")
+        .create_async()
+        .await;
+    server.mock("PUT", "/keys/old")
+        .with_status(200)
+        .with_header("ETag", "\"6\"")
+        .with_header("X-Previous-Version", "4")
+        .with_header("X-Version-Diff", "1")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let created = PutOutcome { version: Version(5), previous: None, version_diff: 0, replayed: false };
+    assert_eq!(client.put_detailed("new", b"v").await, Ok(created));
+    let replaced = PutOutcome { version: Version(6), previous: Some(Version(4)), version_diff: 1, replayed: false };
+    assert_eq!(client.put_detailed("old", b"v").await, Ok(replaced));
+    assert_eq!(client.put("old", b"v").await, Ok(Version(6)));
+}
+
+#[tokio::test]
+async fn test_put_idempotent_reports_replays() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("PUT", "/keys/fresh")
+        .match_header("idempotency-key", "tok-1")
+        .with_status(200)
+        .with_header("ETag", "\"5\"")
+        .create_async()
+        .await;
+    server.mock("PUT", "/keys/again")
+        .match_header("idempotency-key", "tok-2")
+        .with_status(200)
+        .with_header("ETag", "\"3\"")
+        .with_header("X-Idempotent-Replay", "true")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let fresh = client.put_idempotent("fresh", b"v", "tok-1").await.unwrap();
+    assert_eq!((fresh.version, fresh.replayed), (Version(5), false));
+    let again = client.put_idempotent("again", b"v", "tok-2").await.unwrap();
+    assert_eq!((again.version, again.replayed), (Version(3), true));
+}
+
+#[tokio::test]
+async fn test_append_sends_patch_with_idempotency_key() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("PATCH", "/keys/log")
+        .match_header("idempotency-key", mockito::Matcher::Any)
+        .match_body("line\n")
+        .with_status(200)
+        .with_header("ETag", "\"7\"")
+        .create_async()
+        .await;
+    server.mock("PATCH", "/keys/full")
+        .with_status(400)
+        .with_body(r#"{"error":"too large","error_type":"VALUE_TOO_LARGE"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(client.append("log", b"line\n").await, Ok(Version(7)));
+    mock.assert_async().await;
+    assert_eq!(client.append("full", b"x").await, Err(TransDbError::ValueTooLarge(Limits::DEFAULT.max_value_size)));
 }
 
 #[tokio::test]
@@ -296,7 +515,7 @@ async fn test_delete_returns_some_version_on_200() {
         .await;
 
     let client = Client::new(primary_config(&server.url()));
-    assert_eq!(client.delete("my_key").await.unwrap(), Some(7));
+    assert_eq!(client.delete("my_key").await.unwrap(), Some(Version(7)));
 }
 
 #[tokio::test]
@@ -326,14 +545,29 @@ async fn test_delete_returns_http_error_on_503() {
 }
 
 #[tokio::test]
-async fn test_get_returns_network_error_when_server_unreachable() {
+async fn test_get_returns_connect_error_when_server_unreachable() {
     // Port 59210 is not bound to anything — connection will be refused immediately
-    let client = Client::new(ClientConfig {
-        topology: Topology { primary_addr: "127.0.0.1:59210".to_string(), replica_addr: None },
-    });
+    let client = Client::new(ClientConfig::new(Topology {
+        primary_addr: "127.0.0.1:59210".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    }));
     let result = client.get("any_key").await;
 
-    assert!(matches!(result, Err(TransDbError::NetworkError(_))));
+    assert!(matches!(result, Err(TransDbError::ConnectError(_))));
+    assert!(result.unwrap_err().is_retryable());
+}
+
+#[tokio::test]
+async fn test_redirect_loop_is_a_non_retryable_redirect_error() {
+    let mut server = mockito::Server::new_async().await;
+    let location = format!("{}/keys/loop", server.url());
+    server.mock("GET", "/keys/loop").with_status(302).with_header("Location", &location).create_async().await;
+
+    let result = Client::new(primary_config(&server.url())).get("loop").await;
+    assert!(matches!(result, Err(TransDbError::RedirectError(_))), "{:?}", result);
+    assert!(!result.unwrap_err().is_retryable());
 }
 
 // --- Pre-flight size validation ---
@@ -341,7 +575,7 @@ async fn test_get_returns_network_error_when_server_unreachable() {
 #[tokio::test]
 async fn test_get_rejects_oversized_key() {
     let client = localhost_client();
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     assert!(matches!(client.get(&key).await, Err(TransDbError::KeyTooLarge(_))));
     assert!(matches!(client.get_allowing_expired(&key).await, Err(TransDbError::KeyTooLarge(_))));
 }
@@ -349,27 +583,88 @@ async fn test_get_rejects_oversized_key() {
 #[tokio::test]
 async fn test_put_rejects_oversized_key() {
     let client = localhost_client();
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     let result = client.put(&key, b"hello").await;
     assert!(matches!(result, Err(TransDbError::KeyTooLarge(_))));
 }
 
+#[tokio::test]
+async fn test_empty_key_is_rejected_without_contacting_server() {
+    let client = localhost_client();
+    let expected = TransDbError::InvalidKey { key: String::new(), reason: "must not be empty".to_string() };
+    assert_eq!(client.get("").await.unwrap_err(), expected);
+    assert_eq!(client.get_allowing_expired("").await.unwrap_err(), expected);
+    assert_eq!(client.put("", b"v").await.unwrap_err(), expected);
+    assert_eq!(client.delete("").await.unwrap_err(), expected);
+}
+
 #[tokio::test]
 async fn test_put_rejects_oversized_value() {
     let client = localhost_client();
-    let value = vec![0u8; MAX_VALUE_SIZE + 1];
+    let value = vec![0u8; Limits::DEFAULT.max_value_size + 1];
     let result = client.put("my_key", &value).await;
     assert!(matches!(result, Err(TransDbError::ValueTooLarge(_))));
 }
 
+#[tokio::test]
+async fn test_configured_limits_are_checked_and_reported() {
+    let limits = Limits { max_key_size: 4, max_value_size: 8, max_idempotency_key_size: 16, max_batch_keys: 2 };
+    let config = ClientConfig::new(Topology {
+        primary_addr: "127.0.0.1:8080".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    });
+    let client = Client::new(config.with_limits(limits));
+
+    assert_eq!(client.get("abcde").await, Err(TransDbError::KeyTooLarge(4)));
+    assert_eq!(client.put("k", &[0; 9]).await, Err(TransDbError::ValueTooLarge(8)));
+    assert_eq!(client.append("k", &[0; 9]).await, Err(TransDbError::ValueTooLarge(8)));
+    let long = "i".repeat(17);
+    assert_eq!(client.delete_with_idempotency_key("k", &long).await, Err(TransDbError::IdempotencyKeyTooLarge(16)));
+}
+
 #[tokio::test]
 async fn test_delete_rejects_oversized_key() {
     let client = localhost_client();
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     let result = client.delete(&key).await;
     assert!(matches!(result, Err(TransDbError::KeyTooLarge(_))));
 }
 
+#[tokio::test]
+async fn test_strict_keys_rejects_unsafe_characters_without_contacting_server() {
+    // Nothing listens on this port, so a key that got past validation would fail with ConnectError.
+    let topology = Topology {
+        primary_addr: "127.0.0.1:59211".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
+    let strict = Client::new(ClientConfig::new(topology.clone()).with_strict_keys(true));
+    let cases = [
+        ("a/b", "contains '/'"),
+        ("a?b", "contains '?'"),
+        ("a#b", "contains '#'"),
+        ("a b", "contains whitespace ' '"),
+        ("a\tb", "contains whitespace '\\t'"),
+        ("a\u{1}b", "contains control character '\\u{1}'"),
+        ("a\u{7f}b", "contains control character '\\u{7f}'"),
+    ];
+    for (key, reason) in cases {
+        let expected = TransDbError::InvalidKey { key: key.to_string(), reason: reason.to_string() };
+        assert_eq!(strict.get(key).await.unwrap_err(), expected);
+        assert_eq!(strict.put(key, b"v").await.unwrap_err(), expected);
+        assert_eq!(strict.put_with_ttl(key, b"v", 60).await.unwrap_err(), expected);
+        assert_eq!(strict.delete(key).await.unwrap_err(), expected);
+    }
+    assert!(matches!(strict.get("a-b.c_d~e").await, Err(TransDbError::ConnectError(_))));
+
+    // The default percent-encodes instead.
+    let permissive = Client::new(ClientConfig::new(topology));
+    assert!(matches!(permissive.get("a/b").await, Err(TransDbError::ConnectError(_))));
+}
+
 #[tokio::test]
 async fn test_get_parses_400_as_http_error() {
     let mut server = mockito::Server::new_async().await;
@@ -386,6 +681,22 @@ async fn test_get_parses_400_as_http_error() {
     assert!(matches!(result, Err(TransDbError::HttpError(400, ref msg)) if msg == "Key exceeds maximum size of 1024 bytes"));
 }
 
+#[tokio::test]
+async fn test_http_error_display_carries_request_id() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/my_key")
+        .with_status(500)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error": "boom", "error_type": "INTERNAL", "request_id": "req-7"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let err = client.get("my_key").await.unwrap_err();
+
+    assert_eq!(err.to_string(), "HTTP 500: boom (request_id=req-7)");
+}
+
 // --- TTL: put_with_ttl ---
 
 #[tokio::test]
@@ -401,17 +712,125 @@ async fn test_put_with_ttl_sends_x_ttl_header() {
     let client = Client::new(primary_config(&server.url()));
     let version = client.put_with_ttl("my_key", b"hello", 9999).await.unwrap();
 
-    assert_eq!(version, 1);
+    assert_eq!(version, Version(1));
+}
+
+// --- Explicit idempotency keys ---
+
+#[tokio::test]
+async fn test_with_idempotency_key_methods_send_given_key() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("PUT", "/keys/k")
+        .match_header("idempotency-key", "put-token")
+        .with_status(200)
+        .with_header("ETag", "\"3\"")
+        .create_async()
+        .await;
+    server.mock("DELETE", "/keys/k")
+        .match_header("idempotency-key", "delete-token")
+        .with_status(200)
+        .with_header("ETag", "\"4\"")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(client.put_with_idempotency_key("k", b"v", "put-token").await, Ok(Version(3)));
+    assert_eq!(client.delete_with_idempotency_key("k", "delete-token").await, Ok(Some(Version(4))));
+}
+
+#[tokio::test]
+async fn test_oversized_idempotency_key_rejected_without_contacting_server() {
+    let client = localhost_client();
+    let long = "i".repeat(Limits::DEFAULT.max_idempotency_key_size + 1);
+    let expected = Err(TransDbError::IdempotencyKeyTooLarge(Limits::DEFAULT.max_idempotency_key_size));
+    assert_eq!(client.put_with_idempotency_key("k", b"v", &long).await, expected);
+    assert_eq!(client.delete_with_idempotency_key("k", &long).await.map(|_| Version(0)), expected);
+}
+
+#[tokio::test]
+async fn test_422_maps_to_idempotency_conflict() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("PUT", "/keys/typed")
+        .with_status(422)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"reused","error_type":"IDEMPOTENCY_MISMATCH"}"#)
+        .create_async()
+        .await;
+    // Older servers send no error_type; the status alone identifies the conflict.
+    server.mock("DELETE", "/keys/untyped")
+        .with_status(422)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(
+        client.put("typed", b"v").await,
+        Err(TransDbError::IdempotencyConflict { message: "reused".to_string() })
+    );
+    assert!(matches!(
+        client.delete("untyped").await,
+        Err(TransDbError::IdempotencyConflict { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_extend_ttl_many_sends_keys_and_parses_result() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("PATCH", "/batch/ttl")
+        .match_header("idempotency-key", mockito::Matcher::Any)
+        .match_body(mockito::Matcher::Json(serde_json::json!({"keys": ["a", "b"], "expires_at": 9999})))
+        .with_status(200)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"updated":["a"],"not_found":["b"]}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let result = client.extend_ttl_many(&["a", "b"], 9999).await.unwrap();
+
+    assert_eq!(result, BulkTtlResult { updated: vec!["a".to_string()], not_found: vec!["b".to_string()] });
+    let big_key = "k".repeat(Limits::DEFAULT.max_key_size + 1);
+    let too_large = Err(TransDbError::KeyTooLarge(Limits::DEFAULT.max_key_size));
+    assert_eq!(client.extend_ttl_many(&[&big_key], 1).await, too_large);
+}
+
+#[tokio::test]
+async fn test_multi_cas_sends_reads_and_writes_and_maps_conflict() {
+    let mut server = mockito::Server::new_async().await;
+    let expected = serde_json::json!({
+        "reads": [{"key": "a", "version": 3}, {"key": "b", "version": 0}],
+        "writes": [{"key": "b", "value_b64": "dg=="}],
+    });
+    server.mock("POST", "/multi-cas")
+        .match_body(mockito::Matcher::PartialJson(expected))
+        .with_status(200)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"versions":[7]}"#)
+        .create_async()
+        .await;
+    server.mock("POST", "/multi-cas")
+        .match_body(mockito::Matcher::PartialJson(serde_json::json!({"writes": [{"key": "c"}]})))
+        .with_status(409)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"changed","error_type":"VERSION_CONFLICT","details":{"mismatched":["a"]}}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let versions = client.multi_cas(&[("a", Version(3)), ("b", Version(0))], &[("b", b"v")]).await.unwrap();
+    assert_eq!(versions, [Version(7)]);
+    let conflict = client.multi_cas(&[("a", Version(3))], &[("c", b"v")]).await;
+    assert_eq!(conflict, Err(TransDbError::VersionConflict { keys: vec!["a".to_string()] }));
 }
 
 #[tokio::test]
 async fn test_put_with_ttl_rejects_oversized_inputs() {
     let client = localhost_client();
 
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     assert!(matches!(client.put_with_ttl(&key, b"hello", 9999).await, Err(TransDbError::KeyTooLarge(_))));
 
-    let value = vec![0u8; MAX_VALUE_SIZE + 1];
+    let value = vec![0u8; Limits::DEFAULT.max_value_size + 1];
     assert!(matches!(client.put_with_ttl("my_key", &value, 9999).await, Err(TransDbError::ValueTooLarge(_))));
 }
 
@@ -436,7 +855,29 @@ async fn test_get_expired_entry_behavior() {
     // Soft guarantee: expired entry is returned with expired=true
     let result = client.get_allowing_expired("my_key").await.unwrap();
     assert!(result.expired);
-    assert_eq!(result.value, b"stale");
+    assert_eq!(result.value.as_ref(), b"stale");
+}
+
+#[tokio::test]
+async fn test_get_parses_expires_at() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/ttl")
+        .with_status(200)
+        .with_header("ETag", "\"1\"")
+        .with_header("X-Expires-At", "1700000000")
+        .with_body(b"v")
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/plain")
+        .with_status(200)
+        .with_header("ETag", "\"2\"")
+        .with_body(b"v")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(client.get("ttl").await.unwrap().expires_at, Some(1_700_000_000));
+    assert_eq!(client.get("plain").await.unwrap().expires_at, None);
 }
 
 #[tokio::test]
@@ -453,12 +894,12 @@ async fn test_get_live_entry_behavior() {
 
     // Strong guarantee: live entry is returned normally
     let result = client.get("my_key").await.unwrap();
-    assert_eq!(result.value, b"fresh");
+    assert_eq!(result.value.as_ref(), b"fresh");
     assert!(!result.expired);
 
     // Soft guarantee: live entry also has expired=false
     let result = client.get_allowing_expired("my_key").await.unwrap();
-    assert_eq!(result.value, b"fresh");
+    assert_eq!(result.value.as_ref(), b"fresh");
     assert!(!result.expired);
 }
 
@@ -478,3 +919,1008 @@ async fn test_replica_405_surfaced_as_http_error() {
 
     assert!(matches!(client.get("k").await, Err(TransDbError::HttpError(405, _))));
 }
+
+// --- error_type mapping ---
+
+#[tokio::test]
+async fn test_error_type_maps_to_typed_errors() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/big")
+        .with_status(400)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"too big","error_type":"KEY_TOO_LARGE"}"#)
+        .create_async()
+        .await;
+    server.mock("PUT", "/keys/k")
+        .with_status(400)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"too big","error_type":"VALUE_TOO_LARGE"}"#)
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/gone")
+        .with_status(404)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"Key not found: gone","error_type":"KEY_NOT_FOUND"}"#)
+        .create_async()
+        .await;
+    server.mock("DELETE", "/keys/k")
+        .with_status(503)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"Server error: Lock acquisition timed out","error_type":"LOCK_TIMEOUT"}"#)
+        .create_async()
+        .await;
+    server.mock("PUT", "/keys/frozen")
+        .with_status(503)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"Server is in read-only mode","error_type":"READ_ONLY"}"#)
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/busy")
+        .with_status(429)
+        .with_header("Retry-After", "7")
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/swamped")
+        .with_status(503)
+        .with_header("Content-Type", "application/json")
+        .with_header("Retry-After", "1")
+        .with_body(r#"{"error":"Server is overloaded, retry later","error_type":"OVERLOADED"}"#)
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/busier")
+        .with_status(429)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+
+    assert_eq!(client.get("big").await, Err(TransDbError::KeyTooLarge(Limits::DEFAULT.max_key_size)));
+    assert_eq!(client.put("k", b"v").await, Err(TransDbError::ValueTooLarge(Limits::DEFAULT.max_value_size)));
+    assert_eq!(client.get("gone").await, Err(TransDbError::KeyNotFound("gone".to_string())));
+    assert_eq!(
+        client.put("frozen", b"v").await,
+        Err(TransDbError::ReadOnly("Server is in read-only mode".to_string()))
+    );
+    assert_eq!(client.get("busy").await, Err(TransDbError::RateLimited { retry_after_secs: Some(7) }));
+    assert_eq!(client.get("busier").await, Err(TransDbError::RateLimited { retry_after_secs: None }));
+    assert_eq!(client.get("swamped").await, Err(TransDbError::Overloaded { retry_after_secs: Some(1) }));
+    // Types without a dedicated variant keep the message in HttpError.
+    assert!(matches!(
+        client.delete("k").await,
+        Err(TransDbError::HttpError(503, ref msg)) if msg == "Server error: Lock acquisition timed out"
+    ));
+}
+
+// --- Per-call deadline ---
+
+#[tokio::test]
+async fn test_deadline_times_out_slow_call_and_is_sent_to_server() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/slow")
+        .with_status(200)
+        .with_header("ETag", "\"1\"")
+        .with_chunked_body(|w| {
+            std::thread::sleep(Duration::from_millis(300));
+            w.write_all(b"late")
+        })
+        .expect(2)
+        .create_async()
+        .await;
+    server.mock("PUT", "/keys/k")
+        .match_header("x-request-timeout-ms", "250")
+        .with_status(200)
+        .with_header("ETag", "\"2\"")
+        .create_async()
+        .await;
+    server.mock("DELETE", "/keys/k")
+        .match_header("x-request-timeout-ms", "250")
+        .with_status(204)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let short = RequestOptions { deadline: Some(Duration::from_millis(50)) };
+    let relaxed = RequestOptions { deadline: Some(Duration::from_millis(250)) };
+
+    assert_eq!(client.get_ext("slow", &short).await, Err(TransDbError::Timeout(Duration::from_millis(50))));
+    // Without a deadline the same call waits for the body.
+    assert_eq!(client.get("slow").await.unwrap().value.as_ref(), b"late");
+
+    assert_eq!(client.put_ext("k", b"v", &relaxed).await, Ok(Version(2)));
+    assert_eq!(client.delete_ext("k", &relaxed).await, Ok(None));
+}
+
+// --- Circuit breaker ---
+
+#[tokio::test]
+async fn test_circuit_breaker_fails_fast_then_recovers_via_probe() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/slow")
+        .with_status(200)
+        .with_header("ETag", "\"1\"")
+        .with_chunked_body(|w| {
+            std::thread::sleep(Duration::from_millis(200));
+            w.write_all(b"late")
+        })
+        .create_async()
+        .await;
+    let fast = server.mock("GET", "/keys/fast")
+        .with_status(200)
+        .with_header("ETag", "\"2\"")
+        .with_body("ok")
+        .expect(1)
+        .create_async()
+        .await;
+
+    let cool_down = Duration::from_millis(100);
+    let config = primary_config(&server.url())
+        .with_circuit_breaker(CircuitBreakerConfig { failure_threshold: 2, cool_down });
+    let client = Client::new(config);
+    let short = RequestOptions { deadline: Some(Duration::from_millis(20)) };
+    assert_eq!(client.circuit_state(), Some(CircuitState::Closed));
+
+    for _ in 0..2 {
+        assert!(matches!(client.get_ext("slow", &short).await, Err(TransDbError::Timeout(_))));
+    }
+    assert_eq!(client.circuit_state(), Some(CircuitState::Open));
+
+    // Open: fails fast without reaching the server (the fast mock expects only the probe).
+    assert!(matches!(client.get("fast").await, Err(TransDbError::CircuitOpen { .. })));
+
+    tokio::time::sleep(cool_down).await;
+    assert_eq!(client.circuit_state(), Some(CircuitState::HalfOpen));
+    assert_eq!(client.get("fast").await.unwrap().value.as_ref(), b"ok");
+    assert_eq!(client.circuit_state(), Some(CircuitState::Closed));
+    fast.assert_async().await;
+}
+
+// --- key_count ---
+
+#[tokio::test]
+async fn test_key_count_stats_and_507() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("HEAD", "/keys")
+        .with_status(200)
+        .with_header("X-Key-Count", "42")
+        .create_async()
+        .await;
+    server.mock("GET", "/stats")
+        .with_status(200)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"live_count":42,"expired_count":3,"tombstone_count":1}"#)
+        .create_async()
+        .await;
+    server.mock("PUT", "/keys/k")
+        .with_status(507)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"full","error_type":"STORAGE_FULL"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(client.key_count().await, Ok(42));
+    let expected = StoreStats { live_count: 42, expired_count: 3, tombstone_count: 1, ..Default::default() };
+    assert_eq!(client.stats().await, Ok(expected));
+    assert_eq!(client.put("k", b"v").await, Err(TransDbError::StorageFull("full".to_string())));
+}
+
+// --- server_info ---
+
+#[tokio::test]
+async fn test_server_info_parses_version_body() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/version")
+        .with_status(200)
+        .with_header("Content-Type", "application/json")
+        .with_body(concat!(
+            r#"{"server_version":"0.1.0","protocol_version":"1","role":"replica","#,
+            r#""git_hash":"abc1234","build_date":"2025-01-01"}"#,
+        ))
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let expected = ServerInfo {
+        server_version: "0.1.0".to_string(),
+        protocol_version: "1".to_string(),
+        role: "replica".to_string(),
+        git_hash: "abc1234".to_string(),
+        build_date: "2025-01-01".to_string(),
+    };
+    assert_eq!(client.server_info().await, Ok(expected));
+}
+
+// --- get_eventual ---
+
+#[tokio::test]
+async fn test_get_eventual_sends_consistency_header_and_bypasses_cache() {
+    let mut server = mockito::Server::new_async().await;
+    let strong = server.mock("GET", "/keys/k")
+        .match_header("x-consistency", mockito::Matcher::Missing)
+        .with_status(200)
+        .with_header("ETag", "\"5\"")
+        .with_body("fresh")
+        .expect(1)
+        .create_async()
+        .await;
+    let eventual = server.mock("GET", "/keys/k")
+        .match_header("x-consistency", "eventual")
+        .match_header("if-none-match", mockito::Matcher::Missing)
+        .with_status(200)
+        .with_header("ETag", "\"3\"")
+        .with_header("X-Sequence", "4")
+        .with_body("stale")
+        .expect(2)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()).with_cache(CacheConfig { max_entries: 8, max_bytes: 1024 }));
+    assert_eq!(client.get("k").await.unwrap().value.as_ref(), b"fresh");
+    let result = client.get_eventual("k").await.unwrap();
+    assert_eq!((result.value.as_ref(), result.version, result.sequence), (&b"stale"[..], Version(3), 4));
+    client.get_eventual("k").await.unwrap();
+    strong.assert_async().await;
+    eventual.assert_async().await;
+}
+
+// --- JSON values ---
+
+#[tokio::test]
+async fn test_put_json_sends_json_content_type() {
+    let mut server = mockito::Server::new_async().await;
+    let plain = server.mock("PUT", "/keys/doc")
+        .match_header("content-type", "application/json")
+        .match_header("x-ttl", mockito::Matcher::Missing)
+        .match_body(r#"{"a":[1,2]}"#)
+        .with_status(200)
+        .with_header("ETag", "\"4\"")
+        .create_async()
+        .await;
+    let with_ttl = server.mock("PUT", "/keys/doc")
+        .match_header("content-type", "application/json")
+        .match_header("x-ttl", "2000000000")
+        .with_status(200)
+        .with_header("ETag", "\"5\"")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let value = serde_json::json!({"a": [1, 2]});
+    assert_eq!(client.put_json("doc", &value).await, Ok(Version(4)));
+    assert_eq!(client.put_json_with_ttl("doc", &value, 2_000_000_000).await, Ok(Version(5)));
+    plain.assert_async().await;
+    with_ttl.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put_json_rejects_unserializable_value_without_sending() {
+    let client = localhost_client();
+    // JSON object keys must be strings.
+    let value = std::collections::HashMap::from([((1, 2), "v")]);
+    assert!(matches!(client.put_json("doc", &value).await, Err(TransDbError::SerializationError(_))));
+}
+
+#[tokio::test]
+async fn test_get_json_decodes_value_or_reports_deserialization_error() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/doc")
+        .with_status(200)
+        .with_header("ETag", "\"1\"")
+        .with_body("[1,2,3]")
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/text")
+        .with_status(200)
+        .with_header("ETag", "\"2\"")
+        .with_body("hello")
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/old")
+        .with_status(200)
+        .with_header("ETag", "\"3\"")
+        .with_header("X-Expired", "true")
+        .with_body("7")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(client.get_json::<Vec<u32>>("doc").await, Ok(vec![1, 2, 3]));
+    assert!(matches!(client.get_json::<Vec<u32>>("text").await, Err(TransDbError::DeserializationError(_))));
+    assert!(matches!(client.get_json::<u32>("old").await, Err(TransDbError::KeyNotFound(_))));
+    assert_eq!(client.get_json_allowing_expired::<u32>("old").await, Ok(7));
+}
+
+#[tokio::test]
+async fn test_put_json_if_absent_sends_multi_cas_read_at_version_zero() {
+    let mut server = mockito::Server::new_async().await;
+    let expected = serde_json::json!({
+        "reads": [{"key": "doc", "version": 0}],
+        "writes": [{"key": "doc", "value_b64": "dHJ1ZQ=="}],
+    });
+    let mock = server.mock("POST", "/multi-cas")
+        .match_body(mockito::Matcher::PartialJson(expected))
+        .with_status(200)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"versions":[9]}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(client.put_json_if_absent("doc", &true).await, Ok(Version(9)));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put_json_if_absent_rejects_a_multi_cas_reply_without_versions() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("POST", "/multi-cas")
+        .with_status(200)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"versions":[]}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    match client.put_json_if_absent("doc", &true).await {
+        Err(TransDbError::DeserializationError(msg)) => assert!(msg.contains("multi-cas"), "{msg}"),
+        other => panic!("expected DeserializationError, got {other:?}"),
+    }
+}
+
+// --- list_keys ---
+
+#[tokio::test]
+async fn test_list_keys_sends_prefix_and_parses_key_list() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("GET", "/keys")
+        .match_query(mockito::Matcher::UrlEncoded("prefix".into(), "user/ a".into()))
+        .with_status(200)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"keys":["user/ a1","user/ a2"]}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(client.list_keys(Some("user/ a")).await, Ok(vec!["user/ a1".to_string(), "user/ a2".to_string()]));
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_list_keys_streaming_parses_ndjson_lines() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys")
+        .match_header("accept", "application/x-ndjson")
+        .with_status(200)
+        .with_header("Content-Type", "application/x-ndjson")
+        .with_chunked_body(|w| {
+            w.write_all(b"\"a\"\n\"b\\n")?;
+            w.flush()?;
+            w.write_all(b"c\"\n\n\"d\"")
+        })
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let keys: Vec<_> = client.list_keys_streaming(None).collect().await;
+    assert_eq!(keys, vec![Ok("a".to_string()), Ok("b\nc".to_string()), Ok("d".to_string())]);
+}
+
+#[tokio::test]
+async fn test_list_keys_streaming_stops_at_first_error() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys")
+        .match_query(mockito::Matcher::UrlEncoded("prefix".into(), "bad".into()))
+        .with_status(200)
+        .with_body("\"ok\"\nnot json\n\"never\"\n")
+        .create_async()
+        .await;
+    server.mock("GET", "/keys")
+        .match_query(mockito::Matcher::UrlEncoded("prefix".into(), "replica".into()))
+        .with_status(405)
+        .with_header("Content-Type", "application/json")
+        .with_body(r#"{"error":"Replica does not accept key operations","error_type":"NOT_PRIMARY"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let keys: Vec<_> = client.list_keys_streaming(Some("bad")).collect().await;
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0], Ok("ok".to_string()));
+    assert!(matches!(&keys[1], Err(TransDbError::NetworkError(msg)) if msg.starts_with("Invalid NDJSON line")));
+
+    let keys: Vec<_> = client.list_keys_streaming(Some("replica")).collect().await;
+    assert_eq!(keys.len(), 1);
+    assert!(matches!(&keys[0], Err(TransDbError::HttpError(405, _))), "{keys:?}");
+}
+
+// --- Default headers and User-Agent ---
+
+#[tokio::test]
+async fn test_default_headers_and_user_agent_sent_on_every_request() {
+    let mut server = mockito::Server::new_async().await;
+    let mut mocks = Vec::new();
+    for method in ["GET", "PUT", "DELETE"] {
+        mocks.push(
+            server.mock(method, "/keys/k")
+                .match_header("x-service-name", "billing")
+                .match_header("user-agent", "billing-worker/2")
+                .with_status(200)
+                .with_header("ETag", "\"1\"")
+                .create_async()
+                .await,
+        );
+    }
+
+    let config = primary_config(&server.url())
+        .header("X-Service-Name", "billing")
+        .unwrap()
+        .user_agent("billing-worker/2")
+        .unwrap();
+    let client = Client::new(config);
+    client.get("k").await.unwrap();
+    client.put("k", b"v").await.unwrap();
+    client.delete("k").await.unwrap();
+    for mock in mocks {
+        mock.assert_async().await;
+    }
+}
+
+#[tokio::test]
+async fn test_default_user_agent_names_crate_version() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("GET", "/keys/k")
+        .match_header("user-agent", DEFAULT_USER_AGENT)
+        .with_status(200)
+        .with_header("ETag", "\"1\"")
+        .create_async()
+        .await;
+
+    Client::new(primary_config(&server.url())).get("k").await.unwrap();
+    mock.assert_async().await;
+    assert_eq!(DEFAULT_USER_AGENT, concat!("transdb-client/", env!("CARGO_PKG_VERSION")));
+}
+
+// --- Server-Timing ---
+
+#[tokio::test]
+async fn test_server_timing_sink_receives_parsed_timings() {
+    let mut server = mockito::Server::new_async().await;
+    let _put = server.mock("PUT", "/keys/k")
+        .with_status(200)
+        .with_header("ETag", "\"1\"")
+        .with_header("Server-Timing", "lock;dur=1.500, work;dur=0.250")
+        .create_async()
+        .await;
+    let _delete = server.mock("DELETE", "/keys/k")
+        .with_status(200)
+        .with_header("ETag", "\"2\"")
+        .with_header("Server-Timing", "lock;dur=2.000")
+        .create_async()
+        .await;
+    let _get = server.mock("GET", "/keys/k").with_status(404).create_async().await;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let client = Client::new(primary_config(&server.url()).with_server_timing_sink(tx));
+    client.put("k", b"v").await.unwrap();
+    client.delete("k").await.unwrap();
+    let _ = client.get("k").await;
+
+    // The GET carried no Server-Timing, so nothing was sent for it.
+    let timings: Vec<ServerTiming> = rx.try_iter().collect();
+    assert_eq!(
+        timings,
+        vec![
+            ServerTiming { lock: Duration::from_micros(1500), work: Some(Duration::from_micros(250)) },
+            ServerTiming { lock: Duration::from_millis(2), work: None },
+        ]
+    );
+    assert_eq!(ServerTiming::parse("work;dur=1"), None);
+    assert_eq!(ServerTiming::parse("lock;dur=-1"), None);
+}
+
+#[test]
+fn test_reserved_default_headers_are_rejected() {
+    for name in ["Idempotency-Key", "If-Match", "x-ttl", "User-Agent"] {
+        let result = primary_config("127.0.0.1:8080").header(name, "x");
+        assert!(matches!(result, Err(TransDbError::InvalidConfig(_))), "{name} must be rejected");
+    }
+    assert!(matches!(
+        primary_config("127.0.0.1:8080").header("bad header", "x"),
+        Err(TransDbError::InvalidConfig(_))
+    ));
+    let config = primary_config("127.0.0.1:8080").header("X-Service-Name", "billing").unwrap();
+    assert_eq!(config.default_headers()["x-service-name"], "billing");
+}
+
+// --- raw_request ---
+
+#[tokio::test]
+async fn test_raw_request_sends_custom_headers_and_returns_full_response() {
+    let mut server = mockito::Server::new_async().await;
+    let put = server.mock("PUT", "/keys/k")
+        .match_header("idempotency-key", "raw-tok")
+        .match_header("x-custom", "yes")
+        .match_body("v")
+        .with_status(200)
+        .with_header("ETag", "\"9\"")
+        .create_async()
+        .await;
+    server.mock("POST", "/keys/k")
+        .with_status(405)
+        .with_header("Allow", "GET,PUT,DELETE")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let mut headers = HeaderMap::new();
+    headers.insert("Idempotency-Key", "raw-tok".parse().unwrap());
+    headers.insert("X-Custom", "yes".parse().unwrap());
+    let response = client.raw_request(Method::PUT, "k", headers, Some(Bytes::from("v"))).await.unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers["etag"], "\"9\"");
+    put.assert_async().await;
+
+    // Non-2xx statuses are returned as responses, not mapped to errors.
+    let response = client.raw_request(Method::POST, "k", HeaderMap::new(), None).await.unwrap();
+    assert_eq!(response.status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers["allow"], "GET,PUT,DELETE");
+}
+
+// --- Topology refresh ---
+
+fn addr(server: &mockito::Server) -> String {
+    server.url().trim_start_matches("http://").to_string()
+}
+
+fn topology_body(primary: &str, replica: Option<&str>) -> String {
+    serde_json::to_string(&Topology {
+        primary_addr: primary.to_string(),
+        replica_addr: replica.map(str::to_string),
+        primary_id: None,
+        replica_id: None,
+    })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_refresh_topology_adds_replica_and_follows_moved_primary() {
+    let mut old_primary = mockito::Server::new_async().await;
+    let mut new_node = mockito::Server::new_async().await;
+    let (old_addr, new_addr) = (addr(&old_primary), addr(&new_node));
+
+    // The replica moves to `new_node`; only then may the client target it.
+    old_primary.mock("GET", "/topology")
+        .with_status(200)
+        .with_body(topology_body(&old_addr, Some(&new_addr)))
+        .create_async()
+        .await;
+    let replica_read = new_node.mock("GET", "/keys/k")
+        .with_status(200)
+        .with_header("ETag", "\"1\"")
+        .with_body("from-replica")
+        .expect(2)
+        .create_async()
+        .await;
+
+    let mut client = Client::new(primary_config(&old_primary.url()));
+    assert!(matches!(client.set_target(&new_addr), Err(TransDbError::InvalidConfig(_))));
+    let refreshed = client.refresh_topology().await.unwrap();
+    assert_eq!(refreshed.replica_addr.as_deref(), Some(new_addr.as_str()));
+    assert_eq!(client.target(), old_addr, "a primary-targeting client stays on the unchanged primary");
+    client.set_target(&new_addr).unwrap();
+    assert_eq!(client.get("k").await.unwrap().value.as_ref(), b"from-replica");
+
+    // `new_node` is promoted: a client targeting the primary follows it.
+    let client = Client::new(primary_config(&old_primary.url()));
+    old_primary.reset();
+    old_primary.mock("GET", "/topology")
+        .with_status(200)
+        .with_body(topology_body(&new_addr, None))
+        .create_async()
+        .await;
+    client.refresh_topology().await.unwrap();
+    assert_eq!(client.target(), new_addr);
+    assert_eq!(client.get("k").await.unwrap().value.as_ref(), b"from-replica");
+    assert_eq!(client.config.topology.primary_addr, old_addr, "the seed topology is left untouched");
+    replica_read.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_background_topology_refresh() {
+    let mut server = mockito::Server::new_async().await;
+    let own = addr(&server);
+    server.mock("GET", "/topology")
+        .with_status(200)
+        .with_body(topology_body(&own, Some("127.0.0.1:9")))
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()).with_topology_refresh(Duration::from_millis(10)));
+    assert_eq!(client.topology().replica_addr, None);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(client.topology().replica_addr.as_deref(), Some("127.0.0.1:9"));
+}
+
+#[tokio::test]
+async fn test_refresh_topology_maps_missing_topology_to_http_error() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/topology")
+        .with_status(404)
+        .with_body(r#"{"error":"Node was started without a topology","error_type":"TOPOLOGY_UNKNOWN"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert!(matches!(client.refresh_topology().await, Err(TransDbError::HttpError(404, _))));
+    assert_eq!(client.topology(), primary_config(&server.url()).topology);
+}
+
+// --- Request signing ---
+
+#[tokio::test]
+async fn test_signing_key_adds_signature_headers_and_maps_401() {
+    let mut server = mockito::Server::new_async().await;
+    let put = server.mock("PUT", "/keys/k")
+        .match_header("x-timestamp", mockito::Matcher::Regex(r"^\d+$".to_string()))
+        .match_header("x-signature", mockito::Matcher::Regex(r"^HMAC-SHA256:[A-Za-z0-9+/]+=*$".to_string()))
+        .with_status(200)
+        .with_header("ETag", "\"1\"")
+        .create_async()
+        .await;
+    server.mock("DELETE", "/keys/k")
+        .with_status(401)
+        .with_body(r#"{"error":"X-Signature does not match the request","error_type":"INVALID_SIGNATURE"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url())).with_signing_key("secret");
+    assert_eq!(client.put("k", b"v").await, Ok(Version(1)));
+    put.assert_async().await;
+    assert_eq!(
+        client.delete("k").await,
+        Err(TransDbError::Unauthorized("X-Signature does not match the request".to_string()))
+    );
+}
+
+// --- Conditional DELETE ---
+
+#[tokio::test]
+async fn test_delete_if_match_sends_etag_and_maps_412() {
+    let mut server = mockito::Server::new_async().await;
+    let matched = server.mock("DELETE", "/keys/k")
+        .match_header("if-match", "\"4\"")
+        .with_status(200)
+        .with_header("ETag", "\"4\"")
+        .create_async()
+        .await;
+    server.mock("DELETE", "/keys/k")
+        .match_header("if-match", "\"3\"")
+        .with_status(412)
+        .with_header("ETag", "\"5\"")
+        .with_body(r#"{"error":"If-Match 3 but key is at version 5","error_type":"PRECONDITION_FAILED"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    assert_eq!(client.delete_if_match("k", Version(4)).await, Ok(Version(4)));
+    let expected = TransDbError::PreconditionFailed { key: "k".to_string(), current_version: Some(Version(5)) };
+    assert_eq!(client.delete_if_match("k", Version(3)).await, Err(expected));
+    matched.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_mutex_methods_send_owner_and_map_412() {
+    use mockito::Matcher;
+    let mut server = mockito::Server::new_async().await;
+    let query = |owner: &str, ttl: &str| {
+        Matcher::AllOf(vec![
+            Matcher::UrlEncoded("owner".into(), owner.into()),
+            Matcher::UrlEncoded("ttl_secs".into(), ttl.into()),
+        ])
+    };
+    server.mock("POST", "/mutex/jobs/acquire")
+        .match_query(query("a", "30"))
+        .with_status(200)
+        .with_body(r#"{"acquired":true,"owner":"a","ttl_remaining":30}"#)
+        .create_async()
+        .await;
+    server.mock("POST", "/mutex/jobs/acquire")
+        .match_query(query("b", "30"))
+        .with_status(412)
+        .with_body(r#"{"acquired":false,"owner":"a","ttl_remaining":12}"#)
+        .create_async()
+        .await;
+    server.mock("POST", "/mutex/jobs/renew")
+        .match_query(query("a", "60"))
+        .with_status(200)
+        .with_body(r#"{"acquired":true,"owner":"a","ttl_remaining":60}"#)
+        .create_async()
+        .await;
+    server.mock("DELETE", "/mutex/jobs")
+        .match_query(Matcher::UrlEncoded("owner".into(), "a".into()))
+        .with_status(204)
+        .create_async()
+        .await;
+    server.mock("DELETE", "/mutex/jobs")
+        .match_query(Matcher::UrlEncoded("owner".into(), "b".into()))
+        .with_status(412)
+        .with_body(r#"{"acquired":false,"ttl_remaining":0}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let held = |owner: &str, ttl| MutexStatus { acquired: true, owner: Some(owner.to_string()), ttl_remaining: ttl };
+    assert_eq!(client.acquire_mutex("jobs", "a", 30).await, Ok(held("a", 30)));
+    assert_eq!(
+        client.acquire_mutex("jobs", "b", 30).await,
+        Ok(MutexStatus { acquired: false, owner: Some("a".to_string()), ttl_remaining: 12 })
+    );
+    assert_eq!(client.renew_mutex("jobs", "a", 60).await, Ok(held("a", 60)));
+    assert_eq!(client.release_mutex("jobs", "a").await, Ok(true));
+    assert_eq!(client.release_mutex("jobs", "b").await, Ok(false));
+
+    let long = "m".repeat(Limits::DEFAULT.max_key_size);
+    let too_large = Err(TransDbError::KeyTooLarge(Limits::DEFAULT.max_key_size));
+    assert_eq!(client.acquire_mutex(&long, "a", 30).await, too_large);
+}
+
+#[tokio::test]
+async fn test_delete_many_reports_per_key_results() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("DELETE", "/keys/a").with_status(200).with_header("ETag", "\"7\"").create_async().await;
+    server.mock("DELETE", "/keys/b").with_status(204).create_async().await;
+    server.mock("DELETE", "/keys/c")
+        .with_status(503)
+        .with_body(r#"{"error":"Server error: Lock acquisition timed out","error_type":"LOCK_TIMEOUT"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()).with_bulk_concurrency(2));
+    let results = client.delete_many(&["a", "b", "c"]).await.unwrap();
+    let keys: Vec<&str> = results.iter().map(|(key, _)| key.as_str()).collect();
+    assert_eq!(keys, ["a", "b", "c"]);
+    assert_eq!(results[0].1, Ok(Some(Version(7))));
+    assert_eq!(results[1].1, Ok(None));
+    assert!(matches!(results[2].1, Err(TransDbError::HttpError(503, _))));
+
+    // One oversized key rejects the whole call before any request is sent.
+    let long = "k".repeat(Limits::DEFAULT.max_key_size + 1);
+    let untouched = server.mock("DELETE", "/keys/d").expect(0).create_async().await;
+    assert_eq!(client.delete_many(&["d", &long]).await, Err(TransDbError::KeyTooLarge(Limits::DEFAULT.max_key_size)));
+    untouched.assert_async().await;
+
+    let default = ClientConfig::new(Topology {
+        primary_addr: "h:1".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    });
+    assert_eq!(default.bulk_concurrency, DEFAULT_BULK_CONCURRENCY);
+}
+
+#[tokio::test]
+async fn test_put_stream_puts_every_item_and_stops_on_fatal() {
+    let mut server = mockito::Server::new_async().await;
+    let mut mocks = Vec::new();
+    for i in 0..20 {
+        let mock = server.mock("PUT", format!("/keys/k{}", i).as_str())
+            .match_body(format!("v{}", i).as_str())
+            .with_status(200)
+            .with_header("ETag", &format!("\"{}\"", i + 1))
+            .expect(1)
+            .create_async()
+            .await;
+        mocks.push(mock);
+    }
+    let client = Client::new(primary_config(&server.url()));
+    let items = (0..20).map(|i| (format!("k{}", i), Bytes::from(format!("v{}", i))));
+
+    let mut results = client.put_stream(items, 4, true).await;
+    results.sort_by_key(|(_, result)| result.clone().unwrap());
+    let expected: Vec<(String, Result<Version, TransDbError>)> =
+        (0..20).map(|i| (format!("k{}", i), Ok(Version(i + 1)))).collect();
+    assert_eq!(results, expected);
+    for mock in mocks {
+        mock.assert_async().await;
+    }
+
+    // With concurrency 1 the order is the input order, so the fatal error stops the load there.
+    server.mock("PUT", "/keys/full")
+        .with_status(507)
+        .with_body(r#"{"error":"Store is full","error_type":"STORAGE_FULL"}"#)
+        .create_async()
+        .await;
+    let never_sent = server.mock("PUT", "/keys/after").expect(0).create_async().await;
+    let items = ["full", "after"].map(|key| (key.to_string(), Bytes::from("v")));
+    let results = client.put_stream(items.clone(), 1, true).await;
+    assert_eq!(results.len(), 1);
+    assert!(matches!(results[0].1, Err(TransDbError::StorageFull(_))));
+    never_sent.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_put_stream_never_exceeds_concurrency() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // mockito answers on a single thread, so count overlapping requests with a bare listener.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let peak = Arc::new(AtomicUsize::new(0));
+    let served = Arc::new(AtomicUsize::new(0));
+    let counters = (Arc::new(AtomicUsize::new(0)), peak.clone(), served.clone());
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let (in_flight, peak, served) = (counters.0.clone(), counters.1.clone(), counters.2.clone());
+            tokio::spawn(async move {
+                // Read the head, then the body announced by Content-Length.
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                let head_end = loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                };
+                let head = String::from_utf8_lossy(&request[..head_end]).to_lowercase();
+                let length: usize = head
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .map_or(0, |v| v.trim().parse().unwrap());
+                while request.len() < head_end + length {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                served.fetch_add(1, Ordering::SeqCst);
+                let response = "HTTP/1.1 200 OK\r\nETag: \"1\"\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+        }
+    });
+
+    let client = Client::new(primary_config(&format!("http://{}", addr)));
+    let items = (0..30).map(|i| (format!("k{}", i), Bytes::from("v")));
+    let results = client.put_stream(items, 5, false).await;
+
+    assert_eq!(results.len(), 30);
+    assert!(results.iter().all(|(_, result)| *result == Ok(Version(1))));
+    assert_eq!(served.load(Ordering::SeqCst), 30);
+    let peak = peak.load(Ordering::SeqCst);
+    assert!((2..=5).contains(&peak), "peak concurrency {}", peak);
+}
+
+#[tokio::test]
+async fn test_get_range_parses_partial_and_unsatisfiable_responses() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/blob")
+        .match_header("range", "bytes=0-3")
+        .with_status(206)
+        .with_header("ETag", "\"9\"")
+        .with_header("Content-Range", "bytes 0-3/4096")
+        .with_body("head")
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/blob")
+        .match_header("range", "bytes=5000-5100")
+        .with_status(416)
+        .with_header("Content-Range", "bytes */4096")
+        .with_body(r#"{"error":"Range starts beyond the 4096-byte value","error_type":"RANGE_NOT_SATISFIABLE"}"#)
+        .create_async()
+        .await;
+    // A server that ignores Range: the client slices the full value itself.
+    server.mock("GET", "/keys/small")
+        .with_status(200)
+        .with_header("ETag", "\"2\"")
+        .with_body("0123456789")
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let partial = client.get_range("blob", 0, 3).await.unwrap();
+    assert_eq!(partial, PartialGetResult { data: Bytes::from("head"), total_bytes: 4096, version: Version(9) });
+    assert_eq!(
+        client.get_range("blob", 5000, 5100).await,
+        Err(TransDbError::RangeNotSatisfiable { key: "blob".to_string(), total_bytes: Some(4096) })
+    );
+    let sliced = client.get_range("small", 8, 20).await.unwrap();
+    assert_eq!(sliced, PartialGetResult { data: Bytes::from("89"), total_bytes: 10, version: Version(2) });
+    assert_eq!(
+        client.get_range("small", 3, 2).await,
+        Err(TransDbError::RangeNotSatisfiable { key: "small".to_string(), total_bytes: None })
+    );
+}
+
+#[tokio::test]
+async fn test_get_optional_maps_absence_to_none() {
+    let mut server = mockito::Server::new_async().await;
+    server.mock("GET", "/keys/present").with_status(200).with_header("ETag", "\"3\"").with_body("v").create_async().await;
+    server.mock("GET", "/keys/missing")
+        .with_status(404)
+        .with_body(r#"{"error":"Key not found: missing","error_type":"KEY_NOT_FOUND"}"#)
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/deleted")
+        .with_status(404)
+        .with_header("X-Deleted-Version", "4")
+        .with_body(r#"{"error":"Key not found: deleted","error_type":"KEY_NOT_FOUND"}"#)
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/stale")
+        .with_status(200)
+        .with_header("ETag", "\"5\"")
+        .with_header("X-Expired", "true")
+        .with_body("old")
+        .create_async()
+        .await;
+    server.mock("GET", "/keys/busy")
+        .with_status(503)
+        .with_body(r#"{"error":"Server error: Lock acquisition timed out","error_type":"LOCK_TIMEOUT"}"#)
+        .create_async()
+        .await;
+
+    let client = Client::new(primary_config(&server.url()));
+    let present = client.get_optional("present").await.unwrap().unwrap();
+    assert_eq!((present.value.as_ref(), present.version), (&b"v"[..], Version(3)));
+    assert_eq!(client.get_optional("missing").await, Ok(None));
+    assert_eq!(client.get_optional("deleted").await, Ok(None));
+    assert_eq!(client.get_optional("stale").await, Ok(None));
+    assert!(matches!(client.get_optional("busy").await, Err(TransDbError::HttpError(503, _))));
+
+    assert!(client.get_allowing_expired_optional("stale").await.unwrap().unwrap().expired);
+    assert_eq!(client.get_allowing_expired_optional("missing").await, Ok(None));
+    assert!(client.get_allowing_expired_optional("busy").await.is_err());
+}
+
+#[tokio::test]
+async fn test_cache_revalidates_with_if_none_match() {
+    let mut server = mockito::Server::new_async().await;
+    let first = server.mock("GET", "/keys/k")
+        .match_header("if-none-match", mockito::Matcher::Missing)
+        .with_status(200)
+        .with_header("ETag", "\"5\"")
+        .with_body("cached-value")
+        .expect(1)
+        .create_async()
+        .await;
+    let revalidated = server.mock("GET", "/keys/k")
+        .match_header("if-none-match", "\"5\"")
+        .with_status(304)
+        .with_header("ETag", "\"5\"")
+        .expect(2)
+        .create_async()
+        .await;
+
+    let config = primary_config(&server.url()).with_cache(CacheConfig { max_entries: 8, max_bytes: 1024 });
+    let client = Client::new(config);
+    let fetched = client.get("k").await.unwrap();
+    let hit = client.get("k").await.unwrap();
+    assert_eq!((hit.value.as_ref(), hit.version, hit.expired), (&b"cached-value"[..], Version(5), false));
+    assert_eq!(hit.value.as_ptr(), fetched.value.as_ptr(), "a 304 serves the cached bytes without copying");
+    client.get_allowing_expired("k").await.unwrap();
+    first.assert_async().await;
+    revalidated.assert_async().await;
+
+    // A local write drops the entry, so the next read is unconditional again.
+    server.mock("PUT", "/keys/k").with_status(200).with_header("ETag", "\"6\"").create_async().await;
+    client.put("k", b"new").await.unwrap();
+    assert_eq!(client.cached_len(), 0);
+
+    // Expired values are never cached.
+    server.mock("GET", "/keys/stale")
+        .with_status(200)
+        .with_header("ETag", "\"2\"")
+        .with_header("X-Expired", "true")
+        .with_body("old")
+        .create_async()
+        .await;
+    assert!(matches!(client.get("stale").await, Err(TransDbError::KeyNotFound(_))));
+    assert_eq!(client.cached_len(), 0);
+}
@@ -0,0 +1,209 @@
+//! Request and response bodies for the multi-key endpoints: batch writes, multi-get,
+//! conditional transactions and `POST /multi-cas`. Server handlers and the client share these so the two cannot drift.
+//!
+//! Values travel as base64 text ([`Base64Value`]) because JSON has no byte strings. Each request
+//! type checks its own size against the [`Limits`] in force via `check_limits`.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::{Limits, Version};
+
+/// A value as carried in a JSON body: standard base64 with padding. Deserializing does not
+/// decode it; [`Base64Value::to_bytes`] does, and fails if the text is not valid base64.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Base64Value(String);
+
+impl Base64Value {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Self(STANDARD.encode(bytes))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        STANDARD.decode(&self.0).map_err(|e| format!("value is not valid base64: {}", e))
+    }
+
+    /// The encoded text.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// One write in a [`BatchRequest`] or a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOp {
+    Put {
+        key: String,
+        value: Base64Value,
+        /// Absolute Unix epoch timestamp (seconds), as for `X-TTL`; `None` for no TTL.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires_at: Option<u64>,
+    },
+    Delete {
+        key: String,
+    },
+}
+
+impl BatchOp {
+    /// The key this op writes.
+    pub fn key(&self) -> &str {
+        match self {
+            BatchOp::Put { key, .. } | BatchOp::Delete { key } => key,
+        }
+    }
+}
+
+/// Request body for a batch write: independent writes applied in order, each succeeding or
+/// failing on its own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchRequest {
+    pub ops: Vec<BatchOp>,
+}
+
+impl BatchRequest {
+    /// Rejects a batch with more ops than `limits.max_batch_keys`.
+    pub fn check_limits(&self, limits: &Limits) -> Result<(), String> {
+        check_count("batch", "ops", self.ops.len(), limits)
+    }
+}
+
+/// Outcome of one op of a [`BatchRequest`], in request order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchItemResult {
+    pub key: String,
+    /// The HTTP status the op would have had as a single request.
+    pub status: u16,
+    /// Version written by a successful op; `None` for a delete of an absent key or a failure.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<Version>,
+    /// One of the `ERR_*` constants when the op failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_type: Option<String>,
+}
+
+/// Request body for a multi-get.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiGetRequest {
+    pub keys: Vec<String>,
+}
+
+impl MultiGetRequest {
+    /// Rejects a request for more keys than `limits.max_batch_keys`.
+    pub fn check_limits(&self, limits: &Limits) -> Result<(), String> {
+        check_count("multi-get", "keys", self.keys.len(), limits)
+    }
+}
+
+/// A value found by a multi-get.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiGetItem {
+    pub key: String,
+    pub version: Version,
+    pub value: Base64Value,
+}
+
+/// Response body for a multi-get: the live values, and the keys that were absent, deleted, or
+/// expired. Each requested key appears in exactly one of the two.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MultiGetResponse {
+    pub found: Vec<MultiGetItem>,
+    pub not_found: Vec<String>,
+}
+
+/// A check a transaction makes before writing; all must hold for it to commit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "check", rename_all = "snake_case")]
+pub enum TxnCondition {
+    /// The key is live at exactly `version`.
+    VersionIs { key: String, version: Version },
+    /// The key is absent, deleted, or expired.
+    Absent { key: String },
+}
+
+/// A write a transaction makes if its conditions hold. Same shape as a batch op.
+pub type TxnWrite = BatchOp;
+
+/// Request body for a transaction: `writes` are applied atomically if every condition holds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxnRequest {
+    #[serde(default)]
+    pub conditions: Vec<TxnCondition>,
+    pub writes: Vec<TxnWrite>,
+}
+
+impl TxnRequest {
+    /// Rejects a transaction touching more than `limits.max_batch_keys` keys, counting each
+    /// condition and each write.
+    pub fn check_limits(&self, limits: &Limits) -> Result<(), String> {
+        check_count("transaction", "conditions and writes", self.conditions.len() + self.writes.len(), limits)
+    }
+}
+
+/// Response body for a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxnResponse {
+    pub committed: bool,
+    /// When committed, the version assigned to each write, in request order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<Version>,
+    /// When not committed, the index of the first condition that did not hold.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_condition: Option<usize>,
+}
+
+/// A key a `POST /multi-cas` read, and the version it saw. [`Version`]`(0)` stands for "not
+/// live": the key was absent, deleted, or expired.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiCasRead {
+    pub key: String,
+    pub version: Version,
+}
+
+/// A value `POST /multi-cas` writes, without a TTL.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiCasWrite {
+    pub key: String,
+    pub value_b64: Base64Value,
+}
+
+/// Request body for `POST /multi-cas`: apply every write, atomically, only if every read key is
+/// still at the version that was read. The idempotency key travels in the body rather than the
+/// `Idempotency-Key` header and is scoped to the caller like the header's.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiCasRequest {
+    #[serde(default)]
+    pub reads: Vec<MultiCasRead>,
+    pub writes: Vec<MultiCasWrite>,
+    pub idempotency_key: String,
+}
+
+impl MultiCasRequest {
+    /// Rejects a request naming more than `limits.max_batch_keys` keys, counting each read and
+    /// each write.
+    pub fn check_limits(&self, limits: &Limits) -> Result<(), String> {
+        check_count("multi-cas", "reads and writes", self.reads.len() + self.writes.len(), limits)
+    }
+}
+
+/// Response body of a committed `POST /multi-cas`: the version of each write, in request order.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MultiCasResponse {
+    pub versions: Vec<Version>,
+}
+
+/// `details` of the 409 `VERSION_CONFLICT` a `POST /multi-cas` gets when a read is stale: every
+/// read key that has moved on, in request order.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct MultiCasConflict {
+    pub mismatched: Vec<String>,
+}
+
+fn check_count(request: &str, items: &str, count: usize, limits: &Limits) -> Result<(), String> {
+    if count > limits.max_batch_keys {
+        return Err(format!("{} has {} {}; the limit is {}", request, count, items, limits.max_batch_keys));
+    }
+    Ok(())
+}
@@ -1,17 +1,212 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
 use thiserror::Error;
 
-pub const MAX_KEY_SIZE: usize = 1_024;
-pub const MAX_VALUE_SIZE: usize = 4_194_304;
+pub mod batch;
+pub mod replication;
+pub mod signing;
+
+/// Size and count limits on requests. The server enforces its own; a client checks its copy
+/// before sending, so the two should match, but a mismatch only moves where a request is refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Limits {
+    /// Longest key, in bytes of UTF-8 rather than characters. Both sides measure the key itself:
+    /// the client before percent-encoding it into the path, the server after decoding it.
+    pub max_key_size: usize,
+    /// Largest value, in bytes; also caps the result of an append.
+    pub max_value_size: usize,
+    /// Longest `Idempotency-Key` header value, in bytes.
+    pub max_idempotency_key_size: usize,
+    /// Most keys in a single batch request.
+    pub max_batch_keys: usize,
+}
+
+impl Limits {
+    /// The limits a server enforces unless configured otherwise.
+    pub const DEFAULT: Self =
+        Self { max_key_size: 1_024, max_value_size: 4_194_304, max_idempotency_key_size: 128, max_batch_keys: 1_000 };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// A write's position in the server's single version counter: every PUT, append and tombstone
+/// gets the next one, so versions order writes across all keys. On the wire it is a plain number
+/// in JSON and a strong ETag (`"7"`) in headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Version(pub u64);
+
+impl Version {
+    pub fn get(self) -> u64 {
+        self.0
+    }
+
+    /// The ETag header value for this version: the number in double quotes.
+    pub fn to_etag(self) -> String {
+        format!("\"{}\"", self.0)
+    }
+
+    /// Parse one entity tag, strong (`"7"`) or weak (`W/"7"`), ignoring surrounding whitespace.
+    /// Returns `None` for `*`, an unquoted number or anything else that is not a version's tag.
+    pub fn from_etag(tag: &str) -> Option<Version> {
+        let tag = tag.trim();
+        let quoted = tag.strip_prefix("W/").unwrap_or(tag);
+        let number = quoted.strip_prefix('"')?.strip_suffix('"')?;
+        if !number.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        number.parse().ok().map(Version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<u64> for Version {
+    fn from(version: u64) -> Self {
+        Version(version)
+    }
+}
+
+impl From<Version> for u64 {
+    fn from(version: Version) -> Self {
+        version.0
+    }
+}
+
+#[deprecated(note = "use `Limits::max_key_size`; this is only the default")]
+pub const MAX_KEY_SIZE: usize = Limits::DEFAULT.max_key_size;
+#[deprecated(note = "use `Limits::max_value_size`; this is only the default")]
+pub const MAX_VALUE_SIZE: usize = Limits::DEFAULT.max_value_size;
+#[deprecated(note = "use `Limits::max_idempotency_key_size`; this is only the default")]
+pub const MAX_IDEMPOTENCY_KEY_SIZE: usize = Limits::DEFAULT.max_idempotency_key_size;
+#[deprecated(note = "use `Limits::max_batch_keys`; this is only the default")]
+pub const MAX_BATCH_KEYS: usize = Limits::DEFAULT.max_batch_keys;
+/// Prefix of the internal keys backing `/mutex/:name`: mutex `name` is the key `_mutex/{name}`.
+pub const MUTEX_KEY_PREFIX: &str = "_mutex/";
+/// Maximum length of a namespace name, in bytes.
+pub const MAX_NAMESPACE_SIZE: usize = 64;
+/// Top-level path segments served by other routes, which therefore cannot name a namespace.
+pub const RESERVED_NAMESPACES: &[&str] = &["keys", "mutex", "batch", "topology", "stats", "admin", "version"];
+/// Revision of the HTTP API, reported by `GET /version`. Bumped only by changes an existing
+/// client could trip over, not by additions.
+pub const PROTOCOL_VERSION: &str = "1";
+
+/// Checks that `name` can be the `{namespace}` segment of `/{namespace}/keys/:key`: 1 to
+/// [`MAX_NAMESPACE_SIZE`] ASCII letters, digits, `-` or `_`, and not one of
+/// [`RESERVED_NAMESPACES`]. Returns the reason when it cannot.
+pub fn validate_namespace(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAMESPACE_SIZE {
+        return Err(format!("namespace must be 1 to {} bytes", MAX_NAMESPACE_SIZE));
+    }
+    if !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+        return Err(format!("namespace {:?} may only contain ASCII letters, digits, '-' and '_'", name));
+    }
+    if RESERVED_NAMESPACES.contains(&name) {
+        return Err(format!("namespace {:?} is reserved", name));
+    }
+    Ok(())
+}
 
 /// Describes the full cluster topology shared by all nodes.
 ///
 /// `primary_addr` and `replica_addr` are bare `host:port` strings (no scheme).
-/// A single-node deployment omits `replica_addr`.
+/// A single-node deployment omits `replica_addr`. The optional `primary_id` and `replica_id`
+/// give nodes stable names (e.g. `primary-1`) that a server can be started by (`--node-id`);
+/// topology files without them still load.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Topology {
     pub primary_addr: String,
     pub replica_addr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replica_id: Option<String>,
+}
+
+impl Topology {
+    /// Checks that every address is a `host:port` with a non-zero port, that the replica
+    /// does not share the primary's address, and that node IDs are non-empty, distinct, and only
+    /// given for nodes that exist.
+    pub fn validate(&self) -> Result<()> {
+        check_node_addr("primary_addr", &self.primary_addr)?;
+        if let Some(replica) = &self.replica_addr {
+            check_node_addr("replica_addr", replica)?;
+            if *replica == self.primary_addr {
+                return Err(TransDbError::InvalidTopology(format!(
+                    "replica_addr must differ from primary_addr ({})",
+                    replica
+                )));
+            }
+        }
+        for (field, id) in [("primary_id", &self.primary_id), ("replica_id", &self.replica_id)] {
+            if id.as_deref().is_some_and(|id| id.trim().is_empty()) {
+                return Err(TransDbError::InvalidTopology(format!("{} must not be empty", field)));
+            }
+        }
+        if self.replica_id.is_some() && self.replica_addr.is_none() {
+            return Err(TransDbError::InvalidTopology("replica_id is set without replica_addr".to_string()));
+        }
+        if let Some(id) = self.primary_id.as_ref().filter(|id| self.replica_id.as_ref() == Some(id)) {
+            return Err(TransDbError::InvalidTopology(format!("replica_id must differ from primary_id ({})", id)));
+        }
+        Ok(())
+    }
+
+    /// Read and validate the topology JSON file at `path`, as passed to the server's
+    /// `--topology`. Returns `ConfigError` naming the file if it cannot be read or parsed, and
+    /// `InvalidTopology` if it fails [`Topology::validate`].
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Topology> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| {
+            TransDbError::ConfigError(format!("cannot read topology file {}: {e}", path.display()))
+        })?;
+        Topology::from_json(&json, &path.display().to_string())
+    }
+
+    /// Load the topology named by the environment variable `var`: a file path, or inline JSON
+    /// starting with `{`. Errors as for [`Topology::from_file`], plus `ConfigError` if `var` is
+    /// not set.
+    pub fn from_env(var: &str) -> Result<Topology> {
+        let source = std::env::var(var).map_err(|_| TransDbError::ConfigError(format!("{var} is not set")))?;
+        if source.trim_start().starts_with('{') {
+            Topology::from_json(&source, var)
+        } else {
+            Topology::from_file(source)
+        }
+    }
+
+    /// Parse and validate topology JSON; `source` names where it came from in error messages,
+    /// which also give the line and column of a syntax error.
+    pub fn from_json(json: &str, source: &str) -> Result<Topology> {
+        let topology: Topology = serde_json::from_str(json)
+            .map_err(|e| TransDbError::ConfigError(format!("malformed topology JSON in {source}: {e}")))?;
+        topology.validate()?;
+        Ok(topology)
+    }
+}
+
+fn check_node_addr(field: &str, addr: &str) -> Result<()> {
+    let valid = match addr.rsplit_once(':') {
+        Some((host, port)) => !host.is_empty() && port.parse::<u16>().is_ok_and(|port| port != 0),
+        None => false,
+    };
+    if !valid {
+        return Err(TransDbError::InvalidTopology(format!(
+            "{} must be host:port with a non-zero port, got {:?}",
+            field, addr
+        )));
+    }
+    Ok(())
 }
 
 /// Error types for TransDB operations
@@ -20,9 +215,29 @@ pub enum TransDbError {
     #[error("Key not found: {0}")]
     KeyNotFound(String),
 
+    /// The key was deleted; `version` is the version of the tombstone that was observed.
+    #[error("Key deleted: {key} (tombstone version {version})")]
+    KeyDeleted { key: String, version: u64 },
+
     #[error("Network error: {0}")]
     NetworkError(String),
 
+    /// No connection could be made (DNS failure, connection refused); nothing was sent. Retryable,
+    /// and it counts against the client's circuit breaker like `NetworkError`.
+    #[error("Connect error: {0}")]
+    ConnectError(String),
+
+    /// The server's redirects could not be followed (a loop, or too many). Never retryable:
+    /// the same redirects happen again.
+    #[error("Redirect error: {0}")]
+    RedirectError(String),
+
+    /// The TLS handshake failed: the server's certificate is not trusted or does not match its
+    /// address, or the two sides share no protocol version. Never retryable, and it does not
+    /// count against the circuit breaker: the configuration has to change first.
+    #[error("TLS error: {0}")]
+    TlsError(String),
+
     #[error("HTTP {0}: {1}")]
     HttpError(u16, String),
 
@@ -32,14 +247,358 @@ pub enum TransDbError {
     #[error("Value exceeds maximum size of {0} bytes")]
     ValueTooLarge(usize),
 
+    #[error("Idempotency-Key exceeds maximum length of {0} bytes")]
+    IdempotencyKeyTooLarge(usize),
+
+    /// The client refused to send an empty key, or one that would not survive the URL path
+    /// intact (see `ClientConfig::strict_keys`). Nothing was sent.
+    #[error("Invalid key {key:?}: {reason}")]
+    InvalidKey { key: String, reason: String },
+
     #[error("Server response missing ETag header")]
     MissingETag,
+
+    /// The Idempotency-Key was already used for a different method or key (422).
+    /// Never retryable: resending the same request yields the same conflict.
+    #[error("Idempotency conflict: {message}")]
+    IdempotencyConflict { message: String },
+
+    /// The per-call deadline elapsed before the operation completed.
+    #[error("Request timed out after {0:?}")]
+    Timeout(Duration),
+
+    /// The client's circuit breaker for `target` is open; the request was not sent.
+    #[error("Circuit breaker open for {target}")]
+    CircuitOpen { target: String },
+
+    /// The server's live key count reached its configured limit (507); the PUT was not applied.
+    #[error("Storage full: {0}")]
+    StorageFull(String),
+
+    /// A conditional request's `If-Match` did not match the key's current version (412); nothing
+    /// was changed. `current_version` is the key's live version when the server reported one,
+    /// `None` if the key does not exist (or the server predates reporting it).
+    #[error("Precondition failed for key: {key}")]
+    PreconditionFailed { key: String, current_version: Option<Version> },
+
+    /// The caller is being rate limited (429). `retry_after_secs` is the server's `Retry-After`
+    /// hint, if any.
+    #[error("Rate limited")]
+    RateLimited { retry_after_secs: Option<u64> },
+
+    /// The server shed the request because too many were in flight (503 with error type
+    /// `OVERLOADED`); it was not executed. `retry_after_secs` is the `Retry-After` hint, if any.
+    #[error("Server overloaded")]
+    Overloaded { retry_after_secs: Option<u64> },
+
+    /// The server rejected the request's signature (401): missing, invalid, or outside the
+    /// allowed clock skew.
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    /// The client configuration was rejected while building it; nothing was sent.
+    #[error("Invalid client configuration: {0}")]
+    InvalidConfig(String),
+
+    /// A ranged GET started beyond the end of the value (416). `total_bytes` is the value's
+    /// length, or `None` when the client rejected the range (`start > end`) without sending it.
+    #[error("Range not satisfiable for key: {key}")]
+    RangeNotSatisfiable { key: String, total_bytes: Option<u64> },
+
+    /// A [`Topology`] failed [`Topology::validate`].
+    #[error("Invalid topology: {0}")]
+    InvalidTopology(String),
+
+    /// The client could not load its configuration: an unreadable topology file, malformed
+    /// topology JSON, or a missing or invalid environment variable.
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
+
+    /// The server is in read-only mode (503); the write was not applied. Reads still succeed,
+    /// and the write can be retried once an operator turns the mode off.
+    #[error("Server is read-only: {0}")]
+    ReadOnly(String),
+
+    /// The server's content validator rejected the PUT's value (422); `detail` says why.
+    /// Never retryable: the same value is rejected again.
+    #[error("Content rejected for key {key}: {detail}")]
+    ContentRejected { key: String, detail: String },
+
+    /// A multi-key compare-and-set found `keys` at other versions than it read (409); none of its
+    /// writes were applied. Resending fails the same way: read the keys again first.
+    #[error("Version conflict on keys: {keys:?}")]
+    VersionConflict { keys: Vec<String> },
+
+    /// A value could not be encoded as JSON before it was sent; nothing was written.
+    #[error("Serialization error: {0}")]
+    SerializationError(String),
+
+    /// A stored value is not valid JSON for the type it was read as. The value itself was read
+    /// successfully; rereading it fails the same way until someone writes a compatible value.
+    #[error("Deserialization error: {0}")]
+    DeserializationError(String),
 }
 
-/// JSON error envelope returned by the server for all error responses
+impl TransDbError {
+    /// The HTTP status a server would answer with for this error. Client-side failures that
+    /// never reached a server map to 500 (local faults) or 503 (unreachable or unavailable).
+    pub fn http_status_code(&self) -> u16 {
+        match self {
+            TransDbError::KeyNotFound(_) | TransDbError::KeyDeleted { .. } => 404,
+            TransDbError::KeyTooLarge(_)
+            | TransDbError::InvalidKey { .. }
+            | TransDbError::ValueTooLarge(_)
+            | TransDbError::IdempotencyKeyTooLarge(_) => 400,
+            TransDbError::Unauthorized(_) => 401,
+            TransDbError::VersionConflict { .. } => 409,
+            TransDbError::PreconditionFailed { .. } => 412,
+            TransDbError::RateLimited { .. } => 429,
+            TransDbError::RangeNotSatisfiable { .. } => 416,
+            TransDbError::IdempotencyConflict { .. } | TransDbError::ContentRejected { .. } => 422,
+            TransDbError::StorageFull(_) => 507,
+            TransDbError::NetworkError(_)
+            | TransDbError::ConnectError(_)
+            | TransDbError::Timeout(_)
+            | TransDbError::CircuitOpen { .. }
+            | TransDbError::ReadOnly(_)
+            | TransDbError::Overloaded { .. } => 503,
+            TransDbError::MissingETag
+            | TransDbError::RedirectError(_)
+            | TransDbError::TlsError(_)
+            | TransDbError::InvalidConfig(_)
+            | TransDbError::InvalidTopology(_)
+            | TransDbError::ConfigError(_)
+            | TransDbError::SerializationError(_)
+            | TransDbError::DeserializationError(_) => 500,
+            TransDbError::HttpError(code, _) => *code,
+        }
+    }
+
+    /// Whether resending the same request may succeed: transport and connect failures,
+    /// timeouts, an open circuit, read-only mode, rate limiting (429), load shedding and 5xx
+    /// responses.
+    /// Everything else, including redirect and TLS errors, will fail the same way again. Callers that
+    /// retry should use this rather than matching variants themselves.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            // The request may never have reached the server, or its answer was lost; writes carry
+            // an Idempotency-Key, so resending cannot apply them twice.
+            TransDbError::NetworkError(_) | TransDbError::ConnectError(_) | TransDbError::Timeout(_) => true,
+            // The breaker lets a probe through once its cool-down ends.
+            TransDbError::CircuitOpen { .. } => true,
+            // Both are temporary states of the server: an operator lifts read-only mode, and load
+            // shedding stops once in-flight requests drain.
+            TransDbError::ReadOnly(_) | TransDbError::RateLimited { .. } | TransDbError::Overloaded { .. } => true,
+            // 429 and 5xx describe the server's state, any other status the request itself.
+            TransDbError::HttpError(code, _) => *code == 429 || *code >= 500,
+            // The answer is about the key; it only changes if someone writes it.
+            TransDbError::KeyNotFound(_) | TransDbError::KeyDeleted { .. } => false,
+            // Conditions on versions: resending the same versions fails the same way, the caller
+            // has to read again and decide.
+            TransDbError::PreconditionFailed { .. } | TransDbError::VersionConflict { .. } => false,
+            // Rejections of the request's own content, or of how it was sent.
+            TransDbError::KeyTooLarge(_)
+            | TransDbError::ValueTooLarge(_)
+            | TransDbError::IdempotencyKeyTooLarge(_)
+            | TransDbError::InvalidKey { .. }
+            | TransDbError::IdempotencyConflict { .. }
+            | TransDbError::ContentRejected { .. }
+            | TransDbError::RangeNotSatisfiable { .. }
+            | TransDbError::Unauthorized(_)
+            | TransDbError::RedirectError(_) => false,
+            // A certificate or protocol mismatch fails every handshake until it is fixed.
+            TransDbError::TlsError(_) => false,
+            // Only frees up when keys are deleted, which retrying does not do.
+            TransDbError::StorageFull(_) => false,
+            // A server that omits the ETag will keep omitting it; configuration errors are local.
+            TransDbError::MissingETag
+            | TransDbError::InvalidConfig(_)
+            | TransDbError::InvalidTopology(_)
+            | TransDbError::ConfigError(_) => false,
+            // The value, not the transport, is at fault.
+            TransDbError::SerializationError(_) | TransDbError::DeserializationError(_) => false,
+        }
+    }
+
+    /// Whether the error says the key holds no value: it never existed, or it was deleted.
+    /// An expired key surfaces as `KeyNotFound` too.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, TransDbError::KeyNotFound(_) | TransDbError::KeyDeleted { .. })
+    }
+
+    /// The `ERR_*` constant a server would put in [`ErrorResponse::error_type`] for this error;
+    /// [`ERR_INTERNAL`] for errors with no dedicated code.
+    pub fn error_type_str(&self) -> &'static str {
+        match self {
+            TransDbError::KeyNotFound(_) | TransDbError::KeyDeleted { .. } => ERR_KEY_NOT_FOUND,
+            TransDbError::KeyTooLarge(_) => ERR_KEY_TOO_LARGE,
+            TransDbError::InvalidKey { key, .. } if key.is_empty() => ERR_EMPTY_KEY,
+            TransDbError::InvalidKey { .. } => ERR_INVALID_KEY,
+            TransDbError::ValueTooLarge(_) => ERR_VALUE_TOO_LARGE,
+            TransDbError::IdempotencyKeyTooLarge(_) => ERR_IDEMPOTENCY_KEY_TOO_LARGE,
+            TransDbError::Unauthorized(_) => ERR_INVALID_SIGNATURE,
+            TransDbError::PreconditionFailed { .. } => ERR_PRECONDITION_FAILED,
+            TransDbError::RateLimited { .. } => ERR_RATE_LIMITED,
+            TransDbError::Overloaded { .. } => ERR_OVERLOADED,
+            TransDbError::RangeNotSatisfiable { .. } => ERR_RANGE_NOT_SATISFIABLE,
+            TransDbError::IdempotencyConflict { .. } => ERR_IDEMPOTENCY_MISMATCH,
+            TransDbError::StorageFull(_) => ERR_STORAGE_FULL,
+            TransDbError::ReadOnly(_) => ERR_READ_ONLY,
+            TransDbError::ContentRejected { .. } => ERR_CONTENT_REJECTED,
+            TransDbError::VersionConflict { .. } => ERR_VERSION_CONFLICT,
+            TransDbError::NetworkError(_)
+            | TransDbError::ConnectError(_)
+            | TransDbError::RedirectError(_)
+            | TransDbError::TlsError(_)
+            | TransDbError::Timeout(_)
+            | TransDbError::CircuitOpen { .. }
+            | TransDbError::MissingETag
+            | TransDbError::InvalidConfig(_)
+            | TransDbError::InvalidTopology(_)
+            | TransDbError::ConfigError(_)
+            | TransDbError::SerializationError(_)
+            | TransDbError::DeserializationError(_)
+            | TransDbError::HttpError(..) => ERR_INTERNAL,
+        }
+    }
+}
+
+// Machine-readable `error_type` values carried in [`ErrorResponse`].
+pub const ERR_KEY_NOT_FOUND: &str = "KEY_NOT_FOUND";
+pub const ERR_KEY_TOO_LARGE: &str = "KEY_TOO_LARGE";
+pub const ERR_VALUE_TOO_LARGE: &str = "VALUE_TOO_LARGE";
+pub const ERR_INVALID_TTL: &str = "INVALID_TTL";
+pub const ERR_MISSING_IDEMPOTENCY_KEY: &str = "MISSING_IDEMPOTENCY_KEY";
+pub const ERR_IDEMPOTENCY_MISMATCH: &str = "IDEMPOTENCY_MISMATCH";
+pub const ERR_LOCK_TIMEOUT: &str = "LOCK_TIMEOUT";
+pub const ERR_NOT_PRIMARY: &str = "NOT_PRIMARY";
+pub const ERR_INTERNAL: &str = "INTERNAL";
+pub const ERR_INVALID_BODY: &str = "INVALID_BODY";
+pub const ERR_BATCH_TOO_LARGE: &str = "BATCH_TOO_LARGE";
+pub const ERR_STORAGE_FULL: &str = "STORAGE_FULL";
+pub const ERR_IDEMPOTENCY_KEY_TOO_LARGE: &str = "IDEMPOTENCY_KEY_TOO_LARGE";
+pub const ERR_TOPOLOGY_UNKNOWN: &str = "TOPOLOGY_UNKNOWN";
+pub const ERR_INVALID_SIGNATURE: &str = "INVALID_SIGNATURE";
+pub const ERR_PRECONDITION_FAILED: &str = "PRECONDITION_FAILED";
+pub const ERR_INVALID_QUERY: &str = "INVALID_QUERY";
+pub const ERR_RANGE_NOT_SATISFIABLE: &str = "RANGE_NOT_SATISFIABLE";
+pub const ERR_READ_ONLY: &str = "READ_ONLY";
+pub const ERR_INVALID_KEY: &str = "INVALID_KEY";
+pub const ERR_CONTENT_REJECTED: &str = "CONTENT_REJECTED";
+pub const ERR_RATE_LIMITED: &str = "RATE_LIMITED";
+pub const ERR_INVALID_NAMESPACE: &str = "INVALID_NAMESPACE";
+pub const ERR_EMPTY_KEY: &str = "EMPTY_KEY";
+pub const ERR_OVERLOADED: &str = "OVERLOADED";
+pub const ERR_VERSION_CONFLICT: &str = "VERSION_CONFLICT";
+
+/// JSON error envelope returned by the server for all error responses.
+///
+/// `error` is a human-readable message; `error_type` is one of the `ERR_*` constants and is
+/// what clients should match on. Bodies from older servers lack `error_type`, which then
+/// deserializes as an empty string.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
     pub error: String,
+    #[serde(default)]
+    pub error_type: String,
+    /// Optional structured context for the error (shape depends on `error_type`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<serde_json::Value>,
+    /// The failed request's `X-Request-Id`, for finding it in server logs. Absent from older
+    /// servers' bodies.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Request body for `PATCH /batch/ttl`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BulkTtlRequest {
+    pub keys: Vec<String>,
+    /// Absolute Unix epoch timestamp (seconds), as for `X-TTL`.
+    pub expires_at: u64,
+}
+
+/// Response body for `PATCH /batch/ttl`: which keys had their TTL set and which were absent,
+/// deleted, or already expired.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BulkTtlResult {
+    pub updated: Vec<String>,
+    pub not_found: Vec<String>,
+}
+
+/// Response body for `GET /stats`. Every stored entry is counted in exactly one `*_count` field.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct StoreStats {
+    /// Entries holding a value whose TTL (if any) has not elapsed.
+    pub live_count: usize,
+    /// Values or tombstones past their expiry that are still held in memory.
+    pub expired_count: usize,
+    /// Tombstones still within their retention window.
+    pub tombstone_count: usize,
+    /// `GET /keys/:key` lookups answered by the server's get cache since startup; 0 when the
+    /// cache is disabled.
+    #[serde(default)]
+    pub cache_hits: u64,
+    /// `GET /keys/:key` lookups the get cache could not answer since startup.
+    #[serde(default)]
+    pub cache_misses: u64,
+}
+
+/// Media type a client sends in `Accept` to have `GET /keys` stream its answer as NDJSON: one
+/// JSON string per line instead of a single [`KeyList`] document.
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Response body for `GET /keys` when NDJSON was not asked for: the live keys, sorted.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct KeyList {
+    pub keys: Vec<String>,
+}
+
+/// Everything about a stored entry except its bytes: the shape shared by anything that reports
+/// on entries without shipping their values. `created_at`, `updated_at` and `content_type` are
+/// deliberately left out until the store records them; until then they could only be `None`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryMetadata {
+    pub version: Version,
+    /// Unix seconds after which the entry is expired; `None` for no TTL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// Length of the value; 0 for a tombstone.
+    pub size_bytes: u64,
+    /// `true` when the key was deleted and the entry only remembers the deletion's version.
+    pub tombstone: bool,
+}
+
+impl EntryMetadata {
+    /// A value of `size_bytes` written at `version`.
+    pub fn value(version: Version, size_bytes: u64, expires_at: Option<u64>) -> Self {
+        Self { version, expires_at, size_bytes, tombstone: false }
+    }
+
+    /// A deletion at `version`, retained until `expires_at`.
+    pub fn tombstone(version: Version, expires_at: Option<u64>) -> Self {
+        Self { version, expires_at, size_bytes: 0, tombstone: true }
+    }
+}
+
+/// Response body for the `/mutex/:name` endpoints: who holds the mutex and for how long.
+///
+/// `acquired` is `true` when the caller holds the mutex after the request. A caller that lost
+/// the race gets `acquired: false` with the current holder in `owner`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MutexStatus {
+    pub acquired: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    /// Seconds until the lease lapses; 0 when nobody holds the mutex.
+    pub ttl_remaining: u64,
+}
+
+/// Response body of `DELETE /keys?confirm=yes` (servers built with the `test-admin` feature).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeleteAllResult {
+    /// Live keys that were tombstoned.
+    pub deleted_count: u64,
 }
 
 /// Result type for TransDB operations
@@ -0,0 +1,83 @@
+//! Wire format for primary → replica replication, shared by both roles.
+//!
+//! The primary ships [`ReplicationBatch`]es of writes in version order; the replica answers each
+//! with a [`ReplicationAck`]. Messages are JSON, value bytes are base64. Unknown fields are
+//! ignored, so a later wire version may add optional fields without a bump; anything a receiver
+//! must understand bumps [`WIRE_VERSION`], and receivers reject batches newer than they speak.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Version;
+
+/// Wire version this build writes, and the newest it accepts.
+pub const WIRE_VERSION: u32 = 1;
+
+/// What a write left behind for its key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Payload {
+    /// The value written, base64-encoded on the wire.
+    Value(#[serde(with = "base64_bytes")] Vec<u8>),
+    /// The key was deleted.
+    Tombstone,
+}
+
+/// One write, as applied on the primary.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationRecord {
+    pub key: String,
+    pub version: Version,
+    pub payload: Payload,
+    /// Unix seconds after which the entry is expired; `None` for no TTL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+/// Writes shipped together, in ascending `version` order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationBatch {
+    pub wire_version: u32,
+    /// Bumped whenever the primary's history restarts (e.g. a new primary), so a replica can tell
+    /// a fresh stream from a continuation of the old one.
+    pub epoch: u64,
+    pub records: Vec<ReplicationRecord>,
+}
+
+impl ReplicationBatch {
+    /// A batch in this build's [`WIRE_VERSION`].
+    pub fn new(epoch: u64, records: Vec<ReplicationRecord>) -> Self {
+        Self { wire_version: WIRE_VERSION, epoch, records }
+    }
+
+    /// Rejects a batch from a newer wire version than this build speaks (or the invalid version 0).
+    pub fn check_wire_version(&self) -> Result<(), String> {
+        if self.wire_version == 0 || self.wire_version > WIRE_VERSION {
+            return Err(format!(
+                "unsupported replication wire version {}; this node accepts 1 to {}",
+                self.wire_version, WIRE_VERSION
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Replica → primary: every write up to and including this version has been applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationAck {
+    pub last_applied_version: Version,
+}
+
+mod base64_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
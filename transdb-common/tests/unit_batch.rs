@@ -0,0 +1,135 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::json;
+use std::fmt::Debug;
+use transdb_common::batch::{
+    Base64Value, BatchItemResult, BatchOp, BatchRequest, MultiCasConflict, MultiCasRead, MultiCasRequest,
+    MultiCasResponse, MultiCasWrite, MultiGetItem, MultiGetRequest, MultiGetResponse, TxnCondition, TxnRequest,
+    TxnResponse,
+};
+use transdb_common::{Limits, Version};
+
+fn assert_round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T) {
+    let json = serde_json::to_string(value).unwrap();
+    assert_eq!(&serde_json::from_str::<T>(&json).unwrap(), value, "{json}");
+}
+
+/// Ops covering both kinds, empty and non-UTF-8 values, odd keys, and both TTL states.
+fn sample_ops() -> Vec<BatchOp> {
+    let mut ops = Vec::new();
+    for key in ["k", "with space/and:colon", "ключ", ""] {
+        for value in [&b""[..], b"hello", &(0..=255).collect::<Vec<u8>>()] {
+            for expires_at in [None, Some(0), Some(u64::MAX)] {
+                ops.push(BatchOp::Put { key: key.to_string(), value: Base64Value::from_bytes(value), expires_at });
+            }
+        }
+        ops.push(BatchOp::Delete { key: key.to_string() });
+    }
+    ops
+}
+
+#[test]
+fn test_batch_messages_round_trip() {
+    for op in sample_ops() {
+        assert_round_trip(&op);
+    }
+    assert_round_trip(&BatchRequest { ops: sample_ops() });
+    assert_round_trip(&BatchRequest { ops: Vec::new() });
+    for (version, error_type) in [(Some(Version(3)), None), (None, None), (None, Some("KEY_TOO_LARGE".to_string()))] {
+        assert_round_trip(&BatchItemResult { key: "k".into(), status: 200, version, error_type });
+    }
+
+    assert_round_trip(&MultiGetRequest { keys: vec!["a".into(), "ключ".into()] });
+    let item = MultiGetItem { key: "a".into(), version: Version(u64::MAX), value: Base64Value::from_bytes(b"\0\xff") };
+    assert_round_trip(&MultiGetResponse { found: vec![item], not_found: vec!["b".into()] });
+    assert_round_trip(&MultiGetResponse::default());
+
+    let conditions = vec![
+        TxnCondition::VersionIs { key: "a".into(), version: Version(7) },
+        TxnCondition::Absent { key: "b".into() },
+    ];
+    assert_round_trip(&TxnRequest { conditions, writes: sample_ops() });
+    assert_round_trip(&TxnResponse { committed: true, versions: vec![Version(8), Version(9)], failed_condition: None });
+    assert_round_trip(&TxnResponse { committed: false, versions: Vec::new(), failed_condition: Some(1) });
+
+    let reads = vec![MultiCasRead { key: "a".into(), version: Version(0) }];
+    let writes = vec![MultiCasWrite { key: "b".into(), value_b64: Base64Value::from_bytes(&[0, 255]) }];
+    assert_round_trip(&MultiCasRequest { reads, writes, idempotency_key: "tok".into() });
+    assert_round_trip(&MultiCasResponse { versions: vec![Version(4)] });
+    assert_round_trip(&MultiCasConflict { mismatched: vec!["a".into()] });
+}
+
+#[test]
+fn test_batch_wire_shape() {
+    let put = BatchOp::Put { key: "k".into(), value: Base64Value::from_bytes(b"hi"), expires_at: Some(9) };
+    let expected = json!({ "op": "put", "key": "k", "value": "aGk=", "expires_at": 9 });
+    assert_eq!(serde_json::to_value(&put).unwrap(), expected);
+    let delete = BatchOp::Delete { key: "k".into() };
+    assert_eq!(serde_json::to_value(&delete).unwrap(), json!({ "op": "delete", "key": "k" }));
+    assert_eq!((put.key(), delete.key()), ("k", "k"));
+
+    let result = BatchItemResult { key: "k".into(), status: 204, version: None, error_type: None };
+    assert_eq!(serde_json::to_value(&result).unwrap(), json!({ "key": "k", "status": 204 }));
+
+    let condition = TxnCondition::VersionIs { key: "k".into(), version: Version(3) };
+    assert_eq!(serde_json::to_value(&condition).unwrap(), json!({ "check": "version_is", "key": "k", "version": 3 }));
+    let aborted = TxnResponse { committed: false, versions: Vec::new(), failed_condition: Some(0) };
+    assert_eq!(serde_json::to_value(&aborted).unwrap(), json!({ "committed": false, "failed_condition": 0 }));
+
+    // A transaction without conditions may omit them; unknown ops and checks are rejected.
+    let txn: TxnRequest = serde_json::from_value(json!({ "writes": [{ "op": "delete", "key": "k" }] })).unwrap();
+    assert_eq!(txn, TxnRequest { conditions: Vec::new(), writes: vec![delete] });
+    assert!(serde_json::from_value::<BatchOp>(json!({ "op": "append", "key": "k" })).is_err());
+    assert!(serde_json::from_value::<TxnCondition>(json!({ "check": "exists", "key": "k" })).is_err());
+
+    let cas: MultiCasRequest = serde_json::from_value(json!({
+        "reads": [{ "key": "k1", "version": 1 }],
+        "writes": [{ "key": "k2", "value_b64": "aGk=" }],
+        "idempotency_key": "tok",
+    }))
+    .unwrap();
+    assert_eq!(cas.reads, vec![MultiCasRead { key: "k1".into(), version: Version(1) }]);
+    assert_eq!(cas.writes[0].value_b64.to_bytes().unwrap(), b"hi");
+}
+
+#[test]
+fn test_base64_value_conversions() {
+    let value = Base64Value::from_bytes(b"hello");
+    assert_eq!(value.as_str(), "aGVsbG8=");
+    assert_eq!(value.to_bytes().unwrap(), b"hello");
+
+    // Decoding is deferred, so a bad value deserializes and fails only on conversion.
+    let bad: Base64Value = serde_json::from_value(json!("not base64!")).unwrap();
+    assert!(bad.to_bytes().unwrap_err().contains("base64"));
+}
+
+#[test]
+fn test_batch_requests_check_their_size() {
+    let limits = Limits { max_batch_keys: 2, ..Limits::DEFAULT };
+    let delete = |key: &str| BatchOp::Delete { key: key.into() };
+
+    assert_eq!(BatchRequest { ops: vec![delete("a"), delete("b")] }.check_limits(&limits), Ok(()));
+    let err = BatchRequest { ops: vec![delete("a"), delete("b"), delete("c")] }.check_limits(&limits).unwrap_err();
+    assert!(err.contains("3 ops") && err.contains("limit is 2"), "{err}");
+
+    let keys = |n: usize| MultiGetRequest { keys: (0..n).map(|i| i.to_string()).collect() };
+    assert_eq!(keys(2).check_limits(&limits), Ok(()));
+    assert!(keys(3).check_limits(&limits).is_err());
+
+    // Conditions and writes share the budget.
+    let absent = TxnCondition::Absent { key: "a".into() };
+    let txn = TxnRequest { conditions: vec![absent.clone()], writes: vec![delete("a")] };
+    assert_eq!(txn.check_limits(&limits), Ok(()));
+    let txn = TxnRequest { conditions: vec![absent], writes: vec![delete("a"), delete("b")] };
+    assert!(txn.check_limits(&limits).is_err());
+
+    let read = MultiCasRead { key: "a".into(), version: Version(1) };
+    let write = MultiCasWrite { key: "b".into(), value_b64: Base64Value::from_bytes(b"") };
+    let cas = MultiCasRequest { reads: vec![read.clone()], writes: vec![write.clone()], idempotency_key: "t".into() };
+    assert_eq!(cas.check_limits(&limits), Ok(()));
+    let cas = MultiCasRequest { reads: vec![read.clone(), read], writes: vec![write], idempotency_key: "t".into() };
+    assert!(cas.check_limits(&limits).is_err());
+
+    let defaults = BatchRequest { ops: vec![delete("a"); Limits::DEFAULT.max_batch_keys] };
+    assert_eq!(defaults.check_limits(&Limits::default()), Ok(()));
+}
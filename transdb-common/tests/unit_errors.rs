@@ -1,4 +1,10 @@
-use transdb_common::TransDbError;
+use std::time::Duration;
+use transdb_common::{
+    ErrorResponse, TransDbError, ERR_CONTENT_REJECTED, ERR_EMPTY_KEY, ERR_IDEMPOTENCY_KEY_TOO_LARGE,
+    ERR_IDEMPOTENCY_MISMATCH, ERR_INTERNAL, ERR_INVALID_KEY, ERR_INVALID_SIGNATURE,
+    ERR_KEY_NOT_FOUND, ERR_KEY_TOO_LARGE, ERR_OVERLOADED, ERR_PRECONDITION_FAILED, ERR_RANGE_NOT_SATISFIABLE,
+    ERR_RATE_LIMITED, ERR_READ_ONLY, ERR_STORAGE_FULL, ERR_VALUE_TOO_LARGE, ERR_VERSION_CONFLICT, Version,
+};
 
 #[test]
 fn test_error_display() {
@@ -16,12 +22,58 @@ fn test_error_equality() {
     assert_ne!(err1, err3);
 }
 
+#[test]
+fn test_key_deleted() {
+    let err = TransDbError::KeyDeleted { key: "k".to_string(), version: 7 };
+    assert_eq!(err.to_string(), "Key deleted: k (tombstone version 7)");
+}
+
+#[test]
+fn test_idempotency_conflict() {
+    let err = TransDbError::IdempotencyConflict { message: "reused".to_string() };
+    assert_eq!(err.to_string(), "Idempotency conflict: reused");
+}
+
+#[test]
+fn test_timeout() {
+    let err = TransDbError::Timeout(std::time::Duration::from_millis(50));
+    assert_eq!(err.to_string(), "Request timed out after 50ms");
+}
+
+#[test]
+fn test_circuit_open() {
+    let err = TransDbError::CircuitOpen { target: "10.0.0.1:8080".to_string() };
+    assert_eq!(err.to_string(), "Circuit breaker open for 10.0.0.1:8080");
+}
+
+#[test]
+fn test_storage_full() {
+    let err = TransDbError::StorageFull("limit 10".to_string());
+    assert_eq!(err.to_string(), "Storage full: limit 10");
+}
+
+#[test]
+fn test_idempotency_key_too_large() {
+    let err = TransDbError::IdempotencyKeyTooLarge(128);
+    assert_eq!(err.to_string(), "Idempotency-Key exceeds maximum length of 128 bytes");
+}
+
 #[test]
 fn test_network_error() {
     let err = TransDbError::NetworkError("connection failed".to_string());
     assert_eq!(err.to_string(), "Network error: connection failed");
 }
 
+#[test]
+fn test_connect_and_redirect_errors() {
+    let err = TransDbError::ConnectError("connection refused".to_string());
+    assert_eq!(err.to_string(), "Connect error: connection refused");
+    let err = TransDbError::RedirectError("too many redirects".to_string());
+    assert_eq!(err.to_string(), "Redirect error: too many redirects");
+    let err = TransDbError::TlsError("certificate verify failed".to_string());
+    assert_eq!(err.to_string(), "TLS error: certificate verify failed");
+}
+
 #[test]
 fn test_http_error_5xx() {
     let err = TransDbError::HttpError(503, "Server error: Lock acquisition timed out".to_string());
@@ -34,6 +86,36 @@ fn test_key_too_large() {
     assert_eq!(err.to_string(), "Key exceeds maximum size of 1024 bytes");
 }
 
+#[test]
+fn test_precondition_failed() {
+    let err = TransDbError::PreconditionFailed { key: "k".to_string(), current_version: Some(Version(5)) };
+    assert_eq!(err.to_string(), "Precondition failed for key: k");
+}
+
+#[test]
+fn test_rate_limited() {
+    let err = TransDbError::RateLimited { retry_after_secs: Some(2) };
+    assert_eq!(err.to_string(), "Rate limited");
+}
+
+#[test]
+fn test_overloaded() {
+    let err = TransDbError::Overloaded { retry_after_secs: Some(1) };
+    assert_eq!(err.to_string(), "Server overloaded");
+}
+
+#[test]
+fn test_unauthorized() {
+    let err = TransDbError::Unauthorized("bad signature".to_string());
+    assert_eq!(err.to_string(), "Unauthorized: bad signature");
+}
+
+#[test]
+fn test_invalid_config() {
+    let err = TransDbError::InvalidConfig("reserved header".to_string());
+    assert_eq!(err.to_string(), "Invalid client configuration: reserved header");
+}
+
 #[test]
 fn test_value_too_large() {
     let err = TransDbError::ValueTooLarge(4194304);
@@ -51,3 +133,161 @@ fn test_missing_etag() {
     let err = TransDbError::MissingETag;
     assert_eq!(err.to_string(), "Server response missing ETag header");
 }
+
+#[test]
+fn test_structured_variants_round_trip_through_serde() {
+    let errors = [
+        TransDbError::PreconditionFailed { key: "k".to_string(), current_version: Some(Version(5)) },
+        TransDbError::PreconditionFailed { key: "k".to_string(), current_version: None },
+        TransDbError::RateLimited { retry_after_secs: Some(30) },
+        TransDbError::Overloaded { retry_after_secs: None },
+        TransDbError::Timeout(Duration::from_millis(1500)),
+        TransDbError::Unauthorized("stale timestamp".to_string()),
+        TransDbError::VersionConflict { keys: vec!["a".to_string(), "b".to_string()] },
+    ];
+    for err in errors {
+        let json = serde_json::to_string(&err).unwrap();
+        assert_eq!(serde_json::from_str::<TransDbError>(&json).unwrap(), err, "{json}");
+    }
+    // Existing variants keep their externally tagged shape.
+    let json = serde_json::to_string(&TransDbError::KeyNotFound("k".to_string())).unwrap();
+    assert_eq!(json, r#"{"KeyNotFound":"k"}"#);
+}
+
+// --- ErrorResponse envelope ---
+
+#[test]
+fn test_error_response_serde() {
+    // error_type is always serialized; details and request_id are omitted when absent.
+    let resp = ErrorResponse {
+        error: "Key not found: k".to_string(),
+        error_type: ERR_KEY_NOT_FOUND.to_string(),
+        details: None,
+        request_id: None,
+    };
+    let json = serde_json::to_string(&resp).unwrap();
+    assert_eq!(json, r#"{"error":"Key not found: k","error_type":"KEY_NOT_FOUND"}"#);
+
+    // Details round-trip as arbitrary JSON.
+    let json = r#"{"error":"e","error_type":"T","details":{"n":1}}"#;
+    let parsed: ErrorResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(parsed.details, Some(serde_json::json!({"n": 1})));
+
+    // Legacy bodies without error_type still deserialize.
+    let parsed: ErrorResponse = serde_json::from_str(r#"{"error":"old"}"#).unwrap();
+    assert_eq!(parsed.error, "old");
+    assert_eq!(parsed.error_type, "");
+    assert!(parsed.details.is_none());
+}
+
+#[test]
+fn test_error_response_request_id_is_wire_compatible() {
+    let resp = ErrorResponse {
+        error: "e".to_string(),
+        error_type: "T".to_string(),
+        details: None,
+        request_id: Some("req-1".to_string()),
+    };
+    let json = serde_json::to_string(&resp).unwrap();
+    assert_eq!(json, r#"{"error":"e","error_type":"T","request_id":"req-1"}"#);
+    assert_eq!(serde_json::from_str::<ErrorResponse>(&json).unwrap().request_id.as_deref(), Some("req-1"));
+
+    // An old server's body has no request_id.
+    let parsed: ErrorResponse = serde_json::from_str(r#"{"error":"e","error_type":"T"}"#).unwrap();
+    assert!(parsed.request_id.is_none());
+
+    // An old client's envelope, which predates the field, still reads a new server's body.
+    #[derive(serde::Deserialize)]
+    struct OldErrorResponse {
+        error: String,
+        error_type: String,
+    }
+    let old: OldErrorResponse = serde_json::from_str(&json).unwrap();
+    assert_eq!((old.error.as_str(), old.error_type.as_str()), ("e", "T"));
+}
+
+#[test]
+fn test_http_status_code_and_error_type_for_every_variant() {
+    let cases = [
+        (TransDbError::KeyNotFound("k".to_string()), 404, ERR_KEY_NOT_FOUND),
+        (TransDbError::KeyDeleted { key: "k".to_string(), version: 3 }, 404, ERR_KEY_NOT_FOUND),
+        (TransDbError::NetworkError("refused".to_string()), 503, ERR_INTERNAL),
+        (TransDbError::ConnectError("refused".to_string()), 503, ERR_INTERNAL),
+        (TransDbError::RedirectError("loop".to_string()), 500, ERR_INTERNAL),
+        (TransDbError::TlsError("untrusted".to_string()), 500, ERR_INTERNAL),
+        (TransDbError::HttpError(418, "teapot".to_string()), 418, ERR_INTERNAL),
+        (TransDbError::KeyTooLarge(1), 400, ERR_KEY_TOO_LARGE),
+        (TransDbError::InvalidKey { key: "a/b".to_string(), reason: "r".to_string() }, 400, ERR_INVALID_KEY),
+        (TransDbError::InvalidKey { key: String::new(), reason: "r".to_string() }, 400, ERR_EMPTY_KEY),
+        (TransDbError::ValueTooLarge(1), 400, ERR_VALUE_TOO_LARGE),
+        (TransDbError::IdempotencyKeyTooLarge(1), 400, ERR_IDEMPOTENCY_KEY_TOO_LARGE),
+        (TransDbError::MissingETag, 500, ERR_INTERNAL),
+        (TransDbError::IdempotencyConflict { message: "m".to_string() }, 422, ERR_IDEMPOTENCY_MISMATCH),
+        (TransDbError::Timeout(Duration::from_secs(1)), 503, ERR_INTERNAL),
+        (TransDbError::CircuitOpen { target: "t".to_string() }, 503, ERR_INTERNAL),
+        (TransDbError::StorageFull("full".to_string()), 507, ERR_STORAGE_FULL),
+        (TransDbError::PreconditionFailed { key: "k".to_string(), current_version: None }, 412, ERR_PRECONDITION_FAILED),
+        (TransDbError::RateLimited { retry_after_secs: None }, 429, ERR_RATE_LIMITED),
+        (TransDbError::Overloaded { retry_after_secs: None }, 503, ERR_OVERLOADED),
+        (TransDbError::Unauthorized("bad".to_string()), 401, ERR_INVALID_SIGNATURE),
+        (TransDbError::RangeNotSatisfiable { key: "k".to_string(), total_bytes: Some(4) }, 416, ERR_RANGE_NOT_SATISFIABLE),
+        (TransDbError::InvalidConfig("c".to_string()), 500, ERR_INTERNAL),
+        (TransDbError::InvalidTopology("t".to_string()), 500, ERR_INTERNAL),
+        (TransDbError::ConfigError("c".to_string()), 500, ERR_INTERNAL),
+        (TransDbError::ReadOnly("maintenance".to_string()), 503, ERR_READ_ONLY),
+        (TransDbError::ContentRejected { key: "k".to_string(), detail: "d".to_string() }, 422, ERR_CONTENT_REJECTED),
+        (TransDbError::VersionConflict { keys: vec!["k".to_string()] }, 409, ERR_VERSION_CONFLICT),
+        (TransDbError::SerializationError("s".to_string()), 500, ERR_INTERNAL),
+        (TransDbError::DeserializationError("d".to_string()), 500, ERR_INTERNAL),
+    ];
+    for (err, status, error_type) in cases {
+        assert_eq!(err.http_status_code(), status, "{:?}", err);
+        assert_eq!(err.error_type_str(), error_type, "{:?}", err);
+    }
+}
+
+#[test]
+fn test_retry_and_not_found_policy() {
+    let s = || "x".to_string();
+    // (error, is_retryable, is_not_found) for every variant.
+    let table = [
+        (TransDbError::KeyNotFound(s()), false, true),
+        (TransDbError::KeyDeleted { key: s(), version: 3 }, false, true),
+        (TransDbError::NetworkError(s()), true, false),
+        (TransDbError::ConnectError(s()), true, false),
+        (TransDbError::RedirectError(s()), false, false),
+        // Permanent: a certificate or protocol mismatch fails every handshake until it is fixed.
+        (TransDbError::TlsError(s()), false, false),
+        (TransDbError::HttpError(400, s()), false, false),
+        (TransDbError::HttpError(422, s()), false, false),
+        (TransDbError::HttpError(429, s()), true, false),
+        (TransDbError::HttpError(500, s()), true, false),
+        (TransDbError::HttpError(503, s()), true, false),
+        (TransDbError::KeyTooLarge(1), false, false),
+        (TransDbError::ValueTooLarge(1), false, false),
+        (TransDbError::IdempotencyKeyTooLarge(1), false, false),
+        (TransDbError::InvalidKey { key: s(), reason: s() }, false, false),
+        (TransDbError::MissingETag, false, false),
+        (TransDbError::IdempotencyConflict { message: s() }, false, false),
+        (TransDbError::Timeout(Duration::from_secs(1)), true, false),
+        (TransDbError::CircuitOpen { target: s() }, true, false),
+        (TransDbError::StorageFull(s()), false, false),
+        (TransDbError::PreconditionFailed { key: s(), current_version: Some(Version(1)) }, false, false),
+        (TransDbError::RateLimited { retry_after_secs: Some(1) }, true, false),
+        (TransDbError::Overloaded { retry_after_secs: Some(1) }, true, false),
+        (TransDbError::Unauthorized(s()), false, false),
+        (TransDbError::InvalidConfig(s()), false, false),
+        (TransDbError::RangeNotSatisfiable { key: s(), total_bytes: None }, false, false),
+        (TransDbError::InvalidTopology(s()), false, false),
+        (TransDbError::ConfigError(s()), false, false),
+        (TransDbError::ReadOnly(s()), true, false),
+        (TransDbError::ContentRejected { key: s(), detail: s() }, false, false),
+        (TransDbError::VersionConflict { keys: vec![s()] }, false, false),
+        (TransDbError::SerializationError(s()), false, false),
+        (TransDbError::DeserializationError(s()), false, false),
+    ];
+    for (error, retryable, not_found) in table {
+        assert_eq!(error.is_retryable(), retryable, "is_retryable for {error:?}");
+        assert_eq!(error.is_not_found(), not_found, "is_not_found for {error:?}");
+    }
+}
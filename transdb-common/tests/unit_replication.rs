@@ -0,0 +1,83 @@
+use transdb_common::replication::{Payload, ReplicationAck, ReplicationBatch, ReplicationRecord, WIRE_VERSION};
+use transdb_common::Version;
+
+/// Records covering every payload kind, empty and non-UTF-8 values, odd keys, and both TTL states.
+fn sample_records() -> Vec<ReplicationRecord> {
+    let payloads = [
+        Payload::Tombstone,
+        Payload::Value(Vec::new()),
+        Payload::Value(b"hello".to_vec()),
+        Payload::Value((0..=255).collect()),
+    ];
+    let keys = ["k", "with space/and:colon", "ключ", "_mutex/lock"];
+    let mut records = Vec::new();
+    for (i, payload) in payloads.iter().enumerate() {
+        for (j, key) in keys.iter().enumerate() {
+            for expires_at in [None, Some(0), Some(u64::MAX)] {
+                let version = Version((i * keys.len() + j) as u64 + 1);
+                records.push(ReplicationRecord { key: key.to_string(), version, payload: payload.clone(), expires_at });
+            }
+        }
+    }
+    records
+}
+
+#[test]
+fn test_replication_messages_round_trip() {
+    for record in sample_records() {
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(serde_json::from_str::<ReplicationRecord>(&json).unwrap(), record, "{json}");
+    }
+
+    let batch = ReplicationBatch::new(7, sample_records());
+    assert_eq!(batch.wire_version, WIRE_VERSION);
+    let json = serde_json::to_vec(&batch).unwrap();
+    assert_eq!(serde_json::from_slice::<ReplicationBatch>(&json).unwrap(), batch);
+
+    let ack = ReplicationAck { last_applied_version: Version(u64::MAX) };
+    let json = serde_json::to_string(&ack).unwrap();
+    assert_eq!(serde_json::from_str::<ReplicationAck>(&json).unwrap(), ack);
+}
+
+#[test]
+fn test_replication_wire_shape() {
+    let payload = Payload::Value(b"hi".to_vec());
+    let value = ReplicationRecord { key: "k".into(), version: Version(3), payload, expires_at: None };
+    let json = serde_json::to_value(&value).unwrap();
+    assert_eq!(json, serde_json::json!({ "key": "k", "version": 3, "payload": { "value": "aGk=" } }));
+
+    let tombstone =
+        ReplicationRecord { key: "k".into(), version: Version(4), payload: Payload::Tombstone, expires_at: Some(9) };
+    let json = serde_json::to_value(&tombstone).unwrap();
+    assert_eq!(json, serde_json::json!({ "key": "k", "version": 4, "payload": "tombstone", "expires_at": 9 }));
+
+    let bad = r#"{"key":"k","version":1,"payload":{"value":"not base64!"}}"#;
+    assert!(serde_json::from_str::<ReplicationRecord>(bad).is_err());
+}
+
+#[test]
+fn test_replication_tolerates_unknown_fields() {
+    let json = r#"{
+        "wire_version": 1,
+        "epoch": 2,
+        "records": [{"key": "k", "version": 5, "payload": "tombstone", "checksum": "abc"}],
+        "compression": null
+    }"#;
+    let batch: ReplicationBatch = serde_json::from_str(json).unwrap();
+    let record =
+        ReplicationRecord { key: "k".into(), version: Version(5), payload: Payload::Tombstone, expires_at: None };
+    assert_eq!(batch, ReplicationBatch { wire_version: 1, epoch: 2, records: vec![record] });
+
+    let ack: ReplicationAck = serde_json::from_str(r#"{"last_applied_version": 5, "lag_ms": 12}"#).unwrap();
+    assert_eq!(ack.last_applied_version, Version(5));
+}
+
+#[test]
+fn test_replication_rejects_unknown_wire_versions() {
+    let batch = |wire_version| ReplicationBatch { wire_version, epoch: 1, records: Vec::new() };
+    assert_eq!(batch(WIRE_VERSION).check_wire_version(), Ok(()));
+
+    let err = batch(WIRE_VERSION + 1).check_wire_version().unwrap_err();
+    assert!(err.contains(&format!("wire version {}", WIRE_VERSION + 1)), "{err}");
+    assert!(batch(0).check_wire_version().is_err());
+}
@@ -0,0 +1,33 @@
+use transdb_common::signing::{sign, verify, SIGNATURE_SCHEME};
+
+const KEY: &[u8] = b"secret";
+
+#[test]
+fn test_signature_round_trips_and_covers_every_field() {
+    let signature = sign(KEY, "PUT", "/keys/k", b"value", 1_700_000_000);
+    assert!(signature.starts_with(SIGNATURE_SCHEME));
+    assert!(verify(KEY, "PUT", "/keys/k", b"value", 1_700_000_000, &signature));
+
+    assert!(!verify(b"other", "PUT", "/keys/k", b"value", 1_700_000_000, &signature));
+    assert!(!verify(KEY, "DELETE", "/keys/k", b"value", 1_700_000_000, &signature));
+    assert!(!verify(KEY, "PUT", "/keys/other", b"value", 1_700_000_000, &signature));
+    assert!(!verify(KEY, "PUT", "/keys/k", b"tampered", 1_700_000_000, &signature));
+    assert!(!verify(KEY, "PUT", "/keys/k", b"value", 1_700_000_001, &signature));
+}
+
+#[test]
+fn test_signature_covers_the_query() {
+    let signature = sign(KEY, "POST", "/admin/readonly?enabled=true", b"", 1);
+    assert!(verify(KEY, "POST", "/admin/readonly?enabled=true", b"", 1, &signature));
+    assert!(!verify(KEY, "POST", "/admin/readonly?enabled=false", b"", 1, &signature));
+    assert!(!verify(KEY, "POST", "/admin/readonly", b"", 1, &signature));
+}
+
+#[test]
+fn test_malformed_signature_is_rejected() {
+    let signature = sign(KEY, "DELETE", "/keys/k", b"", 1);
+    let bare = signature.trim_start_matches(SIGNATURE_SCHEME);
+    assert!(!verify(KEY, "DELETE", "/keys/k", b"", 1, bare));
+    assert!(!verify(KEY, "DELETE", "/keys/k", b"", 1, "HMAC-SHA256:not base64!"));
+    assert!(!verify(KEY, "DELETE", "/keys/k", b"", 1, ""));
+}
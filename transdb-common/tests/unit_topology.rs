@@ -1,8 +1,14 @@
-use transdb_common::Topology;
+use std::io::Write;
+use transdb_common::{Topology, TransDbError};
 
 #[test]
 fn test_topology_single_node() {
-    let t = Topology { primary_addr: "127.0.0.1:3000".to_string(), replica_addr: None };
+    let t = Topology {
+        primary_addr: "127.0.0.1:3000".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
     assert_eq!(t.primary_addr, "127.0.0.1:3000");
     assert!(t.replica_addr.is_none());
 }
@@ -13,6 +19,8 @@ fn test_topology_with_and_without_replica() {
     let t = Topology {
         primary_addr: "127.0.0.1:3000".to_string(),
         replica_addr: Some("127.0.0.1:3001".to_string()),
+        primary_id: None,
+        replica_id: None,
     };
     assert_eq!(t.primary_addr, "127.0.0.1:3000");
     assert_eq!(t.replica_addr.as_deref(), Some("127.0.0.1:3001"));
@@ -26,9 +34,24 @@ fn test_topology_with_and_without_replica() {
 
 #[test]
 fn test_topology_equality() {
-    let a = Topology { primary_addr: "127.0.0.1:3000".to_string(), replica_addr: None };
-    let b = Topology { primary_addr: "127.0.0.1:3000".to_string(), replica_addr: None };
-    let c = Topology { primary_addr: "10.0.0.1:3000".to_string(), replica_addr: None };
+    let a = Topology {
+        primary_addr: "127.0.0.1:3000".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
+    let b = Topology {
+        primary_addr: "127.0.0.1:3000".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
+    let c = Topology {
+        primary_addr: "10.0.0.1:3000".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
     assert_eq!(a, b);
     assert_ne!(a, c);
 }
@@ -38,8 +61,156 @@ fn test_topology_roundtrip_json() {
     let original = Topology {
         primary_addr: "127.0.0.1:3000".to_string(),
         replica_addr: Some("127.0.0.1:3001".to_string()),
+        primary_id: None,
+        replica_id: None,
     };
     let json = serde_json::to_string(&original).unwrap();
     let decoded: Topology = serde_json::from_str(&json).unwrap();
     assert_eq!(original, decoded);
 }
+
+#[test]
+fn test_topology_validate() {
+    let topology = |primary: &str, replica: Option<&str>| Topology {
+        primary_addr: primary.to_string(),
+        replica_addr: replica.map(str::to_string),
+        primary_id: None,
+        replica_id: None,
+    };
+    assert_eq!(topology("127.0.0.1:3000", None).validate(), Ok(()));
+    assert_eq!(topology("db.internal:3000", Some("[::1]:3001")).validate(), Ok(()));
+
+    for bad in [
+        topology("127.0.0.1", None),
+        topology(":3000", None),
+        topology("127.0.0.1:http", None),
+        topology("127.0.0.1:70000", None),
+        topology("127.0.0.1:0", None),
+        topology("", Some("127.0.0.1:3001")),
+        topology("127.0.0.1:3000", Some("")),
+        topology("127.0.0.1:3000", Some("127.0.0.1:3000")),
+    ] {
+        assert!(matches!(bad.validate(), Err(TransDbError::InvalidTopology(_))), "{:?}", bad);
+    }
+    assert_eq!(
+        topology("127.0.0.1:3000", Some("replica:0")).validate(),
+        Err(TransDbError::InvalidTopology(
+            "replica_addr must be host:port with a non-zero port, got \"replica:0\"".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_topology_node_ids_round_trip_and_legacy_json_still_loads() {
+    let json = concat!(
+        r#"{"primary_addr":"10.0.0.1:3000","replica_addr":"10.0.0.2:3000","#,
+        r#""primary_id":"primary-1","replica_id":"replica-1"}"#,
+    );
+    let with_ids: Topology = serde_json::from_str(json).unwrap();
+    assert_eq!(with_ids.primary_id.as_deref(), Some("primary-1"));
+    assert_eq!(with_ids.replica_id.as_deref(), Some("replica-1"));
+    assert_eq!(serde_json::to_string(&with_ids).unwrap(), json);
+
+    // The pre-ID shape loads with no IDs and serializes back unchanged.
+    let legacy_json = r#"{"primary_addr":"10.0.0.1:3000","replica_addr":"10.0.0.2:3000"}"#;
+    let legacy: Topology = serde_json::from_str(legacy_json).unwrap();
+    assert_eq!(serde_json::to_string(&legacy).unwrap(), legacy_json);
+    assert_eq!(legacy, Topology { primary_id: None, replica_id: None, ..with_ids });
+}
+
+#[test]
+fn test_topology_validate_node_ids() {
+    let topology = |primary_id: Option<&str>, replica_addr: Option<&str>, replica_id: Option<&str>| Topology {
+        primary_addr: "127.0.0.1:3000".to_string(),
+        replica_addr: replica_addr.map(str::to_string),
+        primary_id: primary_id.map(str::to_string),
+        replica_id: replica_id.map(str::to_string),
+    };
+    assert_eq!(topology(Some("p"), Some("127.0.0.1:3001"), Some("r")).validate(), Ok(()));
+    assert_eq!(topology(Some("p"), None, None).validate(), Ok(()));
+
+    for (bad, reason) in [
+        (topology(Some(" "), None, None), "primary_id must not be empty"),
+        (topology(None, None, Some("r")), "replica_id is set without replica_addr"),
+        (topology(Some("n"), Some("127.0.0.1:3001"), Some("n")), "replica_id must differ from primary_id (n)"),
+    ] {
+        assert_eq!(bad.validate(), Err(TransDbError::InvalidTopology(reason.to_string())));
+    }
+}
+
+fn topology_file(contents: &str) -> tempfile::NamedTempFile {
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    file.write_all(contents.as_bytes()).unwrap();
+    file
+}
+
+#[test]
+fn test_topology_from_file_loads_with_and_without_replica() {
+    let file = topology_file(r#"{"primary_addr":"127.0.0.1:3000"}"#);
+    let t = Topology::from_file(file.path()).unwrap();
+    assert_eq!(t.primary_addr, "127.0.0.1:3000");
+    assert!(t.replica_addr.is_none());
+
+    let file = topology_file(r#"{"primary_addr":"127.0.0.1:3000","replica_addr":"127.0.0.1:3001"}"#);
+    let t = Topology::from_file(file.path()).unwrap();
+    assert_eq!(t.replica_addr.as_deref(), Some("127.0.0.1:3001"));
+}
+
+#[test]
+fn test_topology_from_file_missing_file_names_the_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing.json");
+    match Topology::from_file(&path) {
+        Err(TransDbError::ConfigError(msg)) => {
+            assert!(msg.contains("cannot read topology file"), "{msg}");
+            assert!(msg.contains(&path.display().to_string()), "{msg}");
+        }
+        other => panic!("expected ConfigError, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_topology_from_file_invalid_json_names_path_and_position() {
+    let file = topology_file("{\n  \"primary_addr\": \"127.0.0.1:3000\",\n}");
+    match Topology::from_file(file.path()) {
+        Err(TransDbError::ConfigError(msg)) => {
+            assert!(msg.contains("malformed topology JSON"), "{msg}");
+            assert!(msg.contains(&file.path().display().to_string()), "{msg}");
+            assert!(msg.contains("line 3 column 1"), "{msg}");
+        }
+        other => panic!("expected ConfigError, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_topology_from_file_validates() {
+    let file = topology_file(r#"{"primary_addr":"127.0.0.1:3000","replica_addr":"127.0.0.1:3000"}"#);
+    assert!(matches!(Topology::from_file(file.path()), Err(TransDbError::InvalidTopology(_))));
+}
+
+#[test]
+fn test_topology_from_env_accepts_path_or_inline_json() {
+    // A variable name no other test touches, since tests share the process environment.
+    const VAR: &str = "TRANSDB_COMMON_TEST_TOPOLOGY";
+    std::env::remove_var(VAR);
+    match Topology::from_env(VAR) {
+        Err(TransDbError::ConfigError(msg)) => assert!(msg.contains(VAR), "{msg}"),
+        other => panic!("expected ConfigError, got {:?}", other),
+    }
+
+    std::env::set_var(VAR, r#"{"primary_addr":"127.0.0.1:3000","replica_addr":"127.0.0.1:3001"}"#);
+    assert_eq!(Topology::from_env(VAR).unwrap().replica_addr.as_deref(), Some("127.0.0.1:3001"));
+
+    let file = topology_file(r#"{"primary_addr":"127.0.0.1:4000"}"#);
+    std::env::set_var(VAR, file.path());
+    assert_eq!(Topology::from_env(VAR).unwrap().primary_addr, "127.0.0.1:4000");
+
+    std::env::set_var(VAR, "{ not json");
+    match Topology::from_env(VAR) {
+        Err(TransDbError::ConfigError(msg)) => {
+            assert!(msg.contains(&format!("malformed topology JSON in {VAR}")), "{msg}")
+        }
+        other => panic!("expected ConfigError, got {:?}", other),
+    }
+    std::env::remove_var(VAR);
+}
@@ -0,0 +1,31 @@
+use transdb_common::Version;
+
+#[test]
+fn test_version_etag_round_trip() {
+    for version in [Version(0), Version(7), Version(u64::MAX)] {
+        assert_eq!(Version::from_etag(&version.to_etag()), Some(version));
+    }
+    assert_eq!(Version(42).to_etag(), "\"42\"");
+    assert_eq!(Version(42).to_string(), "42");
+
+    // Weak validators and surrounding whitespace parse to the same version.
+    assert_eq!(Version::from_etag("W/\"42\""), Some(Version(42)));
+    assert_eq!(Version::from_etag("  \"42\" "), Some(Version(42)));
+
+    for not_a_version in ["42", "\"42", "42\"", "\"\"", "*", "\"-1\"", "\"+1\"", "\"4 2\"", "w/\"42\"", "\"abc\""] {
+        assert_eq!(Version::from_etag(not_a_version), None, "{not_a_version:?}");
+    }
+    // One past u64::MAX.
+    assert_eq!(Version::from_etag("\"18446744073709551616\""), None);
+}
+
+#[test]
+fn test_version_orders_and_serializes_as_a_number() {
+    assert!(Version(2) > Version(1));
+    assert_eq!([Version(3), Version(1), Version(2)].iter().max(), Some(&Version(3)));
+    assert_eq!(u64::from(Version(5)), 5);
+    assert_eq!(Version::from(5), Version(5));
+
+    assert_eq!(serde_json::to_string(&Version(9)).unwrap(), "9");
+    assert_eq!(serde_json::from_str::<Version>("9").unwrap(), Version(9));
+}
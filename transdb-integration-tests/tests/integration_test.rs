@@ -1,15 +1,29 @@
+use bytes::Bytes;
+use futures_util::StreamExt;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::sync::oneshot;
 use tokio::time::timeout;
-use transdb_client::{Client, ClientConfig};
-use transdb_common::{ErrorResponse, Topology, TransDbError, MAX_KEY_SIZE, MAX_VALUE_SIZE};
-use transdb_server::{NodeRole, Server, ServerConfig};
+use transdb_client::fake::{FakeClient, ManualClock};
+use transdb_client::{Client, ClientConfig, KvClient};
+use transdb_common::{
+    ErrorResponse, Limits, Topology, TransDbError, Version, ERR_KEY_TOO_LARGE, ERR_MISSING_IDEMPOTENCY_KEY,
+    PROTOCOL_VERSION,
+};
+use transdb_server::content::JsonValidator;
+use transdb_server::tls::TlsConfig;
+use transdb_server::{NodeRole, RouteBodyLimits, Server, ServerConfig, TcpOptions};
 
 const SERVER_READY_TIMEOUT: Duration = Duration::from_secs(60);
 
 /// Asserts that `versions` is strictly increasing.
-fn assert_monotonic(versions: &[u64]) {
+fn assert_monotonic(versions: &[Version]) {
     for w in versions.windows(2) {
         assert!(w[1] > w[0], "versions not strictly increasing: {} then {}", w[0], w[1]);
     }
@@ -21,14 +35,34 @@ struct Cluster {
 }
 
 async fn start_node(role: NodeRole) -> SocketAddr {
-    let (ready_tx, ready_rx) = oneshot::channel();
-    let server = Server::new(ServerConfig {
+    start_server(ServerConfig {
         address: "127.0.0.1:0".parse().unwrap(),
         role,
         topology: None,
-    });
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: None,
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: None,
+        read_only: false,
+        content_validator: None,
+        tls: None,
+        get_cache_size: None,
+        read_snapshot_interval: None,
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: false,
+    })
+    .await
+}
+
+async fn start_server(config: ServerConfig) -> SocketAddr {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let server = Server::new(config);
     tokio::spawn(async move {
-        server.run(ready_tx).await.expect("server failed");
+        server.run(ready_tx, None).await.expect("server failed");
     });
     timeout(SERVER_READY_TIMEOUT, ready_rx)
         .await
@@ -45,12 +79,14 @@ async fn start_cluster() -> Cluster {
     let topology = Topology {
         primary_addr: primary_addr.to_string(),
         replica_addr: Some(replica_addr.to_string()),
+        primary_id: None,
+        replica_id: None,
     };
 
-    let primary = Client::new(ClientConfig { topology: topology.clone() });
+    let primary = Client::new(ClientConfig::new(topology.clone()));
 
-    let mut replica = Client::new(ClientConfig { topology: topology.clone() });
-    replica.set_target(topology.replica_addr.as_deref().unwrap());
+    let mut replica = Client::new(ClientConfig::new(topology.clone()));
+    replica.set_target(topology.replica_addr.as_deref().unwrap()).unwrap();
 
     Cluster { primary, replica }
 }
@@ -68,15 +104,15 @@ async fn test_put_and_get_round_trip() {
     let client = start_cluster().await.primary;
 
     let put_version = client.put("my_key", b"hello world").await.expect("put failed");
-    assert!(put_version > 0);
+    assert!(put_version > Version(0));
 
     let result = client.get("my_key").await.expect("get failed");
-    assert_eq!(result.value, b"hello world");
+    assert_eq!(result.value.as_ref(), b"hello world");
     assert_eq!(result.version, put_version);
     assert!(!result.expired);
 
     let result = client.get_allowing_expired("my_key").await.expect("get_allowing_expired failed");
-    assert_eq!(result.value, b"hello world");
+    assert_eq!(result.value.as_ref(), b"hello world");
     assert_eq!(result.version, put_version);
     assert!(!result.expired);
 }
@@ -86,16 +122,18 @@ async fn test_delete_removes_existing_key() {
     let client = start_cluster().await.primary;
 
     let put_version = client.put("my_key", b"hello").await.expect("put failed");
-    assert!(put_version > 0);
+    assert!(put_version > Version(0));
 
     let before = client.get("my_key").await.expect("get before delete failed");
-    assert_eq!(before.value, b"hello");
+    assert_eq!(before.value.as_ref(), b"hello");
     assert_eq!(before.version, put_version);
 
-    client.delete("my_key").await.expect("delete failed");
+    let v_del = client.delete("my_key").await.expect("delete failed").expect("key must be live");
 
+    // A tombstoned key reports the delete version; a never-written key stays a plain KeyNotFound.
     let after = client.get("my_key").await;
-    assert!(matches!(after, Err(TransDbError::KeyNotFound(k)) if k == "my_key"));
+    assert_eq!(after, Err(TransDbError::KeyDeleted { key: "my_key".to_string(), version: v_del.get() }));
+    assert!(matches!(client.get("never_written").await, Err(TransDbError::KeyNotFound(_))));
 }
 
 #[tokio::test]
@@ -110,6 +148,39 @@ async fn test_delete_is_idempotent() {
     assert!(second.is_ok());
 }
 
+#[tokio::test]
+async fn test_key_count_tracks_live_keys() {
+    let client = start_cluster().await.primary;
+    assert_eq!(client.key_count().await, Ok(0));
+
+    client.put("a", b"1").await.unwrap();
+    client.put("b", b"2").await.unwrap();
+    client.delete("a").await.unwrap();
+    assert_eq!(client.key_count().await, Ok(1));
+}
+
+#[tokio::test]
+async fn test_list_keys_in_both_formats() {
+    let cluster = start_cluster().await;
+    let client = cluster.primary;
+    for i in 0..1500 {
+        client.put(&format!("list/{i:04}"), b"v").await.unwrap();
+    }
+    client.put("other", b"v").await.unwrap();
+    client.delete("list/0000").await.unwrap();
+
+    let listed = client.list_keys(Some("list/")).await.unwrap();
+    assert_eq!(listed.len(), 1499);
+    assert_eq!(listed[0], "list/0001");
+
+    let streamed: Vec<String> = client.list_keys_streaming(Some("list/")).map(Result::unwrap).collect().await;
+    assert_eq!(streamed, listed);
+    assert_eq!(client.list_keys(None).await.unwrap().len(), 1500);
+
+    let replica: Vec<_> = cluster.replica.list_keys_streaming(None).collect().await;
+    assert!(matches!(replica[..], [Err(TransDbError::HttpError(405, _))]), "{replica:?}");
+}
+
 #[tokio::test]
 async fn test_put_overwrites_existing_key() {
     let client = start_cluster().await.primary;
@@ -119,7 +190,7 @@ async fn test_put_overwrites_existing_key() {
     assert_monotonic(&[v1, v2]);
 
     let result = client.get("my_key").await.expect("get failed");
-    assert_eq!(result.value, b"second");
+    assert_eq!(result.value.as_ref(), b"second");
     assert_eq!(result.version, v2);
 }
 
@@ -130,7 +201,7 @@ async fn test_put_returns_positive_version_for_new_key() {
     let client = start_cluster().await.primary;
 
     let version = client.put("k", b"v").await.expect("put failed");
-    assert!(version > 0);
+    assert!(version > Version(0));
 }
 
 #[tokio::test]
@@ -153,6 +224,25 @@ async fn test_get_returns_etag_matching_put_version() {
     assert_eq!(result.version, v);
 }
 
+#[tokio::test]
+async fn test_versions_order_writes_across_keys() {
+    let client = start_cluster().await.primary;
+
+    let a1 = client.put("a", b"1").await.expect("put a failed");
+    let b1 = client.put("b", b"1").await.expect("put b failed");
+    let a2 = client.put("a", b"2").await.expect("second put a failed");
+    assert_monotonic(&[a1, b1, a2]);
+
+    let a = client.get("a").await.expect("get a failed");
+    let b = client.get("b").await.expect("get b failed");
+    assert_eq!((a.version, b.version), (a2, b1));
+    // Both reads report the high-water mark, which is the last write's version.
+    assert_eq!((a.sequence, b.sequence), (a2.get(), a2.get()));
+
+    let b2 = client.put("b", b"2").await.expect("second put b failed");
+    assert_eq!(client.get("a").await.expect("get a failed").sequence, b2.get());
+}
+
 #[tokio::test]
 async fn test_version_increases_after_delete_and_recreate() {
     let client = start_cluster().await.primary;
@@ -165,141 +255,159 @@ async fn test_version_increases_after_delete_and_recreate() {
     assert_monotonic(&[v1, v2, v_del, v3]);
 }
 
-// --- Idempotency (via raw reqwest to control the Idempotency-Key header) ---
+// --- Idempotency (via raw_request to control the Idempotency-Key header) ---
+
+/// Headers for a raw write carrying the given Idempotency-Key.
+fn idempotency_headers(token: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("Content-Type", HeaderValue::from_static("application/octet-stream"));
+    headers.insert("Idempotency-Key", HeaderValue::from_str(token).unwrap());
+    headers
+}
 
 #[tokio::test]
 async fn test_put_idempotency_replay_returns_same_version() {
     let client = start_cluster().await.primary;
-    let http = reqwest::Client::new();
-    let url = client.build_key_url("idem_key");
+    let put = || {
+        let headers = idempotency_headers("replay-token-abc");
+        client.raw_request(Method::PUT, "idem_key", headers, Some(Bytes::from("value")))
+    };
 
-    let r1 = http
-        .put(&url)
-        .header("Content-Type", "application/octet-stream")
-        .header("Idempotency-Key", "replay-token-abc")
-        .body(b"value".to_vec())
-        .send()
-        .await
-        .unwrap();
-    assert_eq!(r1.status(), reqwest::StatusCode::OK);
-    let etag1 = r1.headers().get("etag").unwrap().to_str().unwrap().to_string();
+    let r1 = put().await.unwrap();
+    assert_eq!(r1.status, StatusCode::OK);
+    let r2 = put().await.unwrap();
+    assert_eq!(r2.status, StatusCode::OK);
 
-    let r2 = http
-        .put(&url)
-        .header("Content-Type", "application/octet-stream")
-        .header("Idempotency-Key", "replay-token-abc")
-        .body(b"value".to_vec())
-        .send()
-        .await
-        .unwrap();
-    assert_eq!(r2.status(), reqwest::StatusCode::OK);
-    let etag2 = r2.headers().get("etag").unwrap().to_str().unwrap().to_string();
+    assert_eq!(r1.headers["etag"], r2.headers["etag"]);
+}
+
+#[tokio::test]
+async fn test_put_idempotent_distinguishes_replay_from_fresh_execution() {
+    let client = start_cluster().await.primary;
 
-    assert_eq!(etag1, etag2);
+    let first = client.put_idempotent("idem_flag", b"v", "persisted-token").await.unwrap();
+    assert!(!first.replayed);
+    let retry = client.put_idempotent("idem_flag", b"v", "persisted-token").await.unwrap();
+    assert!(retry.replayed);
+    assert_eq!(retry.version, first.version);
+
+    // Once the server has forgotten the token (here: the test-admin reset clears the cache),
+    // the same retry executes afresh.
+    client.delete_all().await.unwrap();
+    let late = client.put_idempotent("idem_flag", b"v", "persisted-token").await.unwrap();
+    assert!(!late.replayed);
+    assert!(late.version > first.version);
 }
 
 #[tokio::test]
 async fn test_put_idempotency_replay_does_not_write_twice() {
     let client = start_cluster().await.primary;
-    let http = reqwest::Client::new();
-    let url = client.build_key_url("idem_write");
 
     // Two PUTs with the same idempotency key
     for _ in 0..2 {
-        http.put(&url)
-            .header("Content-Type", "application/octet-stream")
-            .header("Idempotency-Key", "write-once-token")
-            .body(b"v".to_vec())
-            .send()
+        client
+            .raw_request(Method::PUT, "idem_write", idempotency_headers("write-once-token"), Some(Bytes::from("v")))
             .await
             .unwrap();
     }
 
     // Version should be the same as the first write; value should be what was written
     let result = client.get("idem_write").await.expect("get failed");
-    assert_eq!(result.value, b"v");
-    assert!(result.version > 0);
+    assert_eq!(result.value.as_ref(), b"v");
+    assert!(result.version.get() > 0);
 }
 
 #[tokio::test]
 async fn test_delete_idempotency_replay_returns_204() {
     let client = start_cluster().await.primary;
-    let http = reqwest::Client::new();
-    let url = client.build_key_url("del_key");
 
-    let r1 = http
-        .delete(&url)
-        .header("Idempotency-Key", "del-replay-token")
-        .send()
-        .await
-        .unwrap();
-    assert_eq!(r1.status(), reqwest::StatusCode::NO_CONTENT);
-
-    let r2 = http
-        .delete(&url)
-        .header("Idempotency-Key", "del-replay-token")
-        .send()
-        .await
-        .unwrap();
-    assert_eq!(r2.status(), reqwest::StatusCode::NO_CONTENT);
+    for _ in 0..2 {
+        let response =
+            client.raw_request(Method::DELETE, "del_key", idempotency_headers("del-replay-token"), None).await.unwrap();
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+    }
 }
 
 #[tokio::test]
 async fn test_put_idempotency_mismatch_key_returns_422() {
     let client = start_cluster().await.primary;
-    let http = reqwest::Client::new();
 
     // First PUT for key_a with token X
-    http.put(client.build_key_url("key_a"))
-        .header("Content-Type", "application/octet-stream")
-        .header("Idempotency-Key", "mismatch-token")
-        .body(b"v".to_vec())
-        .send()
+    client
+        .raw_request(Method::PUT, "key_a", idempotency_headers("mismatch-token"), Some(Bytes::from("v")))
         .await
         .unwrap();
 
     // Second PUT for key_b with same token X
-    let r2 = http
-        .put(client.build_key_url("key_b"))
-        .header("Content-Type", "application/octet-stream")
-        .header("Idempotency-Key", "mismatch-token")
-        .body(b"v".to_vec())
-        .send()
+    let r2 = client
+        .raw_request(Method::PUT, "key_b", idempotency_headers("mismatch-token"), Some(Bytes::from("v")))
         .await
         .unwrap();
 
-    assert_eq!(r2.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(r2.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_idempotency_conflict_surfaces_as_typed_error() {
+    let client = start_cluster().await.primary;
+
+    let v1 = client.put_with_idempotency_key("key_a", b"v", "conflict-token").await.unwrap();
+    // Replaying the same write is fine and returns the original version.
+    assert_eq!(client.put_with_idempotency_key("key_a", b"v", "conflict-token").await, Ok(v1));
+
+    // Reusing the token for another key or method is a conflict.
+    assert!(matches!(
+        client.put_with_idempotency_key("key_b", b"v", "conflict-token").await,
+        Err(TransDbError::IdempotencyConflict { .. })
+    ));
+    assert!(matches!(
+        client.delete_with_idempotency_key("key_a", "conflict-token").await,
+        Err(TransDbError::IdempotencyConflict { .. })
+    ));
 }
 
 #[tokio::test]
 async fn test_put_missing_idempotency_key_returns_400() {
     let client = start_cluster().await.primary;
-    let http = reqwest::Client::new();
-    let url = client.build_key_url("k");
+    let mut headers = HeaderMap::new();
+    headers.insert("Content-Type", HeaderValue::from_static("application/octet-stream"));
 
-    let response = http
-        .put(&url)
-        .header("Content-Type", "application/octet-stream")
-        .body(b"v".to_vec())
-        .send()
-        .await
-        .unwrap();
+    let response = client.raw_request(Method::PUT, "k", headers, Some(Bytes::from("v"))).await.unwrap();
 
-    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
-    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let body: ErrorResponse = serde_json::from_slice(&response.body).unwrap();
     assert_eq!(body.error, "Idempotency-Key header is required");
+    assert_eq!(body.error_type, ERR_MISSING_IDEMPOTENCY_KEY);
+}
+
+#[tokio::test]
+async fn test_error_body_request_id_matches_header() {
+    let client = start_cluster().await.primary;
+
+    // Generated by the server when the request has none.
+    let response = client.raw_request(Method::DELETE, "k", HeaderMap::new(), None).await.unwrap();
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let header = response.headers["x-request-id"].to_str().unwrap();
+    let body: ErrorResponse = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!(body.request_id.as_deref(), Some(header));
+
+    // Echoed when the caller supplies one.
+    let mut headers = HeaderMap::new();
+    headers.insert("x-request-id", HeaderValue::from_static("trace-1234"));
+    let response = client.raw_request(Method::DELETE, "k", headers, None).await.unwrap();
+    assert_eq!(response.headers["x-request-id"], "trace-1234");
+    let body: ErrorResponse = serde_json::from_slice(&response.body).unwrap();
+    assert_eq!(body.request_id.as_deref(), Some("trace-1234"));
 }
 
 #[tokio::test]
 async fn test_delete_missing_idempotency_key_returns_400() {
     let client = start_cluster().await.primary;
-    let http = reqwest::Client::new();
-    let url = client.build_key_url("k");
 
-    let response = http.delete(&url).send().await.unwrap();
+    let response = client.raw_request(Method::DELETE, "k", HeaderMap::new(), None).await.unwrap();
 
-    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
-    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let body: ErrorResponse = serde_json::from_slice(&response.body).unwrap();
     assert_eq!(body.error, "Idempotency-Key header is required");
 }
 
@@ -309,7 +417,7 @@ async fn test_delete_missing_idempotency_key_returns_400() {
 async fn test_server_rejects_oversized_key_on_put() {
     let client = start_cluster().await.primary;
     let http = reqwest::Client::new();
-    let oversized_key = "a".repeat(MAX_KEY_SIZE + 1);
+    let oversized_key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     let url = format!("http://{}/keys/{}", client.config.topology.primary_addr, oversized_key);
 
     let response = http
@@ -323,7 +431,7 @@ async fn test_server_rejects_oversized_key_on_put() {
     // Key size check fires before Idempotency-Key check
     assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
     let body: ErrorResponse = response.json().await.unwrap();
-    assert_eq!(body.error, format!("Key exceeds maximum size of {} bytes", MAX_KEY_SIZE));
+    assert_eq!(body.error, format!("Key exceeds maximum size of {} bytes", Limits::DEFAULT.max_key_size));
 }
 
 #[tokio::test]
@@ -331,7 +439,7 @@ async fn test_server_rejects_oversized_value_on_put() {
     let client = start_cluster().await.primary;
     let http = reqwest::Client::new();
     let url = client.build_key_url("my_key");
-    let oversized_value = vec![0u8; MAX_VALUE_SIZE + 1];
+    let oversized_value = vec![0u8; Limits::DEFAULT.max_value_size + 1];
 
     let response = http
         .put(&url)
@@ -344,30 +452,57 @@ async fn test_server_rejects_oversized_value_on_put() {
 
     assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
     let body: ErrorResponse = response.json().await.unwrap();
-    assert_eq!(body.error, format!("Value exceeds maximum size of {} bytes", MAX_VALUE_SIZE));
+    assert_eq!(body.error, format!("Value exceeds maximum size of {} bytes", Limits::DEFAULT.max_value_size));
 }
 
 #[tokio::test]
 async fn test_server_rejects_oversized_key_on_get() {
     let client = start_cluster().await.primary;
     let http = reqwest::Client::new();
-    let oversized_key = "a".repeat(MAX_KEY_SIZE + 1);
+    let oversized_key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     let url = format!("http://{}/keys/{}", client.config.topology.primary_addr, oversized_key);
 
     let response = http.get(&url).send().await.unwrap();
 
     assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
     let body: ErrorResponse = response.json().await.unwrap();
-    assert_eq!(body.error, format!("Key exceeds maximum size of {} bytes", MAX_KEY_SIZE));
+    assert_eq!(body.error, format!("Key exceeds maximum size of {} bytes", Limits::DEFAULT.max_key_size));
+    assert_eq!(body.error_type, ERR_KEY_TOO_LARGE);
+}
+
+#[tokio::test]
+async fn test_multibyte_key_limit_is_in_bytes_on_client_and_server() {
+    let client = start_cluster().await.primary;
+    let http = reqwest::Client::new();
+    // Both keys are 342 characters; '€' is 3 bytes and 'é' 2, so only the first fits.
+    let at_limit = format!("{}a", "€".repeat(341));
+    let over_limit = format!("{}é", "€".repeat(341));
+    assert_eq!(at_limit.len(), Limits::DEFAULT.max_key_size);
+    assert_eq!(over_limit.len(), Limits::DEFAULT.max_key_size + 1);
+
+    client.put(&at_limit, b"v").await.unwrap();
+    assert_eq!(client.get(&at_limit).await.unwrap().value, &b"v"[..]);
+    assert_eq!(client.put(&over_limit, b"v").await, Err(TransDbError::KeyTooLarge(Limits::DEFAULT.max_key_size)));
+
+    // The server, decoding the percent-encoded path, draws the line at the same byte.
+    let response = http.get(client.build_key_url(&at_limit)).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    let response = http.get(client.build_key_url(&over_limit)).send().await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error_type, ERR_KEY_TOO_LARGE);
 }
 
 #[tokio::test]
 async fn test_client_rejects_oversized_key_without_contacting_server() {
     // Uses an unbound address — if the client pre-flight works, no connection is attempted
-    let client = Client::new(ClientConfig {
-        topology: Topology { primary_addr: "127.0.0.1:59212".to_string(), replica_addr: None },
-    });
-    let oversized_key = "a".repeat(MAX_KEY_SIZE + 1);
+    let client = Client::new(ClientConfig::new(Topology {
+        primary_addr: "127.0.0.1:59212".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    }));
+    let oversized_key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
 
     let result = client.get(&oversized_key).await;
 
@@ -378,10 +513,13 @@ async fn test_client_rejects_oversized_key_without_contacting_server() {
 #[tokio::test]
 async fn test_client_rejects_oversized_value_without_contacting_server() {
     // Uses an unbound address — if the client pre-flight works, no connection is attempted
-    let client = Client::new(ClientConfig {
-        topology: Topology { primary_addr: "127.0.0.1:59212".to_string(), replica_addr: None },
-    });
-    let oversized_value = vec![0u8; MAX_VALUE_SIZE + 1];
+    let client = Client::new(ClientConfig::new(Topology {
+        primary_addr: "127.0.0.1:59212".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    }));
+    let oversized_value = vec![0u8; Limits::DEFAULT.max_value_size + 1];
 
     let result = client.put("my_key", &oversized_value).await;
 
@@ -397,17 +535,78 @@ async fn test_expired_entry_behavior() {
 
     // Epoch 1 is well in the past — entry is immediately expired
     let version = client.put_with_ttl("ttl_key", b"stale value", 1).await.expect("put_with_ttl failed");
-    assert!(version > 0);
+    assert!(version > Version(0));
 
     // Strong guarantee: expired entry is treated as not found
     assert!(matches!(client.get("ttl_key").await, Err(TransDbError::KeyNotFound(_))));
 
     // Soft guarantee: expired entry is returned with expired=true
     let result = client.get_allowing_expired("ttl_key").await.expect("get_allowing_expired failed");
-    assert_eq!(result.value, b"stale value");
+    assert_eq!(result.value.as_ref(), b"stale value");
     assert!(result.expired);
 }
 
+#[tokio::test]
+async fn test_strict_ttl_reclaims_expired_entry_on_read() {
+    let addr = start_server(ServerConfig {
+        address: "127.0.0.1:0".parse().unwrap(),
+        role: NodeRole::Primary,
+        topology: None,
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: None,
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: None,
+        read_only: false,
+        content_validator: None,
+        tls: None,
+        get_cache_size: None,
+        read_snapshot_interval: None,
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: true,
+    })
+    .await;
+    let topology = Topology { primary_addr: addr.to_string(), replica_addr: None, primary_id: None, replica_id: None };
+    let client = Client::new(ClientConfig::new(topology));
+
+    client.put_with_ttl("ttl_key", b"stale value", 1).await.unwrap();
+    assert_eq!(client.stats().await.unwrap().expired_count, 1);
+
+    // Even the soft read gets a true 404, and the read removed the entry.
+    assert!(matches!(client.get_allowing_expired("ttl_key").await, Err(TransDbError::KeyNotFound(_))));
+    assert_eq!(client.stats().await.unwrap().expired_count, 0);
+    assert!(matches!(client.get("ttl_key").await, Err(TransDbError::KeyNotFound(_))));
+
+    let far_future = 4_000_000_000;
+    client.put_with_ttl("live_key", b"fresh", far_future).await.unwrap();
+    assert_eq!(client.get("live_key").await.unwrap().value.as_ref(), b"fresh");
+}
+
+#[tokio::test]
+async fn test_extend_ttl_many_revives_expired_session_keys() {
+    let client = start_cluster().await.primary;
+
+    client.put_with_ttl("session_a", b"a", 1).await.unwrap();
+    client.put("session_b", b"b").await.unwrap();
+    client.delete("session_b").await.unwrap();
+    let v_c = client.put("session_c", b"c").await.unwrap();
+
+    let far_future = 4_000_000_000;
+    let result = client.extend_ttl_many(&["session_a", "session_b", "session_c"], far_future).await.unwrap();
+
+    // session_a had already expired and session_b is deleted; neither is extended.
+    assert_eq!(result.updated, vec!["session_c"]);
+    assert_eq!(result.not_found, vec!["session_a", "session_b"]);
+    assert!(matches!(client.get("session_a").await, Err(TransDbError::KeyNotFound(_))));
+
+    let c = client.get("session_c").await.unwrap();
+    assert_eq!(c.value.as_ref(), b"c");
+    assert_eq!(c.version, v_c, "TTL extension must not change the version");
+}
+
 #[tokio::test]
 async fn test_get_returns_ok_for_entry_with_future_ttl() {
     let client = start_cluster().await.primary;
@@ -416,11 +615,11 @@ async fn test_get_returns_ok_for_entry_with_future_ttl() {
     client.put_with_ttl("future_key", b"fresh value", 4_102_444_800).await.expect("put_with_ttl failed");
 
     let result = client.get("future_key").await.expect("get failed");
-    assert_eq!(result.value, b"fresh value");
+    assert_eq!(result.value.as_ref(), b"fresh value");
     assert!(!result.expired);
 
     let result = client.get_allowing_expired("future_key").await.expect("get_allowing_expired failed");
-    assert_eq!(result.value, b"fresh value");
+    assert_eq!(result.value.as_ref(), b"fresh value");
     assert!(!result.expired);
 }
 
@@ -463,16 +662,640 @@ async fn test_set_target_routes_to_replica_and_back() {
 
     // Primary: writes work
     let version = client.put("k", b"v").await.expect("put to primary failed");
-    assert!(version > 0);
+    assert!(version > Version(0));
 
     // Redirect to replica: all operations rejected with 405
-    client.set_target(&replica_addr);
+    client.set_target(&replica_addr).unwrap();
     assert!(matches!(client.get("k").await, Err(TransDbError::HttpError(405, _))));
     assert!(matches!(client.put("k", b"v2").await, Err(TransDbError::HttpError(405, _))));
     assert!(matches!(client.delete("k").await, Err(TransDbError::HttpError(405, _))));
 
     // Redirect back to primary: reads/writes work again
-    client.set_target(&primary_addr);
+    client.set_target(&primary_addr).unwrap();
     let result = client.get("k").await.expect("get from primary failed");
-    assert_eq!(result.value, b"v");
+    assert_eq!(result.value.as_ref(), b"v");
+}
+
+// --- Server version ---
+
+#[tokio::test]
+async fn test_server_info_reports_the_build_on_both_roles() {
+    let cluster = start_cluster().await;
+
+    let primary = cluster.primary.server_info().await.expect("primary must answer GET /version");
+    // The workspace shares one version, so this crate's is the server's too.
+    assert_eq!(primary.server_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(primary.protocol_version, PROTOCOL_VERSION);
+    assert_eq!(primary.role, "primary");
+    assert!(!primary.git_hash.is_empty());
+    assert!(!primary.build_date.is_empty());
+
+    let replica = cluster.replica.server_info().await.expect("a replica must not refuse GET /version");
+    assert_eq!(replica.role, "replica");
+    assert_eq!(replica.server_version, primary.server_version);
+}
+
+// --- Key encoding ---
+
+#[tokio::test]
+async fn test_url_unsafe_keys_round_trip() {
+    let client = start_cluster().await.primary;
+    let keys = ["/user/session", "a/b", "key?version=1", "key#fragment", "key with spaces", "100%", "キー"];
+
+    for key in keys {
+        client.put(key, key.as_bytes()).await.unwrap();
+    }
+    for key in keys {
+        assert_eq!(client.get(key).await.unwrap().value.as_ref(), key.as_bytes(), "{}", key);
+    }
+    // "a/b" is its own key, not a path under "a".
+    assert!(matches!(client.get("a").await, Err(TransDbError::KeyNotFound(_))));
+    assert_eq!(client.key_count().await.unwrap(), keys.len());
+
+    client.delete("a/b").await.unwrap();
+    assert!(matches!(client.get("a/b").await, Err(TransDbError::KeyDeleted { .. })));
+}
+
+// --- Ranged GET ---
+
+#[tokio::test]
+async fn test_get_range_returns_slice_of_large_value() {
+    let client = start_cluster().await.primary;
+    let value: Vec<u8> = (0..Limits::DEFAULT.max_value_size).map(|i| (i % 251) as u8).collect();
+    let version = client.put("blob", &value).await.unwrap();
+
+    let head = client.get_range("blob", 0, 999).await.unwrap();
+    assert_eq!(head.data.as_ref(), &value[..1000]);
+    assert_eq!(head.total_bytes, Limits::DEFAULT.max_value_size as u64);
+    assert_eq!(head.version, version);
+
+    let tail = client.get_range("blob", Limits::DEFAULT.max_value_size as u64 - 10, u64::MAX).await.unwrap();
+    assert_eq!(tail.data.as_ref(), &value[Limits::DEFAULT.max_value_size - 10..]);
+    assert!(matches!(
+        client.get_range("blob", Limits::DEFAULT.max_value_size as u64, u64::MAX).await,
+        Err(TransDbError::RangeNotSatisfiable { total_bytes: Some(_), .. })
+    ));
+}
+
+// --- Eventual reads ---
+
+#[tokio::test]
+async fn test_get_eventual_catches_up_with_the_read_snapshot() {
+    let addr = start_server(ServerConfig {
+        address: "127.0.0.1:0".parse().unwrap(),
+        role: NodeRole::Primary,
+        topology: None,
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: None,
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: None,
+        read_only: false,
+        content_validator: None,
+        tls: None,
+        get_cache_size: None,
+        read_snapshot_interval: Some(Duration::from_millis(50)),
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: false,
+    })
+    .await;
+    let topology = Topology { primary_addr: addr.to_string(), replica_addr: None, primary_id: None, replica_id: None };
+    let client = Client::new(ClientConfig::new(topology));
+
+    let version = client.put("k", b"v").await.unwrap();
+    assert_eq!(client.get("k").await.unwrap().version, version, "strong reads see the write at once");
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    let result = loop {
+        match client.get_eventual("k").await {
+            Ok(result) => break result,
+            Err(TransDbError::KeyNotFound(_)) if tokio::time::Instant::now() < deadline => {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            Err(e) => panic!("eventual read never saw the write: {e}"),
+        }
+    };
+    assert_eq!(result.value.as_ref(), b"v");
+    assert_eq!(result.version, version);
+    assert!(result.sequence >= version.get());
+}
+
+// --- Bulk delete ---
+
+#[tokio::test]
+async fn test_delete_many_removes_every_key() {
+    let client = start_cluster().await.primary;
+    let keys: Vec<String> = (0..100).map(|i| format!("bulk-{}", i)).collect();
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    for key in &keys {
+        client.put(key, b"v").await.unwrap();
+    }
+
+    let results = client.delete_many(&keys).await.unwrap();
+    assert_eq!(results.len(), keys.len());
+    for ((deleted, result), key) in results.iter().zip(&keys) {
+        assert_eq!(deleted, key);
+        assert!(matches!(result, Ok(Some(_))), "{}: {:?}", key, result);
+        assert!(matches!(client.get(key).await, Err(TransDbError::KeyDeleted { .. })));
+    }
+    assert_eq!(client.key_count().await.unwrap(), 0);
+}
+
+// --- Conditional DELETE ---
+
+#[tokio::test]
+async fn test_delete_if_match_reasserts_only_while_still_deleted() {
+    let client = start_cluster().await.primary;
+
+    let v1 = client.put("k", b"v").await.unwrap();
+    let tombstone = client.delete_if_match("k", v1).await.unwrap();
+    assert_eq!(client.delete_if_match("k", tombstone).await, Ok(tombstone));
+
+    let v2 = client.put("k", b"v2").await.unwrap();
+    let expected = TransDbError::PreconditionFailed { key: "k".to_string(), current_version: Some(v2) };
+    assert_eq!(client.delete_if_match("k", tombstone).await, Err(expected));
+    assert_eq!(client.get("k").await.unwrap().value.as_ref(), b"v2");
+}
+
+#[tokio::test]
+async fn test_append_builds_value_across_calls() {
+    let client = start_cluster().await.primary;
+    let v1 = client.append("log", b"a").await.unwrap();
+    let v2 = client.append("log", b"bc").await.unwrap();
+    assert!(v2 > v1);
+    let result = client.get("log").await.unwrap();
+    assert_eq!(result.value.as_ref(), b"abc");
+    assert_eq!(result.version, v2);
+}
+
+#[tokio::test]
+async fn test_pool_and_keepalive_knobs_complete_round_trip() {
+    let addr = start_node(NodeRole::Primary).await;
+    let base = || ClientConfig::new(Topology {
+        primary_addr: addr.to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    });
+    let configs = [
+        ("pool_max_idle_per_host", base().with_pool_max_idle_per_host(0)),
+        ("pool_idle_timeout", base().with_pool_idle_timeout(Duration::from_millis(1))),
+        ("tcp_keepalive", base().with_tcp_keepalive(Duration::from_secs(15))),
+        ("http2_prior_knowledge", base().with_http2_prior_knowledge()),
+    ];
+
+    for (knob, config) in configs {
+        let client = Client::new(config);
+        let version = client.put(knob, b"v").await.unwrap_or_else(|e| panic!("{knob}: {e}"));
+        let result = client.get(knob).await.unwrap_or_else(|e| panic!("{knob}: {e}"));
+        assert_eq!((result.value.as_ref(), result.version), (&b"v"[..], version), "{knob}");
+    }
+}
+
+// --- Mutexes ---
+
+#[tokio::test]
+async fn test_mutex_is_exclusive_until_released() {
+    let client = start_cluster().await.primary;
+
+    assert!(client.acquire_mutex("deploy", "worker-1", 30).await.unwrap().acquired);
+    let contended = client.acquire_mutex("deploy", "worker-2", 30).await.unwrap();
+    assert!(!contended.acquired);
+    assert_eq!(contended.owner.as_deref(), Some("worker-1"));
+
+    assert!(!client.renew_mutex("deploy", "worker-2", 30).await.unwrap().acquired);
+    assert!(client.renew_mutex("deploy", "worker-1", 60).await.unwrap().acquired);
+    assert_eq!(client.release_mutex("deploy", "worker-2").await, Ok(false));
+    assert_eq!(client.release_mutex("deploy", "worker-1").await, Ok(true));
+    assert!(client.acquire_mutex("deploy", "worker-2", 30).await.unwrap().acquired);
+}
+
+// --- test-admin ---
+
+#[tokio::test]
+async fn test_delete_all_empties_store_and_idempotency_cache() {
+    let cluster = start_cluster().await;
+    let client = cluster.primary;
+    client.put("a", b"1").await.unwrap();
+    client.put("b", b"2").await.unwrap();
+    client.delete("b").await.unwrap();
+    client
+        .raw_request(Method::PUT, "c", idempotency_headers("reset-token"), Some(Bytes::from("3")))
+        .await
+        .unwrap();
+
+    // Without ?confirm=yes nothing is deleted.
+    let url = format!("{}?confirm=no", client.build_key_url("").trim_end_matches('/'));
+    let response = reqwest::Client::new().delete(&url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(client.key_count().await, Ok(2));
+
+    assert_eq!(client.delete_all().await, Ok(2));
+    assert_eq!(client.key_count().await, Ok(0));
+    assert!(matches!(client.get("a").await, Err(TransDbError::KeyDeleted { .. })));
+    assert_eq!(client.delete_all().await, Ok(0));
+
+    // The token is forgotten: reusing it for another key is a fresh write, not a 422.
+    let response = client
+        .raw_request(Method::PUT, "d", idempotency_headers("reset-token"), Some(Bytes::from("4")))
+        .await
+        .unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+
+    assert!(matches!(cluster.replica.delete_all().await, Err(TransDbError::HttpError(405, _))));
+}
+
+// --- Tracing ---
+
+type SpanFields = HashMap<String, String>;
+
+/// Subscriber recording the fields of every client request span, in the order they were opened.
+/// Other spans get an ID but record nothing.
+#[derive(Clone, Default)]
+struct RequestSpans(Arc<Mutex<Vec<Option<SpanFields>>>>);
+
+impl RequestSpans {
+    fn recorded(&self) -> Vec<SpanFields> {
+        self.0.lock().unwrap().iter().flatten().cloned().collect()
+    }
+}
+
+struct FieldRecorder<'a>(&'a mut SpanFields);
+
+impl tracing::field::Visit for FieldRecorder<'_> {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl tracing::Subscriber for RequestSpans {
+    fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+        let mut spans = self.0.lock().unwrap();
+        let fields = (span.metadata().name() == transdb_client::trace::SPAN_NAME).then(|| {
+            let mut fields = HashMap::new();
+            span.record(&mut FieldRecorder(&mut fields));
+            fields
+        });
+        spans.push(fields);
+        tracing::span::Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+        if let Some(fields) = &mut self.0.lock().unwrap()[span.into_u64() as usize - 1] {
+            values.record(&mut FieldRecorder(fields));
+        }
+    }
+
+    fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+    fn event(&self, _: &tracing::Event<'_>) {}
+    fn enter(&self, _: &tracing::span::Id) {}
+    fn exit(&self, _: &tracing::span::Id) {}
+}
+
+#[tokio::test]
+async fn test_client_requests_emit_spans_correlated_with_the_server() {
+    let spans = RequestSpans::default();
+    // The test runtime is single-threaded, so every client request is sent on this thread.
+    let _guard = tracing::subscriber::set_default(spans.clone());
+    let cluster = start_cluster().await;
+
+    cluster.primary.put("traced key", b"v").await.unwrap();
+    cluster.primary.get("traced key").await.unwrap();
+    let refused = cluster.replica.get("traced key").await.unwrap_err();
+
+    let recorded = spans.recorded();
+    assert_eq!(recorded.len(), 3, "{recorded:?}");
+    let get = &recorded[1];
+    assert_eq!((get["method"].as_str(), get["key"].as_str()), ("GET", "traced key"));
+    assert_eq!(get["target"], cluster.primary.target());
+    assert_eq!(get["status"], "200");
+    assert!(get["latency_ms"].parse::<f64>().unwrap() > 0.0);
+    assert!(!get["request_id"].is_empty());
+    let traceparent: Vec<_> = get["traceparent"].split('-').collect();
+    assert_eq!(traceparent.iter().map(|part| part.len()).collect::<Vec<_>>(), [2, 32, 16, 2]);
+    assert_ne!(recorded[0]["request_id"], get["request_id"]);
+
+    // The server adopted the request ID the client sent and names it in its error body.
+    let replica_get = &recorded[2];
+    assert_eq!(replica_get["status"], "405");
+    assert!(refused.to_string().contains(&format!("(request_id={})", replica_get["request_id"])), "{refused}");
+}
+
+// --- Request signing ---
+
+#[tokio::test]
+async fn test_signed_client_is_accepted_and_unsigned_writes_are_rejected() {
+    let addr = start_server(ServerConfig {
+        address: "127.0.0.1:0".parse().unwrap(),
+        role: NodeRole::Primary,
+        topology: None,
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: Some("shared-secret".to_string()),
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: None,
+        read_only: false,
+        content_validator: None,
+        tls: None,
+        get_cache_size: None,
+        read_snapshot_interval: None,
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: false,
+    })
+    .await;
+    let topology = Topology { primary_addr: addr.to_string(), replica_addr: None, primary_id: None, replica_id: None };
+
+    let signed = Client::new(ClientConfig::new(topology.clone())).with_signing_key("shared-secret");
+    let version = signed.put("k", b"v").await.expect("signed put failed");
+    assert_eq!(signed.extend_ttl_many(&["k"], 4_102_444_800).await.unwrap().updated, vec!["k".to_string()]);
+    // Mutex calls carry their parameters in the query, which the signature covers too.
+    signed.acquire_mutex("m", "a", 30).await.expect("signed acquire failed");
+    assert!(signed.release_mutex("m", "a").await.expect("signed release failed"));
+
+    let unsigned = Client::new(ClientConfig::new(topology.clone()));
+    assert!(matches!(unsigned.put("k", b"v2").await, Err(TransDbError::Unauthorized(_))));
+    assert!(matches!(unsigned.delete("k").await, Err(TransDbError::Unauthorized(_))));
+    assert_eq!(unsigned.get("k").await.unwrap().version, version, "reads need no signature");
+
+    let wrong_key = Client::new(ClientConfig::new(topology)).with_signing_key("guess");
+    assert!(matches!(wrong_key.delete("k").await, Err(TransDbError::Unauthorized(_))));
+    assert!(signed.delete("k").await.unwrap().is_some());
+}
+
+// --- Admin port ---
+
+#[tokio::test]
+async fn test_admin_port_splits_routes_between_listeners() {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    let (admin_ready_tx, admin_ready_rx) = oneshot::channel();
+    let server = Server::new(ServerConfig {
+        address: "127.0.0.1:0".parse().unwrap(),
+        role: NodeRole::Primary,
+        topology: None,
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: None,
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: Some(0),
+        read_only: false,
+        content_validator: None,
+        tls: None,
+        get_cache_size: None,
+        read_snapshot_interval: None,
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: false,
+    });
+    tokio::spawn(async move {
+        server.run(ready_tx, Some(admin_ready_tx)).await.expect("server failed");
+    });
+    let addr = timeout(SERVER_READY_TIMEOUT, ready_rx).await.unwrap().unwrap();
+    let admin_addr = timeout(SERVER_READY_TIMEOUT, admin_ready_rx).await.unwrap().unwrap();
+    assert_ne!(addr, admin_addr);
+
+    let client = Client::new(ClientConfig::new(Topology {
+        primary_addr: addr.to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    }));
+    client.put("k", b"v").await.unwrap();
+    assert!(matches!(client.stats().await, Err(TransDbError::KeyNotFound(_))), "no /stats on the data port");
+
+    let admin = Client::new(ClientConfig::new(Topology {
+        primary_addr: admin_addr.to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    }));
+    assert_eq!(admin.stats().await.unwrap().live_count, 1);
+    assert!(matches!(admin.get("k").await, Err(TransDbError::KeyNotFound(_))));
+    assert_eq!(admin.delete_all().await, Ok(1));
+    assert!(matches!(client.delete_all().await, Err(TransDbError::HttpError(405, _))));
+}
+
+// --- Content validation ---
+
+#[tokio::test]
+async fn test_content_validator_rejection_surfaces_as_typed_error() {
+    let addr = start_server(ServerConfig {
+        address: "127.0.0.1:0".parse().unwrap(),
+        role: NodeRole::Primary,
+        topology: None,
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: None,
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: None,
+        read_only: false,
+        content_validator: Some(Arc::new(JsonValidator)),
+        tls: None,
+        get_cache_size: None,
+        read_snapshot_interval: None,
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: false,
+    })
+    .await;
+    let client = Client::new(ClientConfig::new(Topology {
+        primary_addr: addr.to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    }));
+
+    let err = client.put("doc", b"{not json").await.unwrap_err();
+    assert!(matches!(&err, TransDbError::ContentRejected { key, detail }
+        if key == "doc" && detail.starts_with("value is not valid JSON")), "{err:?}");
+    assert!(!err.is_retryable());
+    assert!(matches!(client.get("doc").await, Err(TransDbError::KeyNotFound(_))));
+
+    client.put("doc", br#"{"ok": true}"#).await.unwrap();
+}
+
+// --- JSON values ---
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Profile {
+    name: String,
+    tags: Vec<String>,
+    visits: u32,
+}
+
+#[tokio::test]
+async fn test_json_values_round_trip() {
+    let addr = start_node(NodeRole::Primary).await;
+    let topology = Topology { primary_addr: addr.to_string(), replica_addr: None, primary_id: None, replica_id: None };
+    let client = Client::new(ClientConfig::new(topology));
+    let profile = Profile { name: "ada".to_string(), tags: vec!["admin".to_string()], visits: 3 };
+
+    let version = client.put_json("profile", &profile).await.unwrap();
+    assert_eq!(client.get_json::<Profile>("profile").await, Ok(profile.clone()));
+    assert_eq!(client.get("profile").await.unwrap().value, serde_json::to_vec(&profile).unwrap());
+
+    let err = client.put_json_if_absent("profile", &Profile { visits: 4, ..profile.clone() }).await.unwrap_err();
+    assert!(matches!(&err, TransDbError::VersionConflict { keys } if keys == &["profile"]), "{err:?}");
+    assert_eq!(client.get("profile").await.unwrap().version, version);
+    let created = client.put_json_if_absent("other", &profile).await.unwrap();
+    assert!(created > version);
+    assert_eq!(client.get_json::<Profile>("other").await, Ok(profile));
+
+    client.put("raw", b"not json").await.unwrap();
+    assert!(matches!(client.get_json::<Profile>("raw").await, Err(TransDbError::DeserializationError(_))));
+    assert!(matches!(client.get_json::<Profile>("missing").await, Err(TransDbError::KeyNotFound(_))));
+}
+
+// --- Namespaces ---
+
+#[tokio::test]
+async fn test_same_key_in_two_namespaces_is_independent() {
+    let addr = start_node(NodeRole::Primary).await;
+    let topology = Topology { primary_addr: addr.to_string(), replica_addr: None, primary_id: None, replica_id: None };
+    let config = ClientConfig::new(topology);
+    let a = Client::new(config.clone().with_namespace("tenant-a").unwrap());
+    let b = Client::new(config.clone().with_namespace("tenant-b").unwrap());
+    let default = Client::new(config);
+
+    assert!(matches!(a.get("k").await, Err(TransDbError::KeyNotFound(_))), "reads do not create a namespace");
+    assert!(default.namespaces().await.unwrap().is_empty());
+
+    let a1 = a.put("k", b"from a").await.unwrap();
+    let a2 = a.put("k", b"from a again").await.unwrap();
+    let b1 = b.put("k", b"from b").await.unwrap();
+    // Each namespace has its own version counter.
+    assert_eq!((a1, a2, b1), (Version(1), Version(2), Version(1)));
+
+    assert_eq!(a.get("k").await.unwrap().value.as_ref(), b"from a again");
+    assert_eq!(b.get("k").await.unwrap().value.as_ref(), b"from b");
+    assert!(matches!(default.get("k").await, Err(TransDbError::KeyNotFound(_))));
+
+    assert_eq!(b.delete("k").await.unwrap(), Some(Version(2)));
+    assert_eq!(a.get("k").await.unwrap().version, a2);
+    assert_eq!(default.namespaces().await.unwrap(), ["tenant-a", "tenant-b"]);
+}
+
+// --- TLS ---
+
+#[tokio::test]
+async fn test_tls_round_trip_and_plaintext_client_fails_cleanly() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let addr = start_server(ServerConfig {
+        address: "127.0.0.1:0".parse().unwrap(),
+        role: NodeRole::Primary,
+        topology: None,
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: None,
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: None,
+        read_only: false,
+        content_validator: None,
+        tls: Some(TlsConfig { cert_path: fixtures.join("tls_cert.pem"), key_path: fixtures.join("tls_key.pem") }),
+        get_cache_size: None,
+        read_snapshot_interval: None,
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: false,
+    })
+    .await;
+    let config = ClientConfig::new(Topology {
+        primary_addr: addr.to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    });
+
+    let cert = std::fs::read(fixtures.join("tls_cert.pem")).unwrap();
+    let client = Client::new(config.clone().with_tls().with_root_certificate(&cert).unwrap());
+    let version = client.put("k", b"over tls").await.unwrap();
+    let result = client.get("k").await.unwrap();
+    assert_eq!((result.value.as_ref(), result.version), (&b"over tls"[..], version));
+
+    // Plain HTTP never completes a handshake: an error, not a hang or a bogus response.
+    let plaintext = Client::new(config.clone());
+    let result = timeout(Duration::from_secs(5), plaintext.get("k")).await.expect("plaintext client hung");
+    assert!(matches!(result, Err(TransDbError::NetworkError(_))), "{result:?}");
+
+    // Without the self-signed certificate trusted, verification fails, and retrying will not help.
+    let untrusting = Client::new(config.with_tls());
+    let result = untrusting.get("k").await;
+    assert!(matches!(result, Err(TransDbError::TlsError(ref msg)) if msg.contains("certificate")), "{result:?}");
+    assert!(!result.unwrap_err().is_retryable());
+}
+
+// --- FakeClient ---
+
+/// The put/get/delete/TTL contract both [`KvClient`] implementations must honour.
+async fn assert_kv_contract(client: &impl KvClient) {
+    let v1 = client.put("k", b"one").await.unwrap();
+    let got = client.get("k").await.unwrap();
+    assert_eq!((got.value.as_ref(), got.version, got.expired, got.expires_at), (&b"one"[..], v1, false, None));
+    assert!(got.sequence >= v1.get());
+    let v2 = client.put("k", b"two").await.unwrap();
+    assert!(v2 > v1);
+    assert_eq!(client.get("k").await.unwrap().value.as_ref(), b"two");
+
+    let tombstone = client.delete("k").await.unwrap().expect("a live key is deleted with a tombstone");
+    assert!(tombstone > v2);
+    match client.get("k").await {
+        Err(TransDbError::KeyDeleted { key, version }) => assert_eq!((key.as_str(), version), ("k", tombstone.get())),
+        other => panic!("expected KeyDeleted, got {other:?}"),
+    }
+    assert_eq!(client.delete("k").await.unwrap(), None);
+    assert_eq!(client.delete("never").await.unwrap(), None);
+    assert!(matches!(client.get("never").await, Err(TransDbError::KeyNotFound(_))));
+
+    client.put_with_ttl("stale", b"v", 1).await.unwrap();
+    assert!(matches!(client.get("stale").await, Err(TransDbError::KeyNotFound(_))));
+    assert!(client.delete("stale").await.unwrap().is_some(), "an expired value is still replaced by a tombstone");
+    client.put_with_ttl("fresh", b"v", 4_102_444_800).await.unwrap();
+    assert_eq!(client.get("fresh").await.unwrap().expires_at, Some(4_102_444_800));
+
+    assert!(matches!(client.put("", b"v").await, Err(TransDbError::InvalidKey { .. })));
+    let too_long = "k".repeat(Limits::default().max_key_size + 1);
+    assert!(matches!(client.get(&too_long).await, Err(TransDbError::KeyTooLarge(_))));
+}
+
+#[tokio::test]
+async fn test_fake_client_matches_client_contract() {
+    let client = start_cluster().await.primary;
+    assert_kv_contract(&client).await;
+    assert_kv_contract(&FakeClient::new()).await;
+}
+
+#[tokio::test]
+async fn test_fake_client_expires_entries_on_its_clock() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let client = FakeClient::with_clock(clock.clone());
+
+    client.put_with_ttl("session", b"v", 1_010).await.unwrap();
+    assert_eq!(client.get("session").await.unwrap().expires_at, Some(1_010));
+    clock.advance(10);
+    assert!(matches!(client.get("session").await, Err(TransDbError::KeyNotFound(_))));
+
+    let version = client.put("gone", b"v").await.unwrap();
+    let tombstone = client.delete("gone").await.unwrap().unwrap();
+    assert!(tombstone > version);
+    clock.advance(transdb_client::fake::TOMBSTONE_TTL_SECS);
+    assert!(matches!(client.get("gone").await, Err(TransDbError::KeyNotFound(_))), "tombstones lapse");
 }
@@ -0,0 +1,23 @@
+//! Stamps the binary with the commit and date it was built from, for `GET /version`. Outside a
+//! git checkout, or without `git` or `date` on the path, both read `unknown`.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rustc-env=TRANSDB_GIT_HASH={}", output_of("git", &["rev-parse", "--short", "HEAD"]));
+    println!("cargo:rustc-env=TRANSDB_BUILD_DATE={}", output_of("date", &["+%Y-%m-%d"]));
+    // Without `rerun-if-changed` lines the script reruns whenever a file in the crate changes,
+    // which keeps the stamp current for every rebuild of the server itself.
+}
+
+fn output_of(program: &str, args: &[&str]) -> String {
+    Command::new(program)
+        .args(args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|stdout| stdout.trim().to_string())
+        .filter(|stdout| !stdout.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
@@ -0,0 +1,58 @@
+//! Endpoints for resetting a server between test cases. Only compiled with the `test-admin`
+//! feature, so production builds cannot expose them.
+
+use std::sync::atomic::Ordering;
+
+use axum::{
+    extract::{rejection::QueryRejection, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tokio::time::timeout;
+use transdb_common::{DeleteAllResult, Version, ERR_INVALID_QUERY, ERR_NOT_PRIMARY};
+
+use crate::config::{LOCK_TIMEOUT, TOMBSTONE_TTL_SECS};
+use crate::{error_response, lock_timeout_response, AppState, NodeRole};
+
+/// Query string accepted by `DELETE /keys`; only `?confirm=yes` is acted on.
+#[derive(Debug, Deserialize)]
+pub struct DeleteAllParams {
+    pub confirm: Option<String>,
+}
+
+/// Handler for DELETE /keys?confirm=yes — tombstones every live key and clears the idempotency
+/// cache under one write lock, returning [`DeleteAllResult`]. 400 without `confirm=yes`.
+pub async fn handle_delete_all(
+    State(state): State<AppState>,
+    params: Result<Query<DeleteAllParams>, QueryRejection>,
+) -> Response {
+    if state.role == NodeRole::Replica {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+
+    if !matches!(params, Ok(Query(DeleteAllParams { confirm: Some(ref confirm) })) if confirm == "yes") {
+        return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_QUERY, "DELETE /keys requires ?confirm=yes");
+    }
+
+    let mut db_guard = match timeout(LOCK_TIMEOUT, state.db.write()).await {
+        Ok(guard) => guard,
+        Err(_) => return lock_timeout_response(),
+    };
+    let db = &mut *db_guard;
+
+    let clock = state.clock.as_ref();
+    let expires_at = Some(clock.unix_now_secs() + TOMBSTONE_TTL_SECS);
+    let mut deleted_count = 0;
+    for entry in db.store.values_mut().filter(|e| e.is_live(clock)) {
+        entry.value = None;
+        entry.version = Version(db.next_version.fetch_add(1, Ordering::Relaxed) + 1);
+        entry.expires_at = expires_at;
+        deleted_count += 1;
+    }
+    db.idempotency_cache.clear();
+    db.invalidate_all();
+
+    Json(DeleteAllResult { deleted_count }).into_response()
+}
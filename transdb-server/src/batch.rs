@@ -0,0 +1,123 @@
+//! Group commit: PUTs and DELETEs are queued to a single batcher task that applies many writes
+//! under one write-lock acquisition.
+
+use axum::{body::Bytes, response::Response};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::timeout;
+use transdb_common::Version;
+
+use crate::config::LOCK_TIMEOUT;
+use crate::request_id;
+use crate::content::ContentValidator;
+use crate::{apply_append, apply_delete, apply_put, lock_timeout_response, AppendCheck, Clock, Db, IdempotencyScope};
+
+/// Group-commit settings; see [`ServerConfig::write_batch`](crate::ServerConfig).
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteBatchConfig {
+    /// Flush as soon as this many writes are queued.
+    pub max_batch_size: usize,
+    /// Flush at most this long after the first write of a batch arrived.
+    pub flush_interval_ms: u64,
+}
+
+/// A validated write, ready to be applied to the store.
+pub enum WriteOp {
+    Put { key: String, value: Bytes, expires_at: Option<u64> },
+    Delete { key: String, if_match: Option<Version> },
+    /// `content_type` is the request's, for the content validator.
+    Append { key: String, value: Bytes, content_type: Option<String> },
+}
+
+/// What the batcher sends back for a write once its batch is applied.
+pub struct WriteResult {
+    /// The response the handler returns.
+    pub response: Response,
+    /// When the batch obtained the store lock, so the handler's `Server-Timing` can split its
+    /// wait from the time the lock was held up to its write; `None` if the lock timed out.
+    pub locked: Option<Instant>,
+}
+
+/// A write waiting in the batcher queue together with the channel its handler awaits.
+pub struct PendingWrite {
+    pub op: WriteOp,
+    pub idempotency_key: IdempotencyScope,
+    /// The submitting request's `X-Request-Id`, for error bodies built by the batcher.
+    pub request_id: Option<String>,
+    pub reply: oneshot::Sender<WriteResult>,
+}
+
+/// Spawn the batcher task and return the queue handlers should submit to.
+/// The task exits once every sender has been dropped.
+pub fn spawn_batcher(
+    db: Db,
+    clock: Arc<dyn Clock>,
+    max_key_count: Option<usize>,
+    max_value_size: usize,
+    validator: Option<Arc<dyn ContentValidator + Send + Sync>>,
+    config: WriteBatchConfig,
+) -> mpsc::UnboundedSender<PendingWrite> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(run_batcher(db, clock, max_key_count, max_value_size, validator, config, rx));
+    tx
+}
+
+async fn run_batcher(
+    db: Db,
+    clock: Arc<dyn Clock>,
+    max_key_count: Option<usize>,
+    max_value_size: usize,
+    validator: Option<Arc<dyn ContentValidator + Send + Sync>>,
+    config: WriteBatchConfig,
+    mut rx: mpsc::UnboundedReceiver<PendingWrite>,
+) {
+    let max_batch_size = config.max_batch_size.max(1);
+    let flush_interval = Duration::from_millis(config.flush_interval_ms);
+    let mut batch = Vec::with_capacity(max_batch_size);
+
+    while let Some(first) = rx.recv().await {
+        batch.push(first);
+        let deadline = tokio::time::Instant::now() + flush_interval;
+        while batch.len() < max_batch_size {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(write)) => batch.push(write),
+                // Flush interval elapsed, or all senders are gone.
+                _ => break,
+            }
+        }
+
+        let mut db_guard = match timeout(LOCK_TIMEOUT, db.write()).await {
+            Ok(guard) => guard,
+            Err(_) => {
+                for write in batch.drain(..) {
+                    let response = request_id::within(write.request_id, lock_timeout_response);
+                    write.reply.send(WriteResult { response, locked: None }).ok();
+                }
+                continue;
+            }
+        };
+        let locked = Some(Instant::now());
+
+        // Applied in arrival order, so an Idempotency-Key reused within one batch is
+        // replayed (or rejected) exactly as it would be without batching.
+        for write in batch.drain(..) {
+            let response = request_id::within(write.request_id, || match write.op {
+                WriteOp::Put { key, value, expires_at } => {
+                    apply_put(&mut db_guard, clock.as_ref(), max_key_count, key, value, expires_at, write.idempotency_key)
+                }
+                WriteOp::Append { key, value, content_type } => {
+                    let validator = validator.as_deref();
+                    let check = AppendCheck { max_value_size, validator, content_type: content_type.as_deref() };
+                    let idempotency_key = write.idempotency_key;
+                    apply_append(&mut db_guard, clock.as_ref(), max_key_count, &check, key, value, idempotency_key)
+                }
+                WriteOp::Delete { key, if_match } => {
+                    apply_delete(&mut db_guard, clock.as_ref(), key, if_match, write.idempotency_key)
+                }
+            });
+            // The handler may have gone away (client disconnected); the write still stands.
+            write.reply.send(WriteResult { response, locked }).ok();
+        }
+    }
+}
@@ -5,3 +5,18 @@ pub const LOCK_TIMEOUT: Duration = Duration::from_secs(1);
 
 /// How long a tombstone entry lives before the TTL mechanism may expire it (seconds).
 pub const TOMBSTONE_TTL_SECS: u64 = 3600;
+
+/// Largest allowed difference between a signed request's `X-Timestamp` and server time (seconds).
+pub const MAX_SIGNATURE_SKEW_SECS: u64 = 300;
+
+/// Lease length for `/mutex/:name` requests that omit `ttl_secs` (seconds).
+pub const DEFAULT_MUTEX_TTL_SECS: u64 = 30;
+
+/// Longest lease a `/mutex/:name` request may ask for (seconds); a holder renews to keep it longer.
+pub const MAX_MUTEX_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Longest client-supplied `X-Request-Id` that is kept; a longer one is replaced (bytes).
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// `Retry-After` sent with a 503 `OVERLOADED`, when the in-flight limit sheds a request (seconds).
+pub const OVERLOADED_RETRY_AFTER_SECS: u64 = 1;
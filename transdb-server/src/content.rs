@@ -0,0 +1,48 @@
+//! Pluggable validation of stored values; see [`ServerConfig::content_validator`](crate::ServerConfig).
+
+use std::fmt;
+
+/// Why a validator rejected a value; sent to the client as the 422 response's `detail`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentError(pub String);
+
+impl fmt::Display for ContentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Checks a value before it is written: a PUT's body, a multi-cas write, or the whole value
+/// after an append.
+/// `content_type` is the request's `Content-Type` header, if any. Implementations can dispatch
+/// on `key` to enforce per-prefix contracts.
+pub trait ContentValidator: fmt::Debug {
+    fn validate(&self, key: &str, value: &[u8], content_type: Option<&str>) -> Result<(), ContentError>;
+}
+
+/// Accepts only values that parse as JSON.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonValidator;
+
+impl ContentValidator for JsonValidator {
+    fn validate(&self, _key: &str, value: &[u8], _content_type: Option<&str>) -> Result<(), ContentError> {
+        serde_json::from_slice::<serde::de::IgnoredAny>(value)
+            .map(|_| ())
+            .map_err(|e| ContentError(format!("value is not valid JSON: {}", e)))
+    }
+}
+
+/// Accepts only values of at most `max_bytes`, a tighter cap than the server-wide value limit.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxPayloadValidator {
+    pub max_bytes: usize,
+}
+
+impl ContentValidator for MaxPayloadValidator {
+    fn validate(&self, _key: &str, value: &[u8], _content_type: Option<&str>) -> Result<(), ContentError> {
+        if value.len() > self.max_bytes {
+            return Err(ContentError(format!("value is {} bytes, the limit is {}", value.len(), self.max_bytes)));
+        }
+        Ok(())
+    }
+}
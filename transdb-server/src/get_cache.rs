@@ -0,0 +1,105 @@
+//! Optional read cache in front of the store; see [`ServerConfig::get_cache_size`](crate::ServerConfig).
+//!
+//! A hit lets `GET /keys/:key` answer without taking the store lock. Entries are filled under the
+//! read lock and dropped by every write while it holds the write lock (see
+//! [`DbState::invalidate`](crate::DbState::invalidate)), so a hit never returns a value that an
+//! acknowledged write has replaced. TTLs are evaluated at read time, so cached entries expire on
+//! schedule.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::Entry;
+
+/// Handle shared by [`AppState`](crate::AppState), which reads through it, and
+/// [`DbState`](crate::DbState), whose writers invalidate it.
+pub type SharedGetCache = Arc<Mutex<GetCache>>;
+
+#[derive(Debug)]
+struct CachedEntry {
+    entry: Entry,
+    last_used: u64,
+}
+
+/// Least-recently-used map from key to a copy of its store entry, bounded by entry count.
+#[derive(Debug)]
+pub struct GetCache {
+    max_entries: usize,
+    entries: HashMap<String, CachedEntry>,
+    /// `last_used` tick → key, oldest first.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl GetCache {
+    pub fn new(max_entries: usize) -> Self {
+        Self { max_entries, entries: HashMap::new(), recency: BTreeMap::new(), tick: 0, hits: 0, misses: 0 }
+    }
+
+    /// The cached entry for `key`, marking it most recently used. Counts a hit or a miss.
+    pub fn get(&mut self, key: &str) -> Option<Entry> {
+        self.tick += 1;
+        let Some(cached) = self.entries.get_mut(key) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.recency.remove(&cached.last_used);
+        cached.last_used = self.tick;
+        self.recency.insert(self.tick, key.to_string());
+        Some(cached.entry.clone())
+    }
+
+    /// Cache `entry`, evicting the least recently used keys to stay within `max_entries`.
+    pub fn insert(&mut self, key: &str, entry: Entry) {
+        self.invalidate(key);
+        if self.max_entries == 0 {
+            return;
+        }
+        while self.entries.len() >= self.max_entries {
+            let Some((_, oldest)) = self.recency.pop_first() else { break };
+            self.entries.remove(&oldest);
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, key.to_string());
+        self.entries.insert(key.to_string(), CachedEntry { entry, last_used: self.tick });
+    }
+
+    /// Drop `key` because it was written.
+    pub fn invalidate(&mut self, key: &str) {
+        if let Some(cached) = self.entries.remove(key) {
+            self.recency.remove(&cached.last_used);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Lookups answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Lookups that had to go to the store.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+/// Lock `cache`. A panic while it was held cannot have left a torn entry behind, so a poisoned
+/// lock is taken over rather than propagated.
+pub fn lock(cache: &SharedGetCache) -> MutexGuard<'_, GetCache> {
+    cache.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -1,25 +1,64 @@
 use axum::{
-    body::Bytes,
-    extract::{DefaultBodyLimit, Path, State},
+    body::{Body, Bytes},
+    extract::{rejection::QueryRejection, DefaultBodyLimit, Path, Query, State},
     http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware,
     response::{IntoResponse, Response},
-    routing::get,
+    routing::{delete, get, patch, post, put},
     Json, Router,
 };
 use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use socket2::{Domain, Protocol, Socket, TcpKeepalive, Type};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{mpsc, oneshot, RwLock, Semaphore};
 use tokio::time::timeout;
-use transdb_common::{ErrorResponse, Topology, MAX_KEY_SIZE, MAX_VALUE_SIZE};
+use tokio_rustls::TlsAcceptor;
+use transdb_common::{
+    BulkTtlRequest, BulkTtlResult, EntryMetadata, ErrorResponse, KeyList, Limits, StoreStats, Topology,
+    Version, NDJSON_CONTENT_TYPE, ERR_BATCH_TOO_LARGE, ERR_CONTENT_REJECTED, ERR_EMPTY_KEY,
+    ERR_IDEMPOTENCY_KEY_TOO_LARGE, ERR_IDEMPOTENCY_MISMATCH, ERR_INTERNAL, ERR_INVALID_BODY,
+    ERR_INVALID_QUERY, ERR_INVALID_TTL, ERR_KEY_NOT_FOUND, ERR_KEY_TOO_LARGE, ERR_LOCK_TIMEOUT,
+    ERR_MISSING_IDEMPOTENCY_KEY, ERR_NOT_PRIMARY, ERR_PRECONDITION_FAILED,
+    ERR_RANGE_NOT_SATISFIABLE, ERR_READ_ONLY, ERR_STORAGE_FULL, ERR_TOPOLOGY_UNKNOWN,
+    ERR_VALUE_TOO_LARGE, PROTOCOL_VERSION,
+};
 
+#[cfg(feature = "test-admin")]
+pub mod admin;
+pub mod batch;
 pub mod config;
+pub mod content;
+pub mod get_cache;
+pub mod multi_cas;
+pub mod mutex;
+pub mod namespace;
+pub mod overload;
+pub mod request_id;
+pub mod signing;
+pub mod snapshot;
+pub mod tls;
+use batch::{PendingWrite, WriteBatchConfig, WriteOp, WriteResult};
+use content::{ContentError, ContentValidator};
+use get_cache::SharedGetCache;
+use snapshot::SharedSnapshot;
+use namespace::Namespaces;
 use config::{LOCK_TIMEOUT, TOMBSTONE_TTL_SECS};
+use signing::{verify_signature, SignatureCheck};
+use tls::TlsConfig;
 
 /// Abstraction over current time for testability.
 pub trait Clock: Send + Sync {
     fn unix_now_secs(&self) -> u64;
+
+    /// Current time in milliseconds; clocks without sub-second precision inherit this default.
+    fn unix_now_millis(&self) -> u64 {
+        self.unix_now_secs() * 1000
+    }
 }
 
 /// Production clock backed by `SystemTime`.
@@ -32,6 +71,13 @@ impl Clock for SystemClock {
             .unwrap_or_default()
             .as_secs()
     }
+
+    fn unix_now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
 }
 
 /// Role this process plays in the cluster.
@@ -44,7 +90,7 @@ pub enum NodeRole {
 #[derive(Clone, Debug)]
 pub struct Entry {
     pub value: Option<Bytes>, // None = tombstone
-    pub version: u64,
+    pub version: Version,
     pub expires_at: Option<u64>,
 }
 
@@ -56,27 +102,100 @@ impl Entry {
             Some(ts) => clock.unix_now_secs() >= ts,
         }
     }
+
+    /// Returns `true` if the entry holds a value (not a tombstone) that has not expired.
+    pub fn is_live(&self, clock: &dyn Clock) -> bool {
+        self.value.is_some() && !self.is_expired(clock)
+    }
+
+    /// The entry minus its bytes, as reported to clients; `handle_get` builds its entry headers
+    /// from it.
+    pub fn metadata(&self) -> EntryMetadata {
+        match &self.value {
+            Some(value) => EntryMetadata::value(self.version, value.len() as u64, self.expires_at),
+            None => EntryMetadata::tombstone(self.version, self.expires_at),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum HttpMethod {
     Put,
     Delete,
+    /// `PATCH /keys/:key` (append)
+    Append,
+    /// `PATCH /batch/ttl`
+    BatchTtl,
+    /// `POST /multi-cas`
+    MultiCas,
 }
 
 #[derive(Clone, Debug)]
 pub struct IdempotencyRecord {
     pub method: HttpMethod,
+    /// The key a single-key request wrote; for a batch, the [`items_digest`] of its items in the
+    /// canonical form the endpoint compares a reused `Idempotency-Key` against.
     pub key_path: String,
     pub status_code: u16,
-    pub etag: Option<u64>,
-    pub created_at: Instant,
+    pub etag: Option<Version>,
+    /// `X-Previous-Version` of a PUT response, replayed alongside the ETag.
+    pub previous_version: Option<Version>,
+    /// Response body to replay, for endpoints whose result is not captured by `etag`.
+    pub body: Option<Bytes>,
+    /// Unix seconds (from the state's [`Clock`]) when the response was recorded. Unlike an
+    /// `Instant`, it can be serialized and still means the same after a restart.
+    pub created_at: u64,
+}
+
+/// Principal used for requests that carry no bearer token.
+pub const ANONYMOUS_PRINCIPAL: &str = "anonymous";
+
+/// Idempotency cache key: the same `Idempotency-Key` sent by two principals names two writes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IdempotencyScope {
+    pub principal: String,
+    pub key: String,
 }
 
+#[derive(Default)]
 pub struct DbState {
     pub store: HashMap<String, Entry>,
-    pub idempotency_cache: HashMap<String, IdempotencyRecord>,
-    pub next_version: u64,
+    pub idempotency_cache: HashMap<IdempotencyScope, IdempotencyRecord>,
+    /// Global write counter: every write takes the next value as its version, so versions order
+    /// writes across keys, and the current value is the high-water mark GET reports as `X-Sequence`.
+    /// Only advanced under the write lock; shared with [`AppState::sequence`] so that get-cache
+    /// hits can read it without the lock.
+    pub next_version: Arc<AtomicU64>,
+    /// The same cache as [`AppState::get_cache`], so that writers, which only see the store,
+    /// can invalidate it.
+    pub get_cache: Option<SharedGetCache>,
+}
+
+impl DbState {
+    /// Take the next version for a write. The `&mut` borrow means the caller holds the write lock.
+    pub fn allocate_version(&mut self) -> Version {
+        Version(self.next_version.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    /// The global version high-water mark: the version of the latest write, 0 before any.
+    pub fn sequence(&self) -> u64 {
+        self.next_version.load(Ordering::Relaxed)
+    }
+
+    /// Drop `key` from the get cache. Every write calls this while it still holds the write
+    /// lock, so no later read can be served the value it replaced.
+    pub fn invalidate(&self, key: &str) {
+        if let Some(cache) = &self.get_cache {
+            get_cache::lock(cache).invalidate(key);
+        }
+    }
+
+    /// Empty the get cache, for writes that touch every key.
+    pub fn invalidate_all(&self) {
+        if let Some(cache) = &self.get_cache {
+            get_cache::lock(cache).clear();
+        }
+    }
 }
 
 pub type Db = Arc<RwLock<DbState>>;
@@ -86,19 +205,209 @@ pub struct AppState {
     pub db: Db,
     pub clock: Arc<dyn Clock>,
     pub role: NodeRole,
+    /// Queue to the group-commit task; `None` means writes take the lock themselves.
+    pub write_batcher: Option<mpsc::UnboundedSender<PendingWrite>>,
+    /// PUTs that would create a live key beyond this count are rejected with 507.
+    pub max_key_count: Option<usize>,
+    /// Cluster layout served by `GET /topology`; `None` when the node was started without one.
+    pub topology: Option<Topology>,
+    /// HMAC key that PUT, DELETE and PATCH requests must be signed with; `None` disables signing.
+    pub signature_key: Option<Arc<[u8]>>,
+    /// Add `Server-Timing` (lock wait vs. work under the lock) to key GET/PUT/DELETE responses.
+    pub server_timing: bool,
+    /// While set, every write is rejected with 503 `READ_ONLY` and reads are served as usual.
+    /// Shared by all clones of the state; toggled at runtime by `POST /admin/readonly`.
+    pub read_only: Arc<AtomicBool>,
+    /// Values it rejects (PUT bodies, multi-cas writes, appended values) get 422
+    /// `CONTENT_REJECTED` and are not stored.
+    pub content_validator: Option<Arc<dyn ContentValidator + Send + Sync>>,
+    /// Stores behind `/{namespace}/keys/:key`; see [`namespace`].
+    pub namespaces: Namespaces,
+    /// Entries `GET /keys/:key` may serve without taking the store lock; `None` disables it.
+    /// Never set on a namespace's state, whose keys would collide with the default store's.
+    pub get_cache: Option<SharedGetCache>,
+    /// The store's [`DbState::next_version`], installed with `get_cache` so that a hit reports
+    /// `X-Sequence` without taking the lock. Never set on a namespace's state.
+    pub sequence: Option<Arc<AtomicU64>>,
+    /// Periodic copy of the store that `X-Consistency: eventual` reads are served from; `None`
+    /// serves them like any other read. Never set on a namespace's state.
+    pub read_snapshot: Option<SharedSnapshot>,
+    /// Requests the data routes serve at once; more get 503 `OVERLOADED`. Read when the router
+    /// is built. `None` is unlimited.
+    pub max_in_flight: Option<usize>,
+    /// Key, value, idempotency key and batch size limits the handlers enforce.
+    pub limits: Limits,
+    /// Treat an expired value as gone: `GET /keys/:key` removes it from the store and answers a
+    /// plain 404 instead of returning it with `X-Expired: true`.
+    pub strict_ttl: bool,
 }
 
 impl AppState {
     pub fn new(clock: Arc<dyn Clock>, role: NodeRole) -> Self {
         Self {
-            db: Arc::new(RwLock::new(DbState {
-                store: HashMap::new(),
-                idempotency_cache: HashMap::new(),
-                next_version: 0,
-            })),
+            db: Db::default(),
             clock,
             role,
+            write_batcher: None,
+            max_key_count: None,
+            topology: None,
+            signature_key: None,
+            server_timing: false,
+            read_only: Arc::new(AtomicBool::new(false)),
+            content_validator: None,
+            namespaces: Namespaces::default(),
+            get_cache: None,
+            sequence: None,
+            read_snapshot: None,
+            max_in_flight: None,
+            limits: Limits::default(),
+            strict_ttl: false,
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// Route PUT and DELETE through a group-commit batcher task (spawned on the current runtime).
+    /// The batcher snapshots `max_key_count`, `limits` and `content_validator`, so set those first.
+    pub fn with_write_batching(mut self, config: WriteBatchConfig) -> Self {
+        let (db, clock) = (self.db.clone(), self.clock.clone());
+        let (max_key_count, max_value_size) = (self.max_key_count, self.limits.max_value_size);
+        let validator = self.content_validator.clone();
+        self.write_batcher = Some(batch::spawn_batcher(db, clock, max_key_count, max_value_size, validator, config));
+        self
+    }
+
+    /// Serve repeated reads from an LRU cache of up to `max_entries` keys; see [`get_cache`].
+    /// Call before the state is shared, since it installs the cache in the store as well.
+    pub fn with_get_cache(mut self, max_entries: usize) -> Self {
+        let cache = SharedGetCache::new(get_cache::GetCache::new(max_entries).into());
+        let mut db = self.db.try_write().expect("store is not yet shared");
+        db.get_cache = Some(cache.clone());
+        self.sequence = Some(db.next_version.clone());
+        drop(db);
+        self.get_cache = Some(cache);
+        self
+    }
+
+    /// Serve `X-Consistency: eventual` reads from a copy of the store refreshed every
+    /// `interval` by a task spawned on the current runtime; see [`snapshot`].
+    pub fn with_read_snapshot(mut self, interval: Duration) -> Self {
+        self.read_snapshot = Some(snapshot::spawn_refresher(self.db.clone(), interval));
+        self
+    }
+
+    /// Attach `Server-Timing: lock;dur=<ms>, work;dur=<ms>` if enabled. `locked` is when the
+    /// store lock was obtained; `None` (lock timeout) reports the whole wait as `lock`. For a write
+    /// handed to the batcher, `locked` is when its batch got the lock, so `work` covers the writes
+    /// applied ahead of it in the same batch too.
+    fn timed(&self, mut response: Response, started: Instant, locked: Option<Instant>) -> Response {
+        if !self.server_timing {
+            return response;
+        }
+        let now = Instant::now();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let value = match locked {
+            Some(locked) => format!("lock;dur={:.3}, work;dur={:.3}", ms(locked - started), ms(now - locked)),
+            None => format!("lock;dur={:.3}", ms(now - started)),
+        };
+        response.headers_mut().insert("server-timing", HeaderValue::from_str(&value).expect("valid header value"));
+        response
+    }
+}
+
+/// Socket options for the server's listening socket. `nodelay` and `keepalive` are set on the
+/// listener and inherited by the connections it accepts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TcpOptions {
+    /// `SO_REUSEADDR`: rebind a port whose previous socket is still in TIME_WAIT.
+    pub reuse_address: bool,
+    /// `SO_REUSEPORT` (Unix only): let several live sockets share the port.
+    pub reuse_port: bool,
+    /// Maximum queue of accepted-but-not-yet-served connections passed to `listen(2)`.
+    pub backlog: u32,
+    /// `TCP_NODELAY`: send small responses at once instead of letting Nagle's algorithm hold
+    /// them back waiting for the client's ACK.
+    pub nodelay: bool,
+    /// `SO_KEEPALIVE` with this idle time before the first probe, so connections to vanished
+    /// clients are noticed and closed; `None` leaves keepalive off.
+    pub keepalive: Option<Duration>,
+}
+
+impl Default for TcpOptions {
+    fn default() -> Self {
+        // Address reuse and backlog match what `tokio::net::TcpListener::bind` does on Unix.
+        Self {
+            reuse_address: true,
+            reuse_port: false,
+            backlog: 1024,
+            nodelay: true,
+            keepalive: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+impl TcpOptions {
+    /// Bind and listen on `address` with these options. Must be called inside a Tokio runtime.
+    pub fn bind(&self, address: SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+        let socket = Socket::new(Domain::for_address(address), Type::STREAM, Some(Protocol::TCP))?;
+        socket.set_reuse_address(self.reuse_address)?;
+        if self.reuse_port {
+            #[cfg(unix)]
+            socket.set_reuse_port(true)?;
+            #[cfg(not(unix))]
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "SO_REUSEPORT requires Unix"));
+        }
+        socket.set_tcp_nodelay(self.nodelay)?;
+        if let Some(idle) = self.keepalive {
+            socket.set_tcp_keepalive(&TcpKeepalive::new().with_time(idle))?;
         }
+        socket.set_nonblocking(true)?;
+        socket.bind(&address.into())?;
+        socket.listen(self.backlog.min(i32::MAX as u32) as i32)?;
+        tokio::net::TcpListener::from_std(socket.into())
+    }
+}
+
+/// Maximum request body size accepted by each route, in bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteBodyLimits {
+    /// `PUT /keys/:key`
+    pub key_put: usize,
+    /// `PATCH /batch/ttl`
+    pub batch_ttl: usize,
+    /// `POST /multi-cas`
+    pub multi_cas: usize,
+}
+
+impl RouteBodyLimits {
+    /// Body limits that let every request within `limits` through to the handler.
+    pub fn for_limits(limits: &Limits) -> Self {
+        Self {
+            // One byte over `max_value_size` so the handler sees oversized values and returns 400
+            // with a typed error instead of axum's bare 413.
+            key_put: limits.max_value_size + 1,
+            // `max_batch_keys` keys of `max_key_size` bytes, each byte JSON-escaped as `\uXXXX`,
+            // plus quotes/commas and the envelope.
+            batch_ttl: limits.max_batch_keys * (6 * limits.max_key_size + 3) + 64,
+            // One maximum-size value in base64, so the writes of one request share that budget,
+            // plus `max_batch_keys` escaped keys with room for a version or field names each.
+            multi_cas: limits.max_value_size.div_ceil(3) * 4
+                + limits.max_batch_keys * (6 * limits.max_key_size + 64)
+                + 64,
+        }
+    }
+
+    /// The largest body any route accepts.
+    pub fn largest(&self) -> usize {
+        self.key_put.max(self.batch_ttl).max(self.multi_cas)
+    }
+}
+
+impl Default for RouteBodyLimits {
+    fn default() -> Self {
+        Self::for_limits(&Limits::default())
     }
 }
 
@@ -108,6 +417,43 @@ pub struct ServerConfig {
     pub address: SocketAddr,
     pub role: NodeRole,
     pub topology: Option<Topology>,
+    pub body_limits: RouteBodyLimits,
+    /// Group commit for PUT/DELETE; `None` applies each write under its own lock acquisition.
+    pub write_batch: Option<WriteBatchConfig>,
+    /// Cap on live keys; PUTs creating a new key beyond it get 507. `None` means unbounded.
+    pub max_key_count: Option<usize>,
+    /// Require HMAC-signed mutating requests (see [`signing`]); `None` accepts unsigned ones.
+    pub signature_key: Option<String>,
+    pub tcp: TcpOptions,
+    /// Report per-request lock and work durations in a `Server-Timing` header.
+    pub server_timing: bool,
+    /// Serve the admin routes (see [`Server::create_admin_router`]) on this port of `address`'s IP
+    /// instead of on `address`, so they can be firewalled separately. `None` serves everything on
+    /// one port; `Some(0)` picks a free port.
+    pub admin_port: Option<u16>,
+    /// Start in read-only mode; see [`AppState::read_only`].
+    pub read_only: bool,
+    /// Checks every PUT body, multi-cas write, and value an append would produce before it is
+    /// stored; see [`content`]. `None` accepts any bytes.
+    pub content_validator: Option<Arc<dyn ContentValidator + Send + Sync>>,
+    /// Serve HTTPS (data and admin listeners alike) with this certificate; `None` serves plain
+    /// HTTP.
+    pub tls: Option<TlsConfig>,
+    /// Cache up to this many keys' entries so repeated GETs skip the store lock; see
+    /// [`get_cache`]. `None` disables the cache.
+    pub get_cache_size: Option<usize>,
+    /// Refresh a copy of the store this often and answer `X-Consistency: eventual` reads from it
+    /// without the store lock; see [`snapshot`]. `None` serves such reads from the store.
+    pub read_snapshot_interval: Option<Duration>,
+    /// Shed data-plane requests beyond this many in flight with 503 `OVERLOADED`; see
+    /// [`overload`]. The admin routes are never shed. `None` is unlimited.
+    pub max_in_flight: Option<usize>,
+    /// Request size limits; keep `body_limits` (e.g. [`RouteBodyLimits::for_limits`]) large
+    /// enough for them, or oversized requests get a bare 413 instead of a typed 400.
+    pub limits: Limits,
+    /// Reclaim expired values when they are read; see [`AppState::strict_ttl`]. Off by default,
+    /// so that `get_allowing_expired` can still see values past their TTL.
+    pub strict_ttl: bool,
 }
 
 /// TransDB Server
@@ -126,54 +472,276 @@ impl Server {
         self.config.address
     }
 
-    /// Create the application router with the given state
-    pub fn create_router(state: AppState) -> Router {
-        Router::new()
-            .route("/keys/:key", get(handle_get).put(handle_put).delete(handle_delete))
-            // Allow bodies up to MAX_VALUE_SIZE + 1 so our handler can validate and return 400;
-            // axum's default 2MB limit would otherwise return 413 for oversized values.
-            .layer(DefaultBodyLimit::max(MAX_VALUE_SIZE + 1))
-            .with_state(state)
-    }
-
-    /// Run the server, signalling `ready_tx` with the bound address once accepting connections
-    pub async fn run(self, ready_tx: tokio::sync::oneshot::Sender<SocketAddr>) -> Result<(), Box<dyn std::error::Error>> {
-        let state = AppState::new(Arc::new(SystemClock), self.config.role.clone());
-        let app = Self::create_router(state);
-        let listener = tokio::net::TcpListener::bind(self.config.address).await?;
-        let local_addr = listener.local_addr()?;
-        ready_tx.send(local_addr).ok();
-        axum::serve(listener, app).await?;
+    /// Create the application router with the given state: the data-plane routes of
+    /// [`Server::create_data_router`] plus the admin routes of [`Server::create_admin_router`].
+    /// Each body-accepting route gets its own `DefaultBodyLimit` from `limits`; bodies over the
+    /// limit are rejected with 413 before the handler allocates them. With
+    /// `state.signature_key` set, unsigned mutating requests are rejected with 401. With
+    /// `state.max_in_flight` set, data-plane requests beyond it are rejected with 503.
+    pub fn create_router(state: AppState, limits: &RouteBodyLimits) -> Router {
+        let routes = Self::with_load_shedding(data_routes(limits), &state).merge(admin_routes());
+        Self::with_request_ids(Self::with_signing(routes, &state, limits)).with_state(state)
+    }
+
+    /// Routes clients use: keys, mutexes, batches, `GET /topology` and `GET /version`.
+    pub fn create_data_router(state: AppState, limits: &RouteBodyLimits) -> Router {
+        let routes = Self::with_signing(Self::with_load_shedding(data_routes(limits), &state), &state, limits);
+        Self::with_request_ids(routes).with_state(state)
+    }
+
+    /// Operator routes: `GET /stats`, `POST /admin/readonly`, and `DELETE /keys` with the
+    /// `test-admin` feature.
+    pub fn create_admin_router(state: AppState, limits: &RouteBodyLimits) -> Router {
+        Self::with_request_ids(Self::with_signing(admin_routes(), &state, limits)).with_state(state)
+    }
+
+    /// Outermost layer, so that requests refused by load shedding or signing get an ID too.
+    fn with_request_ids(router: Router<AppState>) -> Router<AppState> {
+        router.layer(middleware::from_fn(request_id::assign_request_id))
+    }
+
+    fn with_load_shedding(router: Router<AppState>, state: &AppState) -> Router<AppState> {
+        match state.max_in_flight {
+            Some(max) => {
+                let permits = Arc::new(Semaphore::new(max));
+                router.layer(middleware::from_fn_with_state(permits, overload::shed_load))
+            }
+            None => router,
+        }
+    }
+
+    fn with_signing(router: Router<AppState>, state: &AppState, limits: &RouteBodyLimits) -> Router<AppState> {
+        match &state.signature_key {
+            Some(key) => {
+                let check = SignatureCheck {
+                    key: key.clone(),
+                    clock: state.clock.clone(),
+                    max_body: limits.largest(),
+                };
+                router.layer(middleware::from_fn_with_state(check, verify_signature))
+            }
+            None => router,
+        }
+    }
+
+    /// Run the server, signalling `ready_tx` with the bound data-plane address once accepting
+    /// connections. With `admin_port` set, both listeners are bound before either signal fires,
+    /// and `admin_ready_tx` receives the admin address; otherwise it is dropped unsent.
+    pub async fn run(
+        self,
+        ready_tx: oneshot::Sender<SocketAddr>,
+        admin_ready_tx: Option<oneshot::Sender<SocketAddr>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = AppState::new(Arc::new(SystemClock), self.config.role.clone());
+        state.max_key_count = self.config.max_key_count;
+        state.topology = self.config.topology.clone();
+        state.signature_key = self.config.signature_key.clone().map(|key| Arc::from(key.into_bytes()));
+        state.server_timing = self.config.server_timing;
+        state.read_only.store(self.config.read_only, Ordering::Relaxed);
+        state.content_validator = self.config.content_validator.clone();
+        state.max_in_flight = self.config.max_in_flight;
+        state.limits = self.config.limits;
+        state.strict_ttl = self.config.strict_ttl;
+        if let Some(write_batch) = self.config.write_batch.clone() {
+            state = state.with_write_batching(write_batch);
+        }
+        if let Some(size) = self.config.get_cache_size {
+            state = state.with_get_cache(size);
+        }
+        if let Some(interval) = self.config.read_snapshot_interval {
+            state = state.with_read_snapshot(interval);
+        }
+        let limits = &self.config.body_limits;
+        // Load the certificate before binding, so a bad path fails startup instead of every request.
+        let acceptor = self.config.tls.as_ref().map(TlsConfig::acceptor).transpose()?;
+
+        let Some(admin_port) = self.config.admin_port else {
+            let app = Self::create_router(state, limits);
+            let listener = self.config.tcp.bind(self.config.address)?;
+            ready_tx.send(listener.local_addr()?).ok();
+            serve(listener, app, acceptor).await?;
+            return Ok(());
+        };
+
+        let listener = self.config.tcp.bind(self.config.address)?;
+        let admin_listener = self.config.tcp.bind(SocketAddr::new(self.config.address.ip(), admin_port))?;
+        ready_tx.send(listener.local_addr()?).ok();
+        if let Some(admin_ready_tx) = admin_ready_tx {
+            admin_ready_tx.send(admin_listener.local_addr()?).ok();
+        }
+
+        let data_app = Self::create_data_router(state.clone(), limits);
+        let admin_app = Self::create_admin_router(state, limits);
+        let mut data = tokio::spawn(serve(listener, data_app, acceptor.clone()));
+        let mut admin = tokio::spawn(serve(admin_listener, admin_app, acceptor));
+        // Neither returns unless it fails; take the other down with it.
+        let result = tokio::select! {
+            result = &mut data => { admin.abort(); result }
+            result = &mut admin => { data.abort(); result }
+        };
+        result??;
         Ok(())
     }
 }
 
-fn error_response(status: StatusCode, message: impl Into<String>) -> Response {
-    (status, Json(ErrorResponse { error: message.into() })).into_response()
+/// Serve `app` on `listener`, over TLS when `acceptor` is set.
+async fn serve(listener: tokio::net::TcpListener, app: Router, acceptor: Option<TlsAcceptor>) -> std::io::Result<()> {
+    match acceptor {
+        Some(acceptor) => tls::serve(listener, acceptor, app).await,
+        None => axum::serve(listener, app).await,
+    }
 }
 
-fn etag_value(version: u64) -> HeaderValue {
-    HeaderValue::from_str(&format!("\"{}\"", version)).expect("valid ETag header value")
+fn data_routes(limits: &RouteBodyLimits) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/keys/:key",
+            put(handle_put)
+                .patch(handle_patch)
+                .layer(DefaultBodyLimit::max(limits.key_put))
+                .get(handle_get)
+                .delete(handle_delete),
+        )
+        .route(
+            "/:namespace/keys/:key",
+            put(namespace::handle_namespaced_put)
+                .patch(namespace::handle_namespaced_patch)
+                .layer(DefaultBodyLimit::max(limits.key_put))
+                .get(namespace::handle_namespaced_get)
+                .delete(namespace::handle_namespaced_delete),
+        )
+        .route("/keys", get(handle_list_keys).head(handle_key_count))
+        .route("/topology", get(handle_topology))
+        .route("/version", get(handle_version))
+        .route("/mutex/:name", delete(mutex::handle_release))
+        .route("/mutex/:name/acquire", post(mutex::handle_acquire))
+        .route("/mutex/:name/renew", post(mutex::handle_renew))
+        .route("/batch/ttl", patch(handle_batch_ttl).layer(DefaultBodyLimit::max(limits.batch_ttl)))
+        .route("/multi-cas", post(multi_cas::handle_multi_cas).layer(DefaultBodyLimit::max(limits.multi_cas)))
 }
 
-fn extract_idempotency_key(headers: &HeaderMap) -> Result<String, Response> {
+fn admin_routes() -> Router<AppState> {
+    let router = Router::new()
+        .route("/stats", get(handle_stats))
+        .route("/admin/readonly", post(handle_set_read_only))
+        .route("/admin/namespaces", get(namespace::handle_list_namespaces));
+    #[cfg(feature = "test-admin")]
+    let router = router.route("/keys", delete(admin::handle_delete_all));
+    router
+}
+
+pub(crate) fn error_response(status: StatusCode, error_type: &str, message: impl Into<String>) -> Response {
+    let body = ErrorResponse {
+        error: message.into(),
+        error_type: error_type.to_string(),
+        details: None,
+        request_id: request_id::current(),
+    };
+    (status, Json(body)).into_response()
+}
+
+pub(crate) fn read_only_response() -> Response {
+    error_response(StatusCode::SERVICE_UNAVAILABLE, ERR_READ_ONLY, "Server is in read-only mode")
+}
+
+pub(crate) fn content_rejected_response(e: ContentError) -> Response {
+    let body = ErrorResponse {
+        error: "content validation failed".to_string(),
+        error_type: ERR_CONTENT_REJECTED.to_string(),
+        details: Some(serde_json::json!({ "detail": e.0 })),
+        request_id: request_id::current(),
+    };
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response()
+}
+
+pub(crate) fn lock_timeout_response() -> Response {
+    error_response(StatusCode::SERVICE_UNAVAILABLE, ERR_LOCK_TIMEOUT, "Server error: Lock acquisition timed out")
+}
+
+fn etag_value(version: Version) -> HeaderValue {
+    HeaderValue::from_str(&version.to_etag()).expect("valid ETag header value")
+}
+
+/// The caller's principal: the `Authorization: Bearer` token, or `ANONYMOUS_PRINCIPAL`.
+pub(crate) fn extract_principal(headers: &HeaderMap) -> String {
     headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string())
+        .filter(|token| !token.is_empty())
+        .unwrap_or_else(|| ANONYMOUS_PRINCIPAL.to_string())
+}
+
+#[allow(clippy::result_large_err)]
+fn extract_idempotency_key(headers: &HeaderMap, limits: &Limits) -> Result<IdempotencyScope, Response> {
+    let key = headers
         .get("idempotency-key")
         .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string())
-        .ok_or_else(|| error_response(StatusCode::BAD_REQUEST, "Idempotency-Key header is required"))
+        .ok_or_else(|| {
+            error_response(StatusCode::BAD_REQUEST, ERR_MISSING_IDEMPOTENCY_KEY, "Idempotency-Key header is required")
+        })?;
+    if key.len() > limits.max_idempotency_key_size {
+        return Err(error_response(
+            StatusCode::BAD_REQUEST,
+            ERR_IDEMPOTENCY_KEY_TOO_LARGE,
+            format!("Idempotency-Key exceeds maximum length of {} bytes", limits.max_idempotency_key_size),
+        ));
+    }
+    Ok(IdempotencyScope { principal: extract_principal(headers), key: key.to_string() })
 }
 
-fn verify_and_build_cached_put(record: &IdempotencyRecord, key: &str) -> Response {
-    if record.method != HttpMethod::Put || record.key_path != key {
+/// Parse an optional `If-Match: "<version>"` header; a value that is not a version ETag can never
+/// match, so it is rejected with 412. `If-Match` compares strongly, so a weak ETag is refused too.
+#[allow(clippy::result_large_err)]
+fn extract_if_match(headers: &HeaderMap) -> Result<Option<Version>, Response> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .filter(|v| !v.trim_start().starts_with("W/"))
+        .and_then(Version::from_etag)
+        .map(Some)
+        .ok_or_else(|| precondition_failed_response("If-Match must be a version ETag such as \"7\""))
+}
+
+pub(crate) fn precondition_failed_response(message: &str) -> Response {
+    error_response(StatusCode::PRECONDITION_FAILED, ERR_PRECONDITION_FAILED, message)
+}
+
+fn verify_and_build_cached_put(record: &IdempotencyRecord, method: HttpMethod, key: &str) -> Response {
+    if record.method != method || record.key_path != key {
         return error_response(
             StatusCode::UNPROCESSABLE_ENTITY,
+            ERR_IDEMPOTENCY_MISMATCH,
             "Idempotency-Key was already used for a different method or key path",
         );
     }
+    replayed(put_response(record))
+}
+
+/// Mark `response` as a replay of a cached idempotent response with `X-Idempotent-Replay: true`,
+/// so a client can tell it from a fresh execution of the request.
+fn replayed(mut response: Response) -> Response {
+    response.headers_mut().insert("x-idempotent-replay", HeaderValue::from_static("true"));
+    response
+}
+
+/// 200 response for a PUT, fresh or replayed: ETag plus `X-Previous-Version` when set, and
+/// `X-Version-Diff`, the number of versions handed out between the replaced value and this one
+/// (0 for a new key). Versions come from one counter for the whole store, so that counts writes
+/// to any key, not only to this one.
+fn put_response(record: &IdempotencyRecord) -> Response {
     let mut response = StatusCode::OK.into_response();
     if let Some(etag) = record.etag {
         response.headers_mut().insert(header::ETAG, etag_value(etag));
+        let diff = record.previous_version.map_or(0, |previous| etag.get().saturating_sub(previous.get() + 1));
+        response.headers_mut().insert("x-version-diff", HeaderValue::from(diff));
+    }
+    if let Some(previous) = record.previous_version {
+        response.headers_mut().insert("x-previous-version", HeaderValue::from(previous.get()));
     }
     response
 }
@@ -182,6 +750,7 @@ fn verify_and_build_cached_delete(record: &IdempotencyRecord, key: &str) -> Resp
     if record.method != HttpMethod::Delete || record.key_path != key {
         return error_response(
             StatusCode::UNPROCESSABLE_ENTITY,
+            ERR_IDEMPOTENCY_MISMATCH,
             "Idempotency-Key was already used for a different method or key path",
         );
     }
@@ -189,37 +758,171 @@ fn verify_and_build_cached_delete(record: &IdempotencyRecord, key: &str) -> Resp
     // so etag is always Some here.
     let mut response = StatusCode::OK.into_response();
     response.headers_mut().insert(header::ETAG, etag_value(record.etag.unwrap()));
-    response
+    replayed(response)
+}
+
+/// Hex SHA-256 of a batch's canonical `items` as JSON. The idempotency cache keeps this rather
+/// than the items, so a batch's record stays small however many bytes it wrote.
+pub(crate) fn items_digest(items: &impl Serialize) -> String {
+    let mut hasher = Sha256::new();
+    serde_json::to_writer(&mut hasher, items).expect("batch items serialize");
+    hasher.finalize().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Replay a cached JSON response of a `method` batch request whose canonical items have the
+/// [`items_digest`] `digest`. The original per-item results come back only for the same items; a
+/// key reused for another batch is refused with 422 rather than answered with results that do not
+/// match the request.
+pub(crate) fn verify_and_build_cached_body(record: &IdempotencyRecord, method: HttpMethod, digest: &str) -> Response {
+    if record.method != method {
+        return error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ERR_IDEMPOTENCY_MISMATCH,
+            "Idempotency-Key was already used for a different method or key path",
+        );
+    }
+    if record.key_path != digest {
+        return error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            ERR_IDEMPOTENCY_MISMATCH,
+            "Idempotency-Key was already used for a batch with different items",
+        );
+    }
+    let body = record.body.clone().unwrap_or_default();
+    replayed((StatusCode::OK, [(header::CONTENT_TYPE, "application/json")], body).into_response())
 }
 
 /// Handler for GET /keys/:key — returns the value and ETag (version) if found, 404 if not.
-/// If the entry has an expired TTL, adds `X-Expired: true` to the response.
-pub async fn handle_get(State(state): State<AppState>, Path(key): Path<String>) -> Response {
+/// If the entry has a TTL, `X-Expires-At` carries it (Unix seconds); once it has elapsed, the
+/// response also gets `X-Expired: true`.
+/// A 404 for a tombstoned key carries `X-Deleted-Version` with the tombstone's version;
+/// a key that never existed gets a plain 404.
+/// `If-None-Match` naming the current version of an unexpired value gets an empty 304, which
+/// also carries `X-Expires-At`.
+/// A single `Range: bytes=...` gets 206 with that slice and `Content-Range`, or 416 if it starts
+/// past the end of the value; other `Range` forms are ignored and the whole value is returned.
+/// Every response carries `X-Sequence`, the global version high-water mark, including one served
+/// from the get cache (see [`ServerConfig::get_cache_size`]).
+/// With `X-Consistency: eventual` and a read snapshot configured (see
+/// [`ServerConfig::read_snapshot_interval`]), the entry comes from the latest snapshot without
+/// taking the lock, and `X-Sequence` is the high-water mark when that snapshot was taken.
+/// With [`AppState::strict_ttl`], an expired value gets a plain 404 instead, and unless the
+/// server is read-only (or the read was served from the snapshot) it is removed from the store.
+pub async fn handle_get(State(state): State<AppState>, Path(key): Path<String>, headers: HeaderMap) -> Response {
     if state.role == NodeRole::Replica {
-        return error_response(StatusCode::METHOD_NOT_ALLOWED, "Replica does not accept key operations");
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
     }
 
-    if key.len() > MAX_KEY_SIZE {
+    if key.is_empty() {
+        return empty_key_response();
+    }
+    if key.len() > state.limits.max_key_size {
         return error_response(
             StatusCode::BAD_REQUEST,
-            format!("Key exceeds maximum size of {} bytes", MAX_KEY_SIZE),
+            ERR_KEY_TOO_LARGE,
+            format!("Key exceeds maximum size of {} bytes", state.limits.max_key_size),
         );
     }
 
+    let started = Instant::now();
+    let eventual = headers.get("x-consistency").is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"eventual"));
+    if let Some(snapshot) = state.read_snapshot.as_ref().filter(|_| eventual) {
+        let snapshot = snapshot.load();
+        let mut response = entry_response(&state, &key, snapshot.store.get(&key), &headers);
+        response.headers_mut().insert("x-sequence", HeaderValue::from(snapshot.sequence));
+        return state.timed(response, started, Some(started));
+    }
+    let cached = state.get_cache.as_ref().and_then(|cache| get_cache::lock(cache).get(&key));
+    // Under strict TTL an expired entry goes to the store path, which reclaims it.
+    let cached = cached.filter(|entry| !(state.strict_ttl && entry.is_expired(state.clock.as_ref())));
+    if let (Some(entry), Some(sequence)) = (cached, &state.sequence) {
+        // Read after the hit: a write that lands in between only makes the mark newer than the entry.
+        let sequence = sequence.load(Ordering::Relaxed);
+        let mut response = entry_response(&state, &key, Some(&entry), &headers);
+        response.headers_mut().insert("x-sequence", HeaderValue::from(sequence));
+        return state.timed(response, started, Some(started));
+    }
     let db_guard = match timeout(LOCK_TIMEOUT, state.db.read()).await {
         Ok(guard) => guard,
-        Err(_) => return error_response(StatusCode::SERVICE_UNAVAILABLE, "Server error: Lock acquisition timed out"),
+        Err(_) => return state.timed(lock_timeout_response(), started, None),
     };
+    let locked = Instant::now();
 
-    match db_guard.store.get(&key) {
-        None | Some(Entry { value: None, .. }) => {
-            error_response(StatusCode::NOT_FOUND, format!("Key not found: {}", key))
+    let entry = db_guard.store.get(&key);
+    let reclaim = state.strict_ttl && !state.is_read_only();
+    if reclaim && entry.is_some_and(|e| e.value.is_some() && e.is_expired(state.clock.as_ref())) {
+        drop(db_guard);
+        return state.timed(reclaim_expired(&state, &key, &headers).await, started, Some(locked));
+    }
+    if let (Some(cache), Some(entry)) = (&state.get_cache, entry) {
+        // Filled while the read lock still keeps writers, and their invalidations, out.
+        get_cache::lock(cache).insert(&key, entry.clone());
+    }
+    let mut response = entry_response(&state, &key, entry, &headers);
+    response.headers_mut().insert("x-sequence", HeaderValue::from(db_guard.sequence()));
+    state.timed(response, started, Some(locked))
+}
+
+/// Strict-TTL `handle_get` of an expired value: remove it under the write lock and answer for
+/// whatever the key then holds. A write that replaced the value since the read lock was released
+/// is served as usual rather than removed.
+async fn reclaim_expired(state: &AppState, key: &str, headers: &HeaderMap) -> Response {
+    let mut db_guard = match timeout(LOCK_TIMEOUT, state.db.write()).await {
+        Ok(guard) => guard,
+        Err(_) => return lock_timeout_response(),
+    };
+    let clock = state.clock.as_ref();
+    if db_guard.store.get(key).is_some_and(|e| e.value.is_some() && e.is_expired(clock)) {
+        db_guard.store.remove(key);
+        db_guard.invalidate(key);
+    }
+    let mut response = entry_response(state, key, db_guard.store.get(key), headers);
+    response.headers_mut().insert("x-sequence", HeaderValue::from(db_guard.sequence()));
+    response
+}
+
+/// `handle_get`'s response for `key`'s entry (`None` if it has none), short of `X-Sequence`.
+fn entry_response(state: &AppState, key: &str, entry: Option<&Entry>, headers: &HeaderMap) -> Response {
+    match entry {
+        None => error_response(StatusCode::NOT_FOUND, ERR_KEY_NOT_FOUND, format!("Key not found: {}", key)),
+        Some(entry @ Entry { value: None, .. }) => {
+            let mut response =
+                error_response(StatusCode::NOT_FOUND, ERR_KEY_NOT_FOUND, format!("Key not found: {}", key));
+            response.headers_mut().insert("x-deleted-version", HeaderValue::from(entry.metadata().version.get()));
+            response
+        }
+        Some(entry) if state.strict_ttl && entry.is_expired(state.clock.as_ref()) => {
+            error_response(StatusCode::NOT_FOUND, ERR_KEY_NOT_FOUND, format!("Key not found: {}", key))
+        }
+        Some(entry) if !entry.is_expired(state.clock.as_ref()) && if_none_match(headers, entry.version) => {
+            let mut response = StatusCode::NOT_MODIFIED.into_response();
+            insert_metadata_headers(&mut response, &entry.metadata());
+            response
         }
         Some(entry) => {
             let expired = entry.is_expired(state.clock.as_ref());
             let value = entry.value.clone().unwrap();
-            let mut response = (StatusCode::OK, value).into_response();
-            response.headers_mut().insert(header::ETAG, etag_value(entry.version));
+            let total = value.len();
+            let mut response = match byte_range(headers, total) {
+                ByteRange::Full => (StatusCode::OK, [(header::ACCEPT_RANGES, "bytes")], value).into_response(),
+                ByteRange::Partial(range) => {
+                    let content_range = format!("bytes {}-{}/{}", range.start, range.end - 1, total);
+                    let headers =
+                        [(header::ACCEPT_RANGES, "bytes".to_string()), (header::CONTENT_RANGE, content_range)];
+                    (StatusCode::PARTIAL_CONTENT, headers, value.slice(range)).into_response()
+                }
+                ByteRange::Unsatisfiable => {
+                    let mut response = error_response(
+                        StatusCode::RANGE_NOT_SATISFIABLE,
+                        ERR_RANGE_NOT_SATISFIABLE,
+                        format!("Range starts beyond the {}-byte value", total),
+                    );
+                    let content_range = format!("bytes */{}", total).parse().expect("valid header value");
+                    response.headers_mut().insert(header::CONTENT_RANGE, content_range);
+                    response
+                }
+            };
+            insert_metadata_headers(&mut response, &entry.metadata());
             if expired {
                 response.headers_mut().insert("x-expired", HeaderValue::from_static("true"));
             }
@@ -228,8 +931,61 @@ pub async fn handle_get(State(state): State<AppState>, Path(key): Path<String>)
     }
 }
 
+/// Describe a live entry in `response`'s headers: `ETag`, and `X-Expires-At` if it has a TTL.
+fn insert_metadata_headers(response: &mut Response, metadata: &EntryMetadata) {
+    response.headers_mut().insert(header::ETAG, etag_value(metadata.version));
+    if let Some(expires_at) = metadata.expires_at {
+        response.headers_mut().insert("x-expires-at", HeaderValue::from(expires_at));
+    }
+}
+
+/// Whether `If-None-Match` lists `version`'s ETag, strong or weak (or is `*`).
+fn if_none_match(headers: &HeaderMap, version: Version) -> bool {
+    let Some(value) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    value.split(',').map(str::trim).any(|tag| tag == "*" || Version::from_etag(tag) == Some(version))
+}
+
+enum ByteRange {
+    Full,
+    Partial(std::ops::Range<usize>),
+    Unsatisfiable,
+}
+
+/// Resolve a `Range` header against a value of `len` bytes. Only a single `bytes=` range is
+/// honoured (`first-last`, `first-` or `-suffix`); anything else is ignored, as RFC 9110 allows.
+fn byte_range(headers: &HeaderMap, len: usize) -> ByteRange {
+    let Some(spec) = headers.get(header::RANGE).and_then(|v| v.to_str().ok()).and_then(|v| v.strip_prefix("bytes="))
+    else {
+        return ByteRange::Full;
+    };
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    let len = len as u64;
+    let (start, end) = match (first.parse::<u64>().ok(), last.parse::<u64>().ok()) {
+        (None, Some(suffix)) if first.is_empty() => {
+            if suffix == 0 || len == 0 {
+                return ByteRange::Unsatisfiable;
+            }
+            (len.saturating_sub(suffix), len - 1)
+        }
+        (Some(start), None) if last.is_empty() => (start, len.saturating_sub(1)),
+        (Some(start), Some(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+        _ => return ByteRange::Full,
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    ByteRange::Partial(start as usize..end as usize + 1)
+}
+
 /// Handler for PUT /keys/:key — stores the request body; requires Idempotency-Key header.
 /// Accepts an optional `X-TTL` header containing an absolute Unix epoch timestamp (u64).
+/// When the write replaces a live value, `X-Previous-Version` carries that value's version;
+/// it is absent when the key was new, deleted, or expired.
+/// A body the configured content validator rejects gets 422 `CONTENT_REJECTED` and is not stored.
 pub async fn handle_put(
     State(state): State<AppState>,
     Path(key): Path<String>,
@@ -237,111 +993,628 @@ pub async fn handle_put(
     body: Bytes,
 ) -> Response {
     if state.role == NodeRole::Replica {
-        return error_response(StatusCode::METHOD_NOT_ALLOWED, "Replica does not accept key operations");
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+    if state.is_read_only() {
+        return read_only_response();
     }
 
-    if key.len() > MAX_KEY_SIZE {
-        return error_response(
-            StatusCode::BAD_REQUEST,
-            format!("Key exceeds maximum size of {} bytes", MAX_KEY_SIZE),
-        );
+    if key.is_empty() {
+        return empty_key_response();
     }
-    if body.len() > MAX_VALUE_SIZE {
+    if key.len() > state.limits.max_key_size {
         return error_response(
             StatusCode::BAD_REQUEST,
-            format!("Value exceeds maximum size of {} bytes", MAX_VALUE_SIZE),
+            ERR_KEY_TOO_LARGE,
+            format!("Key exceeds maximum size of {} bytes", state.limits.max_key_size),
         );
     }
+    if body.len() > state.limits.max_value_size {
+        return value_too_large_response(state.limits.max_value_size);
+    }
 
     let expires_at = match headers.get("x-ttl") {
         None => None,
         Some(v) => match v.to_str().ok().and_then(|s| s.parse::<u64>().ok()) {
             Some(ts) => Some(ts),
-            None => return error_response(StatusCode::BAD_REQUEST, "X-TTL must be a non-negative integer"),
+            None => return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_TTL, "X-TTL must be a non-negative integer"),
         },
     };
 
-    let idempotency_key = match extract_idempotency_key(&headers) {
+    let idempotency_key = match extract_idempotency_key(&headers, &state.limits) {
         Ok(k) => k,
         Err(r) => return r,
     };
 
+    if let Some(validator) = &state.content_validator {
+        let content_type = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+        if let Err(e) = validator.validate(&key, &body, content_type) {
+            return content_rejected_response(e);
+        }
+    }
+
+    let started = Instant::now();
+    if let Some(batcher) = &state.write_batcher {
+        let result = submit_write(batcher, WriteOp::Put { key, value: body, expires_at }, idempotency_key).await;
+        return state.timed(result.response, started, result.locked);
+    }
+
     let mut db_guard = match timeout(LOCK_TIMEOUT, state.db.write()).await {
         Ok(guard) => guard,
-        Err(_) => return error_response(StatusCode::SERVICE_UNAVAILABLE, "Server error: Lock acquisition timed out"),
+        Err(_) => return state.timed(lock_timeout_response(), started, None),
     };
+    let locked = Instant::now();
+    let response =
+        apply_put(&mut db_guard, state.clock.as_ref(), state.max_key_count, key, body, expires_at, idempotency_key);
+    state.timed(response, started, Some(locked))
+}
 
-    if let Some(record) = db_guard.idempotency_cache.get(&idempotency_key) {
-        return verify_and_build_cached_put(record, &key);
+/// Handler for PATCH /keys/:key — appends the request body to the current value, creating the
+/// key if it is absent, deleted, or expired; requires Idempotency-Key header. An append keeps
+/// the existing TTL. Returns the new ETag (and `X-Previous-Version`, as for PUT), 400 if the
+/// combined value would exceed the `max_value_size` limit, or 422 if the content validator
+/// rejects the combined value.
+pub async fn handle_patch(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if state.role == NodeRole::Replica {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+    if state.is_read_only() {
+        return read_only_response();
     }
 
-    db_guard.next_version += 1;
-    let version = db_guard.next_version;
-    db_guard.store.insert(key.clone(), Entry { value: Some(body), version, expires_at });
+    if key.is_empty() {
+        return empty_key_response();
+    }
+    if key.len() > state.limits.max_key_size {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            ERR_KEY_TOO_LARGE,
+            format!("Key exceeds maximum size of {} bytes", state.limits.max_key_size),
+        );
+    }
+    if body.len() > state.limits.max_value_size {
+        return value_too_large_response(state.limits.max_value_size);
+    }
 
-    let record = IdempotencyRecord {
-        method: HttpMethod::Put,
-        key_path: key,
-        status_code: 200,
-        etag: Some(version),
-        created_at: Instant::now(),
+    let idempotency_key = match extract_idempotency_key(&headers, &state.limits) {
+        Ok(k) => k,
+        Err(r) => return r,
     };
-    db_guard.idempotency_cache.insert(idempotency_key, record);
 
-    let mut response = StatusCode::OK.into_response();
-    response.headers_mut().insert(header::ETAG, etag_value(version));
-    response
+    let started = Instant::now();
+    let content_type = headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok());
+    if let Some(batcher) = &state.write_batcher {
+        let content_type = content_type.map(str::to_string);
+        let result = submit_write(batcher, WriteOp::Append { key, value: body, content_type }, idempotency_key).await;
+        return state.timed(result.response, started, result.locked);
+    }
+
+    let mut db_guard = match timeout(LOCK_TIMEOUT, state.db.write()).await {
+        Ok(guard) => guard,
+        Err(_) => return state.timed(lock_timeout_response(), started, None),
+    };
+    let locked = Instant::now();
+    let check = AppendCheck {
+        max_value_size: state.limits.max_value_size,
+        validator: state.content_validator.as_deref(),
+        content_type,
+    };
+    let response =
+        apply_append(&mut db_guard, state.clock.as_ref(), state.max_key_count, &check, key, body, idempotency_key);
+    state.timed(response, started, Some(locked))
 }
 
-/// Handler for DELETE /keys/:key — removes the key (no-op if absent); requires Idempotency-Key header
+/// Handler for DELETE /keys/:key — removes the key (no-op if absent); requires Idempotency-Key header.
+/// An optional `If-Match` makes the delete conditional on the current version; see [`apply_delete`].
 pub async fn handle_delete(
     State(state): State<AppState>,
     Path(key): Path<String>,
     headers: HeaderMap,
 ) -> Response {
     if state.role == NodeRole::Replica {
-        return error_response(StatusCode::METHOD_NOT_ALLOWED, "Replica does not accept key operations");
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+    if state.is_read_only() {
+        return read_only_response();
+    }
+
+    if key.is_empty() {
+        return empty_key_response();
+    }
+    if key.len() > state.limits.max_key_size {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            ERR_KEY_TOO_LARGE,
+            format!("Key exceeds maximum size of {} bytes", state.limits.max_key_size),
+        );
+    }
+
+    let if_match = match extract_if_match(&headers) {
+        Ok(v) => v,
+        Err(r) => return r,
+    };
+
+    let idempotency_key = match extract_idempotency_key(&headers, &state.limits) {
+        Ok(k) => k,
+        Err(r) => return r,
+    };
+
+    let started = Instant::now();
+    if let Some(batcher) = &state.write_batcher {
+        let result = submit_write(batcher, WriteOp::Delete { key, if_match }, idempotency_key).await;
+        return state.timed(result.response, started, result.locked);
+    }
+
+    let mut db_guard = match timeout(LOCK_TIMEOUT, state.db.write()).await {
+        Ok(guard) => guard,
+        Err(_) => return state.timed(lock_timeout_response(), started, None),
+    };
+    let locked = Instant::now();
+    let response = apply_delete(&mut db_guard, state.clock.as_ref(), key, if_match, idempotency_key);
+    state.timed(response, started, Some(locked))
+}
+
+/// Handler for HEAD /keys — returns 200 with `X-Key-Count` set to the number of live
+/// (non-tombstoned, non-expired) keys, and no body.
+pub async fn handle_key_count(State(state): State<AppState>) -> Response {
+    if state.role == NodeRole::Replica {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+
+    let db_guard = match timeout(LOCK_TIMEOUT, state.db.read()).await {
+        Ok(guard) => guard,
+        Err(_) => return lock_timeout_response(),
+    };
+
+    let count = live_key_count(&db_guard, state.clock.as_ref());
+    let mut response = StatusCode::OK.into_response();
+    response.headers_mut().insert("x-key-count", HeaderValue::from(count));
+    response
+}
+
+/// Query string accepted by `GET /keys`: an optional `?prefix=` the listed keys must start with.
+#[derive(Debug, Default, Deserialize)]
+pub struct ListKeysParams {
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+/// Keys per chunk of an NDJSON `GET /keys` body.
+const NDJSON_CHUNK_KEYS: usize = 1024;
+
+/// Handler for GET /keys — the live keys, sorted, optionally restricted to `?prefix=`. Answers
+/// a [`KeyList`] document unless `Accept` names [`NDJSON_CONTENT_TYPE`], in which case each key
+/// is written as a JSON string on its own line. Either way the keys are snapshotted under a
+/// brief read lock and the body is produced after it is released.
+pub async fn handle_list_keys(
+    State(state): State<AppState>,
+    params: Result<Query<ListKeysParams>, QueryRejection>,
+    headers: HeaderMap,
+) -> Response {
+    if state.role == NodeRole::Replica {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+    let prefix = match params {
+        Ok(Query(params)) => params.prefix.unwrap_or_default(),
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_QUERY, format!("Invalid query: {}", e)),
+    };
+
+    let mut keys: Vec<String> = {
+        let db_guard = match timeout(LOCK_TIMEOUT, state.db.read()).await {
+            Ok(guard) => guard,
+            Err(_) => return lock_timeout_response(),
+        };
+        let clock = state.clock.as_ref();
+        db_guard
+            .store
+            .iter()
+            .filter(|(key, entry)| key.starts_with(prefix.as_str()) && entry.is_live(clock))
+            .map(|(key, _)| key.clone())
+            .collect()
+    };
+    keys.sort_unstable();
+
+    let wants_ndjson = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains(NDJSON_CONTENT_TYPE));
+    if !wants_ndjson {
+        return Json(KeyList { keys }).into_response();
+    }
+
+    let mut keys = keys.into_iter().peekable();
+    let chunks = std::iter::from_fn(move || {
+        keys.peek()?;
+        let mut chunk = Vec::new();
+        for key in keys.by_ref().take(NDJSON_CHUNK_KEYS) {
+            serde_json::to_writer(&mut chunk, &key).expect("a string serializes");
+            chunk.push(b'\n');
+        }
+        Some(Ok::<_, std::convert::Infallible>(Bytes::from(chunk)))
+    });
+    ([(header::CONTENT_TYPE, NDJSON_CONTENT_TYPE)], Body::from_stream(futures_util::stream::iter(chunks)))
+        .into_response()
+}
+
+pub(crate) fn live_key_count(db: &DbState, clock: &dyn Clock) -> usize {
+    db.store.values().filter(|e| e.is_live(clock)).count()
+}
+
+/// Handler for GET /stats — returns [`StoreStats`] as JSON. Served by both roles, since it
+/// describes the node rather than the key space.
+pub async fn handle_stats(State(state): State<AppState>) -> Response {
+    let db_guard = match timeout(LOCK_TIMEOUT, state.db.read()).await {
+        Ok(guard) => guard,
+        Err(_) => return lock_timeout_response(),
+    };
+
+    let clock = state.clock.as_ref();
+    let mut stats = StoreStats::default();
+    for entry in db_guard.store.values() {
+        if entry.is_expired(clock) {
+            stats.expired_count += 1;
+        } else if entry.value.is_some() {
+            stats.live_count += 1;
+        } else {
+            stats.tombstone_count += 1;
+        }
+    }
+    if let Some(cache) = &state.get_cache {
+        let cache = get_cache::lock(cache);
+        (stats.cache_hits, stats.cache_misses) = (cache.hits(), cache.misses());
+    }
+    Json(stats).into_response()
+}
+
+/// Query string accepted by `POST /admin/readonly`: `?enabled=true|false`.
+#[derive(Debug, Deserialize)]
+pub struct ReadOnlyParams {
+    pub enabled: bool,
+}
+
+/// Handler for POST /admin/readonly?enabled=true|false — turns read-only mode on or off and
+/// answers 204. Like other mutating requests it must be signed when the server has a signature
+/// key, and with `--admin-port` it is only reachable on the admin listener.
+pub async fn handle_set_read_only(
+    State(state): State<AppState>,
+    params: Result<Query<ReadOnlyParams>, QueryRejection>,
+) -> Response {
+    match params {
+        Ok(Query(ReadOnlyParams { enabled })) => {
+            state.read_only.store(enabled, Ordering::Relaxed);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => error_response(StatusCode::BAD_REQUEST, ERR_INVALID_QUERY, format!("Invalid query: {}", e)),
+    }
+}
+
+/// Handler for GET /topology — returns the node's configured [`Topology`] as JSON, so clients
+/// can follow a moved primary or replica. Served by both roles; 404 if none was configured.
+pub async fn handle_topology(State(state): State<AppState>) -> Response {
+    match state.topology {
+        Some(topology) => Json(topology).into_response(),
+        None => error_response(StatusCode::NOT_FOUND, ERR_TOPOLOGY_UNKNOWN, "Node was started without a topology"),
+    }
+}
+
+/// Response body for `GET /version`.
+#[derive(Debug, Serialize)]
+pub struct VersionInfo {
+    pub server_version: &'static str,
+    pub protocol_version: &'static str,
+    /// `primary` or `replica`.
+    pub role: &'static str,
+    /// Short hash of the commit the binary was built from, or `unknown`.
+    pub git_hash: &'static str,
+    /// `YYYY-MM-DD`, or `unknown`.
+    pub build_date: &'static str,
+}
+
+/// Handler for GET /version — the server's build and protocol version and its role. Served by
+/// both roles, and, like every GET, without a signature.
+pub async fn handle_version(State(state): State<AppState>) -> Response {
+    Json(VersionInfo {
+        server_version: env!("CARGO_PKG_VERSION"),
+        protocol_version: PROTOCOL_VERSION,
+        role: match state.role {
+            NodeRole::Primary => "primary",
+            NodeRole::Replica => "replica",
+        },
+        git_hash: env!("TRANSDB_GIT_HASH"),
+        build_date: env!("TRANSDB_BUILD_DATE"),
+    })
+    .into_response()
+}
+
+/// Handler for PATCH /batch/ttl — sets `expires_at` on every listed live key under one write
+/// lock, leaving values and versions unchanged; requires Idempotency-Key header, and a reused
+/// key replays the original result only for the same set of keys (422 otherwise).
+/// Keys that are absent, deleted, or already expired are reported in `not_found`.
+pub async fn handle_batch_ttl(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> Response {
+    if state.role == NodeRole::Replica {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+    if state.is_read_only() {
+        return read_only_response();
     }
 
-    if key.len() > MAX_KEY_SIZE {
+    let request: BulkTtlRequest = match serde_json::from_slice(&body) {
+        Ok(r) => r,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_BODY, format!("Invalid request body: {}", e)),
+    };
+    if request.keys.len() > state.limits.max_batch_keys {
         return error_response(
             StatusCode::BAD_REQUEST,
-            format!("Key exceeds maximum size of {} bytes", MAX_KEY_SIZE),
+            ERR_BATCH_TOO_LARGE,
+            format!("Batch exceeds maximum of {} keys", state.limits.max_batch_keys),
+        );
+    }
+    if request.keys.iter().any(|k| k.len() > state.limits.max_key_size) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            ERR_KEY_TOO_LARGE,
+            format!("Key exceeds maximum size of {} bytes", state.limits.max_key_size),
         );
     }
 
-    let idempotency_key = match extract_idempotency_key(&headers) {
+    let idempotency_key = match extract_idempotency_key(&headers, &state.limits) {
         Ok(k) => k,
         Err(r) => return r,
     };
 
+    // The results list keys, not positions, so the same keys in another order are the same batch;
+    // the same keys with another expiry are not.
+    let mut keys: Vec<&String> = request.keys.iter().collect();
+    keys.sort_unstable();
+    keys.dedup();
+    let items = items_digest(&(request.expires_at, keys));
+
     let mut db_guard = match timeout(LOCK_TIMEOUT, state.db.write()).await {
         Ok(guard) => guard,
-        Err(_) => return error_response(StatusCode::SERVICE_UNAVAILABLE, "Server error: Lock acquisition timed out"),
+        Err(_) => return lock_timeout_response(),
     };
 
     if let Some(record) = db_guard.idempotency_cache.get(&idempotency_key) {
+        return verify_and_build_cached_body(record, HttpMethod::BatchTtl, &items);
+    }
+
+    let mut result = BulkTtlResult::default();
+    for key in request.keys {
+        match db_guard.store.get_mut(&key) {
+            Some(entry) if entry.is_live(state.clock.as_ref()) => {
+                entry.expires_at = Some(request.expires_at);
+                db_guard.invalidate(&key);
+                result.updated.push(key);
+            }
+            _ => result.not_found.push(key),
+        }
+    }
+
+    let body = Bytes::from(serde_json::to_vec(&result).expect("BulkTtlResult serializes"));
+    let record = IdempotencyRecord {
+        method: HttpMethod::BatchTtl,
+        key_path: items,
+        status_code: 200,
+        etag: None,
+        previous_version: None,
+        body: Some(body.clone()),
+        created_at: state.clock.unix_now_secs(),
+    };
+    db_guard.idempotency_cache.insert(idempotency_key, record);
+
+    (StatusCode::OK, [(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+/// Queue a validated write on the group-commit batcher and wait for its response.
+async fn submit_write(
+    batcher: &mpsc::UnboundedSender<PendingWrite>,
+    op: WriteOp,
+    idempotency_key: IdempotencyScope,
+) -> WriteResult {
+    let (reply, result) = oneshot::channel();
+    if batcher.send(PendingWrite { op, idempotency_key, request_id: request_id::current(), reply }).is_err() {
+        return batcher_unavailable();
+    }
+    result.await.unwrap_or_else(|_| batcher_unavailable())
+}
+
+fn batcher_unavailable() -> WriteResult {
+    let response =
+        error_response(StatusCode::INTERNAL_SERVER_ERROR, ERR_INTERNAL, "Server error: write batcher is not running");
+    WriteResult { response, locked: None }
+}
+
+/// Apply a PUT to the locked store: replay a cached idempotent response or write a new version.
+/// With `max_key_count` set, a PUT that would add a live key beyond it is rejected with 507.
+pub(crate) fn apply_put(
+    db: &mut DbState,
+    clock: &dyn Clock,
+    max_key_count: Option<usize>,
+    key: String,
+    value: Bytes,
+    expires_at: Option<u64>,
+    idempotency_key: IdempotencyScope,
+) -> Response {
+    if let Some(record) = db.idempotency_cache.get(&idempotency_key) {
+        return verify_and_build_cached_put(record, HttpMethod::Put, &key);
+    }
+
+    let previous_version = db.store.get(&key).filter(|e| e.is_live(clock)).map(|e| e.version);
+
+    // Overwriting a live key never grows the store, so only creates are checked (O(n) scan).
+    if let Some(max) = max_key_count {
+        if previous_version.is_none() && live_key_count(db, clock) >= max {
+            return error_response(
+                StatusCode::INSUFFICIENT_STORAGE,
+                ERR_STORAGE_FULL,
+                format!("Store is full: live key count has reached the limit of {}", max),
+            );
+        }
+    }
+
+    let version = db.allocate_version();
+    db.store.insert(key.clone(), Entry { value: Some(value), version, expires_at });
+    db.invalidate(&key);
+
+    let record = IdempotencyRecord {
+        method: HttpMethod::Put,
+        key_path: key,
+        status_code: 200,
+        etag: Some(version),
+        previous_version,
+        body: None,
+        created_at: clock.unix_now_secs(),
+    };
+    let response = put_response(&record);
+    db.idempotency_cache.insert(idempotency_key, record);
+    response
+}
+
+/// What an append's combined value must satisfy. Only known under the write lock, so it is
+/// checked there rather than by the handler, as a PUT's value is.
+pub(crate) struct AppendCheck<'a> {
+    pub max_value_size: usize,
+    pub validator: Option<&'a (dyn ContentValidator + Send + Sync)>,
+    /// The PATCH request's `Content-Type`, passed on to the validator.
+    pub content_type: Option<&'a str>,
+}
+
+/// Apply an append to the locked store: replay a cached idempotent response, or write the live
+/// value with `suffix` appended (just `suffix` if there is none) as a new version.
+/// Creating a key is subject to `max_key_count` exactly like [`apply_put`]; the appended value
+/// must fit in `check.max_value_size` and pass `check.validator`.
+pub(crate) fn apply_append(
+    db: &mut DbState,
+    clock: &dyn Clock,
+    max_key_count: Option<usize>,
+    check: &AppendCheck,
+    key: String,
+    suffix: Bytes,
+    idempotency_key: IdempotencyScope,
+) -> Response {
+    if let Some(record) = db.idempotency_cache.get(&idempotency_key) {
+        return verify_and_build_cached_put(record, HttpMethod::Append, &key);
+    }
+
+    let current = db.store.get(&key).filter(|e| e.is_live(clock));
+    let previous_version = current.map(|e| e.version);
+    let (value, expires_at) = match current {
+        Some(Entry { value: Some(existing), expires_at, .. }) => {
+            if existing.len() + suffix.len() > check.max_value_size {
+                return value_too_large_response(check.max_value_size);
+            }
+            let mut value = Vec::with_capacity(existing.len() + suffix.len());
+            value.extend_from_slice(existing);
+            value.extend_from_slice(&suffix);
+            (Bytes::from(value), *expires_at)
+        }
+        _ => {
+            if let Some(max) = max_key_count {
+                if live_key_count(db, clock) >= max {
+                    return error_response(
+                        StatusCode::INSUFFICIENT_STORAGE,
+                        ERR_STORAGE_FULL,
+                        format!("Store is full: live key count has reached the limit of {}", max),
+                    );
+                }
+            }
+            (suffix, None)
+        }
+    };
+    if let Some(validator) = check.validator {
+        if let Err(e) = validator.validate(&key, &value, check.content_type) {
+            return content_rejected_response(e);
+        }
+    }
+
+    let version = db.allocate_version();
+    db.store.insert(key.clone(), Entry { value: Some(value), version, expires_at });
+    db.invalidate(&key);
+
+    let record = IdempotencyRecord {
+        method: HttpMethod::Append,
+        key_path: key,
+        status_code: 200,
+        etag: Some(version),
+        previous_version,
+        body: None,
+        created_at: clock.unix_now_secs(),
+    };
+    let response = put_response(&record);
+    db.idempotency_cache.insert(idempotency_key, record);
+    response
+}
+
+pub(crate) fn empty_key_response() -> Response {
+    error_response(StatusCode::BAD_REQUEST, ERR_EMPTY_KEY, "Key must not be empty")
+}
+
+pub(crate) fn value_too_large_response(max_value_size: usize) -> Response {
+    error_response(
+        StatusCode::BAD_REQUEST,
+        ERR_VALUE_TOO_LARGE,
+        format!("Value exceeds maximum size of {} bytes", max_value_size),
+    )
+}
+
+/// Apply a DELETE to the locked store: replay a cached idempotent response, return 204 for an
+/// absent key, or write a tombstone.
+///
+/// With `if_match`, the key must exist (unexpired) at exactly that version, else 412. Matching a
+/// tombstone re-asserts the delete as a 200 no-op carrying the tombstone's version, so a
+/// reconciler can re-run deletes without clobbering a key that was since recreated.
+pub(crate) fn apply_delete(
+    db: &mut DbState,
+    clock: &dyn Clock,
+    key: String,
+    if_match: Option<Version>,
+    idempotency_key: IdempotencyScope,
+) -> Response {
+    if let Some(record) = db.idempotency_cache.get(&idempotency_key) {
         return verify_and_build_cached_delete(record, &key);
     }
 
-    match db_guard.store.get(&key) {
-        None | Some(Entry { value: None, .. }) => return StatusCode::NO_CONTENT.into_response(),
-        _ => {}
+    let current = db.store.get(&key);
+    if let Some(expected) = if_match {
+        match current.filter(|e| !e.is_expired(clock)) {
+            Some(entry) if entry.version == expected => {}
+            Some(entry) => {
+                let message = format!("If-Match {} but key is at version {}", expected, entry.version);
+                let mut response = precondition_failed_response(&message);
+                response.headers_mut().insert(header::ETAG, etag_value(entry.version));
+                return response;
+            }
+            None => return precondition_failed_response("If-Match given but key does not exist"),
+        }
     }
 
-    db_guard.next_version += 1;
-    let version = db_guard.next_version;
-    let now = state.clock.unix_now_secs();
-    db_guard.store.insert(key.clone(), Entry { value: None, version, expires_at: Some(now + TOMBSTONE_TTL_SECS) });
+    let version = match current {
+        Some(Entry { value: None, version, .. }) if if_match.is_some() => *version,
+        None | Some(Entry { value: None, .. }) => return StatusCode::NO_CONTENT.into_response(),
+        _ => {
+            let version = db.allocate_version();
+            let now = clock.unix_now_secs();
+            db.store.insert(key.clone(), Entry { value: None, version, expires_at: Some(now + TOMBSTONE_TTL_SECS) });
+            db.invalidate(&key);
+            version
+        }
+    };
 
     let record = IdempotencyRecord {
         method: HttpMethod::Delete,
         key_path: key,
         status_code: 200,
         etag: Some(version),
-        created_at: Instant::now(),
+        previous_version: None,
+        body: None,
+        created_at: clock.unix_now_secs(),
     };
-    db_guard.idempotency_cache.insert(idempotency_key, record);
+    db.idempotency_cache.insert(idempotency_key, record);
 
     let mut response = StatusCode::OK.into_response();
     response.headers_mut().insert(header::ETAG, etag_value(version));
@@ -1,7 +1,9 @@
 use clap::{Parser, ValueEnum};
 use std::net::SocketAddr;
-use transdb_common::Topology;
-use transdb_server::{NodeRole, Server, ServerConfig};
+use std::time::Duration;
+use transdb_common::{Limits, Topology};
+use transdb_server::tls::TlsConfig;
+use transdb_server::{batch::WriteBatchConfig, NodeRole, RouteBodyLimits, Server, ServerConfig, TcpOptions};
 
 #[derive(Debug, Clone, ValueEnum)]
 enum Role {
@@ -13,23 +15,119 @@ enum Role {
 #[command(name = "transdb-server")]
 struct Args {
     /// Role this node plays in the cluster.
+    #[arg(long, required_unless_present = "node_id", conflicts_with = "node_id")]
+    role: Option<Role>,
+
+    /// Start as the node with this `primary_id`/`replica_id` in the topology, instead of --role.
     #[arg(long)]
-    role: Role,
+    node_id: Option<String>,
 
     /// Path to a JSON file containing the cluster Topology.
     #[arg(long)]
     topology: std::path::PathBuf,
+
+    /// Enable group commit: apply up to this many PUT/DELETEs per write-lock acquisition.
+    #[arg(long)]
+    write_batch_size: Option<usize>,
+
+    /// With --write-batch-size, how long a batch may wait to fill before it is flushed (ms).
+    #[arg(long, default_value_t = 1)]
+    write_batch_flush_ms: u64,
+
+    /// Reject PUTs that would create a live key beyond this count with 507 Insufficient Storage.
+    #[arg(long)]
+    max_key_count: Option<usize>,
+
+    /// Do not set SO_REUSEADDR on the listening socket.
+    #[arg(long)]
+    no_reuse_address: bool,
+
+    /// Set SO_REUSEPORT on the listening socket (Unix only).
+    #[arg(long)]
+    reuse_port: bool,
+
+    /// Listen backlog: connections the kernel queues before the server accepts them.
+    #[arg(long, default_value_t = TcpOptions::default().backlog)]
+    listen_backlog: u32,
+
+    /// Leave Nagle's algorithm on for accepted connections (TCP_NODELAY is set by default).
+    #[arg(long)]
+    no_tcp_nodelay: bool,
+
+    /// Idle seconds before TCP keepalive probes start on accepted connections; 0 turns keepalive off.
+    #[arg(long, default_value_t = 30)]
+    tcp_keepalive_secs: u64,
+
+    /// File holding the HMAC key that PUT/DELETE/PATCH requests must be signed with.
+    #[arg(long)]
+    signature_key_file: Option<std::path::PathBuf>,
+
+    /// Add a `Server-Timing` header (lock wait vs. work) to key GET/PUT/DELETE responses.
+    #[arg(long)]
+    server_timing: bool,
+
+    /// Serve the admin routes (/stats) on this port instead of the node's data-plane port.
+    #[arg(long)]
+    admin_port: Option<u16>,
+
+    /// Start in read-only mode: writes get 503 until `POST /admin/readonly?enabled=false`.
+    #[arg(long)]
+    read_only: bool,
+
+    /// Serve HTTPS with this PEM certificate chain (requires --tls-key).
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<std::path::PathBuf>,
+
+    /// PEM private key for --tls-cert.
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<std::path::PathBuf>,
+
+    /// Serve repeated GETs of up to this many keys from an in-memory cache, skipping the store lock.
+    #[arg(long)]
+    get_cache_size: Option<usize>,
+
+    /// Refresh a copy of the store every this many milliseconds and serve `X-Consistency: eventual` GETs from it.
+    #[arg(long)]
+    read_snapshot_interval_ms: Option<u64>,
+
+    /// Remove expired values when they are read and answer 404, instead of returning them with `X-Expired: true`.
+    #[arg(long)]
+    strict_ttl: bool,
+
+    /// Answer data-plane requests beyond this many in flight with 503 OVERLOADED instead of queueing them.
+    #[arg(long)]
+    max_in_flight: Option<usize>,
+
+    /// Longest key accepted, in bytes (default: 1024).
+    #[arg(long)]
+    max_key_size: Option<usize>,
+
+    /// Largest value accepted, in bytes (default: 4194304).
+    #[arg(long)]
+    max_value_size: Option<usize>,
+
+    /// Validate the topology and print the address this node would bind, then exit without serving.
+    #[arg(long)]
+    check: bool,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
 
-    let topology: Topology = serde_json::from_str(&std::fs::read_to_string(&args.topology)?)?;
+    let topology = Topology::from_file(&args.topology)?;
 
-    let role = match args.role {
-        Role::Primary => NodeRole::Primary,
-        Role::Replica => NodeRole::Replica,
+    let role = match (&args.node_id, &args.role) {
+        (Some(id), _) if topology.primary_id.as_ref() == Some(id) => NodeRole::Primary,
+        (Some(id), _) if topology.replica_id.as_ref() == Some(id) => NodeRole::Replica,
+        (Some(id), _) => return Err(format!("node id {:?} is not in the topology", id).into()),
+        (None, Some(Role::Primary)) => NodeRole::Primary,
+        (None, Some(Role::Replica)) => NodeRole::Replica,
+        (None, None) => unreachable!("clap requires --role without --node-id"),
+    };
+    let node_id = match role {
+        NodeRole::Primary => topology.primary_id.clone(),
+        NodeRole::Replica => topology.replica_id.clone(),
     };
 
     let address: SocketAddr = match role {
@@ -41,21 +139,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .parse()?,
     };
 
+    if args.check {
+        println!("{}", address);
+        return Ok(());
+    }
+
+    let signature_key = match &args.signature_key_file {
+        Some(path) => Some(std::fs::read_to_string(path)?.trim().to_string()),
+        None => None,
+    };
+
+    let defaults = Limits::default();
+    let limits = Limits {
+        max_key_size: args.max_key_size.unwrap_or(defaults.max_key_size),
+        max_value_size: args.max_value_size.unwrap_or(defaults.max_value_size),
+        ..defaults
+    };
     let config = ServerConfig {
         address,
         role,
         topology: Some(topology),
+        body_limits: RouteBodyLimits::for_limits(&limits),
+        write_batch: args.write_batch_size.map(|max_batch_size| WriteBatchConfig {
+            max_batch_size,
+            flush_interval_ms: args.write_batch_flush_ms,
+        }),
+        max_key_count: args.max_key_count,
+        signature_key,
+        tcp: TcpOptions {
+            reuse_address: !args.no_reuse_address,
+            reuse_port: args.reuse_port,
+            backlog: args.listen_backlog,
+            nodelay: !args.no_tcp_nodelay,
+            keepalive: (args.tcp_keepalive_secs > 0).then(|| Duration::from_secs(args.tcp_keepalive_secs)),
+        },
+        server_timing: args.server_timing,
+        admin_port: args.admin_port,
+        read_only: args.read_only,
+        content_validator: None,
+        tls: args.tls_cert.zip(args.tls_key).map(|(cert_path, key_path)| TlsConfig { cert_path, key_path }),
+        get_cache_size: args.get_cache_size,
+        read_snapshot_interval: args.read_snapshot_interval_ms.map(Duration::from_millis),
+        max_in_flight: args.max_in_flight,
+        limits,
+        strict_ttl: args.strict_ttl,
     };
 
     let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+    let (admin_ready_tx, admin_ready_rx) = tokio::sync::oneshot::channel();
 
     // Print "Listening on <addr>" once the server signals it is bound.
     tokio::spawn(async move {
+        let node = node_id.map(|id| format!(" as {}", id)).unwrap_or_default();
         if let Ok(addr) = ready_rx.await {
-            println!("Listening on {}", addr);
+            println!("Listening on {}{}", addr, node);
+        }
+        if let Ok(addr) = admin_ready_rx.await {
+            println!("Admin listening on {}{}", addr, node);
         }
     });
 
-    Server::new(config).run(ready_tx).await?;
+    Server::new(config).run(ready_tx, Some(admin_ready_tx)).await?;
     Ok(())
 }
@@ -0,0 +1,159 @@
+//! `POST /multi-cas`: a compare-and-set spanning several keys. The request names the version it
+//! read for each of a set of keys; under one write lock the server checks that none of them has
+//! moved on and only then applies the writes. Two requests that read overlapping keys and write
+//! different ones therefore cannot both commit, which rules out write skew.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tokio::time::timeout;
+use transdb_common::batch::{MultiCasConflict, MultiCasRequest, MultiCasResponse};
+use transdb_common::{
+    ErrorResponse, Version, ERR_BATCH_TOO_LARGE, ERR_IDEMPOTENCY_KEY_TOO_LARGE, ERR_INVALID_BODY, ERR_KEY_TOO_LARGE,
+    ERR_MISSING_IDEMPOTENCY_KEY, ERR_NOT_PRIMARY, ERR_STORAGE_FULL, ERR_VERSION_CONFLICT,
+};
+
+use crate::config::LOCK_TIMEOUT;
+use crate::request_id;
+use crate::{
+    content_rejected_response, empty_key_response, error_response, extract_principal, items_digest, live_key_count,
+    lock_timeout_response, read_only_response, value_too_large_response, verify_and_build_cached_body, AppState, Entry,
+    HttpMethod, IdempotencyRecord, IdempotencyScope, NodeRole,
+};
+
+/// Handler for POST /multi-cas — 200 with one new version per write if every read key is still
+/// at the version given for it (`0` meaning absent, deleted or expired), otherwise 409
+/// `VERSION_CONFLICT` listing the keys that changed, and nothing is written. The idempotency
+/// key travels in the body; a conflict is not recorded against it, so the caller may retry
+/// with fresh reads under the same key. Each written value must pass the content validator,
+/// which sees no `Content-Type` since the values carry none; a rejected one fails the whole
+/// request with 422 `CONTENT_REJECTED`.
+pub async fn handle_multi_cas(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> Response {
+    if state.role == NodeRole::Replica {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+    if state.is_read_only() {
+        return read_only_response();
+    }
+
+    let request: MultiCasRequest = match serde_json::from_slice(&body) {
+        Ok(r) => r,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_BODY, format!("Invalid request body: {}", e)),
+    };
+    if let Err(reason) = request.check_limits(&state.limits) {
+        return error_response(StatusCode::BAD_REQUEST, ERR_BATCH_TOO_LARGE, reason);
+    }
+    let keys = request.reads.iter().map(|r| &r.key).chain(request.writes.iter().map(|w| &w.key));
+    for key in keys {
+        if key.is_empty() {
+            return empty_key_response();
+        }
+        if key.len() > state.limits.max_key_size {
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                ERR_KEY_TOO_LARGE,
+                format!("Key exceeds maximum size of {} bytes", state.limits.max_key_size),
+            );
+        }
+    }
+    let mut writes = Vec::with_capacity(request.writes.len());
+    for write in &request.writes {
+        let value = match write.value_b64.to_bytes() {
+            Ok(value) => value,
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_BODY, e),
+        };
+        if value.len() > state.limits.max_value_size {
+            return value_too_large_response(state.limits.max_value_size);
+        }
+        if let Some(validator) = &state.content_validator {
+            if let Err(e) = validator.validate(&write.key, &value, None) {
+                return content_rejected_response(e);
+            }
+        }
+        writes.push((write.key.clone(), Bytes::from(value)));
+    }
+    if request.idempotency_key.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, ERR_MISSING_IDEMPOTENCY_KEY, "idempotency_key is required");
+    }
+    if request.idempotency_key.len() > state.limits.max_idempotency_key_size {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            ERR_IDEMPOTENCY_KEY_TOO_LARGE,
+            format!("idempotency_key exceeds maximum length of {} bytes", state.limits.max_idempotency_key_size),
+        );
+    }
+    // The versions in the response are positional, so the items are compared in order.
+    let items = items_digest(&(&request.reads, &request.writes));
+    let idempotency_key = IdempotencyScope { principal: extract_principal(&headers), key: request.idempotency_key };
+
+    let mut db_guard = match timeout(LOCK_TIMEOUT, state.db.write()).await {
+        Ok(guard) => guard,
+        Err(_) => return lock_timeout_response(),
+    };
+
+    if let Some(record) = db_guard.idempotency_cache.get(&idempotency_key) {
+        return verify_and_build_cached_body(record, HttpMethod::MultiCas, &items);
+    }
+
+    let clock = state.clock.as_ref();
+    let current = |key: &str| db_guard.store.get(key).filter(|e| e.is_live(clock)).map_or(Version(0), |e| e.version);
+    let mut mismatched: Vec<String> = Vec::new();
+    for read in &request.reads {
+        if current(&read.key) != read.version && !mismatched.contains(&read.key) {
+            mismatched.push(read.key.clone());
+        }
+    }
+    if !mismatched.is_empty() {
+        return conflict_response(mismatched);
+    }
+
+    if let Some(max) = state.max_key_count {
+        let mut created: Vec<&str> =
+            writes.iter().map(|(key, _)| key.as_str()).filter(|k| current(k) == Version(0)).collect();
+        created.sort_unstable();
+        created.dedup();
+        if live_key_count(&db_guard, clock) + created.len() > max {
+            return error_response(
+                StatusCode::INSUFFICIENT_STORAGE,
+                ERR_STORAGE_FULL,
+                format!("Store is full: live key count has reached the limit of {}", max),
+            );
+        }
+    }
+
+    let mut response = MultiCasResponse { versions: Vec::with_capacity(writes.len()) };
+    for (key, value) in writes {
+        let version = db_guard.allocate_version();
+        db_guard.invalidate(&key);
+        db_guard.store.insert(key, Entry { value: Some(value), version, expires_at: None });
+        response.versions.push(version);
+    }
+
+    let body = Bytes::from(serde_json::to_vec(&response).expect("MultiCasResponse serializes"));
+    let record = IdempotencyRecord {
+        method: HttpMethod::MultiCas,
+        key_path: items,
+        status_code: 200,
+        etag: None,
+        previous_version: None,
+        body: Some(body.clone()),
+        created_at: clock.unix_now_secs(),
+    };
+    db_guard.idempotency_cache.insert(idempotency_key, record);
+
+    (StatusCode::OK, [(header::CONTENT_TYPE, "application/json")], body).into_response()
+}
+
+fn conflict_response(mismatched: Vec<String>) -> Response {
+    let body = ErrorResponse {
+        error: format!("{} read key(s) changed since they were read", mismatched.len()),
+        error_type: ERR_VERSION_CONFLICT.to_string(),
+        details: Some(serde_json::to_value(MultiCasConflict { mismatched }).expect("MultiCasConflict serializes")),
+        request_id: request_id::current(),
+    };
+    (StatusCode::CONFLICT, Json(body)).into_response()
+}
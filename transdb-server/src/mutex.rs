@@ -0,0 +1,171 @@
+//! Advisory mutexes built on the key store: mutex `name` is the key `_mutex/{name}` whose value
+//! is the holder's owner token and whose TTL is the lease. No state is kept outside the store,
+//! so a holder that stops renewing simply lets the key expire.
+
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tokio::time::timeout;
+use transdb_common::{
+    MutexStatus, ERR_INVALID_QUERY, ERR_INVALID_TTL, ERR_KEY_TOO_LARGE, ERR_NOT_PRIMARY,
+    ERR_STORAGE_FULL, MUTEX_KEY_PREFIX,
+};
+
+use crate::config::{DEFAULT_MUTEX_TTL_SECS, LOCK_TIMEOUT, MAX_MUTEX_TTL_SECS, TOMBSTONE_TTL_SECS};
+use crate::{
+    error_response, live_key_count, lock_timeout_response, read_only_response, AppState, Clock, DbState, Entry,
+    NodeRole,
+};
+
+/// Query string accepted by the mutex endpoints: `?owner=CLIENT&ttl_secs=30`.
+#[derive(Debug, Deserialize)]
+pub struct MutexParams {
+    pub owner: String,
+    pub ttl_secs: Option<u64>,
+}
+
+enum MutexOp {
+    Acquire { ttl_secs: u64 },
+    Renew { ttl_secs: u64 },
+    Release,
+}
+
+/// Handler for POST /mutex/:name/acquire — takes the mutex if it is free, expired, or already
+/// held by `owner` (re-acquiring resets the lease). 412 with the current holder otherwise.
+pub async fn handle_acquire(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    params: Result<Query<MutexParams>, QueryRejection>,
+) -> Response {
+    handle_mutex(state, name, params, |ttl_secs| MutexOp::Acquire { ttl_secs }).await
+}
+
+/// Handler for POST /mutex/:name/renew — extends the lease if `owner` still holds the mutex,
+/// 412 with the current holder (if any) otherwise.
+pub async fn handle_renew(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    params: Result<Query<MutexParams>, QueryRejection>,
+) -> Response {
+    handle_mutex(state, name, params, |ttl_secs| MutexOp::Renew { ttl_secs }).await
+}
+
+/// Handler for DELETE /mutex/:name — 204 after releasing a mutex held by `owner`, 412 with the
+/// current holder (if any) otherwise. `ttl_secs` is ignored.
+pub async fn handle_release(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    params: Result<Query<MutexParams>, QueryRejection>,
+) -> Response {
+    handle_mutex(state, name, params, |_| MutexOp::Release).await
+}
+
+async fn handle_mutex(
+    state: AppState,
+    name: String,
+    params: Result<Query<MutexParams>, QueryRejection>,
+    op: impl FnOnce(u64) -> MutexOp,
+) -> Response {
+    if state.role == NodeRole::Replica {
+        return error_response(StatusCode::METHOD_NOT_ALLOWED, ERR_NOT_PRIMARY, "Replica does not accept key operations");
+    }
+    if state.is_read_only() {
+        return read_only_response();
+    }
+
+    let key = format!("{}{}", MUTEX_KEY_PREFIX, name);
+    if key.len() > state.limits.max_key_size {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            ERR_KEY_TOO_LARGE,
+            format!("Mutex key exceeds maximum size of {} bytes", state.limits.max_key_size),
+        );
+    }
+    let Query(params) = match params {
+        Ok(params) => params,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_QUERY, format!("Invalid query: {}", e)),
+    };
+    if params.owner.is_empty() {
+        return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_QUERY, "owner must not be empty");
+    }
+    let ttl_secs = params.ttl_secs.unwrap_or(DEFAULT_MUTEX_TTL_SECS);
+    if ttl_secs == 0 {
+        return error_response(StatusCode::BAD_REQUEST, ERR_INVALID_TTL, "ttl_secs must be positive");
+    }
+    // Also keeps `now + ttl_secs` from overflowing.
+    if ttl_secs > MAX_MUTEX_TTL_SECS {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            ERR_INVALID_TTL,
+            format!("ttl_secs must not exceed {}", MAX_MUTEX_TTL_SECS),
+        );
+    }
+
+    let mut db_guard = match timeout(LOCK_TIMEOUT, state.db.write()).await {
+        Ok(guard) => guard,
+        Err(_) => return lock_timeout_response(),
+    };
+    apply_mutex(&mut db_guard, state.clock.as_ref(), state.max_key_count, key, &params.owner, op(ttl_secs))
+}
+
+fn apply_mutex(
+    db: &mut DbState,
+    clock: &dyn Clock,
+    max_key_count: Option<usize>,
+    key: String,
+    owner: &str,
+    op: MutexOp,
+) -> Response {
+    let now = clock.unix_now_secs();
+    let holder = db.store.get(&key).filter(|e| e.is_live(clock)).map(|e| {
+        let owner = String::from_utf8_lossy(e.value.as_deref().unwrap_or_default()).into_owned();
+        (owner, e.expires_at.map_or(0, |ts| ts.saturating_sub(now)))
+    });
+    let held_by_caller = holder.as_ref().is_some_and(|(holder, _)| holder == owner);
+
+    match op {
+        MutexOp::Acquire { ttl_secs } if held_by_caller || holder.is_none() => {
+            if let Some(max) = max_key_count {
+                if holder.is_none() && live_key_count(db, clock) >= max {
+                    return error_response(
+                        StatusCode::INSUFFICIENT_STORAGE,
+                        ERR_STORAGE_FULL,
+                        format!("Store is full: live key count has reached the limit of {}", max),
+                    );
+                }
+            }
+            let version = db.allocate_version();
+            let value = Some(owner.to_string().into_bytes().into());
+            db.invalidate(&key);
+            db.store.insert(key, Entry { value, version, expires_at: Some(now + ttl_secs) });
+            held_response(owner, ttl_secs)
+        }
+        MutexOp::Renew { ttl_secs } if held_by_caller => {
+            // Like PATCH /batch/ttl, a lease extension leaves the version unchanged.
+            if let Some(entry) = db.store.get_mut(&key) {
+                entry.expires_at = Some(now + ttl_secs);
+            }
+            db.invalidate(&key);
+            held_response(owner, ttl_secs)
+        }
+        MutexOp::Release if held_by_caller => {
+            let version = db.allocate_version();
+            db.invalidate(&key);
+            db.store.insert(key, Entry { value: None, version, expires_at: Some(now + TOMBSTONE_TTL_SECS) });
+            StatusCode::NO_CONTENT.into_response()
+        }
+        _ => {
+            let (owner, ttl_remaining) = holder.map_or((None, 0), |(owner, ttl)| (Some(owner), ttl));
+            let status = MutexStatus { acquired: false, owner, ttl_remaining };
+            (StatusCode::PRECONDITION_FAILED, Json(status)).into_response()
+        }
+    }
+}
+
+fn held_response(owner: &str, ttl_secs: u64) -> Response {
+    Json(MutexStatus { acquired: true, owner: Some(owner.to_string()), ttl_remaining: ttl_secs }).into_response()
+}
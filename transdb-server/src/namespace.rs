@@ -0,0 +1,114 @@
+//! Independent key spaces served under `/{namespace}/keys/:key`. Each namespace has its own
+//! store, idempotency cache and version counter; the un-prefixed `/keys/:key` routes keep using
+//! [`AppState::db`]. A namespace is created by its first write and lives as long as the process.
+
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::timeout;
+use transdb_common::{validate_namespace, ERR_INVALID_NAMESPACE};
+
+use crate::config::LOCK_TIMEOUT;
+use crate::{
+    error_response, handle_delete, handle_get, handle_patch, handle_put, lock_timeout_response, AppState, Db, NodeRole,
+};
+
+/// Namespace name → its store. Shared by all clones of the state.
+pub type Namespaces = Arc<RwLock<HashMap<String, Db>>>;
+
+impl AppState {
+    /// This state with `db` swapped for `namespace`'s store. A write (`create`) registers an
+    /// empty store for a new namespace; a read of one gets an unregistered empty store. Writes
+    /// bypass the group-commit batcher and reads the get cache and read snapshot, which only serve
+    /// the default store.
+    async fn for_namespace(&self, namespace: &str, create: bool) -> Result<AppState, Response> {
+        if let Err(reason) = validate_namespace(namespace) {
+            return Err(error_response(StatusCode::BAD_REQUEST, ERR_INVALID_NAMESPACE, reason));
+        }
+        let existing = match timeout(LOCK_TIMEOUT, self.namespaces.read()).await {
+            Ok(namespaces) => namespaces.get(namespace).cloned(),
+            Err(_) => return Err(lock_timeout_response()),
+        };
+        // A write the handler is going to refuse must not leave an empty namespace behind.
+        let create = create && self.role == NodeRole::Primary && !self.is_read_only();
+        let db = match existing {
+            Some(db) => db,
+            None if create => match timeout(LOCK_TIMEOUT, self.namespaces.write()).await {
+                Ok(mut namespaces) => namespaces.entry(namespace.to_string()).or_default().clone(),
+                Err(_) => return Err(lock_timeout_response()),
+            },
+            None => Db::default(),
+        };
+        Ok(AppState { db, write_batcher: None, get_cache: None, sequence: None, read_snapshot: None, ..self.clone() })
+    }
+}
+
+/// Handler for GET /:namespace/keys/:key — as [`handle_get`] within the namespace; a namespace
+/// that was never written to holds no keys.
+pub async fn handle_namespaced_get(
+    State(state): State<AppState>,
+    Path((namespace, key)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    match state.for_namespace(&namespace, false).await {
+        Ok(state) => handle_get(State(state), Path(key), headers).await,
+        Err(response) => response,
+    }
+}
+
+/// Handler for PUT /:namespace/keys/:key — as [`handle_put`], creating the namespace if needed.
+pub async fn handle_namespaced_put(
+    State(state): State<AppState>,
+    Path((namespace, key)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    match state.for_namespace(&namespace, true).await {
+        Ok(state) => handle_put(State(state), Path(key), headers, body).await,
+        Err(response) => response,
+    }
+}
+
+/// Handler for PATCH /:namespace/keys/:key — as [`handle_patch`], creating the namespace if needed.
+pub async fn handle_namespaced_patch(
+    State(state): State<AppState>,
+    Path((namespace, key)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    match state.for_namespace(&namespace, true).await {
+        Ok(state) => handle_patch(State(state), Path(key), headers, body).await,
+        Err(response) => response,
+    }
+}
+
+/// Handler for DELETE /:namespace/keys/:key — as [`handle_delete`], creating the namespace if
+/// needed so that idempotent replays and `If-Match` behave as in the default store.
+pub async fn handle_namespaced_delete(
+    State(state): State<AppState>,
+    Path((namespace, key)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    match state.for_namespace(&namespace, true).await {
+        Ok(state) => handle_delete(State(state), Path(key), headers).await,
+        Err(response) => response,
+    }
+}
+
+/// Handler for GET /admin/namespaces — the names of all namespaces, sorted, as a JSON array.
+pub async fn handle_list_namespaces(State(state): State<AppState>) -> Response {
+    let namespaces = match timeout(LOCK_TIMEOUT, state.namespaces.read()).await {
+        Ok(namespaces) => namespaces,
+        Err(_) => return lock_timeout_response(),
+    };
+    let mut names: Vec<&String> = namespaces.keys().collect();
+    names.sort_unstable();
+    Json(names).into_response()
+}
@@ -0,0 +1,28 @@
+//! Load shedding for [`ServerConfig::max_in_flight`](crate::ServerConfig). A request arriving
+//! while the limit is reached is refused at once, rather than queueing behind the store lock
+//! until it times out along with everything else.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use transdb_common::ERR_OVERLOADED;
+
+use crate::config::OVERLOADED_RETRY_AFTER_SECS;
+use crate::error_response;
+
+/// Middleware admitting as many concurrent requests as `permits` has; the rest get 503
+/// `OVERLOADED` with a `Retry-After`.
+pub async fn shed_load(State(permits): State<Arc<Semaphore>>, request: Request, next: Next) -> Response {
+    let Ok(_permit) = permits.try_acquire() else {
+        let mut response =
+            error_response(StatusCode::SERVICE_UNAVAILABLE, ERR_OVERLOADED, "Server is overloaded, retry later");
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(OVERLOADED_RETRY_AFTER_SECS));
+        return response;
+    };
+    next.run(request).await
+}
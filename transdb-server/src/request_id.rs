@@ -0,0 +1,52 @@
+//! `X-Request-Id`: every request gets an ID, the client's own if it sent a usable one and a
+//! fresh UUID otherwise. The ID is echoed on the response and carried in error bodies as
+//! `request_id`, so an error seen by a client can be matched to the request that caused it.
+
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+use crate::config::MAX_REQUEST_ID_LEN;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Middleware assigning the request its ID; handlers read it back through [`current`].
+pub async fn assign_request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_usable(id))
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    let mut response = REQUEST_ID.scope(id.clone(), next.run(request)).await;
+    let value = HeaderValue::from_str(&id).expect("request IDs are visible ASCII");
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+/// The ID of the request being handled; `None` outside [`assign_request_id`], e.g. in a
+/// router built without it.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(String::clone).ok()
+}
+
+/// Run `f` as part of the request `id`, for work done on its behalf by another task (the
+/// group-commit batcher).
+pub(crate) fn within<R>(id: Option<String>, f: impl FnOnce() -> R) -> R {
+    match id {
+        Some(id) => REQUEST_ID.sync_scope(id, f),
+        None => f(),
+    }
+}
+
+fn is_usable(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
@@ -0,0 +1,53 @@
+//! Optional read snapshot of the default store; see
+//! [`ServerConfig::read_snapshot_interval`](crate::ServerConfig).
+//!
+//! A background task copies the store at a fixed interval and publishes the copy with a single
+//! pointer swap. `GET /keys/:key` with `X-Consistency: eventual` is answered from the latest copy
+//! without touching the store lock, so it never waits behind a writer; the price is that it can
+//! miss anything written since the copy was taken.
+
+use arc_swap::ArcSwap;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use crate::{Db, Entry};
+
+/// Handle shared by [`AppState`](crate::AppState), which reads from it, and the refresh task.
+pub type SharedSnapshot = Arc<ArcSwap<Snapshot>>;
+
+/// The default store as it was at one instant.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub store: HashMap<String, Entry>,
+    /// The global version high-water mark when the copy was taken, served as `X-Sequence`.
+    pub sequence: u64,
+}
+
+/// Copy `db` into `snapshot` now. Writers are held off by the read lock for as long as the copy
+/// takes, so each snapshot is a consistent cut of the store.
+pub async fn refresh(db: &Db, snapshot: &ArcSwap<Snapshot>) {
+    let copy = {
+        let db = db.read().await;
+        Snapshot { store: db.store.clone(), sequence: db.sequence() }
+    };
+    snapshot.store(Arc::new(copy));
+}
+
+/// Take a snapshot of `db` now and then every `interval`, on a task spawned on the current
+/// runtime. The task stops once every handle to the returned snapshot has been dropped.
+pub fn spawn_refresher(db: Db, interval: Duration) -> SharedSnapshot {
+    let snapshot = SharedSnapshot::default();
+    tokio::spawn(run_refresher(db, Arc::downgrade(&snapshot), interval));
+    snapshot
+}
+
+async fn run_refresher(db: Db, snapshot: Weak<ArcSwap<Snapshot>>, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticks.tick().await;
+        let Some(snapshot) = snapshot.upgrade() else { return };
+        refresh(&db, &snapshot).await;
+    }
+}
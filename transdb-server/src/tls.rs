@@ -0,0 +1,71 @@
+//! HTTPS for [`ServerConfig::tls`](crate::ServerConfig): a rustls acceptor loaded from PEM files,
+//! and an accept loop that terminates TLS before handing each connection to the router.
+
+use axum::Router;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, ServerConfig as RustlsConfig};
+use tokio_rustls::TlsAcceptor;
+
+/// Certificate chain and private key, both PEM files, for serving HTTPS.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsConfig {
+    /// Leaf certificate first, followed by any intermediates.
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Read both files and build an acceptor offering HTTP/2 and HTTP/1.1 via ALPN. Fails with
+    /// `InvalidData` if either file holds no usable PEM item or the key does not fit the
+    /// certificate.
+    pub fn acceptor(&self) -> io::Result<TlsAcceptor> {
+        let invalid = |path: &PathBuf, e: &dyn std::fmt::Display| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+        };
+        let certs = CertificateDer::pem_file_iter(&self.cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| invalid(&self.cert_path, &e))?;
+        let key = PrivateKeyDer::from_pem_file(&self.key_path).map_err(|e| invalid(&self.key_path, &e))?;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut config = RustlsConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|e| invalid(&self.cert_path, &e))?;
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+/// Serve `app` over TLS on `listener`. Like `axum::serve`, it runs until its task is dropped. A
+/// client that fails the handshake, such as one speaking plain HTTP, only loses its connection.
+pub(crate) async fn serve(listener: TcpListener, acceptor: TlsAcceptor, app: Router) -> io::Result<()> {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            // Per-connection failures (reset before accept, fd exhaustion) are transient; back
+            // off briefly as `axum::serve` does rather than spinning.
+            Err(_) => {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            let Ok(stream) = acceptor.accept(stream).await else {
+                return;
+            };
+            let _ = auto::Builder::new(TokioExecutor::new()).serve_connection(TokioIo::new(stream), service).await;
+        });
+    }
+}
@@ -0,0 +1,59 @@
+use std::process::Command;
+
+/// Run `transdb-server --check` against a topology file written to a temp dir.
+fn run_check(name: &str, role: &str, topology_json: &str) -> std::process::Output {
+    let path = std::env::temp_dir().join(format!("transdb-check-{}-{}.json", std::process::id(), name));
+    std::fs::write(&path, topology_json).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_transdb-server"))
+        .args(["--check", "--role", role, "--topology"])
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).ok();
+    output
+}
+
+#[test]
+fn test_check_accepts_valid_topology_without_serving() {
+    let topology = r#"{"primary_addr":"127.0.0.1:7101","replica_addr":"127.0.0.1:7102"}"#;
+
+    let output = run_check("valid", "replica", topology);
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "127.0.0.1:7102");
+}
+
+#[test]
+fn test_check_rejects_invalid_topology() {
+    let output = run_check("bad-port", "primary", r#"{"primary_addr":"127.0.0.1:http"}"#);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("primary_addr must be host:port"));
+
+    // Valid on its own, but a replica needs replica_addr.
+    let output = run_check("no-replica", "replica", r#"{"primary_addr":"127.0.0.1:7101"}"#);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_check_resolves_node_id_from_topology() {
+    let path = std::env::temp_dir().join(format!("transdb-check-{}-node-id.json", std::process::id()));
+    let topology = r#"{"primary_addr":"127.0.0.1:7101","replica_addr":"127.0.0.1:7102",
+        "primary_id":"primary-1","replica_id":"replica-1"}"#;
+    std::fs::write(&path, topology).unwrap();
+    let check = |node_id: &str| {
+        Command::new(env!("CARGO_BIN_EXE_transdb-server"))
+            .args(["--check", "--node-id", node_id, "--topology"])
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+
+    let output = check("replica-1");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "127.0.0.1:7102");
+
+    let output = check("replica-2");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("is not in the topology"));
+    std::fs::remove_file(&path).ok();
+}
@@ -1,29 +1,56 @@
-use axum::body::Bytes;
+use axum::body::{Body, Bytes};
 use axum::extract::{Path, State};
-use axum::http::{header, HeaderMap, StatusCode};
+use axum::http::{header, HeaderMap, Request, StatusCode};
 use axum::response::Response;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use transdb_common::{MAX_KEY_SIZE, MAX_VALUE_SIZE};
+use transdb_common::{
+    signing, BulkTtlResult, EntryMetadata, ErrorResponse, KeyList, Limits, MutexStatus, StoreStats, Topology,
+    Version, ERR_BATCH_TOO_LARGE, ERR_CONTENT_REJECTED, ERR_EMPTY_KEY,
+    ERR_IDEMPOTENCY_KEY_TOO_LARGE, ERR_IDEMPOTENCY_MISMATCH, ERR_INVALID_BODY,
+    ERR_INVALID_NAMESPACE, ERR_INVALID_QUERY, ERR_INVALID_SIGNATURE, ERR_INVALID_TTL,
+    ERR_KEY_NOT_FOUND, ERR_KEY_TOO_LARGE, ERR_MISSING_IDEMPOTENCY_KEY, ERR_NOT_PRIMARY,
+    ERR_OVERLOADED, ERR_PRECONDITION_FAILED, ERR_RANGE_NOT_SATISFIABLE, ERR_READ_ONLY,
+    ERR_STORAGE_FULL, ERR_TOPOLOGY_UNKNOWN, ERR_VALUE_TOO_LARGE, ERR_VERSION_CONFLICT,
+    MUTEX_KEY_PREFIX, NDJSON_CONTENT_TYPE, PROTOCOL_VERSION,
+};
+use transdb_common::batch::{Base64Value, MultiCasConflict, MultiCasResponse};
 use transdb_server::{
-    config::TOMBSTONE_TTL_SECS, handle_delete, handle_get, handle_put, AppState, Clock, Entry,
-    NodeRole, Server, ServerConfig,
+    batch::WriteBatchConfig,
+    content::{ContentError, ContentValidator, JsonValidator, MaxPayloadValidator},
+    config::{
+        DEFAULT_MUTEX_TTL_SECS, MAX_MUTEX_TTL_SECS, MAX_REQUEST_ID_LEN, MAX_SIGNATURE_SKEW_SECS, TOMBSTONE_TTL_SECS,
+    },
+    snapshot,
+    handle_batch_ttl, handle_delete, handle_get, handle_key_count, handle_patch, handle_put, handle_stats,
+    handle_topology, handle_version, AppState, Clock, Entry, NodeRole, RouteBodyLimits, Server, ServerConfig,
+    SystemClock, TcpOptions,
 };
+use tower::ServiceExt;
 
 // --- Test helpers ---
 
 const NOW: u64 = 10_000;
 
+/// Holds Unix time in milliseconds.
 struct MockClock(AtomicU64);
 
 impl MockClock {
     fn new(now: u64) -> Arc<Self> {
-        Arc::new(Self(AtomicU64::new(now)))
+        Arc::new(Self(AtomicU64::new(now * 1000)))
+    }
+
+    fn set_millis(&self, ms: u64) {
+        self.0.store(ms, Ordering::Relaxed);
     }
 }
 
 impl Clock for MockClock {
     fn unix_now_secs(&self) -> u64 {
+        self.0.load(Ordering::Relaxed) / 1000
+    }
+
+    fn unix_now_millis(&self) -> u64 {
         self.0.load(Ordering::Relaxed)
     }
 }
@@ -40,7 +67,7 @@ async fn store_with(key: &str, value: &[u8]) -> AppState {
     let state = AppState::new(MockClock::new(NOW) as Arc<dyn Clock>, NodeRole::Primary);
     state.db.write().await.store.insert(
         key.to_string(),
-        Entry { value: Some(Bytes::from(value.to_vec())), version: 1, expires_at: None },
+        Entry { value: Some(Bytes::from(value.to_vec())), version: Version(1), expires_at: None },
     );
     state
 }
@@ -62,14 +89,20 @@ async fn response_body(response: Response) -> Vec<u8> {
     axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()
 }
 
+/// Consume an error response body and return its `error_type`.
+async fn response_error_type(response: Response) -> String {
+    let body: ErrorResponse = serde_json::from_slice(&response_body(response).await).unwrap();
+    body.error_type
+}
+
 /// Extract the version number from a response's ETag header.
-fn response_version(response: &Response) -> u64 {
+fn response_version(response: &Response) -> Version {
     let etag = response.headers().get(header::ETAG).unwrap().to_str().unwrap();
-    etag.trim_matches('"').parse().unwrap()
+    Version::from_etag(etag).unwrap()
 }
 
 /// Issue a PUT and return the stored version.
-async fn put_key(state: &AppState, key: &str, value: &[u8], tok: &str) -> u64 {
+async fn put_key(state: &AppState, key: &str, value: &[u8], tok: &str) -> Version {
     let headers = headers_with_idempotency_key(tok);
     let response =
         handle_put(State(state.clone()), Path(key.to_string()), headers, Bytes::from(value.to_vec()))
@@ -79,7 +112,7 @@ async fn put_key(state: &AppState, key: &str, value: &[u8], tok: &str) -> u64 {
 }
 
 /// Issue a DELETE and return `Some(version)` for a live-key tombstone or `None` for a no-op.
-async fn delete_key(state: &AppState, key: &str, tok: &str) -> Option<u64> {
+async fn delete_key(state: &AppState, key: &str, tok: &str) -> Option<Version> {
     let headers = headers_with_idempotency_key(tok);
     let response = handle_delete(State(state.clone()), Path(key.to_string()), headers).await;
     match response.status() {
@@ -92,7 +125,7 @@ async fn delete_key(state: &AppState, key: &str, tok: &str) -> Option<u64> {
 /// Assert the result of GET /keys/:key.
 /// `None` asserts 404; `Some(value)` asserts 200 + matching body.
 async fn assert_get(state: &AppState, key: &str, expected: Option<&[u8]>) {
-    let response = handle_get(State(state.clone()), Path(key.to_string())).await;
+    let response = handle_get(State(state.clone()), Path(key.to_string()), HeaderMap::new()).await;
     match expected {
         None => assert_eq!(response.status(), StatusCode::NOT_FOUND),
         Some(value) => {
@@ -108,7 +141,26 @@ async fn assert_get(state: &AppState, key: &str, expected: Option<&[u8]>) {
 fn test_server_config_custom() {
     use std::net::SocketAddr;
     let addr: SocketAddr = "0.0.0.0:9000".parse().unwrap();
-    let config = ServerConfig { address: addr, role: NodeRole::Primary, topology: None };
+    let config = ServerConfig {
+        address: addr,
+        role: NodeRole::Primary,
+        topology: None,
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: None,
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: None,
+        read_only: false,
+        content_validator: None,
+        tls: None,
+        get_cache_size: None,
+        read_snapshot_interval: None,
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: false,
+    };
     assert_eq!(config.address.to_string(), "0.0.0.0:9000");
 }
 
@@ -116,30 +168,245 @@ fn test_server_config_custom() {
 fn test_server_creation_with_config() {
     use std::net::SocketAddr;
     let addr: SocketAddr = "0.0.0.0:9000".parse().unwrap();
-    let config = ServerConfig { address: addr, role: NodeRole::Primary, topology: None };
+    let config = ServerConfig {
+        address: addr,
+        role: NodeRole::Primary,
+        topology: None,
+        body_limits: RouteBodyLimits::default(),
+        write_batch: None,
+        max_key_count: None,
+        signature_key: None,
+        tcp: TcpOptions::default(),
+        server_timing: false,
+        admin_port: None,
+        read_only: false,
+        content_validator: None,
+        tls: None,
+        get_cache_size: None,
+        read_snapshot_interval: None,
+        max_in_flight: None,
+        limits: Limits::default(),
+        strict_ttl: false,
+    };
     let server = Server::new(config);
     assert_eq!(server.address().to_string(), "0.0.0.0:9000");
 }
 
+/// A port whose last connection was closed server-side (leaving it in TIME_WAIT) can be rebound
+/// straight away when `SO_REUSEADDR` is on.
+#[tokio::test]
+async fn test_listener_rebinds_port_in_time_wait() {
+    use std::io::Read;
+
+    let options = TcpOptions { backlog: 16, ..TcpOptions::default() };
+    let first = options.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = first.local_addr().unwrap();
+
+    let mut client = std::net::TcpStream::connect(addr).unwrap();
+    let (server_side, _) = first.accept().await.unwrap();
+    drop(server_side); // the server closes first, so its end of the connection enters TIME_WAIT
+    client.read_to_end(&mut Vec::new()).unwrap();
+    drop(client);
+    drop(first);
+
+    let second = options.bind(addr).expect("rebinding a TIME_WAIT port must succeed with SO_REUSEADDR");
+    assert_eq!(second.local_addr().unwrap(), addr);
+}
+
+#[tokio::test]
+async fn test_accepted_connections_inherit_nodelay_and_keepalive() {
+    let accept = |options: TcpOptions| async move {
+        let listener = options.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let _client = std::net::TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        listener.accept().await.unwrap().0
+    };
+
+    let stream = accept(TcpOptions::default()).await;
+    assert!(stream.nodelay().unwrap());
+    let socket = socket2::SockRef::from(&stream);
+    assert!(socket.keepalive().unwrap());
+    #[cfg(target_os = "linux")]
+    assert_eq!(socket.tcp_keepalive_time().unwrap(), std::time::Duration::from_secs(30));
+
+    let stream = accept(TcpOptions { nodelay: false, keepalive: None, ..TcpOptions::default() }).await;
+    assert!(!stream.nodelay().unwrap());
+    assert!(!socket2::SockRef::from(&stream).keepalive().unwrap());
+}
+
 #[test]
 fn test_router_creation() {
-    let router =
-        Server::create_router(AppState::new(MockClock::new(NOW) as Arc<dyn Clock>, NodeRole::Primary));
+    let router = Server::create_router(
+        AppState::new(MockClock::new(NOW) as Arc<dyn Clock>, NodeRole::Primary),
+        &RouteBodyLimits::default(),
+    );
     assert!(std::mem::size_of_val(&router) > 0);
 }
 
+#[tokio::test]
+async fn test_router_applies_key_put_body_limit() {
+    let limits = RouteBodyLimits { key_put: 16, ..RouteBodyLimits::default() };
+    let put = |len: usize| {
+        Request::put("/keys/k")
+            .header("idempotency-key", format!("idem-{len}"))
+            .body(Body::from(vec![b'x'; len]))
+            .unwrap()
+    };
+
+    let router = Server::create_router(empty_store(), &limits);
+    let response = router.clone().oneshot(put(16)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = router.clone().oneshot(put(17)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // The limit is scoped to PUT; GET still works.
+    let response = router.oneshot(Request::get("/keys/k").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_router_sheds_requests_beyond_max_in_flight() {
+    let mut state = store_with("k", b"v").await;
+    state.max_in_flight = Some(1);
+    let router = Server::create_router(state.clone(), &RouteBodyLimits::default());
+    let get = || Request::get("/keys/k").body(Body::empty()).unwrap();
+
+    // Park one GET on the store lock so it stays in flight.
+    let writer = state.db.write().await;
+    let parked = tokio::spawn(router.clone().oneshot(get()));
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    let response = router.clone().oneshot(get()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    assert_eq!(response_error_type(response).await, ERR_OVERLOADED);
+    // Operators can still reach the admin routes.
+    let admin = Request::get("/admin/namespaces").body(Body::empty()).unwrap();
+    assert_eq!(router.clone().oneshot(admin).await.unwrap().status(), StatusCode::OK);
+
+    drop(writer);
+    assert_eq!(parked.await.unwrap().unwrap().status(), StatusCode::OK);
+    assert_eq!(router.oneshot(get()).await.unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_router_requires_signed_writes_when_keyed() {
+    let mut state = empty_store();
+    state.signature_key = Some(Arc::from(&b"secret"[..]));
+    let router = Server::create_router(state, &RouteBodyLimits::default());
+    let put = |tok: &str, timestamp: u64, signature: Option<String>| {
+        let mut request = Request::put("/keys/k").header("idempotency-key", tok).header("x-timestamp", timestamp);
+        if let Some(signature) = signature {
+            request = request.header("x-signature", signature);
+        }
+        request.body(Body::from("v")).unwrap()
+    };
+    let signed = |timestamp| Some(signing::sign(b"secret", "PUT", "/keys/k", b"v", timestamp));
+
+    let response = router.clone().oneshot(put("tok-1", NOW, signed(NOW))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stale = NOW - MAX_SIGNATURE_SKEW_SECS - 1;
+    let wrong_key = Some(signing::sign(b"other", "PUT", "/keys/k", b"v", NOW));
+    for request in [put("tok-2", NOW, None), put("tok-3", stale, signed(stale)), put("tok-4", NOW, wrong_key)] {
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response_error_type(response).await, ERR_INVALID_SIGNATURE);
+    }
+
+    // Reads stay unsigned for backwards compatibility.
+    let response = router.oneshot(Request::get("/keys/k").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_signature_covers_the_query_string() {
+    let mut state = empty_store();
+    state.signature_key = Some(Arc::from(&b"secret"[..]));
+    let router = Server::create_router(state.clone(), &RouteBodyLimits::default());
+    let set_read_only = |path_and_query: &str, signature: String| {
+        let request = Request::post(path_and_query)
+            .header("x-timestamp", NOW)
+            .header("x-signature", signature)
+            .body(Body::empty())
+            .unwrap();
+        router.clone().oneshot(request)
+    };
+    let signature = signing::sign(b"secret", "POST", "/admin/readonly?enabled=true", b"", NOW);
+
+    let response = set_read_only("/admin/readonly?enabled=true", signature.clone()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(state.is_read_only());
+
+    // A captured signature does not carry over to another query.
+    let replayed = set_read_only("/admin/readonly?enabled=false", signature).await.unwrap();
+    assert_eq!(replayed.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response_error_type(replayed).await, ERR_INVALID_SIGNATURE);
+    assert!(state.is_read_only());
+}
+
+async fn request_id_and_body(response: Response) -> (String, Option<String>) {
+    let header = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    let body: ErrorResponse = serde_json::from_slice(&response_body(response).await).unwrap();
+    (header, body.request_id)
+}
+
+#[tokio::test]
+async fn test_router_assigns_request_ids_and_reports_them_in_errors() {
+    let router = Server::create_router(empty_store(), &RouteBodyLimits::default());
+    let get = |request_id: Option<&str>| {
+        let mut request = Request::get("/keys/missing");
+        if let Some(id) = request_id {
+            request = request.header("x-request-id", id);
+        }
+        request.body(Body::empty()).unwrap()
+    };
+
+    let response = router.clone().oneshot(get(Some("req-42"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(request_id_and_body(response).await, ("req-42".to_string(), Some("req-42".to_string())));
+
+    // Absent or unusable IDs are replaced by a generated one.
+    let too_long = "x".repeat(MAX_REQUEST_ID_LEN + 1);
+    for request_id in [None, Some(""), Some(too_long.as_str()), Some("has space")] {
+        let (header, body) = request_id_and_body(router.clone().oneshot(get(request_id)).await.unwrap()).await;
+        assert_eq!(header.len(), 36, "{header}");
+        assert_eq!(body.as_deref(), Some(header.as_str()));
+    }
+
+    // Successful responses carry the header too.
+    let put = Request::put("/keys/k").header("idempotency-key", "tok-1").header("x-request-id", "req-43");
+    let response = router.oneshot(put.body(Body::from("v")).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "req-43");
+}
+
+#[tokio::test]
+async fn test_batched_write_errors_carry_the_request_id() {
+    let mut state = empty_store();
+    state.max_key_count = Some(0);
+    let state = state.with_write_batching(WriteBatchConfig { max_batch_size: 1, flush_interval_ms: 5 });
+    let router = Server::create_router(state, &RouteBodyLimits::default());
+    let put = Request::put("/keys/k").header("idempotency-key", "tok-1").header("x-request-id", "req-44");
+    let response = router.oneshot(put.body(Body::from("v")).unwrap()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+    assert_eq!(request_id_and_body(response).await, ("req-44".to_string(), Some("req-44".to_string())));
+}
+
 // --- GET ---
 
 #[tokio::test]
 async fn test_handle_get_returns_404_for_missing_key() {
-    let response = handle_get(State(empty_store()), Path("missing".to_string())).await;
+    let response = handle_get(State(empty_store()), Path("missing".to_string()), HeaderMap::new()).await;
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers().get("x-deleted-version").is_none(), "never-existed key has no version");
+    assert_eq!(response_error_type(response).await, ERR_KEY_NOT_FOUND);
 }
 
 #[tokio::test]
 async fn test_handle_get_returns_value_and_etag() {
     let state = store_with("k", b"hello").await;
-    let response = handle_get(State(state), Path("k".to_string())).await;
+    let response = handle_get(State(state), Path("k".to_string()), HeaderMap::new()).await;
     assert_eq!(response.status(), StatusCode::OK);
     assert!(response.headers().get(header::ETAG).is_some());
     assert_eq!(response_body(response).await, b"hello");
@@ -151,7 +418,7 @@ async fn test_handle_get_returns_value_and_etag() {
 async fn test_handle_put_stores_value() {
     let state = empty_store();
     let v = put_key(&state, "k", b"hello", "tok-1").await;
-    assert!(v > 0, "ETag must be a positive version");
+    assert!(v > Version(0), "ETag must be a positive version");
     assert_eq!(
         state.db.read().await.store.get("k").unwrap().value.as_deref().unwrap(),
         b"hello"
@@ -167,7 +434,7 @@ async fn test_handle_put_version_is_monotonic() {
     let v2 = put_key(&state, "k", b"v2", "tok-2").await;
     assert!(v2 > v1, "second PUT must produce a higher version");
 
-    let response = handle_get(State(state.clone()), Path("k".to_string())).await;
+    let response = handle_get(State(state.clone()), Path("k".to_string()), HeaderMap::new()).await;
     assert_eq!(response_version(&response), v2, "GET must reflect the latest version");
 }
 
@@ -182,6 +449,50 @@ async fn test_handle_put_versions_are_globally_unique() {
     assert!(vb > va, "later PUT must have higher version regardless of key");
 }
 
+/// `X-Previous-Version` is set only when a live value is replaced; creates, writes over a
+/// tombstone, and idempotent replays of a create carry no header.
+#[tokio::test]
+async fn test_handle_put_reports_previous_version() {
+    let state = empty_store();
+    let put = |key: &str, tok: &str| {
+        handle_put(State(state.clone()), Path(key.to_string()), headers_with_idempotency_key(tok), Bytes::from("v"))
+    };
+    let previous = |r: &Response| {
+        r.headers().get("x-previous-version").map(|v| Version(v.to_str().unwrap().parse().unwrap()))
+    };
+
+    let created = put("k", "tok-1").await;
+    assert_eq!(previous(&created), None, "create must not report a previous version");
+    let v1 = response_version(&created);
+
+    let overwrite = put("k", "tok-2").await;
+    assert_eq!(previous(&overwrite), Some(v1));
+    // A replay returns the same header as the original response.
+    assert_eq!(previous(&put("k", "tok-2").await), Some(v1));
+
+    delete_key(&state, "k", "tok-del").await.unwrap();
+    let recreated = put("k", "tok-3").await;
+    assert_eq!(previous(&recreated), None, "overwriting a tombstone is a create");
+}
+
+#[tokio::test]
+async fn test_handle_put_reports_version_diff() {
+    let state = empty_store();
+    let put = |key: &str, tok: &str| {
+        handle_put(State(state.clone()), Path(key.to_string()), headers_with_idempotency_key(tok), Bytes::from("v"))
+    };
+    let diff = |r: &Response| r.headers()["x-version-diff"].to_str().unwrap().parse::<u64>().unwrap();
+
+    assert_eq!(diff(&put("k", "tok-1").await), 0, "a new key has nothing to diff against");
+    assert_eq!(diff(&put("k", "tok-2").await), 0, "no write in between");
+
+    // Versions are global, so writes to other keys count too.
+    put("other", "tok-3").await;
+    put("other", "tok-4").await;
+    assert_eq!(diff(&put("k", "tok-5").await), 2);
+    assert_eq!(diff(&put("k", "tok-5").await), 2, "a replay repeats the original header");
+}
+
 // --- DELETE ---
 
 /// DELETE on a live key writes a tombstone: returns 200+ETag, value=None, expires_at=now+3600.
@@ -199,8 +510,10 @@ async fn test_handle_delete_live_key_writes_tombstone() {
     assert_eq!(entry.value, None, "tombstone value must be None");
     assert_eq!(entry.expires_at, Some(NOW + TOMBSTONE_TTL_SECS), "tombstone must expire in 1 hour");
 
-    // GET on tombstoned key returns 404.
-    assert_get(&state, "k", None).await;
+    // GET on tombstoned key returns 404 carrying the tombstone version.
+    let response = handle_get(State(state.clone()), Path("k".to_string()), HeaderMap::new()).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers().get("x-deleted-version").unwrap().to_str().unwrap(), v_del.to_string());
 }
 
 /// DELETE on a missing key is a no-op: returns 204, store and next_version unchanged.
@@ -209,8 +522,8 @@ async fn test_handle_delete_absent_key_is_noop() {
     let state = empty_store();
     let result = delete_key(&state, "missing", "tok-del").await;
     assert!(result.is_none(), "DELETE on absent key must return 204 No Content");
-    assert!(state.db.read().await.store.get("missing").is_none());
-    assert_eq!(state.db.read().await.next_version, 0, "next_version must not advance");
+    assert!(!state.db.read().await.store.contains_key("missing"));
+    assert_eq!(state.db.read().await.sequence(), 0, "next_version must not advance");
 }
 
 /// DELETE on an already-tombstoned key is a no-op: returns 204, tombstone unchanged.
@@ -225,7 +538,7 @@ async fn test_handle_delete_tombstoned_key_is_noop() {
 
     let entry = state.db.read().await.store.get("k").cloned().unwrap();
     assert_eq!(entry.version, v_del, "tombstone version must be unchanged");
-    assert_eq!(state.db.read().await.next_version, v_del, "next_version must not advance");
+    assert_eq!(Version(state.db.read().await.sequence()), v_del, "next_version must not advance");
 }
 
 /// PUT after DELETE must produce a version strictly greater than the tombstone.
@@ -250,14 +563,89 @@ async fn test_handle_delete_affects_only_specified_key() {
     assert_get(&state, "b", Some(b"bbb")).await; // untouched
 }
 
+// --- Conditional DELETE (If-Match) ---
+
+async fn delete_if_match(state: &AppState, key: &str, if_match: Version, tok: &str) -> Response {
+    let mut headers = headers_with_idempotency_key(tok);
+    headers.insert(header::IF_MATCH, if_match.to_etag().parse().unwrap());
+    handle_delete(State(state.clone()), Path(key.to_string()), headers).await
+}
+
+#[tokio::test]
+async fn test_delete_if_match_reasserts_tombstone_without_new_version() {
+    let state = empty_store();
+    let live = put_key(&state, "k", b"v", "tok-put").await;
+    let tombstone = response_version(&delete_if_match(&state, "k", live, "tok-del-1").await);
+    assert!(tombstone > live);
+
+    let response = delete_if_match(&state, "k", tombstone, "tok-del-2").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response_version(&response), tombstone);
+    let db = state.db.read().await;
+    assert_eq!(Version(db.sequence()), tombstone, "re-asserting a delete must not allocate a version");
+    assert_eq!(db.store["k"].version, tombstone);
+}
+
+#[tokio::test]
+async fn test_delete_if_match_fails_when_recreated_absent_or_stale() {
+    let state = empty_store();
+    put_key(&state, "k", b"v", "tok-put-1").await;
+    let tombstone = delete_key(&state, "k", "tok-del").await.unwrap();
+    let recreated = put_key(&state, "k", b"v2", "tok-put-2").await;
+
+    // The key came back at a higher version: re-asserting the old delete must not clobber it.
+    let response = delete_if_match(&state, "k", tombstone, "tok-cond-1").await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    assert_eq!(response_version(&response), recreated, "412 reports the current version as its ETag");
+    assert_eq!(response_error_type(response).await, ERR_PRECONDITION_FAILED);
+    assert_eq!(state.db.read().await.store["k"].version, recreated);
+
+    let response = delete_if_match(&state, "absent", Version(1), "tok-cond-2").await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    assert!(response.headers().get(header::ETAG).is_none());
+
+    let mut headers = headers_with_idempotency_key("tok-cond-3");
+    headers.insert(header::IF_MATCH, "*".parse().unwrap());
+    let response = handle_delete(State(state.clone()), Path("k".to_string()), headers).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+    // If-Match compares strongly: a weak tag never matches, even for the current version.
+    let mut headers = headers_with_idempotency_key("tok-cond-4");
+    headers.insert(header::IF_MATCH, format!("W/{}", recreated.to_etag()).parse().unwrap());
+    let response = handle_delete(State(state.clone()), Path("k".to_string()), headers).await;
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    assert!(state.db.read().await.idempotency_cache.keys().all(|k| !k.key.starts_with("tok-cond")));
+}
+
 // --- Idempotency-Key validation ---
 
+#[tokio::test]
+async fn test_oversized_idempotency_key_returns_400() {
+    let state = empty_store();
+    let long = "i".repeat(1000);
+
+    let put =
+        handle_put(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key(&long), Bytes::from("v"))
+            .await;
+    assert_eq!(put.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(put).await, ERR_IDEMPOTENCY_KEY_TOO_LARGE);
+
+    let delete =
+        handle_delete(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key(&long)).await;
+    assert_eq!(delete.status(), StatusCode::BAD_REQUEST);
+    assert!(state.db.read().await.idempotency_cache.is_empty(), "rejected key must not be cached");
+
+    // Exactly at the limit is accepted.
+    put_key(&state, "k", b"v", &"i".repeat(Limits::DEFAULT.max_idempotency_key_size)).await;
+}
+
 #[tokio::test]
 async fn test_handle_put_missing_idempotency_key_returns_400() {
     let headers = HeaderMap::new();
     let body = Bytes::from("hello");
     let response = handle_put(State(empty_store()), Path("k".to_string()), headers, body).await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(response).await, ERR_MISSING_IDEMPOTENCY_KEY);
 }
 
 #[tokio::test]
@@ -265,6 +653,7 @@ async fn test_handle_delete_missing_idempotency_key_returns_400() {
     let headers = HeaderMap::new();
     let response = handle_delete(State(empty_store()), Path("k".to_string()), headers).await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(response).await, ERR_MISSING_IDEMPOTENCY_KEY);
 }
 
 // --- Idempotency replay ---
@@ -274,13 +663,13 @@ async fn test_handle_delete_missing_idempotency_key_returns_400() {
 async fn test_handle_put_idempotency_replay() {
     let state = empty_store();
     let v1 = put_key(&state, "k", b"v", "replay-tok").await;
-    let version_before_replay = state.db.read().await.next_version;
+    let version_before_replay = state.db.read().await.sequence();
 
     let v2 = put_key(&state, "k", b"v", "replay-tok").await;
 
     assert_eq!(v1, v2, "replayed PUT must return same ETag");
     assert_eq!(
-        state.db.read().await.next_version,
+        state.db.read().await.sequence(),
         version_before_replay,
         "replay must not advance next_version"
     );
@@ -300,6 +689,28 @@ async fn test_handle_delete_live_key_idempotency_replay() {
     assert_eq!(response_version(&replay), v_del, "replay must return the same ETag");
 }
 
+#[tokio::test]
+async fn test_replays_carry_x_idempotent_replay() {
+    let state = empty_store();
+    let put = |key: &str| {
+        let headers = headers_with_idempotency_key("tok-put");
+        handle_put(State(state.clone()), Path(key.to_string()), headers, Bytes::from("v"))
+    };
+    let delete = || handle_delete(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key("tok-del"));
+
+    for (first, replay) in [(put("k").await, put("k").await), (delete().await, delete().await)] {
+        assert_eq!(first.status(), StatusCode::OK);
+        assert!(!first.headers().contains_key("x-idempotent-replay"), "first execution is not a replay");
+        assert_eq!(replay.status(), StatusCode::OK);
+        assert_eq!(replay.headers()["x-idempotent-replay"], "true");
+    }
+
+    // A token reused for another key is refused, not replayed.
+    let mismatch = put("other").await;
+    assert_eq!(mismatch.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(!mismatch.headers().contains_key("x-idempotent-replay"));
+}
+
 /// Replaying a live-key DELETE after the key has been re-PUT returns the cached response
 /// but does NOT affect the current live entry.
 #[tokio::test]
@@ -338,6 +749,7 @@ async fn test_handle_put_idempotency_mismatch_different_key_returns_422() {
     )
     .await;
     assert_eq!(r2.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response_error_type(r2).await, ERR_IDEMPOTENCY_MISMATCH);
 }
 
 /// PUT with a token previously used for a DELETE (live key) must return 422.
@@ -387,26 +799,63 @@ async fn test_handle_delete_idempotency_mismatch_key_returns_422() {
     assert_eq!(r2.status(), StatusCode::UNPROCESSABLE_ENTITY);
 }
 
+// --- Idempotency scoping by principal ---
+
+fn headers_with_bearer(idempotency_key: &str, token: &str) -> HeaderMap {
+    let mut headers = headers_with_idempotency_key(idempotency_key);
+    headers.insert(header::AUTHORIZATION, format!("Bearer {token}").parse().unwrap());
+    headers
+}
+
+/// The same Idempotency-Key under two bearer tokens names two writes; a replay under the
+/// same token still returns the cached response.
+#[tokio::test]
+async fn test_idempotency_key_is_scoped_by_bearer_token() {
+    let state = empty_store();
+    let put = |token: &'static str, value: &'static str| {
+        let headers = headers_with_bearer("tok", token);
+        handle_put(State(state.clone()), Path("k".to_string()), headers, Bytes::from(value))
+    };
+
+    let tenant_a = put("tenant-a", "a").await;
+    let tenant_b = put("tenant-b", "b").await;
+    assert_eq!(tenant_b.status(), StatusCode::OK);
+    assert_ne!(response_version(&tenant_a), response_version(&tenant_b), "tenants must not share a write");
+
+    let replay = put("tenant-a", "ignored").await;
+    assert_eq!(response_version(&replay), response_version(&tenant_a));
+
+    // Without a token the key falls into the anonymous scope, distinct from both tenants.
+    let anonymous = put_key(&state, "k", b"anon", "tok").await;
+    assert!(anonymous > response_version(&tenant_b));
+
+    let db = state.db.read().await;
+    assert_eq!(db.store["k"].value.as_deref(), Some(&b"anon"[..]));
+    assert_eq!(db.idempotency_cache.len(), 3);
+    assert!(db.idempotency_cache.values().all(|record| record.created_at == NOW), "stamped by the state's clock");
+}
+
 // --- Key size validation ---
 
 #[tokio::test]
 async fn test_handle_get_rejects_key_over_limit() {
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
-    let response = handle_get(State(empty_store()), Path(key)).await;
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
+    let response = handle_get(State(empty_store()), Path(key), HeaderMap::new()).await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(response).await, ERR_KEY_TOO_LARGE);
 }
 
 #[tokio::test]
 async fn test_handle_get_accepts_key_at_limit() {
-    let key = "a".repeat(MAX_KEY_SIZE);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size);
     // Key doesn't exist but size is valid — expect 404, not 400.
-    let response = handle_get(State(empty_store()), Path(key)).await;
+    let response = handle_get(State(empty_store()), Path(key), HeaderMap::new()).await;
     assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
 #[tokio::test]
 async fn test_handle_put_rejects_key_over_limit() {
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     let headers = headers_with_idempotency_key("tok-1");
     let response = handle_put(State(empty_store()), Path(key), headers, Bytes::from("hello")).await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
@@ -414,7 +863,7 @@ async fn test_handle_put_rejects_key_over_limit() {
 
 #[tokio::test]
 async fn test_handle_put_accepts_key_at_limit() {
-    let key = "a".repeat(MAX_KEY_SIZE);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size);
     let headers = headers_with_idempotency_key("tok-1");
     let response = handle_put(State(empty_store()), Path(key), headers, Bytes::from("hello")).await;
     assert_eq!(response.status(), StatusCode::OK);
@@ -423,7 +872,7 @@ async fn test_handle_put_accepts_key_at_limit() {
 #[tokio::test]
 async fn test_handle_put_rejects_value_over_limit() {
     let headers = headers_with_idempotency_key("tok-1");
-    let body = Bytes::from(vec![0u8; MAX_VALUE_SIZE + 1]);
+    let body = Bytes::from(vec![0u8; Limits::DEFAULT.max_value_size + 1]);
     let response = handle_put(State(empty_store()), Path("k".to_string()), headers, body).await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
@@ -431,14 +880,43 @@ async fn test_handle_put_rejects_value_over_limit() {
 #[tokio::test]
 async fn test_handle_put_accepts_value_at_limit() {
     let headers = headers_with_idempotency_key("tok-1");
-    let body = Bytes::from(vec![0u8; MAX_VALUE_SIZE]);
+    let body = Bytes::from(vec![0u8; Limits::DEFAULT.max_value_size]);
     let response = handle_put(State(empty_store()), Path("k".to_string()), headers, body).await;
     assert_eq!(response.status(), StatusCode::OK);
 }
 
+#[tokio::test]
+async fn test_configured_limits_are_enforced_and_reported() {
+    let mut state = store_with("k", b"1234").await;
+    state.limits = Limits { max_key_size: 4, max_value_size: 8, max_idempotency_key_size: 16, max_batch_keys: 2 };
+    let error = |response: Response| async {
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        serde_json::from_slice::<ErrorResponse>(&response_body(response).await).unwrap().error
+    };
+
+    let response = handle_get(State(state.clone()), Path("abcde".to_string()), HeaderMap::new()).await;
+    assert_eq!(error(response).await, "Key exceeds maximum size of 4 bytes");
+    let put = |body: &'static [u8], idempotency_key: &str| {
+        let headers = headers_with_idempotency_key(idempotency_key);
+        handle_put(State(state.clone()), Path("k".to_string()), headers, Bytes::from_static(body))
+    };
+    assert_eq!(error(put(b"123456789", "tok").await).await, "Value exceeds maximum size of 8 bytes");
+    let long = "i".repeat(17);
+    assert_eq!(error(put(b"v", &long).await).await, "Idempotency-Key exceeds maximum length of 16 bytes");
+    assert_eq!(put(b"12345678", "tok").await.status(), StatusCode::OK);
+
+    let headers = headers_with_idempotency_key("append");
+    let response = handle_patch(State(state.clone()), Path("k".to_string()), headers, Bytes::from_static(b"9")).await;
+    assert_eq!(error(response).await, "Value exceeds maximum size of 8 bytes");
+
+    let headers = headers_with_idempotency_key("ttl");
+    let response = handle_batch_ttl(State(state), headers, batch_ttl_body(&["a", "b", "c"], NOW)).await;
+    assert_eq!(error(response).await, "Batch exceeds maximum of 2 keys");
+}
+
 #[tokio::test]
 async fn test_handle_delete_rejects_key_over_limit() {
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     let headers = headers_with_idempotency_key("tok-1");
     let response = handle_delete(State(empty_store()), Path(key), headers).await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
@@ -446,17 +924,34 @@ async fn test_handle_delete_rejects_key_over_limit() {
 
 #[tokio::test]
 async fn test_handle_delete_accepts_key_at_limit() {
-    let key = "a".repeat(MAX_KEY_SIZE);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size);
     let headers = headers_with_idempotency_key("tok-1");
     // Absent key → 204 No Content.
     let response = handle_delete(State(empty_store()), Path(key), headers).await;
     assert_eq!(response.status(), StatusCode::NO_CONTENT);
 }
 
+#[tokio::test]
+async fn test_empty_key_is_rejected_by_every_key_handler() {
+    let state = empty_store();
+    let headers = headers_with_idempotency_key("tok-1");
+    let responses = [
+        handle_get(State(state.clone()), Path(String::new()), HeaderMap::new()).await,
+        handle_put(State(state.clone()), Path(String::new()), headers.clone(), Bytes::from("v")).await,
+        handle_patch(State(state.clone()), Path(String::new()), headers.clone(), Bytes::from("v")).await,
+        handle_delete(State(state.clone()), Path(String::new()), headers).await,
+    ];
+    for response in responses {
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response_error_type(response).await, ERR_EMPTY_KEY);
+    }
+    assert!(state.db.read().await.store.is_empty());
+}
+
 // Key size check must fire before Idempotency-Key check.
 #[tokio::test]
 async fn test_handle_put_key_size_checked_before_idempotency_key() {
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     let response =
         handle_put(State(empty_store()), Path(key), HeaderMap::new(), Bytes::from("hello")).await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
@@ -464,7 +959,7 @@ async fn test_handle_put_key_size_checked_before_idempotency_key() {
 
 #[tokio::test]
 async fn test_handle_delete_key_size_checked_before_idempotency_key() {
-    let key = "a".repeat(MAX_KEY_SIZE + 1);
+    let key = "a".repeat(Limits::DEFAULT.max_key_size + 1);
     let response = handle_delete(State(empty_store()), Path(key), HeaderMap::new()).await;
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
@@ -474,10 +969,43 @@ async fn test_handle_delete_key_size_checked_before_idempotency_key() {
 #[test]
 fn test_entry_is_expired() {
     let clock = MockClock::new(NOW);
-    assert!(!Entry { value: None, version: 1, expires_at: None }.is_expired(clock.as_ref()));
-    assert!(!Entry { value: None, version: 1, expires_at: Some(NOW + 1) }.is_expired(clock.as_ref()));
-    assert!(Entry { value: None, version: 1, expires_at: Some(NOW) }.is_expired(clock.as_ref())); // boundary: now == ttl
-    assert!(Entry { value: None, version: 1, expires_at: Some(NOW - 1) }.is_expired(clock.as_ref())); // past
+    let entry = |expires_at| Entry { value: None, version: Version(1), expires_at };
+    assert!(!entry(None).is_expired(clock.as_ref()));
+    assert!(!entry(Some(NOW + 1)).is_expired(clock.as_ref()));
+    assert!(entry(Some(NOW)).is_expired(clock.as_ref())); // boundary: now == ttl
+    assert!(entry(Some(NOW - 1)).is_expired(clock.as_ref())); // past
+}
+
+#[test]
+fn test_entry_metadata_omits_the_bytes() {
+    let value = Entry { value: Some(Bytes::from_static(b"hello")), version: Version(7), expires_at: Some(NOW) };
+    assert_eq!(value.metadata(), EntryMetadata::value(Version(7), 5, Some(NOW)));
+    let tombstone = Entry { value: None, version: Version(8), expires_at: None };
+    assert_eq!(tombstone.metadata(), EntryMetadata::tombstone(Version(8), None));
+
+    let json = serde_json::to_value(tombstone.metadata()).unwrap();
+    assert_eq!(json, serde_json::json!({ "version": 8, "size_bytes": 0, "tombstone": true }));
+    assert_eq!(serde_json::from_value::<EntryMetadata>(json).unwrap(), tombstone.metadata());
+}
+
+#[test]
+fn test_clock_millis() {
+    let clock = MockClock::new(NOW);
+    clock.set_millis(NOW * 1000 + 999);
+    assert_eq!((clock.unix_now_secs(), clock.unix_now_millis()), (NOW, NOW * 1000 + 999));
+
+    // Second-precision clocks get the default.
+    struct SecondsClock;
+    impl Clock for SecondsClock {
+        fn unix_now_secs(&self) -> u64 {
+            NOW
+        }
+    }
+    assert_eq!(SecondsClock.unix_now_millis(), NOW * 1000);
+
+    let millis = SystemClock.unix_now_millis();
+    assert!(millis / 1000 <= SystemClock.unix_now_secs());
+    assert!(millis > 1_600_000_000_000);
 }
 
 // --- PUT with X-TTL ---
@@ -556,9 +1084,9 @@ async fn test_handle_get_expired_entry() {
     let state = empty_store();
     state.db.write().await.store.insert(
         "k".to_string(),
-        Entry { value: Some(Bytes::from(b"stale".to_vec())), version: 1, expires_at: Some(NOW - 1_000) },
+        Entry { value: Some(Bytes::from(b"stale".to_vec())), version: Version(1), expires_at: Some(NOW - 1_000) },
     );
-    let response = handle_get(State(state), Path("k".to_string())).await;
+    let response = handle_get(State(state), Path("k".to_string()), HeaderMap::new()).await;
     assert_eq!(response.status(), StatusCode::OK);
     assert_eq!(response.headers().get("x-expired").unwrap().to_str().unwrap(), "true");
     assert_eq!(response_body(response).await, b"stale");
@@ -566,9 +1094,9 @@ async fn test_handle_get_expired_entry() {
     let state2 = empty_store();
     state2.db.write().await.store.insert(
         "k".to_string(),
-        Entry { value: Some(Bytes::new()), version: 1, expires_at: Some(NOW) },
+        Entry { value: Some(Bytes::new()), version: Version(1), expires_at: Some(NOW) },
     );
-    let response2 = handle_get(State(state2), Path("k".to_string())).await;
+    let response2 = handle_get(State(state2), Path("k".to_string()), HeaderMap::new()).await;
     assert_eq!(response2.headers().get("x-expired").unwrap().to_str().unwrap(), "true");
 }
 
@@ -578,17 +1106,98 @@ async fn test_handle_get_no_x_expired_for_live_entry() {
     let state = empty_store();
     state.db.write().await.store.insert(
         "k".to_string(),
-        Entry { value: Some(Bytes::from(b"fresh".to_vec())), version: 1, expires_at: Some(NOW + 1_000) },
+        Entry { value: Some(Bytes::from(b"fresh".to_vec())), version: Version(1), expires_at: Some(NOW + 1_000) },
     );
-    let response = handle_get(State(state), Path("k".to_string())).await;
+    let response = handle_get(State(state), Path("k".to_string()), HeaderMap::new()).await;
     assert!(response.headers().get("x-expired").is_none());
 
     // No TTL → no x-expired header.
     let state2 = store_with("k", b"hello").await;
-    let response2 = handle_get(State(state2), Path("k".to_string())).await;
+    let response2 = handle_get(State(state2), Path("k".to_string()), HeaderMap::new()).await;
     assert!(response2.headers().get("x-expired").is_none());
 }
 
+#[tokio::test]
+async fn test_handle_get_strict_ttl_removes_expired_entry_and_returns_404() {
+    let mut state = empty_store();
+    let expired =
+        |version| Entry { value: Some(Bytes::from("stale")), version: Version(version), expires_at: Some(NOW) };
+    {
+        let mut db = state.db.write().await;
+        db.store.insert("expired".to_string(), expired(1));
+        db.store.insert("fresh".to_string(), Entry { expires_at: Some(NOW + 60), ..expired(2) });
+        db.store.insert("deleted".to_string(), Entry { value: None, version: Version(3), expires_at: None });
+    }
+    let get = |state: &AppState, key: &str| handle_get(State(state.clone()), Path(key.to_string()), HeaderMap::new());
+
+    // By default the expired value is served and stays in the store.
+    let response = get(&state, "expired").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-expired"], "true");
+    assert!(state.db.read().await.store.contains_key("expired"));
+
+    state.strict_ttl = true;
+    let response = get(&state, "expired").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers().get("x-expired").is_none());
+    assert!(response.headers().get("x-deleted-version").is_none());
+    assert_eq!(response.headers()["x-sequence"], "0");
+    assert_eq!(response_error_type(response).await, ERR_KEY_NOT_FOUND);
+    assert!(!state.db.read().await.store.contains_key("expired"));
+
+    // Unexpired values and tombstones are answered as before.
+    assert_eq!(response_body(get(&state, "fresh").await).await, b"stale");
+    let response = get(&state, "deleted").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()["x-deleted-version"], "3");
+
+    // A read-only server answers 404 but leaves the store alone.
+    state.db.write().await.store.insert("expired".to_string(), expired(4));
+    state.read_only.store(true, Ordering::Relaxed);
+    assert_eq!(get(&state, "expired").await.status(), StatusCode::NOT_FOUND);
+    assert!(state.db.read().await.store.contains_key("expired"));
+}
+
+#[tokio::test]
+async fn test_handle_get_strict_ttl_reclaims_entry_held_by_get_cache() {
+    let clock = MockClock::new(NOW);
+    let mut state = AppState::new(clock.clone() as Arc<dyn Clock>, NodeRole::Primary).with_get_cache(8);
+    state.strict_ttl = true;
+    state.db.write().await.store.insert(
+        "k".to_string(),
+        Entry { value: Some(Bytes::from("v")), version: Version(1), expires_at: Some(NOW + 1) },
+    );
+    let get = || handle_get(State(state.clone()), Path("k".to_string()), HeaderMap::new());
+
+    assert_eq!(get().await.status(), StatusCode::OK);
+    clock.set_millis((NOW + 1) * 1000);
+    assert_eq!(get().await.status(), StatusCode::NOT_FOUND);
+    assert!(!state.db.read().await.store.contains_key("k"));
+    assert_eq!(get().await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_handle_get_reports_expires_at_only_for_ttl_entries() {
+    let state = store_with("plain", b"v").await;
+    state.db.write().await.store.insert(
+        "ttl".to_string(),
+        Entry { value: Some(Bytes::from("v")), version: Version(2), expires_at: Some(NOW + 60) },
+    );
+    let get = |key: &str, headers: HeaderMap| handle_get(State(state.clone()), Path(key.to_string()), headers);
+
+    let response = get("ttl", HeaderMap::new()).await;
+    assert_eq!(response.headers()["x-expires-at"], (NOW + 60).to_string());
+    // A revalidation keeps the client's copy, so it needs the TTL too.
+    let mut if_none_match = HeaderMap::new();
+    if_none_match.insert(header::IF_NONE_MATCH, "\"2\"".parse().unwrap());
+    let response = get("ttl", if_none_match).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["x-expires-at"], (NOW + 60).to_string());
+
+    let response = get("plain", HeaderMap::new()).await;
+    assert!(response.headers().get("x-expires-at").is_none());
+}
+
 // --- Replica role enforcement ---
 
 #[tokio::test]
@@ -596,13 +1205,1054 @@ async fn test_replica_rejects_all_key_operations_with_405() {
     let state = replica_store();
     let headers = headers_with_idempotency_key("tok-1");
 
-    let get_resp = handle_get(State(state.clone()), Path("k".to_string())).await;
+    let get_resp = handle_get(State(state.clone()), Path("k".to_string()), HeaderMap::new()).await;
     assert_eq!(get_resp.status(), StatusCode::METHOD_NOT_ALLOWED);
 
     let put_resp =
         handle_put(State(state.clone()), Path("k".to_string()), headers.clone(), Bytes::from("v")).await;
     assert_eq!(put_resp.status(), StatusCode::METHOD_NOT_ALLOWED);
 
+    let patch_resp =
+        handle_patch(State(state.clone()), Path("k".to_string()), headers.clone(), Bytes::from("v")).await;
+    assert_eq!(patch_resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+
     let del_resp = handle_delete(State(state.clone()), Path("k".to_string()), headers).await;
     assert_eq!(del_resp.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response_error_type(del_resp).await, ERR_NOT_PRIMARY);
+}
+
+// --- Write batching (group commit) ---
+
+fn batched_store(max_batch_size: usize) -> AppState {
+    empty_store().with_write_batching(WriteBatchConfig { max_batch_size, flush_interval_ms: 5 })
+}
+
+#[tokio::test]
+async fn test_batched_puts_all_apply_with_distinct_versions() {
+    let state = batched_store(8);
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for i in 0..20 {
+        tasks.spawn(handle_put(
+            State(state.clone()),
+            Path(format!("k{i}")),
+            headers_with_idempotency_key(&format!("idem-{i}")),
+            Bytes::from(format!("v{i}")),
+        ));
+    }
+
+    let mut versions: Vec<u64> = Vec::new();
+    while let Some(response) = tasks.join_next().await {
+        let response = response.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        versions.push(response.headers()[header::ETAG].to_str().unwrap().trim_matches('"').parse().unwrap());
+    }
+    versions.sort_unstable();
+    versions.dedup();
+    assert_eq!(versions.len(), 20);
+
+    let db = state.db.read().await;
+    for i in 0..20 {
+        assert_eq!(db.store[&format!("k{i}")].value.as_deref(), Some(format!("v{i}").as_bytes()));
+    }
+}
+
+#[tokio::test]
+async fn test_batched_writes_keep_idempotency_and_delete_semantics() {
+    let state = batched_store(4);
+
+    let put = |idem: &str, key: &str| {
+        handle_put(State(state.clone()), Path(key.to_string()), headers_with_idempotency_key(idem), Bytes::from("v"))
+    };
+    // Same token twice in one batch: the second is a replay, not a second write.
+    let (first, replay) = tokio::join!(put("same", "k"), put("same", "k"));
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(replay.status(), StatusCode::OK);
+    assert_eq!(first.headers()[header::ETAG], replay.headers()[header::ETAG]);
+
+    let mismatch = put("same", "other").await;
+    assert_eq!(mismatch.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let deleted =
+        handle_delete(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key("del-1")).await;
+    assert_eq!(deleted.status(), StatusCode::OK);
+    let absent =
+        handle_delete(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key("del-2")).await;
+    assert_eq!(absent.status(), StatusCode::NO_CONTENT);
+    assert!(state.db.read().await.store["k"].value.is_none());
+}
+
+// --- PATCH /batch/ttl ---
+
+fn batch_ttl_body(keys: &[&str], expires_at: u64) -> Bytes {
+    Bytes::from(serde_json::json!({ "keys": keys, "expires_at": expires_at }).to_string())
+}
+
+#[tokio::test]
+async fn test_batch_ttl_updates_live_keys_only() {
+    let state = store_with("live", b"v").await;
+    {
+        let mut db = state.db.write().await;
+        db.store.insert("deleted".to_string(), Entry { value: None, version: Version(2), expires_at: None });
+        db.store.insert(
+            "expired".to_string(),
+            Entry { value: Some(Bytes::from("v")), version: Version(3), expires_at: Some(NOW - 1) },
+        );
+    }
+
+    let response = handle_batch_ttl(
+        State(state.clone()),
+        headers_with_idempotency_key("ttl-1"),
+        batch_ttl_body(&["live", "deleted", "expired", "missing"], NOW + 60),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: BulkTtlResult = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(result.updated, vec!["live"]);
+    assert_eq!(result.not_found, vec!["deleted", "expired", "missing"]);
+
+    let db = state.db.read().await;
+    let live = &db.store["live"];
+    assert_eq!(live.expires_at, Some(NOW + 60));
+    assert_eq!(live.version, Version(1), "TTL extension must not bump the version");
+    assert_eq!(live.value.as_deref(), Some(&b"v"[..]));
+    assert_eq!(db.store["expired"].expires_at, Some(NOW - 1));
+}
+
+#[tokio::test]
+async fn test_batch_ttl_replay_returns_original_result() {
+    let state = store_with("k", b"v").await;
+    let call = |expires_at| {
+        handle_batch_ttl(State(state.clone()), headers_with_idempotency_key("ttl-replay"), batch_ttl_body(&["k"], expires_at))
+    };
+
+    let first = response_body(call(NOW + 60).await).await;
+    // The replay is not re-applied.
+    let replay = call(NOW + 60).await;
+    assert_eq!(replay.status(), StatusCode::OK);
+    assert_eq!(response_body(replay).await, first);
+
+    // The same keys with another expiry are another request: replaying the first result would
+    // leave the new TTL silently unapplied.
+    let other_expiry = call(NOW + 120).await;
+    assert_eq!(other_expiry.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response_error_type(other_expiry).await, ERR_IDEMPOTENCY_MISMATCH);
+    assert_eq!(state.db.read().await.store["k"].expires_at, Some(NOW + 60));
+
+    // Reusing the token on a different endpoint is a mismatch.
+    let put = handle_put(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key("ttl-replay"), Bytes::from("v")).await;
+    assert_eq!(put.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_batch_ttl_replay_requires_the_same_keys() {
+    let state = store_with("a", b"v").await;
+    let call = |keys: &'static [&'static str]| {
+        let headers = headers_with_idempotency_key("ttl-items");
+        handle_batch_ttl(State(state.clone()), headers, batch_ttl_body(keys, NOW + 60))
+    };
+
+    let first = response_body(call(&["a", "b"]).await).await;
+    // Order and repeats do not matter: the results name keys, not positions.
+    let replay = call(&["b", "a", "b"]).await;
+    assert_eq!(replay.status(), StatusCode::OK);
+    assert_eq!(replay.headers()["x-idempotent-replay"], "true");
+    assert_eq!(response_body(replay).await, first);
+
+    for keys in [&["a"][..], &["a", "b", "c"]] {
+        let response = call(keys).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response_error_type(response).await, ERR_IDEMPOTENCY_MISMATCH);
+    }
+}
+
+#[tokio::test]
+async fn test_batch_ttl_rejects_bad_requests() {
+    let missing_idem = handle_batch_ttl(State(empty_store()), HeaderMap::new(), batch_ttl_body(&["k"], NOW)).await;
+    assert_eq!(missing_idem.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(missing_idem).await, ERR_MISSING_IDEMPOTENCY_KEY);
+
+    let bad_body =
+        handle_batch_ttl(State(empty_store()), headers_with_idempotency_key("t"), Bytes::from("{\"keys\":1}")).await;
+    assert_eq!(bad_body.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(bad_body).await, ERR_INVALID_BODY);
+
+    let big_key = "k".repeat(Limits::DEFAULT.max_key_size + 1);
+    let too_large =
+        handle_batch_ttl(State(empty_store()), headers_with_idempotency_key("t"), batch_ttl_body(&[&big_key], NOW)).await;
+    assert_eq!(response_error_type(too_large).await, ERR_KEY_TOO_LARGE);
+
+    let replica =
+        handle_batch_ttl(State(replica_store()), headers_with_idempotency_key("t"), batch_ttl_body(&["k"], NOW)).await;
+    assert_eq!(replica.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+// --- HEAD /keys and max_key_count ---
+
+async fn key_count(state: &AppState) -> usize {
+    let response = handle_key_count(State(state.clone())).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    response.headers()["x-key-count"].to_str().unwrap().parse().unwrap()
+}
+
+#[tokio::test]
+async fn test_key_count_counts_only_live_keys() {
+    let state = empty_store();
+    assert_eq!(key_count(&state).await, 0);
+
+    put_key(&state, "a", b"1", "tok-a").await;
+    put_key(&state, "b", b"2", "tok-b").await;
+    put_key(&state, "c", b"3", "tok-c").await;
+    delete_key(&state, "b", "tok-del").await;
+    state.db.write().await.store.get_mut("c").unwrap().expires_at = Some(NOW);
+    assert_eq!(key_count(&state).await, 1);
+
+    let response = handle_key_count(State(replica_store())).await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+}
+
+// --- GET /keys ---
+
+fn list_keys_request(query: &str, accept: Option<&str>) -> Request<Body> {
+    let mut request = Request::get(format!("/keys{query}"));
+    if let Some(accept) = accept {
+        request = request.header(header::ACCEPT, accept);
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_list_keys_returns_sorted_live_keys_matching_prefix() {
+    let state = empty_store();
+    for (key, tok) in [("user/2", "tok-2"), ("user/1", "tok-1"), ("user/3", "tok-3"), ("order/1", "tok-o")] {
+        put_key(&state, key, b"v", tok).await;
+    }
+    delete_key(&state, "user/3", "tok-del").await;
+    state.db.write().await.store.get_mut("order/1").unwrap().expires_at = Some(NOW);
+    let router = Server::create_router(state, &RouteBodyLimits::default());
+
+    let response = router.clone().oneshot(list_keys_request("", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let list: KeyList = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(list.keys, ["user/1", "user/2"]);
+
+    let response = router.clone().oneshot(list_keys_request("?prefix=order", None)).await.unwrap();
+    let list: KeyList = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert!(list.keys.is_empty());
+
+    let response = router.oneshot(list_keys_request("?prefix=user%2F2", None)).await.unwrap();
+    let list: KeyList = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(list.keys, ["user/2"]);
+}
+
+#[tokio::test]
+async fn test_list_keys_streams_ndjson_when_accepted() {
+    let state = empty_store();
+    let count = 2500;
+    for i in 0..count {
+        put_key(&state, &format!("k{i:04}"), b"v", &format!("tok-{i}")).await;
+    }
+    put_key(&state, "quote\"d", b"v", "tok-quote").await;
+    let router = Server::create_router(state, &RouteBodyLimits::default());
+
+    let accept = "application/json;q=0.5, application/x-ndjson";
+    let response = router.oneshot(list_keys_request("", Some(accept))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], NDJSON_CONTENT_TYPE);
+    let body = String::from_utf8(response_body(response).await).unwrap();
+    let keys: Vec<String> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(keys.len(), count + 1);
+    assert_eq!(keys[0], "k0000");
+    assert_eq!(keys[count - 1], format!("k{:04}", count - 1));
+    assert_eq!(keys[count], "quote\"d");
+    assert!(body.ends_with('\n'));
+}
+
+#[tokio::test]
+async fn test_list_keys_rejects_replicas_and_bad_queries() {
+    let router = Server::create_router(replica_store(), &RouteBodyLimits::default());
+    let response = router.oneshot(list_keys_request("", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response_error_type(response).await, ERR_NOT_PRIMARY);
+
+    let router = Server::create_router(empty_store(), &RouteBodyLimits::default());
+    let response = router.oneshot(list_keys_request("?prefix=a&prefix=b", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(response).await, ERR_INVALID_QUERY);
+}
+
+#[tokio::test]
+async fn test_stats_partitions_entries_by_state() {
+    let state = empty_store();
+    put_key(&state, "live", b"1", "tok-live").await;
+    put_key(&state, "expired", b"2", "tok-expired").await;
+    put_key(&state, "deleted", b"3", "tok-put-deleted").await;
+    put_key(&state, "old-tombstone", b"4", "tok-put-old").await;
+    delete_key(&state, "deleted", "tok-del").await;
+    delete_key(&state, "old-tombstone", "tok-del-old").await;
+    {
+        let mut db = state.db.write().await;
+        db.store.get_mut("expired").unwrap().expires_at = Some(NOW);
+        db.store.get_mut("old-tombstone").unwrap().expires_at = Some(NOW - 1);
+    }
+
+    let response = handle_stats(State(state.clone())).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let stats: StoreStats = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(stats, StoreStats { live_count: 1, expired_count: 2, tombstone_count: 1, ..Default::default() });
+
+    // Stats describe the node, so replicas answer too.
+    assert_eq!(handle_stats(State(replica_store())).await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_get_cache_serves_hits_without_the_lock_until_a_write() {
+    let clock = MockClock::new(NOW);
+    let state = AppState::new(clock.clone() as Arc<dyn Clock>, NodeRole::Primary).with_get_cache(2);
+    put_key(&state, "hot", b"v1", "tok-1").await;
+
+    // The first read fills the cache; the second is answered even while a writer holds the lock.
+    let get = || handle_get(State(state.clone()), Path("hot".to_string()), HeaderMap::new());
+    assert!(get().await.headers().contains_key("x-sequence"));
+    let response = {
+        let _writer = state.db.write().await;
+        get().await
+    };
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-sequence"], "1");
+    assert_eq!(response_body(response).await, b"v1");
+
+    // Every kind of write drops the entry: PUT, TTL change, DELETE.
+    put_key(&state, "hot", b"v2", "tok-2").await;
+    assert_get(&state, "hot", Some(b"v2")).await;
+    let response =
+        handle_batch_ttl(State(state.clone()), headers_with_idempotency_key("ttl"), batch_ttl_body(&["hot"], NOW + 5))
+            .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_get(&state, "hot", Some(b"v2")).await;
+    clock.set_millis((NOW + 5) * 1000);
+    let response = get().await;
+    assert_eq!(response.headers()["x-expired"], "true", "a cached TTL still expires");
+    put_key(&state, "hot", b"v3", "tok-3").await;
+    delete_key(&state, "hot", "tok-del").await;
+    assert_get(&state, "hot", None).await;
+
+    // Least recently used keys are evicted beyond the configured size.
+    for key in ["a", "b", "hot"] {
+        put_key(&state, key, b"x", key).await;
+        assert_get(&state, key, Some(b"x")).await;
+    }
+    assert_eq!(state.get_cache.as_ref().unwrap().lock().unwrap().len(), 2);
+
+    let response = handle_stats(State(state.clone())).await;
+    let stats: StoreStats = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!((stats.cache_hits, stats.cache_misses), (2, 7));
+}
+
+#[tokio::test]
+async fn test_get_cache_hit_reports_the_current_sequence() {
+    let state = empty_store().with_get_cache(4);
+    put_key(&state, "hot", b"v", "tok-hot").await;
+    let get = |key: &str| handle_get(State(state.clone()), Path(key.to_string()), HeaderMap::new());
+    assert_eq!(get("hot").await.headers()["x-sequence"], "1");
+
+    // A write to another key moves the high-water mark past the cached entry's version.
+    put_key(&state, "other", b"v", "tok-other").await;
+    let hit = get("hot").await;
+    let miss = get("other").await;
+    assert_eq!(hit.headers()["etag"], "\"1\"");
+    assert_eq!(hit.headers()["x-sequence"], miss.headers()["x-sequence"]);
+    assert_eq!(hit.headers()["x-sequence"], "2");
+    let cache = state.get_cache.as_ref().unwrap().lock().unwrap();
+    assert_eq!((cache.hits(), cache.misses()), (1, 2));
+}
+
+#[tokio::test]
+async fn test_eventual_reads_come_from_the_last_snapshot_without_the_lock() {
+    let state = empty_store().with_read_snapshot(std::time::Duration::from_secs(3600));
+    // Let the refresher take its first snapshot, of the empty store, before anything is written.
+    tokio::task::yield_now().await;
+    let get_eventual = |key: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-consistency", "eventual".parse().unwrap());
+        handle_get(State(state.clone()), Path(key.to_string()), headers)
+    };
+
+    let v1 = put_key(&state, "k", b"v1", "tok-1").await;
+    assert_eq!(get_eventual("k").await.status(), StatusCode::NOT_FOUND, "written after the snapshot");
+
+    snapshot::refresh(&state.db, state.read_snapshot.as_ref().unwrap()).await;
+    put_key(&state, "k", b"v2", "tok-2").await;
+    put_key(&state, "new", b"n", "tok-new").await;
+
+    // Served while a writer holds the lock: the value from before the snapshot, not the newer one.
+    let (stale, missing) = {
+        let _writer = state.db.write().await;
+        (get_eventual("k").await, get_eventual("new").await)
+    };
+    assert_eq!(stale.status(), StatusCode::OK);
+    assert_eq!(response_version(&stale), v1);
+    assert_eq!(stale.headers()["x-sequence"], v1.get().to_string().as_str());
+    assert_eq!(response_body(stale).await, b"v1");
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+    // Strong reads, the default, still see every acknowledged write.
+    assert_get(&state, "k", Some(b"v2")).await;
+    assert_get(&state, "new", Some(b"n")).await;
+
+    // Without a snapshot the header is ignored.
+    let state = empty_store();
+    put_key(&state, "k", b"v1", "tok-1").await;
+    let mut headers = HeaderMap::new();
+    headers.insert("x-consistency", "eventual".parse().unwrap());
+    let response = handle_get(State(state.clone()), Path("k".to_string()), headers).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_topology_served_when_configured() {
+    let response = handle_topology(State(replica_store())).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response_error_type(response).await, ERR_TOPOLOGY_UNKNOWN);
+
+    let topology = Topology {
+        primary_addr: "10.0.0.1:7000".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
+    let mut state = empty_store();
+    state.topology = Some(topology.clone());
+    let response = handle_topology(State(state)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(serde_json::from_slice::<Topology>(&response_body(response).await).unwrap(), topology);
+}
+
+#[tokio::test]
+async fn test_version_reports_build_and_role() {
+    let response = handle_version(State(replica_store())).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(body["server_version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(body["protocol_version"], PROTOCOL_VERSION);
+    assert_eq!(body["role"], "replica");
+    assert!(body["git_hash"].as_str().is_some_and(|hash| !hash.is_empty()));
+    assert!(body["build_date"].as_str().is_some_and(|date| !date.is_empty()));
+
+    let response = handle_version(State(empty_store())).await;
+    let body: serde_json::Value = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(body["role"], "primary");
+}
+
+#[tokio::test]
+async fn test_put_beyond_max_key_count_returns_507() {
+    let mut state = empty_store();
+    state.max_key_count = Some(2);
+    put_key(&state, "a", b"1", "tok-a").await;
+    put_key(&state, "b", b"2", "tok-b").await;
+
+    let put = |key: &str, tok: &str| {
+        handle_put(State(state.clone()), Path(key.to_string()), headers_with_idempotency_key(tok), Bytes::from("v"))
+    };
+    let full = put("c", "tok-c").await;
+    assert_eq!(full.status(), StatusCode::INSUFFICIENT_STORAGE);
+    assert_eq!(response_error_type(full).await, ERR_STORAGE_FULL);
+
+    // Overwrites are allowed at the limit, and deleting a key frees a slot.
+    assert_eq!(put("a", "tok-a2").await.status(), StatusCode::OK);
+    delete_key(&state, "a", "tok-del").await;
+    assert_eq!(put("c", "tok-c2").await.status(), StatusCode::OK);
+}
+
+// --- Server-Timing ---
+
+/// Parse `Server-Timing` into `(metric, milliseconds)` pairs.
+fn server_timing(response: &Response) -> Option<Vec<(String, f64)>> {
+    let value = response.headers().get("server-timing")?.to_str().unwrap();
+    let metric = |m: &str| {
+        let (name, dur) = m.split_once(";dur=").unwrap();
+        (name.to_string(), dur.parse::<f64>().unwrap())
+    };
+    Some(value.split(", ").map(metric).collect())
+}
+
+#[tokio::test]
+async fn test_server_timing_reports_lock_and_work_when_enabled() {
+    let put = |state: &AppState, tok: &str| {
+        handle_put(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key(tok), Bytes::from("v"))
+    };
+    assert_eq!(server_timing(&put(&empty_store(), "tok-off").await), None);
+
+    let mut state = empty_store();
+    state.server_timing = true;
+    let responses = [
+        put(&state, "tok-put").await,
+        handle_get(State(state.clone()), Path("k".to_string()), HeaderMap::new()).await,
+        handle_delete(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key("tok-del")).await,
+    ];
+    for response in &responses {
+        let metrics = server_timing(response).expect("Server-Timing header");
+        let names: Vec<&str> = metrics.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["lock", "work"]);
+        assert!(metrics.iter().all(|(_, ms)| *ms >= 0.0));
+    }
+
+    // A lock timeout reports only the time spent waiting.
+    let _writer = state.db.clone().write_owned().await;
+    let response = handle_get(State(state.clone()), Path("k".to_string()), HeaderMap::new()).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let metrics = server_timing(&response).unwrap();
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].0, "lock");
+    assert!(metrics[0].1 >= 1000.0);
+}
+
+#[tokio::test]
+async fn test_server_timing_reports_lock_hold_for_batched_writes() {
+    let mut state = batched_store(2);
+    state.server_timing = true;
+    let put = |tok: &str| {
+        handle_put(State(state.clone()), Path("k".to_string()), headers_with_idempotency_key(tok), Bytes::from("v"))
+    };
+
+    let (first, second) = tokio::join!(put("tok-1"), put("tok-2"));
+    for response in [first, second] {
+        let metrics = server_timing(&response).expect("Server-Timing header");
+        let names: Vec<&str> = metrics.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["lock", "work"], "the batch's lock hold is reported like a direct write's");
+    }
+}
+
+// --- /mutex/:name ---
+
+async fn mutex_request(state: &AppState, method: &str, uri: &str) -> (StatusCode, Option<MutexStatus>) {
+    let router = Server::create_router(state.clone(), &RouteBodyLimits::default());
+    let request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    let status = response.status();
+    (status, serde_json::from_slice(&response_body(response).await).ok())
+}
+
+#[tokio::test]
+async fn test_mutex_acquire_contend_renew_release() {
+    let clock = MockClock::new(NOW);
+    let state = AppState::new(clock.clone() as Arc<dyn Clock>, NodeRole::Primary);
+
+    let (status, body) = mutex_request(&state, "POST", "/mutex/jobs/acquire?owner=a&ttl_secs=30").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, Some(MutexStatus { acquired: true, owner: Some("a".to_string()), ttl_remaining: 30 }));
+    assert_eq!(state.db.read().await.store[&format!("{}jobs", MUTEX_KEY_PREFIX)].expires_at, Some(NOW + 30));
+
+    clock.set_millis((NOW + 10) * 1000);
+    let (status, body) = mutex_request(&state, "POST", "/mutex/jobs/acquire?owner=b").await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(body, Some(MutexStatus { acquired: false, owner: Some("a".to_string()), ttl_remaining: 20 }));
+
+    // Only the holder may renew or release; a renewal keeps the version.
+    let version = state.db.read().await.store["_mutex/jobs"].version;
+    assert_eq!(mutex_request(&state, "POST", "/mutex/jobs/renew?owner=b").await.0, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(mutex_request(&state, "DELETE", "/mutex/jobs?owner=b").await.0, StatusCode::PRECONDITION_FAILED);
+    let (status, body) = mutex_request(&state, "POST", "/mutex/jobs/renew?owner=a&ttl_secs=60").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.unwrap().ttl_remaining, 60);
+    assert_eq!(state.db.read().await.store["_mutex/jobs"].expires_at, Some(NOW + 70));
+    assert_eq!(state.db.read().await.store["_mutex/jobs"].version, version);
+
+    assert_eq!(mutex_request(&state, "DELETE", "/mutex/jobs?owner=a").await.0, StatusCode::NO_CONTENT);
+    assert!(state.db.read().await.store["_mutex/jobs"].value.is_none(), "release leaves a tombstone");
+    let (status, body) = mutex_request(&state, "DELETE", "/mutex/jobs?owner=a").await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(body, Some(MutexStatus { acquired: false, owner: None, ttl_remaining: 0 }));
+    assert_eq!(mutex_request(&state, "POST", "/mutex/jobs/acquire?owner=b").await.0, StatusCode::OK);
+
+    // A lapsed lease can be taken over and can no longer be renewed by its old holder.
+    clock.set_millis((NOW + 10 + DEFAULT_MUTEX_TTL_SECS) * 1000);
+    assert_eq!(mutex_request(&state, "POST", "/mutex/jobs/renew?owner=b").await.0, StatusCode::PRECONDITION_FAILED);
+    assert_eq!(mutex_request(&state, "POST", "/mutex/jobs/acquire?owner=a").await.0, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_mutex_rejects_bad_requests() {
+    let state = empty_store();
+    let cases = [
+        ("/mutex/m/acquire", ERR_INVALID_QUERY),
+        ("/mutex/m/acquire?owner=", ERR_INVALID_QUERY),
+        ("/mutex/m/acquire?owner=a&ttl_secs=soon", ERR_INVALID_QUERY),
+        ("/mutex/m/acquire?owner=a&ttl_secs=0", ERR_INVALID_TTL),
+        ("/mutex/m/acquire?owner=a&ttl_secs=86401", ERR_INVALID_TTL),
+        ("/mutex/m/acquire?owner=a&ttl_secs=18446744073709551615", ERR_INVALID_TTL),
+        ("/mutex/m/renew?owner=a&ttl_secs=18446744073709551615", ERR_INVALID_TTL),
+    ];
+    for (uri, error_type) in cases {
+        let router = Server::create_router(state.clone(), &RouteBodyLimits::default());
+        let response = router.oneshot(Request::post(uri).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        assert_eq!(response_error_type(response).await, error_type, "{}", uri);
+    }
+    assert!(state.db.read().await.store.is_empty());
+
+    let uri = format!("/mutex/{}/acquire?owner=a", "m".repeat(Limits::DEFAULT.max_key_size));
+    assert_eq!(mutex_request(&state, "POST", &uri).await.0, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        mutex_request(&replica_store(), "POST", "/mutex/m/acquire?owner=a").await.0,
+        StatusCode::METHOD_NOT_ALLOWED
+    );
+    let longest = format!("/mutex/m/acquire?owner=a&ttl_secs={MAX_MUTEX_TTL_SECS}");
+    assert_eq!(mutex_request(&state, "POST", &longest).await.0, StatusCode::OK);
+}
+
+// --- GET with Range ---
+
+#[tokio::test]
+async fn test_get_range_serves_partial_content() {
+    let state = store_with("k", b"0123456789").await;
+    let get_range = |range: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, range.parse().unwrap());
+        handle_get(State(state.clone()), Path("k".to_string()), headers)
+    };
+
+    let cases: [(&str, &[u8], &str); 4] = [
+        ("bytes=0-3", b"0123", "bytes 0-3/10"),
+        ("bytes=7-", b"789", "bytes 7-9/10"),
+        ("bytes=-2", b"89", "bytes 8-9/10"),
+        ("bytes=5-999", b"56789", "bytes 5-9/10"),
+    ];
+    for (range, body, content_range) in cases {
+        let response = get_range(range).await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT, "{}", range);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], content_range);
+        assert_eq!(response_version(&response), Version(1), "ETag is the version for partial responses too");
+        assert_eq!(response_body(response).await, body);
+    }
+
+    let response = get_range("bytes=10-12").await;
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */10");
+    assert_eq!(response_error_type(response).await, ERR_RANGE_NOT_SATISFIABLE);
+
+    // Unsupported or malformed ranges are ignored.
+    for range in ["bytes=0-1,4-5", "bytes=5-2", "items=0-1", "bytes=abc"] {
+        let response = get_range(range).await;
+        assert_eq!(response.status(), StatusCode::OK, "{}", range);
+        assert_eq!(response_body(response).await, b"0123456789");
+    }
+}
+
+#[tokio::test]
+async fn test_get_if_none_match_returns_304_for_current_unexpired_version() {
+    let state = store_with("k", b"value").await;
+    let get = |etag: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag.parse().unwrap());
+        handle_get(State(state.clone()), Path("k".to_string()), headers)
+    };
+
+    // If-None-Match compares weakly, so a weak tag for the current version also matches.
+    for etag in ["\"1\"", "\"0\", \"1\"", "W/\"1\"", "*"] {
+        let response = get(etag).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "{}", etag);
+        assert_eq!(response_version(&response), Version(1));
+        assert!(response_body(response).await.is_empty());
+    }
+    assert_eq!(get("\"2\"").await.status(), StatusCode::OK);
+
+    // An expired value is always sent in full, flagged, rather than revalidated.
+    state.db.write().await.store.get_mut("k").unwrap().expires_at = Some(NOW);
+    let response = get("\"1\"").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-expired"], "true");
+}
+
+// --- Append (PATCH /keys/:key) ---
+
+async fn append(state: &AppState, key: &str, suffix: &[u8], tok: &str) -> Response {
+    let headers = headers_with_idempotency_key(tok);
+    handle_patch(State(state.clone()), Path(key.to_string()), headers, Bytes::from(suffix.to_vec())).await
+}
+
+#[tokio::test]
+async fn test_append_extends_existing_value_and_keeps_ttl() {
+    let state = store_with("log", b"line1\n").await;
+    {
+        let mut db = state.db.write().await;
+        db.next_version.store(1, Ordering::Relaxed);
+        db.store.get_mut("log").unwrap().expires_at = Some(NOW + 60);
+    }
+
+    let response = append(&state, "log", b"line2\n", "tok-1").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-previous-version"], "1");
+    let version = response_version(&response);
+    assert!(version > Version(1));
+    assert_get(&state, "log", Some(b"line1\nline2\n")).await;
+    assert_eq!(state.db.read().await.store["log"].expires_at, Some(NOW + 60));
+
+    // A replay returns the original version without appending twice.
+    let replay = append(&state, "log", b"line2\n", "tok-1").await;
+    assert_eq!(response_version(&replay), version);
+    assert_get(&state, "log", Some(b"line1\nline2\n")).await;
+
+    // Reusing the token for a PUT of the same key is a mismatch.
+    let headers = headers_with_idempotency_key("tok-1");
+    let put = handle_put(State(state.clone()), Path("log".to_string()), headers, Bytes::from("x")).await;
+    assert_eq!(put.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response_error_type(put).await, ERR_IDEMPOTENCY_MISMATCH);
+}
+
+#[tokio::test]
+async fn test_append_creates_absent_or_deleted_key() {
+    let state = empty_store();
+    let response = append(&state, "new", b"first", "tok-1").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("x-previous-version").is_none());
+    assert_get(&state, "new", Some(b"first")).await;
+
+    delete_key(&state, "new", "tok-2").await.unwrap();
+    assert_eq!(append(&state, "new", b"again", "tok-3").await.status(), StatusCode::OK);
+    assert_get(&state, "new", Some(b"again")).await;
+
+    let missing_key = handle_patch(State(state.clone()), Path("k".to_string()), HeaderMap::new(), Bytes::new()).await;
+    assert_eq!(missing_key.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(missing_key).await, ERR_MISSING_IDEMPOTENCY_KEY);
+}
+
+#[tokio::test]
+async fn test_append_beyond_max_value_size_returns_400() {
+    let state = store_with("k", &vec![b'a'; Limits::DEFAULT.max_value_size - 1]).await;
+
+    let response = append(&state, "k", b"bc", "tok-1").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(response).await, ERR_VALUE_TOO_LARGE);
+    assert_eq!(state.db.read().await.store["k"].version, Version(1));
+
+    assert_eq!(append(&state, "k", b"b", "tok-2").await.status(), StatusCode::OK);
+    assert_eq!(state.db.read().await.store["k"].value.as_ref().unwrap().len(), Limits::DEFAULT.max_value_size);
+}
+
+// --- Read-only mode ---
+
+#[tokio::test]
+async fn test_read_only_mode_rejects_writes_and_serves_reads() {
+    let state = store_with("k", b"v").await;
+    let router = Server::create_router(state.clone(), &RouteBodyLimits::default());
+    let set_read_only = |query: &str| {
+        let request = Request::post(format!("/admin/readonly?{query}")).body(Body::empty()).unwrap();
+        router.clone().oneshot(request)
+    };
+
+    assert_eq!(set_read_only("enabled=true").await.unwrap().status(), StatusCode::NO_CONTENT);
+    assert!(state.is_read_only());
+
+    let headers = headers_with_idempotency_key("tok-1");
+    let key = || Path("k".to_string());
+    let rejected = [
+        handle_put(State(state.clone()), key(), headers.clone(), Bytes::from("v2")).await,
+        handle_patch(State(state.clone()), key(), headers.clone(), Bytes::from("v2")).await,
+        handle_delete(State(state.clone()), key(), headers.clone()).await,
+        handle_batch_ttl(State(state.clone()), headers.clone(), Bytes::from(r#"{"keys":["k"],"expires_at":1}"#))
+            .await,
+        router.clone().oneshot(Request::post("/mutex/m/acquire?owner=a").body(Body::empty()).unwrap()).await.unwrap(),
+    ];
+    for response in rejected {
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response_error_type(response).await, ERR_READ_ONLY);
+    }
+    assert_get(&state, "k", Some(b"v")).await;
+
+    // Turning it off accepts writes again, including one reusing the rejected request's key.
+    assert_eq!(set_read_only("enabled=false").await.unwrap().status(), StatusCode::NO_CONTENT);
+    put_key(&state, "k", b"v2", "tok-1").await;
+    assert_get(&state, "k", Some(b"v2")).await;
+
+    let response = set_read_only("enabled=maybe").await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(response).await, ERR_INVALID_QUERY);
+    assert!(!state.is_read_only());
+}
+
+// --- Content validation ---
+
+#[test]
+fn test_builtin_content_validators() {
+    assert_eq!(JsonValidator.validate("k", br#"{"a": [1, 2]}"#, None), Ok(()));
+    assert!(JsonValidator.validate("k", b"{not json", Some("application/json")).is_err());
+
+    let max = MaxPayloadValidator { max_bytes: 3 };
+    assert_eq!(max.validate("k", b"abc", None), Ok(()));
+    assert_eq!(max.validate("k", b"abcd", None), Err(ContentError("value is 4 bytes, the limit is 3".to_string())));
+}
+
+/// Requires JSON under `config/` only, and sees the request's Content-Type.
+#[derive(Debug)]
+struct ConfigIsJson;
+
+impl ContentValidator for ConfigIsJson {
+    fn validate(&self, key: &str, value: &[u8], content_type: Option<&str>) -> Result<(), ContentError> {
+        if !key.starts_with("config/") {
+            return Ok(());
+        }
+        if content_type != Some("application/json") {
+            return Err(ContentError("config/ keys must be sent as application/json".to_string()));
+        }
+        JsonValidator.validate(key, value, content_type)
+    }
+}
+
+#[tokio::test]
+async fn test_put_rejected_by_content_validator_returns_422_and_stores_nothing() {
+    let mut state = AppState::new(MockClock::new(NOW) as Arc<dyn Clock>, NodeRole::Primary);
+    state.content_validator = Some(Arc::new(ConfigIsJson));
+    let put = |key: &str, content_type: &str, body: &'static str| {
+        let mut headers = headers_with_idempotency_key(&format!("tok-{key}-{body}"));
+        headers.insert(header::CONTENT_TYPE, content_type.parse().unwrap());
+        handle_put(State(state.clone()), Path(key.to_string()), headers, Bytes::from(body))
+    };
+
+    let response = put("config/app", "application/json", "{oops").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: ErrorResponse = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(body.error, "content validation failed");
+    assert_eq!(body.error_type, ERR_CONTENT_REJECTED);
+    let detail = body.details.unwrap()["detail"].as_str().unwrap().to_string();
+    assert!(detail.starts_with("value is not valid JSON"), "{detail}");
+    assert_get(&state, "config/app", None).await;
+
+    let response = put("config/app", "text/plain", "{}").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    assert_eq!(put("config/app", "application/json", "{}").await.status(), StatusCode::OK);
+    assert_eq!(put("notes", "text/plain", "{oops").await.status(), StatusCode::OK);
+    assert_get(&state, "config/app", Some(b"{}")).await;
+}
+
+#[tokio::test]
+async fn test_append_rejected_by_content_validator_leaves_the_value() {
+    for batched in [false, true] {
+        let mut state = AppState::new(MockClock::new(NOW) as Arc<dyn Clock>, NodeRole::Primary);
+        state.content_validator = Some(Arc::new(ConfigIsJson));
+        if batched {
+            state = state.with_write_batching(WriteBatchConfig { max_batch_size: 1, flush_interval_ms: 5 });
+        }
+        let json = |tok: &str| {
+            let mut headers = headers_with_idempotency_key(tok);
+            headers.insert(header::CONTENT_TYPE, "application/json".parse().unwrap());
+            headers
+        };
+        let path = |key: &str| Path(key.to_string());
+        let response = handle_put(State(state.clone()), path("config/app"), json("put"), "[1]".into()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The suffix alone is JSON; the value it would make is not.
+        let response = handle_patch(State(state.clone()), path("config/app"), json("bad"), "[2]".into()).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY, "batched: {batched}");
+        assert_eq!(response_error_type(response).await, ERR_CONTENT_REJECTED);
+        assert_get(&state, "config/app", Some(b"[1]")).await;
+
+        // An append that starts a key is checked as well.
+        let response = handle_patch(State(state.clone()), path("config/new"), json("new"), "{".into()).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_get(&state, "config/new", None).await;
+    }
+}
+
+// --- Namespaces ---
+
+#[tokio::test]
+async fn test_namespaced_routes_use_their_own_store() {
+    let state = store_with("k", b"default").await;
+    let router = Server::create_router(state.clone(), &RouteBodyLimits::default());
+    let send = |request: Request<Body>| router.clone().oneshot(request);
+    let put = |path: &str, tok: &str| Request::put(path).header("idempotency-key", tok).body(Body::from("ns")).unwrap();
+
+    let response = send(put("/tenant/keys/k", "tok-1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response_version(&response), Version(1), "a new namespace starts its own version counter");
+    assert_get(&state, "k", Some(b"default")).await;
+
+    let response = send(Request::get("/tenant/keys/k").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response_body(response).await, b"ns");
+    let response = send(Request::get("/other/keys/k").body(Body::empty()).unwrap()).await.unwrap();
+    assert_eq!(response_error_type(response).await, ERR_KEY_NOT_FOUND);
+
+    let response = send(put("/bad.name/keys/k", "tok-2")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(response_error_type(response).await, ERR_INVALID_NAMESPACE);
+
+    // A refused write does not leave an empty namespace behind.
+    state.read_only.store(true, Ordering::Relaxed);
+    assert_eq!(send(put("/frozen/keys/k", "tok-3")).await.unwrap().status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let response = send(Request::get("/admin/namespaces").body(Body::empty()).unwrap()).await.unwrap();
+    let names: Vec<String> = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(names, ["tenant"]);
+}
+
+// --- POST /multi-cas ---
+
+async fn multi_cas(state: &AppState, body: serde_json::Value) -> Response {
+    let router = Server::create_router(state.clone(), &RouteBodyLimits::default());
+    let request = Request::post("/multi-cas").body(Body::from(body.to_string())).unwrap();
+    router.oneshot(request).await.unwrap()
+}
+
+fn multi_cas_body(reads: &[(&str, u64)], writes: &[(&str, &[u8])], tok: &str) -> serde_json::Value {
+    let reads: Vec<_> =
+        reads.iter().map(|(key, version)| serde_json::json!({ "key": key, "version": version })).collect();
+    let writes: Vec<_> = writes
+        .iter()
+        .map(|(key, value)| serde_json::json!({ "key": key, "value_b64": Base64Value::from_bytes(value) }))
+        .collect();
+    serde_json::json!({ "reads": reads, "writes": writes, "idempotency_key": tok })
+}
+
+#[tokio::test]
+async fn test_multi_cas_commits_when_reads_are_current() {
+    let state = store_with("a", b"1").await;
+    state.db.write().await.next_version.store(1, Ordering::Relaxed);
+
+    // "b" was never written, so it is read at version 0.
+    let response = multi_cas(&state, multi_cas_body(&[("a", 1), ("b", 0)], &[("b", b"2"), ("c", b"3")], "cas-1")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let result: MultiCasResponse = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(result.versions, [Version(2), Version(3)]);
+    assert_get(&state, "b", Some(b"2")).await;
+    assert_get(&state, "c", Some(b"3")).await;
+}
+
+#[tokio::test]
+async fn test_multi_cas_conflict_writes_nothing() {
+    let state = store_with("a", b"1").await;
+    state.db.write().await.next_version.store(1, Ordering::Relaxed);
+    put_key(&state, "b", b"x", "tok-b").await;
+
+    let body = multi_cas_body(&[("a", 1), ("b", 0), ("b", 0)], &[("a", b"new"), ("c", b"new")], "cas-1");
+    let response = multi_cas(&state, body).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let error: ErrorResponse = serde_json::from_slice(&response_body(response).await).unwrap();
+    assert_eq!(error.error_type, ERR_VERSION_CONFLICT);
+    let conflict: MultiCasConflict = serde_json::from_value(error.details.unwrap()).unwrap();
+    assert_eq!(conflict.mismatched, ["b"]);
+    assert_get(&state, "a", Some(b"1")).await;
+    assert_get(&state, "c", None).await;
+
+    // A conflict is not cached, so the same token can be retried with fresh reads.
+    let body = multi_cas_body(&[("a", 1), ("b", 2)], &[("a", b"new")], "cas-1");
+    assert_eq!(multi_cas(&state, body).await.status(), StatusCode::OK);
+    assert_get(&state, "a", Some(b"new")).await;
+}
+
+#[tokio::test]
+async fn test_multi_cas_replay_returns_original_versions() {
+    let state = empty_store();
+    let first = multi_cas(&state, multi_cas_body(&[("k", 0)], &[("k", b"v")], "cas-replay")).await;
+    assert_eq!(first.status(), StatusCode::OK);
+    assert!(!first.headers().contains_key("x-idempotent-replay"));
+    let first = response_body(first).await;
+
+    // The replay is not re-checked: "k" is no longer at version 0, yet the original result comes back.
+    let replay = multi_cas(&state, multi_cas_body(&[("k", 0)], &[("k", b"v")], "cas-replay")).await;
+    assert_eq!(replay.status(), StatusCode::OK);
+    assert_eq!(replay.headers()["x-idempotent-replay"], "true");
+    assert_eq!(response_body(replay).await, first);
+    assert_eq!(state.db.read().await.sequence(), 1);
+}
+
+#[tokio::test]
+async fn test_multi_cas_replay_with_different_items_is_rejected() {
+    let state = empty_store();
+    let first = multi_cas(&state, multi_cas_body(&[], &[("a", b"1"), ("b", b"2")], "cas-items")).await;
+    assert_eq!(first.status(), StatusCode::OK);
+
+    // Another value, another read, or the same writes reordered (the versions are positional)
+    // would each get results that do not describe them.
+    let mismatches = [
+        multi_cas_body(&[], &[("a", b"1"), ("b", b"3")], "cas-items"),
+        multi_cas_body(&[("a", 1)], &[("a", b"1"), ("b", b"2")], "cas-items"),
+        multi_cas_body(&[], &[("b", b"2"), ("a", b"1")], "cas-items"),
+    ];
+    for body in mismatches {
+        let response = multi_cas(&state, body).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(response_error_type(response).await, ERR_IDEMPOTENCY_MISMATCH);
+    }
+    assert_eq!(state.db.read().await.sequence(), 2);
+}
+
+#[tokio::test]
+async fn test_multi_cas_rejected_by_content_validator_writes_nothing() {
+    let mut state = empty_store();
+    state.content_validator = Some(Arc::new(JsonValidator));
+
+    // One bad value fails the whole request, including the valid write before it.
+    let body = multi_cas_body(&[("a", 0)], &[("a", b"{}"), ("b", b"{oops")], "cas-json");
+    let response = multi_cas(&state, body).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response_error_type(response).await, ERR_CONTENT_REJECTED);
+    assert_get(&state, "a", None).await;
+    assert_get(&state, "b", None).await;
+    assert_eq!(state.db.read().await.sequence(), 0);
+
+    let body = multi_cas_body(&[("a", 0)], &[("a", b"{}"), ("b", b"[1]")], "cas-json");
+    assert_eq!(multi_cas(&state, body).await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_signed_multi_cas_larger_than_a_put_is_accepted() {
+    let mut state = empty_store();
+    state.signature_key = Some(Arc::from(&b"secret"[..]));
+    let limits = RouteBodyLimits::default();
+    let router = Server::create_router(state, &limits);
+    // A value just under the limit is larger than any PUT body once base64-encoded.
+    let value = vec![b'x'; Limits::DEFAULT.max_value_size - 1024];
+    let body = multi_cas_body(&[], &[("k", &value[..])], "cas-signed").to_string();
+    assert!(body.len() > limits.key_put);
+
+    let signature = signing::sign(b"secret", "POST", "/multi-cas", body.as_bytes(), NOW);
+    let request = Request::post("/multi-cas")
+        .header("x-timestamp", NOW)
+        .header("x-signature", signature)
+        .body(Body::from(body))
+        .unwrap();
+    assert_eq!(router.oneshot(request).await.unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_multi_cas_idempotency_record_does_not_hold_the_values() {
+    let state = empty_store();
+    let big = vec![b'x'; 64 * 1024];
+    let response = multi_cas(&state, multi_cas_body(&[], &[("k", &big[..])], "cas-big")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The record keeps a digest of the items, the same size whatever was written.
+    let db = state.db.read().await;
+    let record = db.idempotency_cache.values().next().unwrap();
+    assert_eq!(record.key_path.len(), 64);
+}
+
+#[tokio::test]
+async fn test_multi_cas_rejects_bad_requests() {
+    let state = empty_store();
+    let cases = [
+        (multi_cas_body(&[], &[("k", b"v")], ""), ERR_MISSING_IDEMPOTENCY_KEY),
+        (multi_cas_body(&[("", 0)], &[], "t"), ERR_EMPTY_KEY),
+        (serde_json::json!({ "writes": [{ "key": "k", "value_b64": "!" }], "idempotency_key": "t" }), ERR_INVALID_BODY),
+        (serde_json::json!({ "writes": 1, "idempotency_key": "t" }), ERR_INVALID_BODY),
+    ];
+    for (body, error_type) in cases {
+        let response = multi_cas(&state, body).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response_error_type(response).await, error_type);
+    }
+
+    let big_key = "k".repeat(Limits::DEFAULT.max_key_size + 1);
+    let response = multi_cas(&state, multi_cas_body(&[(&big_key, 0)], &[], "t")).await;
+    assert_eq!(response_error_type(response).await, ERR_KEY_TOO_LARGE);
+    let reads: Vec<(&str, u64)> = vec![("k", 0); Limits::DEFAULT.max_batch_keys + 1];
+    let response = multi_cas(&state, multi_cas_body(&reads, &[], "t")).await;
+    assert_eq!(response_error_type(response).await, ERR_BATCH_TOO_LARGE);
+
+    let response = multi_cas(&replica_store(), multi_cas_body(&[], &[("k", b"v")], "t")).await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert!(state.db.read().await.store.is_empty());
 }
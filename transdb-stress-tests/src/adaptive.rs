@@ -0,0 +1,110 @@
+//! `--adaptive-key-space`: resize the key space mid-run so GETs keep hitting a useful share of
+//! written keys. Too large a key space makes most reads misses; too small a one makes every read
+//! a hit on a handful of hot keys. A background task watches the GET hit rate and publishes the
+//! size the worker should draw keys from.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
+
+/// Span of GET results the hit rate is computed over, and how often it is re-evaluated.
+pub const WINDOW: Duration = Duration::from_secs(10);
+/// Halve the key space when fewer than this fraction of GETs find a value.
+pub const SHRINK_BELOW: f64 = 0.40;
+/// Double the key space when more than this fraction of GETs find a value.
+pub const GROW_ABOVE: f64 = 0.95;
+
+/// The key space to use after a window with `hit_rate`: halved (never below 1) when reads mostly
+/// miss, doubled when they almost all hit, otherwise unchanged.
+pub fn next_key_space(current: usize, hit_rate: f64) -> usize {
+    if hit_rate < SHRINK_BELOW {
+        (current / 2).max(1)
+    } else if hit_rate > GROW_ABOVE {
+        current.saturating_mul(2)
+    } else {
+        current
+    }
+}
+
+/// GET results from the last `window`, oldest first.
+#[derive(Debug)]
+pub struct HitRateWindow {
+    window: Duration,
+    samples: VecDeque<(Instant, bool)>,
+    hits: usize,
+}
+
+impl HitRateWindow {
+    pub fn new(window: Duration) -> Self {
+        Self { window, samples: VecDeque::new(), hits: 0 }
+    }
+
+    /// Record a GET that completed at `at`; `hit` is whether it found a value.
+    pub fn record(&mut self, at: Instant, hit: bool) {
+        self.samples.push_back((at, hit));
+        if hit {
+            self.hits += 1;
+        }
+    }
+
+    /// Fraction of GETs within `window` of `now` that were hits; `None` when there were none.
+    pub fn hit_rate(&mut self, now: Instant) -> Option<f64> {
+        while let Some(&(at, hit)) = self.samples.front() {
+            if now.saturating_duration_since(at) <= self.window {
+                break;
+            }
+            self.samples.pop_front();
+            if hit {
+                self.hits -= 1;
+            }
+        }
+        if self.samples.is_empty() {
+            return None;
+        }
+        Some(self.hits as f64 / self.samples.len() as f64)
+    }
+
+    /// Forget every sample, so the next evaluation only sees reads against the current key space.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.hits = 0;
+    }
+}
+
+/// Start the tuner for a run beginning with `initial` keys. The worker reports each GET on the
+/// returned sender and reads its key space from the receiver; every `window` the tuner applies
+/// [`next_key_space`] and logs any change. It stops once the sender is dropped.
+pub fn spawn(initial: usize, window: Duration) -> (mpsc::UnboundedSender<(Instant, bool)>, watch::Receiver<usize>) {
+    let (samples_tx, mut samples_rx) = mpsc::unbounded_channel();
+    let (key_space_tx, key_space_rx) = watch::channel(initial);
+
+    tokio::spawn(async move {
+        let mut samples = HitRateWindow::new(window);
+        let mut current = initial;
+        let mut interval = tokio::time::interval(window);
+        interval.tick().await; // consume the immediate first tick
+        loop {
+            tokio::select! {
+                sample = samples_rx.recv() => match sample {
+                    Some((at, hit)) => samples.record(at, hit),
+                    None => return,
+                },
+                _ = interval.tick() => {
+                    let Some(hit_rate) = samples.hit_rate(Instant::now()) else { continue };
+                    let next = next_key_space(current, hit_rate);
+                    if next == current {
+                        continue;
+                    }
+                    println!("Key space {current} → {next} (GET hit rate {:.1}%)", hit_rate * 100.0);
+                    current = next;
+                    samples.clear();
+                    if key_space_tx.send(next).is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+    });
+
+    (samples_tx, key_space_rx)
+}
@@ -0,0 +1,65 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use transdb_client::{Client, GetResult};
+use transdb_common::{Result, TransDbError, Version};
+
+/// Message carried by the `NetworkError` returned for an injected fault.
+pub const INJECTED_FAULT: &str = "injected fault";
+
+/// Wraps a [`Client`] and fails a fraction of operations locally, before they reach the server,
+/// so retry and failover paths can be exercised deterministically.
+pub struct FaultInjectingClient {
+    inner: Client,
+    fault_rate: f64,
+    rng: Mutex<StdRng>,
+    injected: AtomicU64,
+}
+
+impl FaultInjectingClient {
+    /// `fault_rate` is the probability (0.0–1.0) that any single operation fails; which ones do
+    /// is drawn from `seed`.
+    pub fn new(inner: Client, fault_rate: f64, seed: u64) -> Self {
+        assert!((0.0..=1.0).contains(&fault_rate), "fault_rate must be in 0.0..=1.0");
+        Self { inner, fault_rate, rng: Mutex::new(StdRng::seed_from_u64(seed)), injected: AtomicU64::new(0) }
+    }
+
+    /// Number of operations short-circuited so far.
+    pub fn faults_injected(&self) -> u64 {
+        self.injected.load(Ordering::Relaxed)
+    }
+
+    pub async fn get(&self, key: &str) -> Result<GetResult> {
+        self.maybe_fault()?;
+        self.inner.get(key).await
+    }
+
+    pub async fn get_allowing_expired(&self, key: &str) -> Result<GetResult> {
+        self.maybe_fault()?;
+        self.inner.get_allowing_expired(key).await
+    }
+
+    pub async fn put(&self, key: &str, value: &[u8]) -> Result<Version> {
+        self.maybe_fault()?;
+        self.inner.put(key, value).await
+    }
+
+    pub async fn put_with_ttl(&self, key: &str, value: &[u8], expires_at: u64) -> Result<Version> {
+        self.maybe_fault()?;
+        self.inner.put_with_ttl(key, value, expires_at).await
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<Option<Version>> {
+        self.maybe_fault()?;
+        self.inner.delete(key).await
+    }
+
+    fn maybe_fault(&self) -> Result<()> {
+        if self.fault_rate > 0.0 && self.rng.lock().expect("fault rng poisoned").gen_bool(self.fault_rate) {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+            return Err(TransDbError::NetworkError(INJECTED_FAULT.to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+//! Latency histogram with a fixed, log-linear bucket layout, in the style of HdrHistogram.
+//!
+//! Values below 256 get a bucket each. Above that, every power of two is split into 128 equal
+//! buckets, so a bucket is never wider than 1/128 of the values in it. Recording is a single
+//! counter increment, whatever the run's length, and two histograms merge bucket by bucket.
+//! Percentiles are reported as the lower bound of the bucket they fall in, so they read up to
+//! 1/128 (under 0.8%) below the recorded value; the minimum, maximum and mean are exact.
+
+/// Values up to this are recorded exactly.
+const LINEAR: u64 = 256;
+/// Buckets per power of two above [`LINEAR`].
+const SUB_BUCKETS: u64 = 128;
+const SUB_BUCKET_BITS: u32 = SUB_BUCKETS.trailing_zeros();
+/// Enough buckets for every `u64`: the linear ones plus one group per power of two from 2^8.
+const BUCKETS: usize = (LINEAR + (u64::BITS - LINEAR.trailing_zeros()) as u64 * SUB_BUCKETS) as usize;
+
+/// Recorded values, typically nanoseconds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    /// Count per bucket; left empty until the first value is recorded.
+    counts: Vec<u64>,
+    count: u64,
+    sum: u128,
+    min: u64,
+    max: u64,
+}
+
+/// One non-empty bucket of a [`Histogram`]: `count` values between `low` and `high` inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    pub low: u64,
+    pub high: u64,
+    pub count: u64,
+}
+
+impl Histogram {
+    pub fn record(&mut self, value: u64) {
+        if self.counts.is_empty() {
+            self.counts = vec![0; BUCKETS];
+            self.min = value;
+        }
+        self.counts[bucket_of(value)] += 1;
+        self.count += 1;
+        self.sum += u128::from(value);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Add every value recorded in `other`, as if they had been recorded here.
+    pub fn merge(&mut self, other: &Histogram) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            *self = other.clone();
+            return;
+        }
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+        self.count += other.count;
+        self.sum += other.sum;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Number of values recorded.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The value at index `floor(p * n)` of the recorded values in ascending order, to within
+    /// the bucket resolution; the last index is the exact maximum. 0 when nothing was recorded.
+    pub fn percentile(&self, p: f64) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        let rank = ((p * self.count as f64).floor() as u64).min(self.count - 1);
+        if rank == self.count - 1 {
+            return self.max;
+        }
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen > rank {
+                return bucket_bounds(index).0.max(self.min);
+            }
+        }
+        unreachable!("bucket counts add up to the number of values")
+    }
+
+    /// Smallest recorded value; 0 when nothing was recorded.
+    pub fn min(&self) -> u64 {
+        self.min
+    }
+
+    /// Largest recorded value; 0 when nothing was recorded.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Mean of the recorded values; 0 when nothing was recorded.
+    pub fn mean(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        self.sum as f64 / self.count as f64
+    }
+
+    /// The non-empty buckets, in ascending order.
+    pub fn buckets(&self) -> impl Iterator<Item = Bucket> + '_ {
+        self.counts.iter().enumerate().filter(|(_, count)| **count > 0).map(|(index, count)| {
+            let (low, high) = bucket_bounds(index);
+            Bucket { low, high, count: *count }
+        })
+    }
+}
+
+impl Extend<u64> for Histogram {
+    fn extend<I: IntoIterator<Item = u64>>(&mut self, values: I) {
+        for value in values {
+            self.record(value);
+        }
+    }
+}
+
+impl FromIterator<u64> for Histogram {
+    fn from_iter<I: IntoIterator<Item = u64>>(values: I) -> Self {
+        let mut histogram = Histogram::default();
+        histogram.extend(values);
+        histogram
+    }
+}
+
+fn bucket_of(value: u64) -> usize {
+    if value < LINEAR {
+        return value as usize;
+    }
+    // `value >> shift` keeps the top SUB_BUCKET_BITS + 1 bits, the leading one included.
+    let top_bit = u64::BITS - 1 - value.leading_zeros();
+    let shift = top_bit - SUB_BUCKET_BITS;
+    let group = u64::from(top_bit - LINEAR.trailing_zeros());
+    (LINEAR + group * SUB_BUCKETS + ((value >> shift) - SUB_BUCKETS)) as usize
+}
+
+/// The lowest and highest value that fall in bucket `index`.
+fn bucket_bounds(index: usize) -> (u64, u64) {
+    let index = index as u64;
+    if index < LINEAR {
+        return (index, index);
+    }
+    let (group, sub) = ((index - LINEAR) / SUB_BUCKETS, (index - LINEAR) % SUB_BUCKETS);
+    let shift = group as u32 + LINEAR.trailing_zeros() - SUB_BUCKET_BITS;
+    let low = (SUB_BUCKETS + sub) << shift;
+    (low, low + ((1 << shift) - 1))
+}
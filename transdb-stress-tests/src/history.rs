@@ -1,6 +1,13 @@
-use std::collections::HashMap;
-use std::time::Instant;
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use transdb_common::Version;
 
+/// Slack for comparing a TTL with the client's timestamps. The server decides expiry by its wall
+/// clock, which can be stepped or slewed against the client's monotonic one.
+pub const TTL_CLOCK_TOLERANCE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpKind {
     Put,
     Get,
@@ -8,13 +15,27 @@ pub enum OpKind {
     Delete,
 }
 
+impl OpKind {
+    /// How the report names operations of this kind.
+    pub fn as_name(&self) -> &'static str {
+        match self {
+            Self::Put => "PUT",
+            Self::Get => "GET",
+            Self::GetAllowingExpired => "GET allowing expired",
+            Self::Delete => "DELETE",
+        }
+    }
+}
+
 pub enum OpOutcome {
-    /// The PUT succeeded. `value` is what was written (needed for correctness checking).
-    PutOk { version: u64, value: Vec<u8> },
-    GetOk { version: u64, value: Vec<u8> },
+    /// The PUT succeeded. `value` is what was written (needed for correctness checking);
+    /// `expires_at` is when the TTL it was written with elapses, on the client's clock, if it had one.
+    PutOk { version: Version, value: Bytes, expires_at: Option<Instant> },
+    /// `expired` is set when a GET allowing expired values returned one past its TTL.
+    GetOk { version: Version, value: Bytes, expired: bool },
     NotFound,
-    DeleteOk { version: u64 },
-    /// 5xx or network failure.
+    DeleteOk { version: Version },
+    /// 5xx, network failure, or a fault injected by the harness.
     Error,
 }
 
@@ -28,7 +49,14 @@ pub struct OpRecord {
     pub outcome: OpOutcome,
 }
 
-pub struct History(pub Vec<OpRecord>);
+/// Operation records in completion order. With a record limit it is a ring buffer: the oldest
+/// records are evicted, so everything retained completed after everything dropped.
+pub struct History {
+    records: VecDeque<OpRecord>,
+    /// `None` keeps every record.
+    max_records: Option<usize>,
+    records_dropped: u64,
+}
 
 pub enum ViolationKind {
     /// GET returned a version for which no PUT was ever recorded.
@@ -41,48 +69,162 @@ pub enum ViolationKind {
     /// GET returned stale data. Not counted as an error by default (eventual consistency).
     /// `latest_known_version` is the highest write version (PUT or tombstone) that was
     /// already ACKed before the GET started.
-    StaleDataReturned { latest_known_version: u64 },
+    StaleDataReturned { latest_known_version: Version },
+    /// GET returned NotFound although a PUT (`expected_version`) was the latest write ACKed
+    /// before the GET started and no DELETE could have overlapped it — lost data.
+    UnexpectedNotFound { expected_version: Version },
+    /// GET returned a version older than `known_version`, which an operation on the same key
+    /// had already written or read before the GET started. Only reported by
+    /// [`History::check_causal_consistency`].
+    CausalOrderViolated { known_version: Version },
+    /// GET returned an older version of a key although its latest write ACKed before the GET
+    /// started was a DELETE (`tombstone_version`): the key should have read as deleted.
+    /// Reported by [`History::check_delete_correctness`].
+    StaleReadAfterConfirmedDelete { tombstone_version: Version },
+    /// A strict GET returned a value whose TTL had elapsed before the GET started; only
+    /// [`OpKind::GetAllowingExpired`] may return one.
+    ExpiredValueReturned { expires_at: Instant, get_start_ts: Instant },
 }
 
 pub struct Violation {
     pub key: String,
-    pub version: u64,
+    pub version: Version,
     pub kind: ViolationKind,
 }
 
 /// The data payload of a write operation.
 enum WriteValue {
-    Data(Vec<u8>),
+    Data(Bytes),
     Tombstone,
 }
 
 /// Entry in the unified write index.
 struct WriteEntry {
     write_value: WriteValue,
+    /// When the write's TTL elapses, if it carried one; from then on the key may legitimately
+    /// read as NotFound.
+    expires_at: Option<Instant>,
     write_start_ts: Instant,
     write_ack_ts: Instant,
 }
 
+impl From<Vec<OpRecord>> for History {
+    /// An unlimited history holding `records`.
+    fn from(records: Vec<OpRecord>) -> Self {
+        Self { records: records.into(), max_records: None, records_dropped: 0 }
+    }
+}
+
 impl History {
-    /// Check every successful GET against the unified write index.
+    /// An empty history keeping at most `max_records` records (`None`: unlimited).
+    pub fn new(max_records: Option<usize>) -> Self {
+        Self { records: VecDeque::new(), max_records, records_dropped: 0 }
+    }
+
+    /// Append a record, evicting the oldest one if the history is full.
+    pub fn push(&mut self, record: OpRecord) {
+        self.records.push_back(record);
+        self.evict_overflow();
+    }
+
+    /// Insert `records`, which completed before everything already held, at the front. If that
+    /// overflows the limit they are the first to go.
+    pub fn prepend(&mut self, records: Vec<OpRecord>) {
+        for record in records.into_iter().rev() {
+            self.records.push_front(record);
+        }
+        self.evict_overflow();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &OpRecord> {
+        self.records.iter()
+    }
+
+    /// Records evicted to stay within the limit.
+    pub fn records_dropped(&self) -> u64 {
+        self.records_dropped
+    }
+
+    fn evict_overflow(&mut self) {
+        let Some(max) = self.max_records else { return };
+        while self.records.len() > max {
+            self.records.pop_front();
+            self.records_dropped += 1;
+        }
+    }
+
+    /// Check every GET against the unified write index: successful GETs for the data they
+    /// returned, NotFound GETs for data that should have been visible.
+    /// An expired value returned by [`OpKind::GetAllowingExpired`] is checked like any other read:
+    /// consistent if it is the recorded PUT's version and bytes, even though its TTL elapsed. A
+    /// strict [`OpKind::Get`] returning it is [`ViolationKind::ExpiredValueReturned`].
     /// Returns one [`Violation`] per inconsistent GET, with [`ViolationKind::StaleDataReturned`]
     /// reported separately (informational only — not counted as an error by default).
+    ///
+    /// Once records were dropped only the retained window is checked: a GET of an unrecorded
+    /// version is not flagged, as an evicted write may have produced it, and violations
+    /// involving evicted records are missed.
     pub fn check_correctness(&self) -> Vec<Violation> {
-        let write_index = build_write_index(&self.0);
+        let write_index = build_write_index(&self.records);
+        let failed_deletes = build_failed_delete_index(&self.records);
+        let truncated = self.records_dropped > 0;
+
+        self.records
+            .iter()
+            .filter_map(|r| classify_record(r, &write_index, &failed_deletes, truncated))
+            .collect()
+    }
+
+    /// Check reads against the DELETEs before them: a GET must not return data for a key whose
+    /// latest write ACKed before the GET started was a DELETE. Returns one
+    /// [`ViolationKind::StaleReadAfterConfirmedDelete`] per such GET. A GET whose version was
+    /// never recorded is left to [`History::check_correctness`].
+    pub fn check_delete_correctness(&self) -> Vec<Violation> {
+        let write_index = build_write_index(&self.records);
+        self.records.iter().filter_map(|r| classify_stale_after_delete(r, &write_index)).collect()
+    }
+
+    /// [`History::check_correctness`] and [`History::check_delete_correctness`] together, with
+    /// at most one violation per GET. A stale read after a confirmed DELETE is reported as
+    /// [`ViolationKind::StaleReadAfterConfirmedDelete`] rather than `StaleDataReturned`.
+    pub fn check_all(&self) -> Vec<Violation> {
+        let write_index = build_write_index(&self.records);
+        let failed_deletes = build_failed_delete_index(&self.records);
+        let truncated = self.records_dropped > 0;
 
-        self.0
+        self.records
+            .iter()
+            .filter_map(|r| match classify_record(r, &write_index, &failed_deletes, truncated) {
+                Some(v) if !matches!(v.kind, ViolationKind::StaleDataReturned { .. }) => Some(v),
+                stale => classify_stale_after_delete(r, &write_index).or(stale),
+            })
+            .collect()
+    }
+
+    /// Check that no GET observed a state older than one it causally follows: a version of the
+    /// key that was written (PUT or DELETE ACKed) or read (GET completed) before the GET started.
+    /// This is weaker than requiring one global order of all operations. Unlike
+    /// [`History::check_correctness`], though, it also catches a read going back past a version
+    /// some other read already returned, and it counts every stale read as a violation.
+    ///
+    /// Returns one [`ViolationKind::CausalOrderViolated`] per such GET. NotFound results are
+    /// left to `check_correctness`, which accounts for TTLs and DELETEs of unknown effect. As
+    /// there, only the retained window of a truncated history is checked.
+    pub fn check_causal_consistency(&self) -> Vec<Violation> {
+        let known = build_known_version_index(&self.records);
+
+        self.records
             .iter()
             .filter_map(|r| {
-                if let OpOutcome::GetOk { version, value } = &r.outcome {
-                    classify_get(
-                        &r.key, *version, value,
-                        r.client_start_ts, r.client_ack_ts,
-                        &write_index,
-                    )
-                    .map(|kind| Violation { key: r.key.clone(), version: *version, kind })
-                } else {
-                    None
-                }
+                let OpOutcome::GetOk { version, .. } = &r.outcome else { return None };
+                let observations = &known[r.key.as_str()];
+                let before = observations.partition_point(|(ack, _)| *ack < r.client_start_ts);
+                let known_version = observations[..before].last()?.1;
+                (*version < known_version).then(|| Violation {
+                    key: r.key.clone(),
+                    version: *version,
+                    kind: ViolationKind::CausalOrderViolated { known_version },
+                })
             })
             .collect()
     }
@@ -90,19 +232,44 @@ impl History {
 
 // --- Index builder ---
 
+/// key → `(ack, version)` for every operation that wrote or read a version of it, sorted by ACK
+/// time, where `version` is the highest one known by that ACK.
+fn build_known_version_index(records: &VecDeque<OpRecord>) -> HashMap<&str, Vec<(Instant, Version)>> {
+    let mut index: HashMap<&str, Vec<(Instant, Version)>> = HashMap::new();
+    for r in records {
+        let version = match &r.outcome {
+            OpOutcome::PutOk { version, .. } | OpOutcome::GetOk { version, .. } | OpOutcome::DeleteOk { version } => {
+                *version
+            }
+            OpOutcome::NotFound | OpOutcome::Error => continue,
+        };
+        index.entry(r.key.as_str()).or_default().push((r.client_ack_ts, version));
+    }
+    for observations in index.values_mut() {
+        observations.sort_by_key(|(ack, _)| *ack);
+        let mut highest = Version::default();
+        for (_, version) in observations.iter_mut() {
+            highest = highest.max(*version);
+            *version = highest;
+        }
+    }
+    index
+}
+
 /// (key, version) → the write (PUT or DELETE/tombstone) that produced that version.
 ///
 /// With a global monotonic version counter, each `(key, version)` pair is unique across
 /// all time, so each entry maps to exactly one `WriteEntry`.
-fn build_write_index(records: &[OpRecord]) -> HashMap<(String, u64), WriteEntry> {
-    let mut index: HashMap<(String, u64), WriteEntry> = HashMap::new();
+fn build_write_index(records: &VecDeque<OpRecord>) -> HashMap<(String, Version), WriteEntry> {
+    let mut index: HashMap<(String, Version), WriteEntry> = HashMap::new();
     for r in records {
         match &r.outcome {
-            OpOutcome::PutOk { version, value } => {
+            OpOutcome::PutOk { version, value, expires_at } => {
                 index.insert(
                     (r.key.clone(), *version),
                     WriteEntry {
                         write_value: WriteValue::Data(value.clone()),
+                        expires_at: *expires_at,
                         write_start_ts: r.client_start_ts,
                         write_ack_ts: r.client_ack_ts,
                     },
@@ -113,6 +280,7 @@ fn build_write_index(records: &[OpRecord]) -> HashMap<(String, u64), WriteEntry>
                     (r.key.clone(), *version),
                     WriteEntry {
                         write_value: WriteValue::Tombstone,
+                        expires_at: None,
                         write_start_ts: r.client_start_ts,
                         write_ack_ts: r.client_ack_ts,
                     },
@@ -124,20 +292,107 @@ fn build_write_index(records: &[OpRecord]) -> HashMap<(String, u64), WriteEntry>
     index
 }
 
+/// key → start times of DELETEs that returned `Error`. The server may or may not have applied
+/// them, so they make any later NotFound ambiguous.
+fn build_failed_delete_index(records: &VecDeque<OpRecord>) -> HashMap<String, Vec<Instant>> {
+    let mut index: HashMap<String, Vec<Instant>> = HashMap::new();
+    for r in records {
+        if matches!((&r.kind, &r.outcome), (OpKind::Delete, OpOutcome::Error)) {
+            index.entry(r.key.clone()).or_default().push(r.client_start_ts);
+        }
+    }
+    index
+}
+
 // --- Per-GET classification ---
 
+/// The [`History::check_correctness`] violation for one record, if any.
+fn classify_record(
+    r: &OpRecord,
+    write_index: &HashMap<(String, Version), WriteEntry>,
+    failed_deletes: &HashMap<String, Vec<Instant>>,
+    truncated: bool,
+) -> Option<Violation> {
+    match (&r.kind, &r.outcome) {
+        (_, OpOutcome::GetOk { version, value, .. }) => {
+            let kind = classify_get(
+                &r.key, *version, value,
+                r.client_start_ts, r.client_ack_ts,
+                write_index, truncated,
+            );
+            // A stale read is only informational, so it must not hide an expired one.
+            let kind = match kind {
+                None | Some(ViolationKind::StaleDataReturned { .. }) => classify_expired_get(r, write_index).or(kind),
+                Some(_) => kind,
+            };
+            kind.map(|kind| Violation { key: r.key.clone(), version: *version, kind })
+        }
+        (OpKind::Get | OpKind::GetAllowingExpired, OpOutcome::NotFound) => classify_not_found(
+            &r.key,
+            r.client_start_ts, r.client_ack_ts,
+            write_index, failed_deletes,
+        )
+        .map(|expected_version| Violation {
+            key: r.key.clone(),
+            version: expected_version,
+            kind: ViolationKind::UnexpectedNotFound { expected_version },
+        }),
+        _ => None,
+    }
+}
+
+/// The [`History::check_delete_correctness`] violation for one record, if any: a `GetOk` of a
+/// recorded PUT's version, older than a tombstone that is the latest write ACKed before the GET.
+fn classify_stale_after_delete(
+    r: &OpRecord,
+    write_index: &HashMap<(String, Version), WriteEntry>,
+) -> Option<Violation> {
+    let OpOutcome::GetOk { version, .. } = &r.outcome else { return None };
+    let returned = write_index.get(&(r.key.clone(), *version))?;
+    if !matches!(returned.write_value, WriteValue::Data(_)) {
+        return None;
+    }
+    let (&latest_version, latest) = write_index
+        .iter()
+        .filter(|((k, _), entry)| *k == r.key && entry.write_ack_ts < r.client_start_ts)
+        .map(|((_, v), entry)| (v, entry))
+        .max_by_key(|(v, _)| **v)?;
+    if !matches!(latest.write_value, WriteValue::Tombstone) || latest_version <= *version {
+        return None;
+    }
+    Some(Violation {
+        key: r.key.clone(),
+        version: *version,
+        kind: ViolationKind::StaleReadAfterConfirmedDelete { tombstone_version: latest_version },
+    })
+}
+
+/// The [`ViolationKind::ExpiredValueReturned`] for a strict GET of a recorded PUT's version, if
+/// that PUT's TTL had elapsed before the GET started.
+fn classify_expired_get(
+    r: &OpRecord,
+    write_index: &HashMap<(String, Version), WriteEntry>,
+) -> Option<ViolationKind> {
+    let (OpKind::Get, OpOutcome::GetOk { version, .. }) = (&r.kind, &r.outcome) else { return None };
+    let expires_at = write_index.get(&(r.key.clone(), *version))?.expires_at?;
+    (expires_at + TTL_CLOCK_TOLERANCE <= r.client_start_ts)
+        .then_some(ViolationKind::ExpiredValueReturned { expires_at, get_start_ts: r.client_start_ts })
+}
+
 /// Returns the violation kind for a single GET result, or `None` if it is consistent.
 fn classify_get(
     key: &str,
-    version: u64,
+    version: Version,
     value: &[u8],
     get_start: Instant,
     get_ack: Instant,
-    write_index: &HashMap<(String, u64), WriteEntry>,
+    write_index: &HashMap<(String, Version), WriteEntry>,
+    truncated: bool,
 ) -> Option<ViolationKind> {
-    // 1. No write (PUT or DELETE) ever produced this (key, version).
+    // 1. No write (PUT or DELETE) ever produced this (key, version) — unless the write was
+    //    evicted from a truncated history.
     let Some(entry) = write_index.get(&(key.to_owned(), version)) else {
-        return Some(ViolationKind::VersionNotFound { actual: value.to_vec() });
+        return (!truncated).then(|| ViolationKind::VersionNotFound { actual: value.to_vec() });
     };
 
     // 2. Write started after GET was fully acked — server could not have had the data yet.
@@ -155,7 +410,7 @@ fn classify_get(
         match &entry.write_value {
             WriteValue::Data(expected) if expected != value => {
                 return Some(ViolationKind::ValueMismatch {
-                    expected: expected.clone(),
+                    expected: expected.to_vec(),
                     actual: value.to_vec(),
                 });
             }
@@ -177,17 +432,59 @@ fn classify_get(
     None
 }
 
+/// Returns the version the GET should have seen if a NotFound result is a violation, or `None`
+/// if NotFound is consistent with the history.
+///
+/// NotFound is only flagged when the highest-versioned write ACKed before the GET started was
+/// a PUT whose TTL, if any, had not elapsed by the time the GET was ACKed (a value past its TTL
+/// may be hidden or collected), no newer DELETE started before the GET was ACKed (overlap is
+/// ambiguous, as for `GetOk`), and no failed DELETE of unknown effect started before the GET was
+/// ACKed.
+fn classify_not_found(
+    key: &str,
+    get_start: Instant,
+    get_ack: Instant,
+    write_index: &HashMap<(String, Version), WriteEntry>,
+    failed_deletes: &HashMap<String, Vec<Instant>>,
+) -> Option<Version> {
+    let (&latest_version, latest) = write_index
+        .iter()
+        .filter(|((k, _), entry)| k == key && entry.write_ack_ts <= get_start)
+        .map(|((_, v), entry)| (v, entry))
+        .max_by_key(|(v, _)| **v)?;
+
+    let may_have_expired = latest.expires_at.is_some_and(|at| at <= get_ack + TTL_CLOCK_TOLERANCE);
+    if matches!(latest.write_value, WriteValue::Tombstone) || may_have_expired {
+        return None;
+    }
+
+    let overlapping_delete = write_index.iter().any(|((k, v), entry)| {
+        k == key
+            && *v > latest_version
+            && matches!(entry.write_value, WriteValue::Tombstone)
+            && entry.write_start_ts < get_ack
+    });
+    let uncertain_delete = failed_deletes
+        .get(key)
+        .is_some_and(|starts| starts.iter().any(|&start| start < get_ack));
+
+    if overlapping_delete || uncertain_delete {
+        return None;
+    }
+    Some(latest_version)
+}
+
 // --- Helper ---
 
 /// Returns the highest version for `key` greater than `returned_version` for which the
 /// write (PUT or DELETE/tombstone) was ACKed before `get_start_ts`, or `None` if
 /// `returned_version` is already the latest known.
 fn newer_write_acked(
-    write_index: &HashMap<(String, u64), WriteEntry>,
+    write_index: &HashMap<(String, Version), WriteEntry>,
     key: &str,
-    returned_version: u64,
+    returned_version: Version,
     get_start_ts: Instant,
-) -> Option<u64> {
+) -> Option<Version> {
     write_index
         .iter()
         .filter(|((k, v), entry)| {
@@ -1,5 +1,11 @@
+pub mod adaptive;
+pub mod fault;
+pub mod histogram;
 pub mod history;
 pub mod metrics;
+pub mod multi_cas;
+pub mod report;
+pub mod schedule;
 pub mod server;
 pub mod worker;
 pub mod workload;
@@ -1,155 +1,558 @@
 use clap::Parser;
-use std::io::Write;
+use std::fmt::Display;
+use std::path::PathBuf;
 use std::process;
-use std::time::Duration;
-use transdb_stress_tests::history::ViolationKind;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use transdb_client::{Client, ClientConfig};
+use transdb_common::StoreStats;
+use transdb_stress_tests::history::{History, OpOutcome, ViolationKind};
+use transdb_stress_tests::metrics::{gc_effectiveness, LiveCounters, Metrics};
+use transdb_stress_tests::multi_cas;
+use transdb_stress_tests::report::{
+    rfc3339_utc, write_atomically, MetricsSummary, Report, RunSummary, StressTestResult, Threshold, Verdict,
+    ViolationSummary,
+};
 use transdb_stress_tests::server::Cluster;
-use transdb_stress_tests::workload::WorkloadProfile;
-use transdb_stress_tests::worker;
+use transdb_stress_tests::workload::{ValueSizeDistribution, WarmupMode, WorkloadProfile};
+use transdb_stress_tests::worker::{self, RunConfig};
+use uuid::Uuid;
 
-#[derive(Parser)]
+#[derive(Parser, serde::Serialize)]
 #[command(name = "transdb-stress", about = "TransDB stress test harness")]
 struct Args {
     /// How long to run (seconds)
     #[arg(long, default_value_t = 5)]
     duration: u64,
 
-    /// Workload profile: read-heavy | balanced | write-heavy | put-only
+    /// Workload: read-heavy | balanced | write-heavy | put-only | delete-only | ttl-reads | multi-cas
     #[arg(long, default_value = "balanced")]
     workload: String,
 
+    /// Warm-up before the measured phase: none | populate (PUT one value per key)
+    #[arg(long, default_value = "none")]
+    warmup_mode: String,
+
     /// Number of distinct keys in the key space
     #[arg(long, default_value_t = 1000)]
     key_space: usize,
 
+    /// How written value sizes are spread: uniform | lognormal (mostly small, a few very large) | fixed
+    #[arg(long, default_value = "uniform")]
+    value_size_dist: String,
+
+    /// Smallest value in bytes; lognormal centres its sizes on the geometric midpoint of this and the max
+    #[arg(long, default_value_t = 8)]
+    value_size_min: usize,
+
+    /// Largest value in bytes, and the size of every value with fixed; lognormal may exceed it
+    #[arg(long, default_value_t = 1024)]
+    value_size_max: usize,
+
+    /// With lognormal: spread of sizes around the median; the larger, the longer the tail of big values
+    #[arg(long, default_value_t = 1.0)]
+    value_lognormal_sigma: f64,
+
+    /// Fraction of PUTs (0.0–1.0) sent with a TTL, expiring anywhere from 5 s before they are sent
+    /// to 30 s after. The checker then expects those keys to vanish, and strict GETs not to return them
+    #[arg(long, default_value_t = 0.0)]
+    ttl_fraction: f64,
+
+    /// Halve the key space when the GET hit rate over 10 s drops below 40%, double it above 95%
+    #[arg(long)]
+    adaptive_key_space: bool,
+
+    /// Print a progress line (interval throughput, cumulative error rate) every N seconds
+    #[arg(long, default_value_t = 5)]
+    progress_interval_secs: u64,
+
     /// Fail if the 5xx error rate exceeds this fraction
     #[arg(long, default_value_t = 0.01)]
     max_error_rate: f64,
 
+    /// Launch this many operations per second on a fixed schedule, whether or not earlier ones have
+    /// finished, and also report latency measured from when each was due (default: back to back)
+    #[arg(long)]
+    rate: Option<f64>,
+
+    /// With --rate: operations in flight at once; one that falls due while all are busy waits for a slot
+    #[arg(long, default_value_t = 16, requires = "rate")]
+    concurrency: usize,
+
+    /// Seed for every random choice of the run: operations, keys, values and injected faults. With
+    /// the same seed and flags, a run issues the same operations (default: random, printed in the report)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Fraction of operations (0.0–1.0) to fail locally as network errors before they reach the server
+    #[arg(long, default_value_t = 0.0)]
+    fault_rate: f64,
+
     /// Fail if correctness violations exceed this count
     #[arg(long, default_value_t = 0)]
     max_violations: u64,
+
+    /// Query the primary's GET /stats after the run and report expired-but-not-GCed entries
+    #[arg(long)]
+    post_run_gc_check: bool,
+
+    /// Fail if GC effectiveness (0.0–1.0) falls below this; any value above 0 implies --post-run-gc-check
+    #[arg(long, default_value_t = 0.0)]
+    min_gc_effectiveness: f64,
+
+    /// Also check causal consistency: no GET may return a version older than one written or read
+    /// before it started. Violations are counted separately, against --max-violations
+    #[arg(long)]
+    causal_check: bool,
+
+    /// Fail if the observed share of GETs, PUTs or DELETEs differs from the workload's by more than
+    /// this many percentage points. Short runs deviate by chance, so leave room for that
+    #[arg(long)]
+    max_mix_deviation: Option<f64>,
+
+    /// Keep only the most recent N operation records for the correctness check (default: unlimited)
+    #[arg(long)]
+    max_history_records: Option<usize>,
+
+    /// Client: idle connections kept per node (default: unbounded)
+    #[arg(long)]
+    pool_max_idle_per_host: Option<usize>,
+
+    /// Client: close pooled connections idle for this many seconds (default: 90)
+    #[arg(long)]
+    pool_idle_timeout_secs: Option<u64>,
+
+    /// Client: TCP keepalive interval in seconds (default: off)
+    #[arg(long)]
+    tcp_keepalive_secs: Option<u64>,
+
+    /// Client: speak HTTP/2 with prior knowledge, multiplexing requests over one connection
+    #[arg(long)]
+    http2: bool,
+
+    /// Report format on stdout: text | json. With json, progress and status lines go to stderr
+    #[arg(long, default_value = "text")]
+    report_format: String,
+
+    /// Also write the report as JSON to this file
+    #[arg(long)]
+    report_json: Option<PathBuf>,
+
+    /// Archive the run to this JSON file, whatever the report format: its run ID, start time, every
+    /// flag, metrics and violations. Written even when the run fails a threshold
+    #[arg(long)]
+    output_file: Option<PathBuf>,
+
+    /// Run ID recorded in --output-file, to correlate the run with an external system (default: random)
+    #[arg(long)]
+    #[serde(skip)]
+    run_id: Option<Uuid>,
+
+    /// Primary: shed requests beyond this many in flight with 503 OVERLOADED (default: unlimited)
+    #[arg(long)]
+    server_max_in_flight: Option<usize>,
+}
+
+/// Client configuration for the cluster's topology file with the tuning flags applied.
+fn client_config(args: &Args, cluster: &Cluster) -> ClientConfig {
+    let mut config = ClientConfig::from_topology_file(cluster.topology_path()).unwrap_or_else(|e| {
+        eprintln!("Failed to load the cluster topology: {e}");
+        process::exit(3);
+    });
+    if let Some(max) = args.pool_max_idle_per_host {
+        config = config.with_pool_max_idle_per_host(max);
+    }
+    if let Some(secs) = args.pool_idle_timeout_secs {
+        config = config.with_pool_idle_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = args.tcp_keepalive_secs {
+        config = config.with_tcp_keepalive(Duration::from_secs(secs));
+    }
+    if args.http2 {
+        config = config.with_http2_prior_knowledge();
+    }
+    config
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
 
-    let profile = WorkloadProfile::from_name(&args.workload).unwrap_or_else(|| {
-        eprintln!(
-            "Unknown workload {:?}. Valid values: read-heavy, balanced, write-heavy, put-only",
-            args.workload
-        );
+    // `multi-cas` is not a profile of the single-key worker but a workload of its own.
+    let profile = match args.workload.as_str() {
+        multi_cas::WORKLOAD_NAME => None,
+        name => Some(WorkloadProfile::from_name(name).unwrap_or_else(|| {
+            eprintln!(
+                "Unknown workload {:?}. Valid values: read-heavy, balanced, write-heavy, put-only, delete-only, \
+                 ttl-reads, multi-cas",
+                name
+            );
+            process::exit(3);
+        })),
+    };
+
+    let warmup = WarmupMode::from_name(&args.warmup_mode).unwrap_or_else(|| {
+        eprintln!("Unknown warm-up mode {:?}. Valid values: none, populate", args.warmup_mode);
         process::exit(3);
     });
 
-    let cluster = Cluster::build_and_spawn().unwrap_or_else(|e| {
+    let value_size = ValueSizeDistribution::from_flags(
+        &args.value_size_dist,
+        args.value_size_min,
+        args.value_size_max,
+        args.value_lognormal_sigma,
+    )
+    .unwrap_or_else(|reason| {
+        eprintln!("{reason}");
+        process::exit(3);
+    });
+
+    if !(0.0..=1.0).contains(&args.ttl_fraction) {
+        eprintln!("--ttl-fraction must be between 0.0 and 1.0, got {}", args.ttl_fraction);
+        process::exit(3);
+    }
+    if !(0.0..=1.0).contains(&args.fault_rate) {
+        eprintln!("--fault-rate must be between 0.0 and 1.0, got {}", args.fault_rate);
+        process::exit(3);
+    }
+
+    if args.rate.is_some_and(|rate| !(rate > 0.0 && rate.is_finite())) || args.concurrency == 0 {
+        eprintln!("--rate must be a positive number of operations per second, and --concurrency at least 1");
+        process::exit(3);
+    }
+    if args.rate.is_some() && profile.is_none() {
+        eprintln!("--rate does not apply to the {} workload, which runs its own workers", multi_cas::WORKLOAD_NAME);
+        process::exit(3);
+    }
+
+    if !(0.0..=1.0).contains(&args.min_gc_effectiveness) {
+        eprintln!("--min-gc-effectiveness must be between 0.0 and 1.0, got {}", args.min_gc_effectiveness);
+        process::exit(3);
+    }
+
+    // With the JSON report on stdout, everything else printed there would break parsing it.
+    let json_stdout = match args.report_format.as_str() {
+        "text" => false,
+        "json" => true,
+        other => {
+            eprintln!("Unknown report format {:?}. Valid values: text, json", other);
+            process::exit(3);
+        }
+    };
+
+    let cluster = Cluster::build_and_spawn(args.server_max_in_flight).unwrap_or_else(|e| {
         eprintln!("Failed to start cluster: {e}");
         process::exit(3);
     });
 
-    println!(
-        "Cluster ready:  primary {}  |  replica {}",
-        cluster.primary.addr,
-        cluster.replica.addr,
+    status(
+        json_stdout,
+        format_args!("Cluster ready:  primary {}  |  replica {}", cluster.primary.addr, cluster.replica.addr),
     );
 
-    let topology = cluster.topology.clone();
+    let config = client_config(&args, &cluster);
     let duration = Duration::from_secs(args.duration);
+    let seed = args.seed.unwrap_or_else(rand::random);
+
+    let warmup_records = match warmup {
+        WarmupMode::None => Vec::new(),
+        WarmupMode::Populate => {
+            status(json_stdout, format_args!("Populating {} keys", args.key_space));
+            worker::populate(&config, args.key_space, &value_size, seed).await
+        }
+    };
+    let keys_populated = warmup_records
+        .iter()
+        .filter(|r| matches!(r.outcome, OpOutcome::PutOk { .. }))
+        .count() as u64;
+
+    let run_id = args.run_id.unwrap_or_else(Uuid::new_v4);
+    let started_at = SystemTime::now();
+    status(
+        json_stdout,
+        format_args!("Running {}s {} workload (seed {}, run {})", args.duration, args.workload, seed, run_id),
+    );
 
-    print!("Running {}s {} workload ", args.duration, profile.as_name());
-    std::io::stdout().flush().ok();
+    let live = Arc::new(LiveCounters::default());
+    let progress_handle = tokio::spawn(print_progress(
+        Arc::clone(&live),
+        Duration::from_secs(args.progress_interval_secs.max(1)),
+        json_stdout,
+    ));
 
-    let dot_handle = tokio::spawn(async {
-        let mut interval = tokio::time::interval(Duration::from_secs(1));
-        interval.tick().await; // consume the immediate first tick
-        loop {
-            interval.tick().await;
-            print!(".");
-            std::io::stdout().flush().ok();
+    let (mut metrics, mut history, write_skews) = match profile {
+        Some(profile) => {
+            let run_config = RunConfig {
+                profile,
+                key_space: args.key_space,
+                duration,
+                fault_rate: args.fault_rate,
+                max_history_records: args.max_history_records,
+                adaptive_key_space: args.adaptive_key_space,
+                rate: args.rate,
+                concurrency: args.concurrency,
+                seed,
+                value_size,
+                ttl_fraction: args.ttl_fraction,
+            };
+            let (metrics, history) = worker::run(&config, &run_config, &live).await;
+            (metrics, history, Vec::new())
         }
-    });
+        None => {
+            let (metrics, commits) = multi_cas::run(&config, duration, value_size, seed, &live).await;
+            (metrics, History::new(args.max_history_records), multi_cas::find_write_skew(&commits))
+        }
+    };
+    metrics.keys_populated = keys_populated;
+    // Warm-up writes precede every measured op; the checker needs them to validate later reads.
+    history.prepend(warmup_records);
 
-    let (metrics, history) = worker::run(topology, profile, args.key_space, duration).await;
+    progress_handle.abort();
+    status(json_stdout, "");
 
-    dot_handle.abort();
-    println!();
+    if profile == Some(WorkloadProfile::DeleteOnly) {
+        let reads = worker::verify_deleted(&config, &history).await;
+        for record in reads {
+            history.push(record);
+        }
+    }
+    metrics.records_dropped = history.records_dropped();
+
+    let gc_stats = if args.post_run_gc_check || args.min_gc_effectiveness > 0.0 {
+        let client = Client::new(config.clone());
+        Some(client.stats().await.unwrap_or_else(|e| {
+            eprintln!("Failed to query /stats for the GC check: {e}");
+            process::exit(3);
+        }))
+    } else {
+        None
+    };
 
     drop(cluster);
 
-    let violations = history.check_correctness();
-    let hard_violation_count: u64 = violations
-        .iter()
-        .filter(|v| !matches!(v.kind, ViolationKind::StaleDataReturned { .. }))
-        .count() as u64;
+    let violations = history.check_all();
+    // Stale reads are normally tolerated, but a delete-only run has no PUTs that could
+    // legitimately resurrect a key, so data returned after a confirmed DELETE is a hard failure.
+    let is_hard = |kind: &ViolationKind| {
+        profile == Some(WorkloadProfile::DeleteOnly)
+            || !matches!(
+                kind,
+                ViolationKind::StaleDataReturned { .. } | ViolationKind::StaleReadAfterConfirmedDelete { .. }
+            )
+    };
+    // Every write skew is a hard violation: the server committed two requests it must not have.
+    let hard_violation_count =
+        violations.iter().filter(|v| is_hard(&v.kind)).count() as u64 + write_skews.len() as u64;
+    let causal = if args.causal_check { history.check_causal_consistency() } else { Vec::new() };
+    let causal_violations = causal.len() as u64;
 
-    print_report(&args, &metrics, hard_violation_count, profile);
+    let error_rate_exceeded = metrics.error_rate() > args.max_error_rate;
+    let violations_exceeded =
+        hard_violation_count > args.max_violations || causal_violations > args.max_violations;
+    let gc_below_threshold = gc_stats.as_ref().is_some_and(|s| gc_effectiveness(s) < args.min_gc_effectiveness);
+    let mix_exceeded = mix_deviation_exceeded(&args, profile, &metrics);
+    let failed = [
+        (error_rate_exceeded, Threshold::ErrorRate),
+        (violations_exceeded, Threshold::Violations),
+        (gc_below_threshold, Threshold::GcEffectiveness),
+        (mix_exceeded, Threshold::OpMix),
+    ];
+    let verdict = Verdict::new(failed.into_iter().filter(|(exceeded, _)| *exceeded).map(|(_, t)| t).collect());
 
-    for v in &violations {
-        if matches!(v.kind, ViolationKind::StaleDataReturned { .. }) {
-            continue;
+    let reported: Vec<ViolationSummary> = violations
+        .iter()
+        .filter(|v| is_hard(&v.kind))
+        .chain(&causal)
+        .map(ViolationSummary::new)
+        .chain(write_skews.iter().map(ViolationSummary::write_skew))
+        .collect();
+    let report = Report {
+        config: RunSummary {
+            workload: args.workload.clone(),
+            duration_secs: args.duration,
+            key_space: args.key_space,
+            final_key_space: metrics.final_key_space,
+            seed,
+            value_size_distribution: value_size.as_name().to_string(),
+            rate: args.rate,
+            concurrency: args.rate.map(|_| args.concurrency),
+        },
+        metrics: MetricsSummary::new(&metrics),
+        violation_count: hard_violation_count,
+        causal_violation_count: causal_violations,
+        violations: reported,
+        verdict,
+    };
+    let json = serde_json::to_string_pretty(&report).expect("the report serializes");
+    if let Some(path) = &args.report_json {
+        if let Err(e) = write_atomically(path, &json) {
+            eprintln!("Failed to write the JSON report to {}: {e}", path.display());
+            process::exit(3);
         }
-        let detail = match &v.kind {
-            ViolationKind::VersionNotFound { actual } => {
-                format!("VersionNotFound: got {} bytes for unrecorded version", actual.len())
-            }
-            ViolationKind::ReadBeforeWriteStart { .. } => {
-                "ReadBeforeWriteStart: GET completed before PUT even started".to_string()
-            }
-            ViolationKind::ValueMismatch { expected, actual } => {
-                format!(
-                    "ValueMismatch: expected {} bytes, got {} bytes",
-                    expected.len(),
-                    actual.len()
-                )
-            }
-            ViolationKind::StaleDataReturned { .. } => unreachable!(),
+    }
+    if let Some(path) = &args.output_file {
+        let result = StressTestResult {
+            run_id: run_id.to_string(),
+            timestamp: rfc3339_utc(started_at),
+            config: serde_json::to_value(&args).expect("the flags serialize"),
+            metrics: report.metrics.clone(),
+            violations: report.violations.clone(),
+            verdict: report.verdict.clone(),
         };
-        eprintln!("VIOLATION key={} version={} {}", v.key, v.version, detail);
+        let result = serde_json::to_string_pretty(&result).expect("the result serializes");
+        if let Err(e) = write_atomically(path, &result) {
+            eprintln!("Failed to write the results file {}: {e}", path.display());
+            process::exit(3);
+        }
+    }
+    if json_stdout {
+        println!("{json}");
+    } else {
+        print_report(&args, seed, profile, &metrics, hard_violation_count, causal_violations, gc_stats.as_ref());
     }
 
-    let error_rate_exceeded = metrics.requests_total > 0
-        && metrics.error_rate() > args.max_error_rate;
-    let violations_exceeded = hard_violation_count > args.max_violations;
+    for v in &report.violations {
+        eprintln!("VIOLATION key={} version={} {}: {}", v.key, v.version, v.kind, v.detail);
+    }
+
+    process::exit(report.verdict.exit_code);
+}
 
-    let exit_code = if error_rate_exceeded {
-        1
-    } else if violations_exceeded {
-        2
+/// Print a line that is not part of the report: on stdout, or on stderr when the report goes
+/// to stdout as JSON.
+fn status(to_stderr: bool, line: impl Display) {
+    if to_stderr {
+        eprintln!("{line}");
     } else {
-        0
-    };
+        println!("{line}");
+    }
+}
+
+/// Every `every`, print the throughput since the previous line and the error rate so far.
+async fn print_progress(live: Arc<LiveCounters>, every: Duration, to_stderr: bool) {
+    let start = Instant::now();
+    let mut interval = tokio::time::interval(every);
+    interval.tick().await; // consume the immediate first tick
+    let (mut previous, mut previous_at) = (live.snapshot(), Instant::now());
+    loop {
+        interval.tick().await;
+        let (current, now) = (live.snapshot(), Instant::now());
+        let line = format!(
+            "[{:>4}s] {:>9.1} rps   errors {:.3}%   requests {}",
+            start.elapsed().as_secs(),
+            current.throughput_since(&previous, now - previous_at),
+            current.error_rate() * 100.0,
+            format_thousands(current.requests),
+        );
+        status(to_stderr, line);
+        (previous, previous_at) = (current, now);
+    }
+}
 
-    process::exit(exit_code);
+/// Whether `--max-mix-deviation` is set and the run's operation mix strayed further than that
+/// from `profile`'s. Never for `multi-cas`, which has no profile.
+fn mix_deviation_exceeded(args: &Args, profile: Option<WorkloadProfile>, metrics: &Metrics) -> bool {
+    match (args.max_mix_deviation, profile) {
+        (Some(max), Some(profile)) => metrics.op_counts.mix().max_deviation(&profile.expected_mix()) > max,
+        _ => false,
+    }
 }
 
-fn print_report(args: &Args, metrics: &transdb_stress_tests::metrics::Metrics, violation_count: u64, profile: WorkloadProfile) {
+fn print_report(
+    args: &Args,
+    seed: u64,
+    profile: Option<WorkloadProfile>,
+    metrics: &Metrics,
+    violation_count: u64,
+    causal_violations: u64,
+    gc_stats: Option<&StoreStats>,
+) {
     let pass_fail = |exceeded: bool| if exceeded { "✗" } else { "✓" };
 
-    let error_rate_exceeded = metrics.requests_total > 0
-        && metrics.error_rate() > args.max_error_rate;
+    let error_rate_exceeded = metrics.error_rate() > args.max_error_rate;
     let violations_exceeded = violation_count > args.max_violations;
-    let overall_pass = !error_rate_exceeded && !violations_exceeded;
+    let causal_exceeded = causal_violations > args.max_violations;
+    let gc_below_threshold = gc_stats.is_some_and(|s| gc_effectiveness(s) < args.min_gc_effectiveness);
+    let mix_exceeded = mix_deviation_exceeded(args, profile, metrics);
+    let overall_pass =
+        !error_rate_exceeded && !violations_exceeded && !causal_exceeded && !gc_below_threshold && !mix_exceeded;
 
     println!("TransDB Stress Test Results");
     println!("===========================");
     println!("Duration:              {:.1} s", args.duration as f64);
-    println!("Workload:              {}", profile.as_name());
+    println!("Workload:              {}", args.workload);
+    println!("Seed:                  {}", seed);
     println!("Key space:             {}", args.key_space);
+    println!("Value sizes:           {}", args.value_size_dist);
+    if args.ttl_fraction > 0.0 {
+        println!("PUTs with TTL:         {:.1}%", args.ttl_fraction * 100.0);
+    }
+    if args.adaptive_key_space {
+        println!("Final key space:       {}", metrics.final_key_space);
+    }
     println!("Nodes:                 primary + replica");
+    if let Some(max) = args.max_history_records.filter(|_| metrics.records_dropped > 0) {
+        println!("History:               truncated, retaining last {max} records");
+    }
     println!();
     println!("Requests:              {}", format_thousands(metrics.requests_total));
+    if let Some(rate) = args.rate {
+        println!("Target rate:           {:.1} rps  (concurrency {})", rate, args.concurrency);
+    }
     println!("Throughput:            {:.1} rps", metrics.throughput_rps());
     println!("P50 latency:           {:.1} ms", ns_to_ms(metrics.p50_ns()));
+    println!("P90 latency:           {:.1} ms", ns_to_ms(metrics.latency_ns.percentile(0.90)));
+    println!("P95 latency:           {:.1} ms", ns_to_ms(metrics.latency_ns.percentile(0.95)));
     println!("P99 latency:           {:.1} ms", ns_to_ms(metrics.p99_ns()));
+    println!("P99.9 latency:         {:.1} ms", ns_to_ms(metrics.latency_ns.percentile(0.999)));
+    println!("Max latency:           {:.1} ms", ns_to_ms(metrics.latency_ns.max()));
+    println!("Mean latency:          {:.1} ms", ns_to_ms(metrics.latency_ns.mean() as u64));
+    if metrics.latency_by_kind.iter().next().is_some() {
+        println!("Latency by kind:       P50 / P99");
+        for (kind, latency) in metrics.latency_by_kind.iter() {
+            let (p50, p99) = (latency.percentile(0.50), latency.percentile(0.99));
+            println!("  {:<21}{:.1} / {:.1} ms", kind.as_name(), ns_to_ms(p50), ns_to_ms(p99));
+        }
+    }
+    if !metrics.response_latency_ns.is_empty() {
+        println!("Response P50:          {:.1} ms", ns_to_ms(metrics.response_p50_ns()));
+        println!("Response P99:          {:.1} ms", ns_to_ms(metrics.response_p99_ns()));
+    }
+    if let Some(profile) = profile {
+        let (actual, expected) = (metrics.op_counts.mix(), profile.expected_mix());
+        print!(
+            "Op mix:                GET {:.1}% ({}%)  PUT {:.1}% ({}%)  DELETE {:.1}% ({}%)",
+            actual.get, expected.get, actual.put, expected.put, actual.delete, expected.delete,
+        );
+        match args.max_mix_deviation {
+            Some(max) => println!("    [threshold: ±{max} pts]  {}", pass_fail(mix_exceeded)),
+            None => println!(),
+        }
+    }
+    if !metrics.lock_wait_ns.is_empty() {
+        println!("Lock wait P99:         {:.1} µs", ns_to_us(metrics.lock_wait_p99_ns()));
+        println!("Lock hold P99:         {:.1} µs", ns_to_us(metrics.lock_hold_p99_ns()));
+    }
+    if metrics.keys_populated > 0 {
+        println!();
+        println!("Delete throughput:     {:.1} keys/sec", metrics.delete_throughput());
+        println!("Key drain rate:        {:.1}%", metrics.drain_rate() * 100.0);
+        println!("Keys remaining:        {}", format_thousands(metrics.keys_remaining()));
+    }
     println!();
     println!("5xx errors:            {}", format_thousands(metrics.errors_5xx));
+    if metrics.faults_injected > 0 {
+        println!("  of which injected:   {}", format_thousands(metrics.faults_injected));
+    }
     println!(
         "Error rate:            {:.3}%    [threshold: {:.3}%]  {}",
         metrics.error_rate() * 100.0,
         args.max_error_rate * 100.0,
         pass_fail(error_rate_exceeded),
     );
+    if args.workload == multi_cas::WORKLOAD_NAME {
+        println!("Multi-CAS conflicts:   {}", format_thousands(metrics.multi_cas_conflicts));
+    }
     println!();
     println!(
         "Correctness violations: {}        [threshold: {}]        {}",
@@ -157,6 +560,24 @@ fn print_report(args: &Args, metrics: &transdb_stress_tests::metrics::Metrics, v
         args.max_violations,
         pass_fail(violations_exceeded),
     );
+    if args.causal_check {
+        println!(
+            "Causal violations:     {}        [threshold: {}]        {}",
+            causal_violations,
+            args.max_violations,
+            pass_fail(causal_exceeded),
+        );
+    }
+    if let Some(stats) = gc_stats {
+        println!();
+        println!("Expired-but-not-GCed:  {}", format_thousands(stats.expired_count as u64));
+        println!(
+            "GC effectiveness:      {:.1}%    [threshold: {:.1}%]  {}",
+            gc_effectiveness(stats) * 100.0,
+            args.min_gc_effectiveness * 100.0,
+            pass_fail(gc_below_threshold),
+        );
+    }
     println!();
     println!("Result: {}", if overall_pass { "PASS" } else { "FAIL" });
 }
@@ -174,3 +595,7 @@ fn format_thousands(n: u64) -> String {
 fn ns_to_ms(ns: u64) -> f64 {
     ns as f64 / 1_000_000.0
 }
+
+fn ns_to_us(ns: u64) -> f64 {
+    ns as f64 / 1_000.0
+}
@@ -1,37 +1,211 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use transdb_common::StoreStats;
+
+use crate::histogram::Histogram;
+use crate::history::OpKind;
+use crate::workload::OpCounts;
+
+#[derive(Default)]
 pub struct Metrics {
     pub requests_total: u64,
     pub errors_5xx: u64,
-    /// One entry per completed operation, in insertion order (unsorted).
-    pub latency_ns: Vec<u64>,
+    /// Latency of every completed operation, from its start to its ACK.
+    pub latency_ns: Histogram,
+    /// The same latencies split by the kind of operation; empty for `multi-cas`, whose requests
+    /// are not single-key operations.
+    pub latency_by_kind: LatencyByKind,
     pub elapsed_secs: f64,
+    /// DELETEs that wrote a tombstone (`DeleteOk`) during the measured phase.
+    pub tombstones_written: u64,
+    /// Keys written by the warm-up phase (0 when no warm-up ran).
+    pub keys_populated: u64,
+    /// Operations failed locally by `--fault-rate`; also counted in `errors_5xx`.
+    pub faults_injected: u64,
+    /// Oldest history records evicted by `--max-history-records`.
+    pub records_dropped: u64,
+    /// Server-side wait for the store lock, one value per PUT/DELETE that reported it.
+    pub lock_wait_ns: Histogram,
+    /// Server-side time under the store lock, from acquiring it until the write was applied; for
+    /// a batched write that includes the writes ahead of it in its batch.
+    pub lock_hold_ns: Histogram,
+    /// Key space the run ended with; differs from `--key-space` only under `--adaptive-key-space`.
+    pub final_key_space: usize,
+    /// `multi-cas` requests refused because a key they read had changed; not errors.
+    pub multi_cas_conflicts: u64,
+    /// From each operation's due time to its ACK, one value per operation of a `--rate` run and
+    /// none otherwise. Unlike `latency_ns` it includes any wait to start, so it shows what a
+    /// caller issuing requests at that rate would see.
+    pub response_latency_ns: Histogram,
+    /// Operations the worker drew from its profile, by kind; empty for `multi-cas`.
+    pub op_counts: OpCounts,
 }
 
 impl Metrics {
+    /// Like every percentile here, exact below 256 ns and otherwise up to 1/128 below the
+    /// recorded value; see [`Histogram::percentile`].
     pub fn p50_ns(&self) -> u64 {
-        percentile(&self.latency_ns, 0.50)
+        self.latency_ns.percentile(0.50)
     }
 
     pub fn p99_ns(&self) -> u64 {
-        percentile(&self.latency_ns, 0.99)
+        self.latency_ns.percentile(0.99)
+    }
+
+    pub fn response_p50_ns(&self) -> u64 {
+        self.response_latency_ns.percentile(0.50)
+    }
+
+    pub fn response_p99_ns(&self) -> u64 {
+        self.response_latency_ns.percentile(0.99)
+    }
+
+    pub fn lock_wait_p99_ns(&self) -> u64 {
+        self.lock_wait_ns.percentile(0.99)
+    }
+
+    pub fn lock_hold_p99_ns(&self) -> u64 {
+        self.lock_hold_ns.percentile(0.99)
+    }
+
+    /// Record the latency of a completed `kind` operation, overall and for its kind.
+    pub fn record_latency(&mut self, kind: OpKind, latency_ns: u64) {
+        self.latency_ns.record(latency_ns);
+        self.latency_by_kind.of_mut(kind).record(latency_ns);
     }
 
+    /// Fraction of requests that failed; 0 when nothing was sent.
     pub fn error_rate(&self) -> f64 {
+        if self.requests_total == 0 {
+            return 0.0;
+        }
         self.errors_5xx as f64 / self.requests_total as f64
     }
 
     pub fn throughput_rps(&self) -> f64 {
-        self.requests_total as f64 / self.elapsed_secs
+        self.per_sec(self.requests_total)
+    }
+
+    /// Estimated live keys left after the run: populated minus tombstoned.
+    /// Ignores keys re-created by PUTs, so it is only exact for `delete-only` runs.
+    pub fn keys_remaining(&self) -> u64 {
+        self.keys_populated.saturating_sub(self.tombstones_written)
+    }
+
+    pub fn delete_throughput(&self) -> f64 {
+        self.per_sec(self.tombstones_written)
+    }
+
+    /// Fraction of populated keys that were tombstoned; 0 when nothing was populated.
+    pub fn drain_rate(&self) -> f64 {
+        if self.keys_populated == 0 {
+            return 0.0;
+        }
+        self.tombstones_written.min(self.keys_populated) as f64 / self.keys_populated as f64
+    }
+
+    /// `count` over the run's elapsed time; 0 for a run that took no measurable time.
+    fn per_sec(&self, count: u64) -> f64 {
+        if self.elapsed_secs <= 0.0 {
+            return 0.0;
+        }
+        count as f64 / self.elapsed_secs
+    }
+}
+
+/// Operation latencies in nanoseconds, one histogram per [`OpKind`]. A mixed workload's overall
+/// percentiles hide that, say, writes are slower than reads; these show it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyByKind {
+    pub put: Histogram,
+    pub get: Histogram,
+    pub get_allowing_expired: Histogram,
+    pub delete: Histogram,
+}
+
+impl LatencyByKind {
+    pub fn of(&self, kind: OpKind) -> &Histogram {
+        match kind {
+            OpKind::Put => &self.put,
+            OpKind::Get => &self.get,
+            OpKind::GetAllowingExpired => &self.get_allowing_expired,
+            OpKind::Delete => &self.delete,
+        }
+    }
+
+    pub fn of_mut(&mut self, kind: OpKind) -> &mut Histogram {
+        match kind {
+            OpKind::Put => &mut self.put,
+            OpKind::Get => &mut self.get,
+            OpKind::GetAllowingExpired => &mut self.get_allowing_expired,
+            OpKind::Delete => &mut self.delete,
+        }
+    }
+
+    /// The kinds with at least one recorded operation, reads first.
+    pub fn iter(&self) -> impl Iterator<Item = (OpKind, &Histogram)> {
+        [OpKind::Get, OpKind::GetAllowingExpired, OpKind::Put, OpKind::Delete]
+            .into_iter()
+            .map(|kind| (kind, self.of(kind)))
+            .filter(|(_, latency)| !latency.is_empty())
+    }
+}
+
+/// Running totals the worker bumps after every operation, readable while the run is in progress.
+#[derive(Debug, Default)]
+pub struct LiveCounters {
+    requests: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl LiveCounters {
+    /// Count one completed operation.
+    pub fn record(&self, is_error: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if is_error {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> CounterSnapshot {
+        // Errors are read first so a concurrent `record` can never push them above requests.
+        let errors = self.errors.load(Ordering::Relaxed);
+        CounterSnapshot { requests: self.requests.load(Ordering::Relaxed), errors }
+    }
+}
+
+/// [`LiveCounters`] at one instant.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CounterSnapshot {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+impl CounterSnapshot {
+    /// Requests per second between `earlier` and this snapshot, taken `window` apart; 0 for an
+    /// empty window.
+    pub fn throughput_since(&self, earlier: &CounterSnapshot, window: Duration) -> f64 {
+        if window.is_zero() {
+            return 0.0;
+        }
+        self.requests.saturating_sub(earlier.requests) as f64 / window.as_secs_f64()
+    }
+
+    /// Fraction of all requests so far that failed; 0 when nothing was sent.
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.requests as f64
     }
 }
 
-/// Sort `data` ascending and return the element at index `floor(p * n)`.
-/// Returns 0 for an empty slice.
-fn percentile(data: &[u64], p: f64) -> u64 {
-    if data.is_empty() {
-        return 0;
+/// Share of non-tombstone entries that are not expired-but-uncollected:
+/// `1 - expired / (expired + live)`. 1.0 when the store holds neither.
+pub fn gc_effectiveness(stats: &StoreStats) -> f64 {
+    let total = stats.expired_count + stats.live_count;
+    if total == 0 {
+        return 1.0;
     }
-    let mut sorted = data.to_vec();
-    sorted.sort_unstable();
-    let idx = (p * sorted.len() as f64).floor() as usize;
-    sorted[idx.min(sorted.len() - 1)]
+    1.0 - stats.expired_count as f64 / total as f64
 }
@@ -0,0 +1,150 @@
+//! The `multi-cas` workload: concurrent workers contend on a few pairs of keys, each reading
+//! both keys of a pair and then writing one of them with `POST /multi-cas`, conditional on both
+//! reads. If the server checked and applied those requests atomically, the committed ones form
+//! a serial history; [`find_write_skew`] looks for a commit that proves otherwise.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use transdb_client::{Client, ClientConfig};
+use transdb_common::{TransDbError, Version};
+
+use crate::metrics::{LiveCounters, Metrics};
+use crate::worker::{generate_value, worker_seed};
+use crate::workload::ValueSizeDistribution;
+
+/// CLI name of the workload.
+pub const WORKLOAD_NAME: &str = "multi-cas";
+
+/// Pairs of keys the workers contend on; few enough that conflicts are common.
+pub const KEY_PAIRS: usize = 4;
+
+/// Workers running at once, each issuing one request at a time.
+pub const WORKERS: usize = 8;
+
+/// A multi-cas the server acknowledged: the versions it was conditional on, and the versions
+/// its writes were given.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Commit {
+    pub reads: Vec<(String, Version)>,
+    pub writes: Vec<(String, Version)>,
+}
+
+/// A commit that read `key` at `read` although a commit ordered before it had already written
+/// `latest`, so the two cannot have been serialized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteSkew {
+    pub key: String,
+    pub read: Version,
+    pub latest: Version,
+}
+
+/// Replay `commits` in the order of their write versions and report every read older than the
+/// latest version an earlier commit wrote to that key. A read newer than anything known is not
+/// flagged: it can come from a write whose response was lost, and that is no anomaly.
+pub fn find_write_skew(commits: &[Commit]) -> Vec<WriteSkew> {
+    let mut ordered: Vec<&Commit> = commits.iter().filter(|c| !c.writes.is_empty()).collect();
+    ordered.sort_by_key(|c| c.writes.iter().map(|(_, version)| *version).min());
+
+    let mut latest: HashMap<&str, Version> = HashMap::new();
+    let mut skews = Vec::new();
+    for commit in ordered {
+        for (key, read) in &commit.reads {
+            let known = latest.get(key.as_str()).copied().unwrap_or_default();
+            if *read < known {
+                skews.push(WriteSkew { key: key.clone(), read: *read, latest: known });
+            }
+        }
+        for (key, version) in &commit.writes {
+            latest.insert(key, *version);
+        }
+    }
+    skews
+}
+
+/// Run [`WORKERS`] workers against the primary for `duration`, counting every request in
+/// `live`; worker `i` draws its choices from [`worker_seed`]`(seed, i)`, and the values it writes
+/// are sized by `value_size`. Returns the metrics, with rejected requests in
+/// `multi_cas_conflicts`, and the commits.
+pub async fn run(
+    config: &ClientConfig,
+    duration: Duration,
+    value_size: ValueSizeDistribution,
+    seed: u64,
+    live: &Arc<LiveCounters>,
+) -> (Metrics, Vec<Commit>) {
+    let run_start = Instant::now();
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|i| {
+            let rng = StdRng::seed_from_u64(worker_seed(seed, i));
+            let client = Client::new(config.clone());
+            tokio::spawn(run_worker(client, rng, value_size, run_start + duration, Arc::clone(live)))
+        })
+        .collect();
+
+    let mut metrics = Metrics::default();
+    let mut commits = Vec::new();
+    for worker in workers {
+        let (worker_metrics, worker_commits) = worker.await.expect("multi-cas worker panicked");
+        metrics.requests_total += worker_metrics.requests_total;
+        metrics.errors_5xx += worker_metrics.errors_5xx;
+        metrics.multi_cas_conflicts += worker_metrics.multi_cas_conflicts;
+        metrics.latency_ns.merge(&worker_metrics.latency_ns);
+        commits.extend(worker_commits);
+    }
+    metrics.elapsed_secs = run_start.elapsed().as_secs_f64();
+    (metrics, commits)
+}
+
+async fn run_worker(
+    client: Client,
+    mut rng: StdRng,
+    value_size: ValueSizeDistribution,
+    deadline: Instant,
+    live: Arc<LiveCounters>,
+) -> (Metrics, Vec<Commit>) {
+    let mut metrics = Metrics::default();
+    let mut commits = Vec::new();
+    let record = |metrics: &mut Metrics, started: Instant, is_error: bool| {
+        metrics.requests_total += 1;
+        metrics.errors_5xx += u64::from(is_error);
+        metrics.latency_ns.record(started.elapsed().as_nanos() as u64);
+        live.record(is_error);
+    };
+
+    while Instant::now() < deadline {
+        let (pair, written) = (rng.gen_range(0..KEY_PAIRS), rng.gen_range(0..2));
+        let value = generate_value(&mut rng, &value_size);
+        let keys = [format!("cas_{pair}_a"), format!("cas_{pair}_b")];
+
+        let mut reads = Vec::with_capacity(keys.len());
+        for key in &keys {
+            let started = Instant::now();
+            let result = client.get_optional(key).await;
+            record(&mut metrics, started, result.is_err());
+            match result {
+                Ok(found) => reads.push((key.as_str(), found.map_or(Version(0), |r| r.version))),
+                Err(_) => break,
+            }
+        }
+        if reads.len() < keys.len() {
+            continue;
+        }
+
+        let started = Instant::now();
+        let result = client.multi_cas(&reads, &[(&keys[written], &value)]).await;
+        let conflict = matches!(result, Err(TransDbError::VersionConflict { .. }));
+        record(&mut metrics, started, result.is_err() && !conflict);
+        match result {
+            Ok(versions) => commits.push(Commit {
+                reads: reads.iter().map(|(key, version)| (key.to_string(), *version)).collect(),
+                writes: versions.into_iter().map(|version| (keys[written].clone(), version)).collect(),
+            }),
+            Err(_) if conflict => metrics.multi_cas_conflicts += 1,
+            Err(_) => {}
+        }
+    }
+    (metrics, commits)
+}
@@ -0,0 +1,296 @@
+//! The machine-readable run report written by `--report-json` and `--report-format json`, for CI
+//! jobs that track runs over time rather than read the human table, and the archival
+//! [`StressTestResult`] written by `--output-file`.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::histogram::Histogram;
+use crate::history::{Violation, ViolationKind};
+use crate::metrics::Metrics;
+use crate::multi_cas::WriteSkew;
+
+/// Everything the human report shows, as one JSON document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub config: RunSummary,
+    pub metrics: MetricsSummary,
+    /// Correctness violations that count against `--max-violations`.
+    pub violation_count: u64,
+    /// Causal violations, counted against `--max-violations` separately; 0 without `--causal-check`.
+    pub causal_violation_count: u64,
+    /// Every violation the run reported, hard and causal alike.
+    pub violations: Vec<ViolationSummary>,
+    pub verdict: Verdict,
+}
+
+/// How the run was configured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub workload: String,
+    pub duration_secs: u64,
+    pub key_space: usize,
+    /// The key space the run ended with; differs from `key_space` only under
+    /// `--adaptive-key-space`.
+    pub final_key_space: usize,
+    pub seed: u64,
+    /// `--value-size-dist`.
+    pub value_size_distribution: String,
+    /// `--rate`, for an open-loop run.
+    pub rate: Option<f64>,
+    /// `--concurrency`, for an open-loop run.
+    pub concurrency: Option<usize>,
+}
+
+/// The run's throughput, latency and errors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSummary {
+    pub requests_total: u64,
+    pub elapsed_secs: f64,
+    pub throughput_rps: f64,
+    /// From the start of each operation to its ACK.
+    pub latency: LatencySummary,
+    /// `latency` split by operation kind, for each kind the run issued; empty for `multi-cas`.
+    pub latency_by_kind: Vec<KindLatency>,
+    /// From when each operation was due to its ACK; only for an open-loop run.
+    pub response_latency: Option<LatencySummary>,
+    pub errors: ErrorCounts,
+    pub error_rate: f64,
+}
+
+impl MetricsSummary {
+    pub fn new(metrics: &Metrics) -> Self {
+        Self {
+            requests_total: metrics.requests_total,
+            elapsed_secs: metrics.elapsed_secs,
+            throughput_rps: metrics.throughput_rps(),
+            latency: LatencySummary::new(&metrics.latency_ns),
+            latency_by_kind: metrics
+                .latency_by_kind
+                .iter()
+                .map(|(kind, latency)| KindLatency {
+                    kind: kind.as_name().to_string(),
+                    latency: LatencySummary::new(latency),
+                })
+                .collect(),
+            response_latency: (!metrics.response_latency_ns.is_empty())
+                .then(|| LatencySummary::new(&metrics.response_latency_ns)),
+            errors: ErrorCounts {
+                server: metrics.errors_5xx - metrics.faults_injected,
+                injected: metrics.faults_injected,
+                multi_cas_conflicts: metrics.multi_cas_conflicts,
+            },
+            error_rate: metrics.error_rate(),
+        }
+    }
+}
+
+/// Latency percentiles, maximum and mean in milliseconds, with the histogram behind them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub p999_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    /// The histogram's non-empty buckets, in ascending order.
+    pub buckets: Vec<BucketCount>,
+}
+
+impl LatencySummary {
+    /// Summary of `latency_ns`, one value per operation; all 0 when it is empty.
+    pub fn new(latency_ns: &Histogram) -> Self {
+        let ms = |ns: f64| ns / 1_000_000.0;
+        let p = |p: f64| ms(latency_ns.percentile(p) as f64);
+        Self {
+            p50_ms: p(0.50),
+            p90_ms: p(0.90),
+            p95_ms: p(0.95),
+            p99_ms: p(0.99),
+            p999_ms: p(0.999),
+            max_ms: ms(latency_ns.max() as f64),
+            mean_ms: ms(latency_ns.mean()),
+            buckets: latency_ns
+                .buckets()
+                .map(|b| BucketCount { low_ns: b.low, high_ns: b.high, count: b.count })
+                .collect(),
+        }
+    }
+}
+
+/// The latency of one kind of operation, named as in the text report (`GET`, `PUT`, ...).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KindLatency {
+    pub kind: String,
+    pub latency: LatencySummary,
+}
+
+/// `count` operations took between `low_ns` and `high_ns` inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketCount {
+    pub low_ns: u64,
+    pub high_ns: u64,
+    pub count: u64,
+}
+
+/// Failed operations by cause. `server` and `injected` together make up the error rate;
+/// multi-cas conflicts are expected outcomes, not errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorCounts {
+    /// 5xx responses and transport failures.
+    pub server: u64,
+    /// Operations failed locally by `--fault-rate`.
+    pub injected: u64,
+    pub multi_cas_conflicts: u64,
+}
+
+/// One violation, as the `VIOLATION` line on stderr describes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViolationSummary {
+    pub key: String,
+    pub version: u64,
+    /// The [`ViolationKind`] variant, or `WriteSkew`.
+    pub kind: String,
+    pub detail: String,
+}
+
+impl ViolationSummary {
+    pub fn new(violation: &Violation) -> Self {
+        let (kind, detail) = describe(&violation.kind);
+        Self { key: violation.key.clone(), version: violation.version.get(), kind: kind.to_string(), detail }
+    }
+
+    pub fn write_skew(skew: &WriteSkew) -> Self {
+        Self {
+            key: skew.key.clone(),
+            version: skew.read.get(),
+            kind: "WriteSkew".to_string(),
+            detail: format!("read after v{} was committed", skew.latest),
+        }
+    }
+}
+
+/// The name of `kind`'s variant and what it means for this violation.
+pub fn describe(kind: &ViolationKind) -> (&'static str, String) {
+    match kind {
+        ViolationKind::VersionNotFound { actual } => {
+            ("VersionNotFound", format!("got {} bytes for unrecorded version", actual.len()))
+        }
+        ViolationKind::ReadBeforeWriteStart { .. } => {
+            ("ReadBeforeWriteStart", "GET completed before PUT even started".to_string())
+        }
+        ViolationKind::ValueMismatch { expected, actual } => {
+            ("ValueMismatch", format!("expected {} bytes, got {} bytes", expected.len(), actual.len()))
+        }
+        ViolationKind::UnexpectedNotFound { expected_version } => {
+            ("UnexpectedNotFound", format!("v{expected_version} was acked and never deleted"))
+        }
+        ViolationKind::StaleDataReturned { latest_known_version } => {
+            ("StaleDataReturned", format!("v{latest_known_version} was already acked"))
+        }
+        ViolationKind::StaleReadAfterConfirmedDelete { tombstone_version } => {
+            ("StaleReadAfterConfirmedDelete", format!("tombstone v{tombstone_version} was already acked"))
+        }
+        ViolationKind::CausalOrderViolated { known_version } => {
+            ("CausalOrderViolated", format!("v{known_version} was written or read before this GET started"))
+        }
+        ViolationKind::ExpiredValueReturned { expires_at, get_start_ts } => (
+            "ExpiredValueReturned",
+            format!("TTL elapsed {} ms before the strict GET started", (*get_start_ts - *expires_at).as_millis()),
+        ),
+    }
+}
+
+/// A threshold the run can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Threshold {
+    /// `--max-error-rate`.
+    ErrorRate,
+    /// `--max-violations`, for correctness or causal violations.
+    Violations,
+    /// `--min-gc-effectiveness`.
+    GcEffectiveness,
+    /// `--max-mix-deviation`.
+    OpMix,
+}
+
+impl Threshold {
+    /// The harness's exit code when this is the threshold the run failed.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::ErrorRate => 1,
+            Self::Violations => 2,
+            Self::GcEffectiveness => 4,
+            Self::OpMix => 5,
+        }
+    }
+}
+
+/// Whether the run passed, and if not, which thresholds it failed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Verdict {
+    pub pass: bool,
+    /// The harness's exit code: that of the first threshold in `failed`, or 0.
+    pub exit_code: i32,
+    /// In exit-code order.
+    pub failed: Vec<Threshold>,
+}
+
+impl Verdict {
+    pub fn new(mut failed: Vec<Threshold>) -> Self {
+        failed.sort_by_key(|t| t.exit_code());
+        failed.dedup();
+        Self { pass: failed.is_empty(), exit_code: failed.first().map_or(0, |t| t.exit_code()), failed }
+    }
+}
+
+/// One run as `--output-file` archives it: its results, the run ID a test management system can
+/// correlate it by, when it ran, and every flag it was started with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StressTestResult {
+    /// `--run-id`, or a random UUID.
+    pub run_id: String,
+    /// When the measured phase started, in RFC 3339 UTC.
+    pub timestamp: String,
+    /// Every command-line flag, as parsed, defaults included.
+    pub config: serde_json::Value,
+    pub metrics: MetricsSummary,
+    pub violations: Vec<ViolationSummary>,
+    pub verdict: Verdict,
+}
+
+/// `time` as RFC 3339 UTC with whole seconds, e.g. `2025-01-01T00:00:00Z`.
+pub fn rfc3339_utc(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Days since 1970-01-01 to a proleptic Gregorian date, counting in 400-year eras of 146097
+    // days that start on March 1st, so the leap day falls at the end of each year.
+    let days = days + 719_468;
+    let (era, day_of_era) = (days / 146_097, days % 146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+/// Write `contents` to `path` by way of `<path>.tmp` and a rename, so a reader never sees a
+/// half-written file and an interrupted write leaves any previous file in place.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut tmp = PathBuf::from(path);
+    tmp.as_mut_os_string().push(".tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
@@ -0,0 +1,35 @@
+//! Open-loop pacing for `--rate`. Operations fall due on a fixed grid whether or not earlier ones
+//! have finished, and each operation's latency is measured from when it was due. An operation
+//! held up behind a slow one therefore still counts its wait; a closed loop hides that wait, an
+//! effect known as coordinated omission.
+
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+/// Largest shift of an operation from its grid slot, as a fraction of the interval between
+/// slots. It keeps runs from beating in lockstep with periodic work on the server.
+pub const JITTER: f64 = 0.1;
+
+/// Due times for operations launched at a fixed rate from `start`.
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    start: Instant,
+    rate: f64,
+    next: u64,
+}
+
+impl Schedule {
+    /// `rate` is in operations per second and must be positive.
+    pub fn new(start: Instant, rate: f64) -> Self {
+        assert!(rate > 0.0 && rate.is_finite(), "rate must be a positive number of operations per second");
+        Self { start, rate, next: 0 }
+    }
+
+    /// When the next operation is due: its grid slot, shifted by up to [`JITTER`] intervals.
+    /// Successive due times never decrease.
+    pub fn next_due(&mut self, rng: &mut impl Rng) -> Instant {
+        let slot = self.next as f64 + rng.gen_range(-JITTER..=JITTER);
+        self.next += 1;
+        self.start + Duration::from_secs_f64(slot.max(0.0) / self.rate)
+    }
+}
@@ -1,10 +1,14 @@
 use std::net::{SocketAddr, TcpStream};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command};
 use std::time::{Duration, Instant};
 use tempfile::NamedTempFile;
 use transdb_common::Topology;
 
+/// Node IDs the cluster's topology gives its two servers, which are started by ID.
+pub const PRIMARY_ID: &str = "primary-1";
+pub const REPLICA_ID: &str = "replica-1";
+
 pub struct ServerProcess {
     child: Child,
     pub addr: SocketAddr,
@@ -21,7 +25,7 @@ pub struct Cluster {
     pub replica: ServerProcess,
     pub topology: Topology,
     // Kept alive so the topology file remains on disk until both processes exit.
-    _tmpfile: NamedTempFile,
+    tmpfile: NamedTempFile,
 }
 
 /// Reserve `count` free TCP ports by binding to port 0 for each, then
@@ -55,10 +59,12 @@ impl Cluster {
     /// Build the `transdb-server` binary, spawn a primary and replica, wait
     /// until both are ready to serve HTTP, and return the live `Cluster`.
     ///
+    /// `max_in_flight` is passed to the primary as `--max-in-flight`.
+    ///
     /// Returns `Err` if the build fails, a process cannot be spawned, or the
     /// readiness deadline elapses.  The caller should map this error to exit
     /// code 3 as documented in the CLI spec.
-    pub fn build_and_spawn() -> Result<Self, String> {
+    pub fn build_and_spawn(max_in_flight: Option<usize>) -> Result<Self, String> {
         // 1. Build the server binary.
         let status = Command::new("cargo")
             .args(["build", "-p", "transdb-server"])
@@ -77,6 +83,8 @@ impl Cluster {
         let topology = Topology {
             primary_addr: primary_addr.to_string(),
             replica_addr: Some(replica_addr.to_string()),
+            primary_id: Some(PRIMARY_ID.to_string()),
+            replica_id: Some(REPLICA_ID.to_string()),
         };
         let tmpfile =
             NamedTempFile::new().map_err(|e| format!("Failed to create topology tmpfile: {e}"))?;
@@ -87,15 +95,19 @@ impl Cluster {
         let topo_path = tmpfile.path().to_str().unwrap().to_string();
 
         // 4. Spawn primary.
-        let primary_child = Command::new(&server_bin)
-            .args(["--role", "primary", "--topology", &topo_path])
+        let mut primary_cmd = Command::new(&server_bin);
+        primary_cmd.args(["--node-id", PRIMARY_ID, "--topology", &topo_path, "--server-timing"]);
+        if let Some(max) = max_in_flight {
+            primary_cmd.args(["--max-in-flight", &max.to_string()]);
+        }
+        let primary_child = primary_cmd
             .spawn()
             .map_err(|e| format!("Failed to spawn primary: {e}"))?;
         let primary = ServerProcess { child: primary_child, addr: primary_addr };
 
         // 5. Spawn replica.
         let replica_child = Command::new(&server_bin)
-            .args(["--role", "replica", "--topology", &topo_path])
+            .args(["--node-id", REPLICA_ID, "--topology", &topo_path])
             .spawn()
             .map_err(|e| format!("Failed to spawn replica: {e}"))?;
         let replica = ServerProcess { child: replica_child, addr: replica_addr };
@@ -116,7 +128,13 @@ impl Cluster {
             .map_err(|_| "Replica readiness thread panicked".to_string())?
             .map_err(|e| format!("Replica not ready within timeout: {e}"))?;
 
-        Ok(Cluster { primary, replica, topology, _tmpfile: tmpfile })
+        Ok(Cluster { primary, replica, topology, tmpfile })
+    }
+
+    /// The topology file both nodes were started with; clients load it with
+    /// [`Client::from_topology_file`](transdb_client::Client::from_topology_file).
+    pub fn topology_path(&self) -> &Path {
+        self.tmpfile.path()
     }
 }
 
@@ -1,77 +1,383 @@
-use rand::Rng;
-use std::time::{Duration, Instant};
-use transdb_client::{Client, ClientConfig};
-use transdb_common::{TransDbError, Topology};
+use bytes::Bytes;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::ops::RangeInclusive;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use transdb_client::{Client, ClientConfig, GetResult, ServerTiming};
 
+use crate::adaptive;
+use crate::fault::FaultInjectingClient;
 use crate::history::{History, OpKind, OpOutcome, OpRecord};
-use crate::metrics::Metrics;
-use crate::workload::{Op, WorkloadProfile};
+use crate::metrics::{LiveCounters, Metrics};
+use crate::schedule::Schedule;
+use crate::workload::{Op, OpCounts, ValueSizeDistribution, WorkloadProfile};
 
-/// Drive the primary with `profile` for `duration`, recording every operation.
-/// Returns raw metrics and the full operation history for post-run correctness checking.
-pub async fn run(
-    topology: Topology,
+/// Lifetime of values written by the `ttl-reads` workload's [`Op::PutWithTtl`], short enough to
+/// expire mid-run.
+pub const PUT_TTL_SECS: i64 = 2;
+
+/// Expirations of the PUTs [`RunConfig::ttl_fraction`] gives a TTL, in seconds from when each is
+/// sent: from already past, through mid-run, to after most runs end.
+pub const TTL_OFFSET_SECS: RangeInclusive<i64> = -5..=30;
+
+/// Settings for a measured run; see [`run`].
+#[derive(Debug, Clone)]
+pub struct RunConfig {
+    pub profile: WorkloadProfile,
+    pub key_space: usize,
+    pub duration: Duration,
+    /// Fraction of operations that fail locally as `Error` without reaching the server.
+    pub fault_rate: f64,
+    /// Keep only this many of the most recent history records; `None` keeps all.
+    pub max_history_records: Option<usize>,
+    /// Start at `key_space` and follow [`adaptive::spawn`] from there.
+    pub adaptive_key_space: bool,
+    /// Launch operations open-loop at this many per second (see [`crate::schedule`]); `None` runs them
+    /// one after another, each as soon as the previous one finishes.
+    pub rate: Option<f64>,
+    /// With `rate`, the most operations in flight at once. An operation that falls due while all
+    /// are busy waits for one, and the wait counts toward its response latency.
+    pub concurrency: usize,
+    /// Every random choice of the run is drawn from this seed, so two runs with the same seed
+    /// and settings issue the same operations in the same order.
+    pub seed: u64,
+    /// Sizes of the values PUTs write.
+    pub value_size: ValueSizeDistribution,
+    /// Fraction of PUTs sent with a TTL, drawn from [`TTL_OFFSET_SECS`].
+    pub ttl_fraction: f64,
+}
+
+/// The seed of worker `worker_index` in a run seeded with `seed`.
+pub fn worker_seed(seed: u64, worker_index: usize) -> u64 {
+    seed.wrapping_add(worker_index as u64)
+}
+
+/// An operation drawn for the measured phase, before it is sent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedOp {
+    pub op: Op,
+    pub key: String,
+    /// The value to write, for [`Op::Put`] and [`Op::PutWithTtl`].
+    pub value: Option<Bytes>,
+    /// For [`Op::PutWithTtl`], seconds from sending it until its value expires; negative when
+    /// the value is written already expired.
+    pub expires_in_secs: Option<i64>,
+}
+
+/// Draw the next operation of a run: its kind from `profile`, its key from a key space of
+/// `key_space` keys, and for writes its value, sized by `value_size`. A `ttl_fraction` of
+/// plain PUTs become [`Op::PutWithTtl`] with an expiry drawn from [`TTL_OFFSET_SECS`].
+/// Everything comes from `rng`, so a seeded `rng` yields the same operations every time.
+pub fn plan_op(
     profile: WorkloadProfile,
     key_space: usize,
-    duration: Duration,
-) -> (Metrics, History) {
-    let client = Client::new(ClientConfig { topology });
-    let mut rng = rand::thread_rng();
-    let mut records: Vec<OpRecord> = Vec::new();
-    let mut requests_total: u64 = 0;
-    let mut errors_5xx: u64 = 0;
-    let mut latency_ns: Vec<u64> = Vec::new();
+    value_size: &ValueSizeDistribution,
+    ttl_fraction: f64,
+    rng: &mut impl Rng,
+) -> PlannedOp {
+    let mut op = profile.sample(rng);
+    let key = key_name(rng.gen_range(0..key_space));
+    let mut expires_in_secs = (op == Op::PutWithTtl).then_some(PUT_TTL_SECS);
+    // Drawn only when TTLs are on, so a run without them issues the same operations as before.
+    if op == Op::Put && ttl_fraction > 0.0 && rng.gen_bool(ttl_fraction) {
+        op = Op::PutWithTtl;
+        expires_in_secs = Some(rng.gen_range(TTL_OFFSET_SECS));
+    }
+    let value = matches!(op, Op::Put | Op::PutWithTtl).then(|| Bytes::from(generate_value(rng, value_size)));
+    PlannedOp { op, key, value, expires_in_secs }
+}
+
+/// One operation of the measured phase, as the worker hands it to [`Recorder`].
+struct Completed {
+    /// When the schedule wanted the operation to start; `None` in a closed-loop run.
+    due: Option<Instant>,
+    started: Instant,
+    acked: Instant,
+    key: String,
+    kind: OpKind,
+    outcome: OpOutcome,
+    /// What the server reported in `Server-Timing` for this operation.
+    timings: Vec<ServerTiming>,
+}
+
+/// Folds completed operations into the run's metrics and history.
+struct Recorder<'a> {
+    live: &'a LiveCounters,
+    history: History,
+    metrics: Metrics,
+    /// Feeds GET hit/miss samples to the adaptive key-space tuner, if one is running.
+    samples_tx: Option<tokio::sync::mpsc::UnboundedSender<(Instant, bool)>>,
+}
+
+impl Recorder<'_> {
+    fn record(&mut self, op: Completed) {
+        let is_error = is_error(&op.outcome);
+        if matches!(op.kind, OpKind::Put | OpKind::Delete) {
+            for timing in op.timings {
+                self.metrics.lock_wait_ns.record(timing.lock.as_nanos() as u64);
+                self.metrics.lock_hold_ns.extend(timing.work.map(|work| work.as_nanos() as u64));
+            }
+        }
+        if let Some(samples_tx) = &self.samples_tx {
+            if matches!(op.kind, OpKind::Get | OpKind::GetAllowingExpired) && !is_error {
+                let _ = samples_tx.send((op.acked, matches!(op.outcome, OpOutcome::GetOk { .. })));
+            }
+        }
+        self.live.record(is_error);
+        self.metrics.requests_total += 1;
+        self.metrics.errors_5xx += u64::from(is_error);
+        if matches!(op.outcome, OpOutcome::DeleteOk { .. }) {
+            self.metrics.tombstones_written += 1;
+        }
+        self.metrics.record_latency(op.kind, (op.acked - op.started).as_nanos() as u64);
+        if let Some(due) = op.due {
+            self.metrics.response_latency_ns.record(op.acked.saturating_duration_since(due).as_nanos() as u64);
+        }
+        self.history.push(OpRecord {
+            client_start_ts: op.started,
+            client_ack_ts: op.acked,
+            key: op.key,
+            kind: op.kind,
+            outcome: op.outcome,
+        });
+    }
+}
+
+/// Drive the primary as `run_config` describes, recording every operation and counting each
+/// one in `live` as it completes, so progress can be read mid-run.
+/// Returns raw metrics and the operation history for post-run correctness checking. Lock wait and
+/// hold times come from the `Server-Timing` of PUT and DELETE responses, when the server sends it.
+pub async fn run(config: &ClientConfig, run_config: &RunConfig, live: &LiveCounters) -> (Metrics, History) {
+    let tuner = run_config.adaptive_key_space.then(|| adaptive::spawn(run_config.key_space, adaptive::WINDOW));
+    let mut recorder = Recorder {
+        live,
+        history: History::new(run_config.max_history_records),
+        metrics: Metrics::default(),
+        samples_tx: tuner.as_ref().map(|(samples_tx, _)| samples_tx.clone()),
+    };
+    let key_space_rx = tuner.map(|(_, key_space_rx)| key_space_rx);
+
+    let summary = match run_config.rate {
+        Some(rate) => run_open_loop(config, run_config, rate, key_space_rx, &mut recorder).await,
+        None => run_closed_loop(config, run_config, key_space_rx, &mut recorder).await,
+    };
+
+    let Recorder { history, mut metrics, .. } = recorder;
+    metrics.elapsed_secs = summary.elapsed.as_secs_f64();
+    metrics.faults_injected = summary.faults_injected;
+    metrics.records_dropped = history.records_dropped();
+    metrics.final_key_space = summary.final_key_space;
+    metrics.op_counts = summary.op_counts;
+    (metrics, history)
+}
+
+/// What a measured loop reports besides the operations it hands to the [`Recorder`].
+struct LoopSummary {
+    op_counts: OpCounts,
+    final_key_space: usize,
+    faults_injected: u64,
+    /// From the first operation until the last one was acknowledged; client setup is excluded.
+    elapsed: Duration,
+}
+
+/// Issue operations back to back until the run's duration is up.
+async fn run_closed_loop(
+    config: &ClientConfig,
+    run_config: &RunConfig,
+    key_space_rx: Option<watch::Receiver<usize>>,
+    recorder: &mut Recorder<'_>,
+) -> LoopSummary {
+    let mut rng = StdRng::seed_from_u64(worker_seed(run_config.seed, 0));
+    let (timing_tx, timing_rx) = mpsc::channel();
+    let client = FaultInjectingClient::new(
+        Client::new(config.clone().with_server_timing_sink(timing_tx)),
+        run_config.fault_rate,
+        rng.gen(),
+    );
+    let mut op_counts = OpCounts::default();
+    let mut key_space = run_config.key_space;
 
     let run_start = Instant::now();
+    while run_start.elapsed() < run_config.duration {
+        if let Some(key_space_rx) = &key_space_rx {
+            key_space = *key_space_rx.borrow();
+        }
+        let planned = plan_op(run_config.profile, key_space, &run_config.value_size, run_config.ttl_fraction, &mut rng);
+        op_counts.record(planned.op);
 
-    while run_start.elapsed() < duration {
-        let op = profile.sample(&mut rng);
-        let key_idx = rng.gen_range(0..key_space);
-        let key = format!("key_{key_idx}");
+        let started = Instant::now();
+        let (kind, outcome) = execute_op(&client, &planned).await;
+        let acked = Instant::now();
+        // Operations run one at a time, so whatever arrived belongs to this one.
+        let timings = timing_rx.try_iter().collect();
+        recorder.record(Completed { due: None, started, acked, key: planned.key, kind, outcome, timings });
+    }
+    let faults_injected = client.faults_injected();
+    LoopSummary { op_counts, final_key_space: key_space, faults_injected, elapsed: run_start.elapsed() }
+}
 
-        let op_start = Instant::now();
-        let (kind, outcome) = execute_op(&client, op, &key, &mut rng).await;
-        let op_end = Instant::now();
+/// A client of the open-loop pool together with the `Server-Timing` entries it receives.
+struct Slot {
+    client: FaultInjectingClient,
+    timings: mpsc::Receiver<ServerTiming>,
+}
+
+/// Launch operations at `rate` per second on a pool of `run_config.concurrency` clients until
+/// the run's duration is up, then wait for those still in flight. Operations are drawn in launch
+/// order by this one loop, so they do not depend on which client happens to be idle.
+async fn run_open_loop(
+    config: &ClientConfig,
+    run_config: &RunConfig,
+    rate: f64,
+    key_space_rx: Option<watch::Receiver<usize>>,
+    recorder: &mut Recorder<'_>,
+) -> LoopSummary {
+    let mut rng = StdRng::seed_from_u64(worker_seed(run_config.seed, 0));
+    let concurrency = run_config.concurrency.max(1);
+    let (idle_tx, mut idle_rx) = tokio::sync::mpsc::channel(concurrency);
+    for _ in 0..concurrency {
+        let (timing_tx, timings) = mpsc::channel();
+        let client = FaultInjectingClient::new(
+            Client::new(config.clone().with_server_timing_sink(timing_tx)),
+            run_config.fault_rate,
+            rng.gen(),
+        );
+        idle_tx.try_send(Slot { client, timings }).unwrap_or_else(|_| unreachable!("the pool has room for every slot"));
+    }
+    let (done_tx, mut done_rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut op_counts = OpCounts::default();
+    let mut key_space = run_config.key_space;
 
-        if is_error(&outcome) {
-            errors_5xx += 1;
+    let run_start = Instant::now();
+    let mut schedule = Schedule::new(run_start, rate);
+    loop {
+        let due = schedule.next_due(&mut rng);
+        if due >= run_start + run_config.duration {
+            break;
+        }
+        tokio::time::sleep_until(due.into()).await;
+        let slot = idle_rx.recv().await.expect("the pool keeps its own sender");
+        while let Ok(completed) = done_rx.try_recv() {
+            recorder.record(completed);
         }
 
-        requests_total += 1;
-        latency_ns.push((op_end - op_start).as_nanos() as u64);
+        if let Some(key_space_rx) = &key_space_rx {
+            key_space = *key_space_rx.borrow();
+        }
+        let planned = plan_op(run_config.profile, key_space, &run_config.value_size, run_config.ttl_fraction, &mut rng);
+        op_counts.record(planned.op);
+        let (idle_tx, done_tx) = (idle_tx.clone(), done_tx.clone());
+        tokio::spawn(async move {
+            let started = Instant::now();
+            let (kind, outcome) = execute_op(&slot.client, &planned).await;
+            let acked = Instant::now();
+            // A slot runs one operation at a time, so whatever arrived belongs to this one.
+            let timings = slot.timings.try_iter().collect();
+            let key = planned.key;
+            let _ = done_tx.send(Completed { due: Some(due), started, acked, key, kind, outcome, timings });
+            let _ = idle_tx.send(slot).await;
+        });
+    }
+
+    // Every slot comes back once its last operation has reported, so this waits for them all.
+    let mut faults_injected = 0;
+    for _ in 0..concurrency {
+        faults_injected += idle_rx.recv().await.expect("the pool keeps its own sender").client.faults_injected();
+    }
+    while let Ok(completed) = done_rx.try_recv() {
+        recorder.record(completed);
+    }
+    LoopSummary { op_counts, final_key_space: key_space, faults_injected, elapsed: run_start.elapsed() }
+}
+
+/// Warm-up for [`WarmupMode::Populate`](crate::workload::WarmupMode): PUT one value per key in
+/// the key space. The records are not part of the measured metrics, but callers should merge
+/// them into the run's history so the checker knows which versions were written. The values
+/// are drawn from `seed`, sized by `value_size`.
+pub async fn populate(
+    config: &ClientConfig,
+    key_space: usize,
+    value_size: &ValueSizeDistribution,
+    seed: u64,
+) -> Vec<OpRecord> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let ops: Vec<_> = (0..key_space)
+        .map(|i| {
+            let value = Bytes::from(generate_value(&mut rng, value_size));
+            PlannedOp { op: Op::Put, key: key_name(i), value: Some(value), expires_in_secs: None }
+        })
+        .collect();
+    run_each(config, ops).await
+}
+
+/// Read back every key that `history` tombstoned. A correct primary answers each GET with
+/// `NotFound`; a `GetOk` shows up in the checker as
+/// [`StaleReadAfterConfirmedDelete`](crate::history::ViolationKind).
+pub async fn verify_deleted(config: &ClientConfig, history: &History) -> Vec<OpRecord> {
+    let mut keys: Vec<String> = history
+        .iter()
+        .filter(|r| matches!(r.outcome, OpOutcome::DeleteOk { .. }))
+        .map(|r| r.key.clone())
+        .collect();
+    keys.sort_unstable();
+    keys.dedup();
+    let reads = keys.into_iter().map(|key| PlannedOp { op: Op::Get, key, value: None, expires_in_secs: None });
+    run_each(config, reads).await
+}
+
+/// Issue `ops` one after another, outside the measured phase.
+async fn run_each(config: &ClientConfig, ops: impl IntoIterator<Item = PlannedOp>) -> Vec<OpRecord> {
+    let client = FaultInjectingClient::new(Client::new(config.clone()), 0.0, 0);
+    let mut records = Vec::new();
+
+    for planned in ops {
+        let op_start = Instant::now();
+        let (kind, outcome) = execute_op(&client, &planned).await;
         records.push(OpRecord {
             client_start_ts: op_start,
-            client_ack_ts: op_end,
-            key,
+            client_ack_ts: Instant::now(),
+            key: planned.key,
             kind,
             outcome,
         });
     }
+    records
+}
 
-    let elapsed_secs = run_start.elapsed().as_secs_f64();
-    let metrics = Metrics { requests_total, errors_5xx, latency_ns, elapsed_secs };
-    (metrics, History(records))
+fn key_name(idx: usize) -> String {
+    format!("key_{idx}")
 }
 
-async fn execute_op(
-    client: &Client,
-    op: Op,
-    key: &str,
-    rng: &mut impl Rng,
-) -> (OpKind, OpOutcome) {
-    match op {
-        Op::Get => {
-            let outcome = match client.get(key).await {
-                Ok(r) => OpOutcome::GetOk { version: r.version, value: r.value },
-                Err(TransDbError::KeyNotFound(_)) => OpOutcome::NotFound,
-                Err(_) => OpOutcome::Error,
-            };
-            (OpKind::Get, outcome)
+async fn execute_op(client: &FaultInjectingClient, planned: &PlannedOp) -> (OpKind, OpOutcome) {
+    let key = planned.key.as_str();
+    // Writes always carry a value; see `plan_op`.
+    let value = || planned.value.clone().unwrap_or_default();
+    match planned.op {
+        Op::Get => (OpKind::Get, get_outcome(client.get(key).await)),
+        Op::GetAllowingExpired => {
+            (OpKind::GetAllowingExpired, get_outcome(client.get_allowing_expired(key).await))
         }
         Op::Put => {
-            let value = generate_value(rng);
+            let value = value();
             let outcome = match client.put(key, &value).await {
-                Ok(version) => OpOutcome::PutOk { version, value },
+                Ok(version) => OpOutcome::PutOk { version, value, expires_at: None },
+                Err(_) => OpOutcome::Error,
+            };
+            (OpKind::Put, outcome)
+        }
+        Op::PutWithTtl => {
+            let value = value();
+            let (wall, now) = (SystemTime::now(), Instant::now());
+            let wall_secs = wall.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let expires_at = wall_secs.saturating_add_signed(planned.expires_in_secs.unwrap_or(PUT_TTL_SECS));
+            let outcome = match client.put_with_ttl(key, &value, expires_at).await {
+                Ok(version) => OpOutcome::PutOk {
+                    version,
+                    value,
+                    expires_at: Some(instant_at_unix_secs(expires_at, wall, now)),
+                },
                 Err(_) => OpOutcome::Error,
             };
             (OpKind::Put, outcome)
@@ -87,10 +393,28 @@ async fn execute_op(
     }
 }
 
-/// Generate a random byte payload for use in PUT operations (8–64 bytes).
-pub fn generate_value(rng: &mut impl Rng) -> Vec<u8> {
-    let len: usize = rng.gen_range(8..=1024);
-    (0..len).map(|_| rng.gen::<u8>()).collect()
+/// When, on the monotonic clock, the wall clock reads `unix_secs`, given that it read `wall` at
+/// `now`: the server expires values by the wall clock, the checker compares `Instant`s.
+fn instant_at_unix_secs(unix_secs: u64, wall: SystemTime, now: Instant) -> Instant {
+    match (UNIX_EPOCH + Duration::from_secs(unix_secs)).duration_since(wall) {
+        Ok(ahead) => now + ahead,
+        Err(behind) => now.checked_sub(behind.duration()).unwrap_or(now),
+    }
+}
+
+fn get_outcome(result: transdb_common::Result<GetResult>) -> OpOutcome {
+    match result {
+        Ok(r) => OpOutcome::GetOk { version: r.version, value: r.value, expired: r.expired },
+        Err(e) if e.is_not_found() => OpOutcome::NotFound,
+        Err(_) => OpOutcome::Error,
+    }
+}
+
+/// Generate a random byte payload for use in PUT operations, its length drawn from `size`.
+pub fn generate_value(rng: &mut impl Rng, size: &ValueSizeDistribution) -> Vec<u8> {
+    let mut value = vec![0; size.sample(rng)];
+    rng.fill(&mut value[..]);
+    value
 }
 
 /// Returns `true` if `outcome` represents a server-side error (5xx or network failure).
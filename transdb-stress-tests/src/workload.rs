@@ -1,9 +1,14 @@
 use rand::Rng;
+use transdb_common::Limits;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Op {
     Get,
+    /// GET that still returns a value whose TTL has elapsed, flagged as expired.
+    GetAllowingExpired,
     Put,
+    /// PUT whose value expires shortly after it is written.
+    PutWithTtl,
     Delete,
 }
 
@@ -15,12 +20,19 @@ pub enum Op {
 /// | Balanced    |   50  |   45  |    5     |
 /// | WriteHeavy  |   20  |   75  |    5     |
 /// | PutOnly     |    0  |  100  |    0     |
+/// | DeleteOnly  |    0  |    0  |  100     |
+/// | TtlReads    |   70* |   30† |    0     |
+///
+/// \* 20% strict GET, 50% GET allowing expired. † Every PUT carries a short TTL, so reads
+/// race expiry.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WorkloadProfile {
     ReadHeavy,
     Balanced,
     WriteHeavy,
     PutOnly,
+    DeleteOnly,
+    TtlReads,
 }
 
 impl WorkloadProfile {
@@ -37,6 +49,8 @@ impl WorkloadProfile {
             "balanced" => Some(Self::Balanced),
             "write-heavy" => Some(Self::WriteHeavy),
             "put-only" => Some(Self::PutOnly),
+            "delete-only" => Some(Self::DeleteOnly),
+            "ttl-reads" => Some(Self::TtlReads),
             _ => None,
         }
     }
@@ -48,9 +62,25 @@ impl WorkloadProfile {
             Self::Balanced => "balanced",
             Self::WriteHeavy => "write-heavy",
             Self::PutOnly => "put-only",
+            Self::DeleteOnly => "delete-only",
+            Self::TtlReads => "ttl-reads",
         }
     }
 
+    /// The GET/PUT/DELETE percentages of the table above. Written out rather than derived from
+    /// [`Self::op_for_roll`], so that a run's observed mix can be checked against it.
+    pub fn expected_mix(&self) -> OpMix {
+        let (get, put, delete) = match self {
+            Self::ReadHeavy => (80.0, 20.0, 0.0),
+            Self::Balanced => (50.0, 45.0, 5.0),
+            Self::WriteHeavy => (20.0, 75.0, 5.0),
+            Self::PutOnly => (0.0, 100.0, 0.0),
+            Self::DeleteOnly => (0.0, 0.0, 100.0),
+            Self::TtlReads => (70.0, 30.0, 0.0),
+        };
+        OpMix { get, put, delete }
+    }
+
     /// Map a roll in `0..100` to an `Op` according to the profile's percentages.
     /// Exposed for deterministic testing.
     pub fn op_for_roll(&self, roll: u32) -> Op {
@@ -68,6 +98,150 @@ impl WorkloadProfile {
                 if roll < 20 { Op::Get } else if roll < 95 { Op::Put } else { Op::Delete }
             }
             WorkloadProfile::PutOnly => Op::Put,
+            WorkloadProfile::DeleteOnly => Op::Delete,
+            WorkloadProfile::TtlReads => {
+                // GET 20%, GET allowing expired 50%, PUT with TTL 30%
+                if roll < 20 { Op::Get } else if roll < 70 { Op::GetAllowingExpired } else { Op::PutWithTtl }
+            }
+        }
+    }
+}
+
+/// Shares of GETs, PUTs and DELETEs in a workload, in percent. [`Op::GetAllowingExpired`]
+/// counts as a GET and [`Op::PutWithTtl`] as a PUT.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OpMix {
+    pub get: f64,
+    pub put: f64,
+    pub delete: f64,
+}
+
+impl OpMix {
+    /// Largest difference from `other` in any of the three shares, in percentage points.
+    pub fn max_deviation(&self, other: &OpMix) -> f64 {
+        [self.get - other.get, self.put - other.put, self.delete - other.delete]
+            .into_iter()
+            .map(f64::abs)
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Operations a run issued, counted as in [`OpMix`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpCounts {
+    pub get: u64,
+    pub put: u64,
+    pub delete: u64,
+}
+
+impl OpCounts {
+    pub fn record(&mut self, op: Op) {
+        match op {
+            Op::Get | Op::GetAllowingExpired => self.get += 1,
+            Op::Put | Op::PutWithTtl => self.put += 1,
+            Op::Delete => self.delete += 1,
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.get + self.put + self.delete
+    }
+
+    /// The observed mix; all zero when nothing was counted.
+    pub fn mix(&self) -> OpMix {
+        let total = self.total();
+        if total == 0 {
+            return OpMix::default();
+        }
+        let share = |count: u64| count as f64 * 100.0 / total as f64;
+        OpMix { get: share(self.get), put: share(self.put), delete: share(self.delete) }
+    }
+}
+
+/// What the harness does before the measured phase starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmupMode {
+    /// Start measuring against an empty store.
+    None,
+    /// PUT one value per key in the key space first (e.g. so `delete-only` has keys to drain).
+    Populate,
+}
+
+impl WarmupMode {
+    /// Parse a warm-up mode from its CLI name (`"none"` or `"populate"`).
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(Self::None),
+            "populate" => Some(Self::Populate),
+            _ => None,
+        }
+    }
+}
+
+/// How the sizes of generated values are spread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueSizeDistribution {
+    /// Every size from `min` to `max` bytes, inclusive, equally likely.
+    Uniform { min: usize, max: usize },
+    /// Log-normal around `median` bytes, as real values tend to be: most are small, a few are
+    /// far larger, and `sigma` sets how long that tail is. Sizes are capped at
+    /// [`MAX_VALUE_SIZE`].
+    LogNormal { median: usize, sigma: f64 },
+    /// Always this many bytes, to keep size out of latency comparisons.
+    Fixed(usize),
+}
+
+/// Largest value the server accepts by default, and so the largest size ever drawn.
+pub const MAX_VALUE_SIZE: usize = Limits::DEFAULT.max_value_size;
+
+impl Default for ValueSizeDistribution {
+    fn default() -> Self {
+        Self::Uniform { min: 8, max: 1024 }
+    }
+}
+
+impl ValueSizeDistribution {
+    /// Build a distribution from the `--value-size-*` flags. `uniform` draws between `min` and
+    /// `max`; `lognormal` is centred on their geometric midpoint, with spread `sigma`; `fixed`
+    /// always uses `max`. Returns the reason when the flags do not describe one.
+    pub fn from_flags(name: &str, min: usize, max: usize, sigma: f64) -> Result<Self, String> {
+        if min == 0 || min > max || max > MAX_VALUE_SIZE {
+            return Err(format!(
+                "value sizes must satisfy 1 <= --value-size-min <= --value-size-max <= {MAX_VALUE_SIZE}, \
+                 got {min} and {max}"
+            ));
+        }
+        match name {
+            "uniform" => Ok(Self::Uniform { min, max }),
+            "lognormal" if sigma > 0.0 && sigma.is_finite() => {
+                Ok(Self::LogNormal { median: ((min as f64) * (max as f64)).sqrt().round() as usize, sigma })
+            }
+            "lognormal" => Err(format!("--value-lognormal-sigma must be a positive number, got {sigma}")),
+            "fixed" => Ok(Self::Fixed(max)),
+            _ => Err(format!("Unknown value size distribution {name:?}. Valid values: uniform, lognormal, fixed")),
+        }
+    }
+
+    /// The distribution's CLI name.
+    pub fn as_name(&self) -> &'static str {
+        match self {
+            Self::Uniform { .. } => "uniform",
+            Self::LogNormal { .. } => "lognormal",
+            Self::Fixed(_) => "fixed",
+        }
+    }
+
+    /// Draw a value size in bytes.
+    pub fn sample(&self, rng: &mut impl Rng) -> usize {
+        match *self {
+            Self::Uniform { min, max } => rng.gen_range(min..=max),
+            Self::LogNormal { median, sigma } => {
+                // Box-Muller: a standard normal from two uniforms; 1 - u keeps ln away from 0.
+                let (u1, u2): (f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+                let normal = (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos();
+                ((median as f64 * (sigma * normal).exp()).round() as usize).clamp(1, MAX_VALUE_SIZE)
+            }
+            Self::Fixed(size) => size,
         }
     }
 }
@@ -0,0 +1,47 @@
+use std::time::{Duration, Instant};
+use transdb_stress_tests::adaptive::{self, next_key_space, HitRateWindow};
+
+#[test]
+fn test_next_key_space_halves_and_doubles_at_the_thresholds() {
+    assert_eq!(next_key_space(1000, 0.10), 500);
+    assert_eq!(next_key_space(1000, 0.40), 1000);
+    assert_eq!(next_key_space(1000, 0.95), 1000);
+    assert_eq!(next_key_space(1000, 0.99), 2000);
+    // Never shrinks below one key, never overflows.
+    assert_eq!(next_key_space(1, 0.0), 1);
+    assert_eq!(next_key_space(usize::MAX, 1.0), usize::MAX);
+}
+
+#[test]
+fn test_hit_rate_window_only_counts_recent_gets() {
+    let start = Instant::now();
+    let mut window = HitRateWindow::new(Duration::from_secs(10));
+    assert_eq!(window.hit_rate(start), None);
+
+    window.record(start, false);
+    window.record(start, false);
+    window.record(start + Duration::from_secs(5), true);
+    window.record(start + Duration::from_secs(6), true);
+    assert_eq!(window.hit_rate(start + Duration::from_secs(6)), Some(0.5));
+
+    // The two misses slide out of the window.
+    assert_eq!(window.hit_rate(start + Duration::from_secs(11)), Some(1.0));
+    assert_eq!(window.hit_rate(start + Duration::from_secs(17)), None);
+
+    window.record(start + Duration::from_secs(17), true);
+    window.clear();
+    assert_eq!(window.hit_rate(start + Duration::from_secs(17)), None);
+}
+
+#[tokio::test]
+async fn test_tuner_publishes_a_smaller_key_space_after_a_window_of_misses() {
+    let (samples_tx, key_space_rx) = adaptive::spawn(100, Duration::from_millis(50));
+    let deadline = Instant::now() + Duration::from_secs(5);
+    // Keep the window populated, as a running worker would, until the tuner reacts.
+    while !key_space_rx.has_changed().unwrap() {
+        assert!(Instant::now() < deadline, "key space was never adjusted");
+        samples_tx.send((Instant::now(), false)).unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(*key_space_rx.borrow(), 50);
+}
@@ -0,0 +1,69 @@
+use transdb_stress_tests::histogram::{Bucket, Histogram};
+
+#[test]
+fn test_small_values_are_exact() {
+    let h: Histogram = (0..256).rev().collect();
+    assert_eq!(h.len(), 256);
+    assert_eq!((h.min(), h.max()), (0, 255));
+    assert_eq!(h.percentile(0.5), 128);
+    assert_eq!(h.percentile(0.9), 230);
+    assert_eq!(h.mean(), 127.5);
+    assert!(h.buckets().all(|b| b.low == b.high && b.count == 1));
+}
+
+#[test]
+fn test_large_values_within_bucket_resolution() {
+    for value in [256, 1_000, 123_456, 9_999_999, 1 << 40, u64::MAX - 1] {
+        let h: Histogram = [0, value, value, u64::MAX].into_iter().collect();
+        let reported = h.percentile(0.5);
+        assert!(reported <= value && value - reported <= value / 128, "{value} reported as {reported}");
+        let bucket = h.buckets().find(|b| b.high >= value).unwrap();
+        assert!(bucket.low <= value && bucket.count >= 2, "{value} outside {bucket:?}");
+    }
+}
+
+#[test]
+fn test_buckets_tile_the_value_range() {
+    let h: Histogram = (0..20).map(|shift| 1u64 << (shift * 3)).chain([u64::MAX]).collect();
+    let buckets: Vec<Bucket> = h.buckets().collect();
+    assert_eq!(buckets.len(), 21);
+    assert_eq!(buckets.last().unwrap().high, u64::MAX);
+    for pair in buckets.windows(2) {
+        assert!(pair[0].high < pair[1].low);
+    }
+}
+
+#[test]
+fn test_percentile_extremes_and_empty() {
+    let empty = Histogram::default();
+    assert!(empty.is_empty());
+    assert_eq!((empty.percentile(0.5), empty.max(), empty.mean()), (0, 0, 0.0));
+    assert_eq!(empty.buckets().count(), 0);
+
+    let h: Histogram = [5_000, 1_000_003, 77].into_iter().collect();
+    assert_eq!(h.percentile(0.0), 77);
+    // The top rank is the exact maximum rather than its bucket's lower bound.
+    assert_eq!(h.percentile(1.0), 1_000_003);
+    assert_eq!(h.percentile(0.999), 1_000_003);
+}
+
+#[test]
+fn test_merge_equals_recording_everything_in_one() {
+    let (evens, odds): (Vec<u64>, Vec<u64>) = (0..5_000u64).map(|v| v * 997).partition(|v| v % 2 == 0);
+    let mut merged: Histogram = evens.iter().copied().collect();
+    merged.merge(&odds.iter().copied().collect());
+    let whole: Histogram = evens.into_iter().chain(odds).collect();
+    assert_eq!(merged.len(), whole.len());
+    assert_eq!((merged.min(), merged.max(), merged.mean()), (whole.min(), whole.max(), whole.mean()));
+    for p in [0.5, 0.9, 0.95, 0.99, 0.999] {
+        assert_eq!(merged.percentile(p), whole.percentile(p));
+    }
+    assert!(merged.buckets().eq(whole.buckets()));
+
+    let mut empty = Histogram::default();
+    empty.merge(&whole);
+    assert_eq!(empty, whole);
+    let mut unchanged = whole.clone();
+    unchanged.merge(&Histogram::default());
+    assert_eq!(unchanged, whole);
+}
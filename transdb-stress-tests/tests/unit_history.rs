@@ -1,5 +1,7 @@
+use bytes::Bytes;
 use std::time::{Duration, Instant};
-use transdb_stress_tests::history::{History, OpKind, OpOutcome, OpRecord, ViolationKind};
+use transdb_common::Version;
+use transdb_stress_tests::history::{History, OpKind, OpOutcome, OpRecord, Violation, ViolationKind};
 
 fn put(key: &str, version: u64, value: &[u8], start: Instant, ack: Instant) -> OpRecord {
     OpRecord {
@@ -7,7 +9,7 @@ fn put(key: &str, version: u64, value: &[u8], start: Instant, ack: Instant) -> O
         client_ack_ts: ack,
         key: key.to_string(),
         kind: OpKind::Put,
-        outcome: OpOutcome::PutOk { version, value: value.to_vec() },
+        outcome: OpOutcome::PutOk { version: Version(version), value: Bytes::copy_from_slice(value), expires_at: None },
     }
 }
 
@@ -17,7 +19,27 @@ fn get(key: &str, version: u64, value: &[u8], start: Instant, ack: Instant) -> O
         client_ack_ts: ack,
         key: key.to_string(),
         kind: OpKind::Get,
-        outcome: OpOutcome::GetOk { version, value: value.to_vec() },
+        outcome: OpOutcome::GetOk { version: Version(version), value: Bytes::copy_from_slice(value), expired: false },
+    }
+}
+
+fn put_with_ttl(key: &str, version: u64, value: &[u8], start: Instant, ack: Instant, expires_at: Instant) -> OpRecord {
+    let mut r = put(key, version, value, start, ack);
+    r.outcome = OpOutcome::PutOk {
+        version: Version(version),
+        value: Bytes::copy_from_slice(value),
+        expires_at: Some(expires_at),
+    };
+    r
+}
+
+fn get_expired(key: &str, version: u64, value: &[u8], start: Instant, ack: Instant) -> OpRecord {
+    OpRecord {
+        client_start_ts: start,
+        client_ack_ts: ack,
+        key: key.to_string(),
+        kind: OpKind::GetAllowingExpired,
+        outcome: OpOutcome::GetOk { version: Version(version), value: Bytes::copy_from_slice(value), expired: true },
     }
 }
 
@@ -27,7 +49,27 @@ fn delete(key: &str, version: u64, start: Instant, ack: Instant) -> OpRecord {
         client_ack_ts: ack,
         key: key.to_string(),
         kind: OpKind::Delete,
-        outcome: OpOutcome::DeleteOk { version },
+        outcome: OpOutcome::DeleteOk { version: Version(version) },
+    }
+}
+
+fn get_not_found(key: &str, start: Instant, ack: Instant) -> OpRecord {
+    OpRecord {
+        client_start_ts: start,
+        client_ack_ts: ack,
+        key: key.to_string(),
+        kind: OpKind::Get,
+        outcome: OpOutcome::NotFound,
+    }
+}
+
+fn failed_delete(key: &str, start: Instant, ack: Instant) -> OpRecord {
+    OpRecord {
+        client_start_ts: start,
+        client_ack_ts: ack,
+        key: key.to_string(),
+        kind: OpKind::Delete,
+        outcome: OpOutcome::Error,
     }
 }
 
@@ -56,7 +98,7 @@ fn ts8() -> (Instant, Instant, Instant, Instant, Instant, Instant, Instant, Inst
 #[test]
 fn test_no_violations_when_gets_match_puts() {
     let (t0, t1, t2, t3, _, _) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"hello", t0, t1),
         get("k", 1, b"hello", t2, t3),
     ]);
@@ -65,7 +107,7 @@ fn test_no_violations_when_gets_match_puts() {
 
 #[test]
 fn test_empty_history_has_no_violations() {
-    assert!(History(vec![]).check_correctness().is_empty());
+    assert!(History::from(vec![]).check_correctness().is_empty());
 }
 
 // --- VersionNotFound ---
@@ -73,10 +115,10 @@ fn test_empty_history_has_no_violations() {
 #[test]
 fn test_violation_when_version_not_in_write_index() {
     let (t0, t1, ..) = ts6();
-    let h = History(vec![get("k", 99, b"ghost", t0, t1)]);
+    let h = History::from(vec![get("k", 99, b"ghost", t0, t1)]);
     let v = h.check_correctness();
     assert_eq!(v.len(), 1);
-    assert_eq!(v[0].version, 99);
+    assert_eq!(v[0].version, Version(99));
     assert!(matches!(&v[0].kind, ViolationKind::VersionNotFound { actual } if actual == b"ghost"));
 }
 
@@ -86,7 +128,7 @@ fn test_violation_when_version_not_in_write_index() {
 fn test_violation_when_get_acks_before_put_starts() {
     // Timeline: GET_start → GET_ack → PUT_start → PUT_ack
     let (t0, t1, t2, t3, _, _) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"hello", t2, t3),
         get("k", 1, b"hello", t0, t1),
     ]);
@@ -94,7 +136,7 @@ fn test_violation_when_get_acks_before_put_starts() {
     assert_eq!(v.len(), 1);
     assert!(matches!(&v[0].kind, ViolationKind::ReadBeforeWriteStart { .. }));
 
-    let h = History(vec![
+    let h = History::from(vec![
         get("k", 1, b"hello", t0, t1),
         put("k", 1, b"hello", t2, t3),
     ]);
@@ -109,13 +151,13 @@ fn test_no_violation_when_get_overlaps_with_put() {
     // Verified with both record orderings to ensure the check is order-independent.
     let (t0, t1, t2, t3, _, _) = ts6();
 
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"hello", t1, t3),
         get("k", 1, b"hello", t0, t2),
     ]);
     assert!(h.check_correctness().is_empty());
 
-    let h = History(vec![
+    let h = History::from(vec![
         get("k", 1, b"hello", t0, t2),
         put("k", 1, b"hello", t1, t3),
     ]);
@@ -130,7 +172,7 @@ fn test_stale_data_after_delete_acked() {
     // Classified as stale (eventual consistency), not a hard error.
     // The tombstone's version (2) IS the latest_known_version.
     let (t0, t1, t2, t3, t4, _) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"hello", t0, t1),
         delete("k", 2, t1, t2),
         get("k", 1, b"hello", t3, t4),
@@ -139,7 +181,7 @@ fn test_stale_data_after_delete_acked() {
     assert_eq!(v.len(), 1);
     assert!(matches!(
         &v[0].kind,
-        ViolationKind::StaleDataReturned { latest_known_version: 2 }
+        ViolationKind::StaleDataReturned { latest_known_version: Version(2) }
     ));
 }
 
@@ -148,7 +190,7 @@ fn test_no_violation_get_overlaps_with_delete() {
     // GET started before DELETE acked — overlap is acceptable.
     // Timeline: GET_start → DELETE_ack → GET_ack
     let (t0, t1, t2, t3, t4, _) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"hello", t0, t1),
         delete("k", 2, t1, t3),
         get("k", 1, b"hello", t2, t4),
@@ -161,7 +203,7 @@ fn test_no_violation_after_delete_then_reput() {
     // DELETE then re-PUT with global versions (v=1, v=2 tombstone, v=3 re-PUT).
     // Case A: GET reads the re-PUT's value.
     let (t0, t1, t2, t3, t4, t5, t6) = ts7();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"first", t0, t1),
         delete("k", 2, t1, t2),
         put("k", 3, b"second", t3, t4),
@@ -172,7 +214,7 @@ fn test_no_violation_after_delete_then_reput() {
     // Case B: GET reads the first PUT's value before DELETE and re-PUT start.
     // No newer write was acked before GET started.
     let (t0, t1, t2, t3, t4, t5, t6, t7) = ts8();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"first", t0, t1),
         get("k", 1, b"first", t2, t3),
         delete("k", 2, t4, t5),
@@ -188,7 +230,7 @@ fn test_stale_after_tombstone_and_reput() {
     // PUT v=1, DELETE v=2 (tombstone), PUT v=3 — all acked.
     // GET v=1 after all done → stale with latest_known_version = 3 (the re-PUT, not the tombstone).
     let (t0, t1, t2, t3, t4, t5, t6, t7) = ts8();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"first", t0, t1),
         delete("k", 2, t1, t2),
         put("k", 3, b"second", t3, t4),
@@ -200,7 +242,7 @@ fn test_stale_after_tombstone_and_reput() {
     // Tombstone v=2 is also newer, but v=3 is the maximum.
     assert!(matches!(
         &v[0].kind,
-        ViolationKind::StaleDataReturned { latest_known_version: 3 }
+        ViolationKind::StaleDataReturned { latest_known_version: Version(3) }
     ));
     let _ = t7; // unused
 }
@@ -223,7 +265,7 @@ fn test_no_violation_when_later_ack_does_not_mean_later_write() {
     //   t6: GET v=3 starts
     //   t7: GET v=3 acks  →  returns (v=3, b"second")  [correct: v=3 is the latest]
     let (t0, t1, t2, t3, t4, t5, t6, t7) = ts8();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"first",  t0, t5),  // PUT v=1: early start, late ack
         delete("k", 2,          t1, t2),
         put("k", 3, b"second", t3, t4),  // PUT v=3: after DELETE, acks before PUT v=1
@@ -241,7 +283,7 @@ fn test_stale_when_delete_acks_before_get_starts() {
     // definitely advanced past v=1 by the time the client issued the GET.
     // GET returning v=1 is therefore stale.
     let (t0, t1, t2, t3, t4, t5) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"hello", t0, t2),  // PUT: t0..t2
         delete("k", 2, t1, t3),          // DELETE: t1..t3  (starts during PUT)
         get("k", 1, b"hello", t4, t5),   // GET: after DELETE acked
@@ -250,10 +292,69 @@ fn test_stale_when_delete_acks_before_get_starts() {
     assert_eq!(v.len(), 1);
     assert!(matches!(
         &v[0].kind,
-        ViolationKind::StaleDataReturned { latest_known_version: 2 }
+        ViolationKind::StaleDataReturned { latest_known_version: Version(2) }
     ));
 }
 
+// --- StaleReadAfterConfirmedDelete ---
+
+#[test]
+fn test_delete_check_flags_read_after_confirmed_delete() {
+    // PUT v1, DELETE v2 ACKed, no later PUT: a GET returning v1 should have seen the tombstone.
+    let (t0, t1, t2, t3, t4, _) = ts6();
+    let h = History::from(vec![
+        put("k", 1, b"hello", t0, t1),
+        delete("k", 2, t1, t2),
+        get("k", 1, b"hello", t3, t4),
+    ]);
+    let v = h.check_delete_correctness();
+    assert_eq!(v.len(), 1);
+    assert_eq!(v[0].version, Version(1));
+    assert!(matches!(v[0].kind, ViolationKind::StaleReadAfterConfirmedDelete { tombstone_version: Version(2) }));
+
+    // check_all reports it once, as the more specific kind.
+    let v = h.check_all();
+    assert_eq!(v.len(), 1);
+    assert!(matches!(v[0].kind, ViolationKind::StaleReadAfterConfirmedDelete { tombstone_version: Version(2) }));
+}
+
+#[test]
+fn test_delete_check_ignores_overlaps_reputs_and_unrecorded_versions() {
+    let (t0, t1, t2, t3, t4, t5, t6, _) = ts8();
+
+    // The DELETE had not been ACKed when the GET started.
+    let h = History::from(vec![
+        put("k", 1, b"hello", t0, t1),
+        delete("k", 2, t1, t3),
+        get("k", 1, b"hello", t2, t4),
+    ]);
+    assert!(h.check_delete_correctness().is_empty());
+    assert!(h.check_all().is_empty());
+
+    // A re-PUT was ACKed after the DELETE: still stale, but superseded by a PUT.
+    let h = History::from(vec![
+        put("k", 1, b"first", t0, t1),
+        delete("k", 2, t1, t2),
+        put("k", 3, b"second", t3, t4),
+        get("k", 1, b"first", t5, t6),
+    ]);
+    assert!(h.check_delete_correctness().is_empty());
+    let v = h.check_all();
+    assert_eq!(v.len(), 1);
+    assert!(matches!(v[0].kind, ViolationKind::StaleDataReturned { latest_known_version: Version(3) }));
+
+    // A version no PUT produced is check_correctness's VersionNotFound.
+    let h = History::from(vec![
+        put("k", 1, b"hello", t0, t1),
+        delete("k", 2, t1, t2),
+        get("k", 9, b"ghost", t3, t4),
+    ]);
+    assert!(h.check_delete_correctness().is_empty());
+    let v = h.check_all();
+    assert_eq!(v.len(), 1);
+    assert!(matches!(v[0].kind, ViolationKind::VersionNotFound { .. }));
+}
+
 // --- GET returning data for a tombstone version ---
 
 #[test]
@@ -262,7 +363,7 @@ fn test_version_not_found_when_get_returns_data_for_tombstone_version() {
     // This cannot happen in a correct system (server returns 404 for tombstones),
     // but the checker must still report VersionNotFound.
     let (t0, t1, t2, t3, _, _) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         delete("k", 1, t0, t1),
         get("k", 1, b"phantom", t2, t3),
     ]);
@@ -276,7 +377,7 @@ fn test_version_not_found_when_get_returns_data_for_tombstone_version() {
 #[test]
 fn test_violation_on_value_mismatch() {
     let (t0, t1, t2, t3, _, _) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"hello", t0, t1),
         get("k", 1, b"world", t2, t3),
     ]);
@@ -289,13 +390,96 @@ fn test_violation_on_value_mismatch() {
     ));
 }
 
+// --- GetAllowingExpired ---
+
+#[test]
+fn test_expired_read_checked_against_its_put() {
+    // An expired value that matches its PUT is consistent, as is NotFound once the TTL'd value
+    // may have expired or been collected.
+    let (t0, t1, t2, t3, t4, t5) = ts6();
+    let h = History::from(vec![
+        put_with_ttl("k", 1, b"hello", t0, t1, t1),
+        get_expired("k", 1, b"hello", t2, t3),
+        get_not_found("k", t4, t5),
+    ]);
+    assert!(h.check_correctness().is_empty());
+
+    // Wrong bytes or an unknown version are still flagged.
+    let h = History::from(vec![
+        put_with_ttl("k", 1, b"hello", t0, t1, t1),
+        get_expired("k", 1, b"world", t2, t3),
+        get_expired("k", 7, b"hello", t4, t5),
+    ]);
+    let v = h.check_correctness();
+    assert_eq!(v.len(), 2);
+    assert!(matches!(
+        &v[0].kind,
+        ViolationKind::ValueMismatch { expected, actual }
+            if expected == b"hello" && actual == b"world"
+    ));
+    assert!(matches!(&v[1].kind, ViolationKind::VersionNotFound { .. }));
+}
+
+// --- TTL ---
+
+#[test]
+fn test_not_found_before_ttl_elapses_is_flagged() {
+    // NotFound is only explained by a TTL that could have elapsed by the GET's ACK.
+    let (t0, t1, t2, t3, _, _) = ts6();
+    let later = t3 + Duration::from_secs(10);
+    let h = History::from(vec![put_with_ttl("k", 1, b"hello", t0, t1, later), get_not_found("k", t2, t3)]);
+    let v = h.check_correctness();
+    assert_eq!(v.len(), 1);
+    assert!(matches!(v[0].kind, ViolationKind::UnexpectedNotFound { expected_version: Version(1) }));
+
+    // Within the clock tolerance of the ACK, expiry may already have happened.
+    let soon = t3 + Duration::from_millis(500);
+    let h = History::from(vec![put_with_ttl("k", 1, b"hello", t0, t1, soon), get_not_found("k", t2, t3)]);
+    assert!(h.check_correctness().is_empty());
+}
+
+#[test]
+fn test_strict_get_of_expired_value_is_flagged() {
+    let (t0, t1, t2, t3, _, _) = ts6();
+    let (start, ack) = (t1 + Duration::from_secs(2), t1 + Duration::from_secs(3));
+    let h = History::from(vec![
+        put_with_ttl("k", 1, b"hello", t0, t1, t1),
+        // Right after expiry the server's clock may not have got there yet.
+        get("k", 1, b"hello", t2, t3),
+        get("k", 1, b"hello", start, ack),
+        // Reading past the TTL is what GET allowing expired is for.
+        get_expired("k", 1, b"hello", start, ack),
+    ]);
+    let v = h.check_all();
+    assert_eq!(v.len(), 1);
+    assert!(matches!(
+        v[0].kind,
+        ViolationKind::ExpiredValueReturned { expires_at, get_start_ts } if expires_at == t1 && get_start_ts == start
+    ));
+}
+
+#[test]
+fn test_expired_value_outranks_stale_read() {
+    // v1 is both stale (v2 was ACKed first) and expired; the hard violation wins.
+    let (t0, t1, t2, t3, _, _) = ts6();
+    let (start, ack) = (t3 + Duration::from_secs(2), t3 + Duration::from_secs(3));
+    let h = History::from(vec![
+        put_with_ttl("k", 1, b"hello", t0, t1, t1),
+        put("k", 2, b"world", t2, t3),
+        get("k", 1, b"hello", start, ack),
+    ]);
+    let v = h.check_correctness();
+    assert_eq!(v.len(), 1);
+    assert!(matches!(v[0].kind, ViolationKind::ExpiredValueReturned { .. }));
+}
+
 // --- StaleDataReturned ---
 
 #[test]
 fn test_stale_data_returned_when_newer_version_was_acked() {
     // PUT v1 then PUT v2 (both acked). GET returns v1 — stale.
     let (t0, t1, t2, t3, t4, t5) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"first", t0, t1),
         put("k", 2, b"second", t2, t3),
         get("k", 1, b"first", t4, t5),
@@ -304,7 +488,7 @@ fn test_stale_data_returned_when_newer_version_was_acked() {
     assert_eq!(v.len(), 1);
     assert!(matches!(
         &v[0].kind,
-        ViolationKind::StaleDataReturned { latest_known_version: 2 }
+        ViolationKind::StaleDataReturned { latest_known_version: Version(2) }
     ));
 }
 
@@ -312,17 +496,160 @@ fn test_stale_data_returned_when_newer_version_was_acked() {
 fn test_no_stale_violation_when_newer_put_not_yet_acked() {
     // PUT v2 started but not yet ACKed when GET started — not stale from client's view.
     let (t0, t1, t2, t3, t4, t5) = ts6();
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"first", t0, t1),
         put("k", 2, b"second", t2, t5),
         get("k", 1, b"first", t3, t4),
     ]);
     assert!(h.check_correctness().is_empty());
 
-    let h = History(vec![
+    let h = History::from(vec![
         put("k", 1, b"first", t0, t1),
         put("k", 2, b"second", t2, t5),
         get("k", 2, b"second", t3, t4),
     ]);
     assert!(h.check_correctness().is_empty());
 }
+
+// --- UnexpectedNotFound ---
+
+#[test]
+fn test_unexpected_not_found_after_acked_put() {
+    // PUT acked, nothing removes the key, GET still says NotFound → lost data.
+    let (t0, t1, t2, t3, ..) = ts6();
+    let h = History::from(vec![
+        put("k", 1, b"hello", t0, t1),
+        get_not_found("k", t2, t3),
+    ]);
+    let v = h.check_correctness();
+    assert_eq!(v.len(), 1);
+    assert_eq!(v[0].version, Version(1));
+    assert!(matches!(v[0].kind, ViolationKind::UnexpectedNotFound { expected_version: Version(1) }));
+
+    // The latest acked write wins: a re-PUT after a delete must also be visible.
+    let (t0, t1, t2, t3, t4, t5, t6) = ts7();
+    let h = History::from(vec![
+        put("k", 1, b"a", t0, t1),
+        delete("k", 2, t1, t2),
+        put("k", 3, b"b", t3, t4),
+        get_not_found("k", t5, t6),
+    ]);
+    assert!(matches!(
+        h.check_correctness()[..],
+        [Violation { kind: ViolationKind::UnexpectedNotFound { expected_version: Version(3) }, .. }]
+    ));
+}
+
+#[test]
+fn test_not_found_is_consistent_when_deleted_or_ambiguous() {
+    let (t0, t1, t2, t3, t4, t5) = ts6();
+    let cases = [
+        // Never written.
+        vec![get_not_found("k", t0, t1)],
+        // Legitimately deleted before the GET started.
+        vec![put("k", 1, b"v", t0, t1), delete("k", 2, t2, t3), get_not_found("k", t4, t5)],
+        // DELETE overlaps the GET.
+        vec![put("k", 1, b"v", t0, t1), delete("k", 2, t2, t5), get_not_found("k", t3, t4)],
+        // PUT not yet acked when the GET started.
+        vec![put("k", 1, b"v", t0, t3), get_not_found("k", t2, t4)],
+        // A DELETE failed with unknown effect.
+        vec![put("k", 1, b"v", t0, t1), failed_delete("k", t2, t3), get_not_found("k", t4, t5)],
+    ];
+    for (i, records) in cases.into_iter().enumerate() {
+        assert!(History::from(records).check_correctness().is_empty(), "case {i} flagged a violation");
+    }
+}
+
+// --- Bounded history ---
+
+#[test]
+fn test_bounded_history_evicts_oldest_and_checks_retained_window() {
+    let (t0, t1, t2, t3, t4, t5, t6, t7) = ts8();
+    let mut h = History::new(Some(3));
+    h.push(put("k", 1, b"v1", t0, t1));
+    h.push(put("k", 2, b"v2", t1, t2));
+    h.push(get("k", 2, b"v2", t2, t3));
+    h.push(get("k", 1, b"v1", t3, t4));
+    assert_eq!(h.records_dropped(), 1);
+    // Warm-up records are older than everything held, so they are evicted first.
+    h.prepend(vec![put("w", 0, b"w", t0, t0)]);
+    assert_eq!(h.records_dropped(), 2);
+    assert_eq!(h.iter().map(|r| r.client_start_ts).collect::<Vec<_>>(), [t1, t2, t3]);
+
+    // v1's PUT was evicted, so the read of it cannot be checked.
+    assert!(h.check_correctness().is_empty());
+
+    // Versions written within the window are still checked; an evicted one never is.
+    h.push(delete("k", 3, t5, t6));
+    h.push(get("k", 3, b"v3", t6, t7));
+    h.push(get("k", 2, b"xx", t7, after(t7)));
+    assert!(matches!(
+        h.check_correctness()[..],
+        [Violation { kind: ViolationKind::VersionNotFound { .. }, version: Version(3), .. }]
+    ));
+}
+
+// --- Causal consistency ---
+
+fn causal_versions(h: &History) -> Vec<(Version, Version)> {
+    h.check_causal_consistency()
+        .iter()
+        .map(|v| match v.kind {
+            ViolationKind::CausalOrderViolated { known_version } => (v.version, known_version),
+            _ => panic!("check_causal_consistency reports only CausalOrderViolated"),
+        })
+        .collect()
+}
+
+#[test]
+fn test_causal_violation_when_get_misses_a_write_acked_before_it() {
+    // PUT(k, v5) ACKed before the GET started, so the GET must not return anything older.
+    let (t0, t1, t2, t3, t4, t5) = ts6();
+    let h = History::from(vec![
+        put("k", 4, b"old", t0, t1),
+        put("k", 5, b"new", t2, t3),
+        get("k", 4, b"old", t4, t5),
+    ]);
+    assert_eq!(causal_versions(&h), [(Version(4), Version(5))]);
+
+    // A DELETE's tombstone counts as a write too.
+    let h = History::from(vec![
+        put("k", 4, b"old", t0, t1),
+        delete("k", 5, t2, t3),
+        get("k", 4, b"old", t4, t5),
+    ]);
+    assert_eq!(causal_versions(&h), [(Version(4), Version(5))]);
+}
+
+#[test]
+fn test_causal_violation_when_get_goes_back_past_an_earlier_read() {
+    // PUT v2 is still in flight, so check_correctness cannot flag the second GET; but the first
+    // GET had already seen v2 when the second one started.
+    let (t0, t1, t2, t3, t4, t5, t6, t7) = ts8();
+    let h = History::from(vec![
+        put("k", 1, b"first", t0, t1),
+        get("k", 2, b"second", t3, t4),
+        get("k", 1, b"first", t5, t6),
+        put("k", 2, b"second", t2, t7),
+    ]);
+    assert!(h.check_correctness().is_empty());
+    assert_eq!(causal_versions(&h), [(Version(1), Version(2))]);
+}
+
+#[test]
+fn test_no_causal_violation_for_concurrent_or_other_key_operations() {
+    let (t0, t1, t2, t3, t4, t5) = ts6();
+    let h = History::from(vec![
+        put("k", 1, b"first", t0, t1),
+        // Overlaps the GET below, which may order before it.
+        put("k", 3, b"third", t2, t5),
+        get("k", 1, b"first", t3, t4),
+        // Another key's newer version says nothing about "k".
+        put("other", 2, b"x", t0, t1),
+        get("other", 2, b"x", t2, t3),
+        // NotFound reads are left to check_correctness.
+        get_not_found("k", t4, t5),
+    ]);
+    assert!(causal_versions(&h).is_empty());
+    assert!(History::from(vec![]).check_causal_consistency().is_empty());
+}
@@ -1,7 +1,11 @@
-use transdb_stress_tests::metrics::Metrics;
+use std::time::Duration;
+use transdb_common::StoreStats;
+use transdb_stress_tests::history::OpKind;
+use transdb_stress_tests::metrics::{gc_effectiveness, CounterSnapshot, LiveCounters, Metrics};
 
 fn make(latency_ns: Vec<u64>, errors_5xx: u64, requests_total: u64, elapsed_secs: f64) -> Metrics {
-    Metrics { requests_total, errors_5xx, latency_ns, elapsed_secs }
+    let latency_ns = latency_ns.into_iter().collect();
+    Metrics { requests_total, errors_5xx, latency_ns, elapsed_secs, ..Default::default() }
 }
 
 #[test]
@@ -31,9 +35,109 @@ fn test_percentiles_empty_returns_zero() {
     assert_eq!(m.p99_ns(), 0);
 }
 
+#[test]
+fn test_lock_percentiles() {
+    let mut m = make(vec![], 0, 0, 1.0);
+    assert_eq!(m.lock_wait_p99_ns(), 0);
+    m.lock_wait_ns = (1..=100).collect();
+    m.lock_hold_ns = [7, 3, 5].into_iter().collect();
+    assert_eq!(m.lock_wait_p99_ns(), 100);
+    assert_eq!(m.lock_hold_p99_ns(), 7);
+}
+
+#[test]
+fn test_latency_by_kind_separates_fast_reads_from_slow_writes() {
+    let mut m = Metrics::default();
+    assert_eq!(m.latency_by_kind.iter().count(), 0);
+    // GETs take 1–100 µs, PUTs 1–100 ms, interleaved as a mixed workload would record them.
+    for i in 1..=100u64 {
+        m.record_latency(OpKind::Get, i * 1_000);
+        m.record_latency(OpKind::Put, i * 1_000_000);
+    }
+
+    let (get, put) = (m.latency_by_kind.of(OpKind::Get), m.latency_by_kind.of(OpKind::Put));
+    assert_eq!((get.len(), put.len(), m.latency_ns.len()), (100, 100, 200));
+    // Index floor(0.5 * 100) = 50 holds the 51st value; p99 is the maximum. Both within 1/128.
+    let close = |actual: u64, expected: u64| actual <= expected && expected - actual <= expected / 128;
+    assert!(close(get.percentile(0.50), 51_000), "{}", get.percentile(0.50));
+    assert!(close(put.percentile(0.50), 51_000_000), "{}", put.percentile(0.50));
+    assert_eq!((get.percentile(0.99), put.percentile(0.99)), (100_000, 100_000_000));
+    // Overall, the median lands at the fastest PUT and hides both distributions.
+    assert!(close(m.p50_ns(), 1_000_000), "{}", m.p50_ns());
+
+    let kinds: Vec<_> = m.latency_by_kind.iter().map(|(kind, _)| kind.as_name()).collect();
+    assert_eq!(kinds, ["GET", "PUT"]);
+}
+
 #[test]
 fn test_error_rate_and_throughput() {
     let m = make(vec![], 1, 10, 2.0);
     assert_eq!(m.error_rate(), 0.1);
     assert_eq!(m.throughput_rps(), 5.0);
 }
+
+#[test]
+fn test_error_rate_and_throughput_without_requests_or_time() {
+    let m = make(vec![], 0, 0, 2.0);
+    assert_eq!(m.error_rate(), 0.0);
+    assert_eq!(m.throughput_rps(), 0.0);
+
+    let m = make(vec![], 0, 10, 0.0);
+    assert_eq!(m.throughput_rps(), 0.0);
+    assert_eq!(m.delete_throughput(), 0.0);
+}
+
+#[test]
+fn test_delete_drain_metrics() {
+    let mut m = make(vec![], 0, 400, 2.0);
+    m.keys_populated = 1000;
+    m.tombstones_written = 250;
+    assert_eq!(m.keys_remaining(), 750);
+    assert!((m.delete_throughput() - 125.0).abs() < f64::EPSILON);
+    assert!((m.drain_rate() - 0.25).abs() < f64::EPSILON);
+
+    // Without a warm-up there is nothing to drain.
+    let m = make(vec![], 0, 10, 1.0);
+    assert_eq!(m.keys_remaining(), 0);
+    assert_eq!(m.drain_rate(), 0.0);
+}
+
+#[test]
+fn test_gc_effectiveness() {
+    let stats = |live_count, expired_count, tombstone_count| StoreStats {
+        live_count,
+        expired_count,
+        tombstone_count,
+        ..Default::default()
+    };
+    assert_eq!(gc_effectiveness(&stats(95, 5, 0)), 0.95);
+    // Tombstones are neither live nor garbage yet, so they do not move the ratio.
+    assert_eq!(gc_effectiveness(&stats(95, 5, 1000)), 0.95);
+    assert_eq!(gc_effectiveness(&stats(0, 10, 0)), 0.0);
+    assert_eq!(gc_effectiveness(&stats(0, 0, 3)), 1.0);
+}
+
+#[test]
+fn test_interval_snapshots() {
+    let live = LiveCounters::default();
+    let start = live.snapshot();
+    assert_eq!(start, CounterSnapshot::default());
+    assert_eq!(start.error_rate(), 0.0);
+
+    for i in 0..30 {
+        live.record(i % 10 == 0);
+    }
+    let first = live.snapshot();
+    assert_eq!(first, CounterSnapshot { requests: 30, errors: 3 });
+    // 30 requests in a 2 s window.
+    assert_eq!(first.throughput_since(&start, Duration::from_secs(2)), 15.0);
+
+    for _ in 0..10 {
+        live.record(false);
+    }
+    let second = live.snapshot();
+    // Throughput only counts the window; the error rate is cumulative.
+    assert_eq!(second.throughput_since(&first, Duration::from_millis(500)), 20.0);
+    assert_eq!(second.error_rate(), 3.0 / 40.0);
+    assert_eq!(second.throughput_since(&first, Duration::ZERO), 0.0);
+}
@@ -0,0 +1,32 @@
+use transdb_common::Version;
+use transdb_stress_tests::multi_cas::{find_write_skew, Commit, WriteSkew};
+
+fn commit(reads: &[(&str, u64)], writes: &[(&str, u64)]) -> Commit {
+    let pairs = |pairs: &[(&str, u64)]| pairs.iter().map(|(key, v)| (key.to_string(), Version(*v))).collect();
+    Commit { reads: pairs(reads), writes: pairs(writes) }
+}
+
+#[test]
+fn test_serial_commits_have_no_write_skew() {
+    // Listed out of order: the checker orders commits by the versions they wrote.
+    let commits = [
+        commit(&[("a", 1), ("b", 0)], &[("b", 2)]),
+        commit(&[("a", 0), ("b", 0)], &[("a", 1)]),
+        commit(&[("a", 1), ("b", 2)], &[("a", 3)]),
+    ];
+    assert_eq!(find_write_skew(&commits), []);
+}
+
+#[test]
+fn test_commits_reading_the_same_snapshot_are_write_skew() {
+    // Both read (a@0, b@0), then each wrote a different key: no serial order explains that.
+    let commits = [commit(&[("a", 0), ("b", 0)], &[("a", 1)]), commit(&[("a", 0), ("b", 0)], &[("b", 2)])];
+    assert_eq!(find_write_skew(&commits), [WriteSkew { key: "a".to_string(), read: Version(0), latest: Version(1) }]);
+}
+
+#[test]
+fn test_read_of_an_unrecorded_write_is_not_flagged() {
+    // Version 5 of "a" came from a request whose response was lost, so no commit recorded it.
+    let commits = [commit(&[("a", 0)], &[("a", 1)]), commit(&[("a", 5)], &[("a", 6)])];
+    assert_eq!(find_write_skew(&commits), []);
+}
@@ -0,0 +1,180 @@
+use std::time::{Duration, UNIX_EPOCH};
+use transdb_common::Version;
+use transdb_stress_tests::histogram::Histogram;
+use transdb_stress_tests::history::{Violation, ViolationKind};
+use transdb_stress_tests::metrics::Metrics;
+use transdb_stress_tests::multi_cas::WriteSkew;
+use transdb_stress_tests::report::{
+    rfc3339_utc, write_atomically, LatencySummary, MetricsSummary, Report, RunSummary, StressTestResult, Threshold,
+    Verdict, ViolationSummary,
+};
+
+fn make_report(violations: Vec<ViolationSummary>, verdict: Verdict) -> Report {
+    let metrics = Metrics {
+        requests_total: 10,
+        errors_5xx: 3,
+        faults_injected: 1,
+        latency_ns: (1..=10).map(|ms| ms * 1_000_000).collect(),
+        elapsed_secs: 2.0,
+        ..Default::default()
+    };
+    Report {
+        config: RunSummary {
+            workload: "read-heavy".to_string(),
+            duration_secs: 2,
+            key_space: 100,
+            final_key_space: 100,
+            seed: 42,
+            value_size_distribution: "uniform".to_string(),
+            rate: None,
+            concurrency: None,
+        },
+        metrics: MetricsSummary::new(&metrics),
+        violation_count: violations.len() as u64,
+        causal_violation_count: 0,
+        violations,
+        verdict,
+    }
+}
+
+#[test]
+fn test_metrics_summary() {
+    let metrics = Metrics {
+        requests_total: 10,
+        errors_5xx: 3,
+        faults_injected: 1,
+        multi_cas_conflicts: 4,
+        latency_ns: (1..=10).rev().map(|ms| ms * 1_000_000).collect(),
+        elapsed_secs: 2.0,
+        ..Default::default()
+    };
+    let summary = MetricsSummary::new(&metrics);
+    assert_eq!(summary.throughput_rps, 5.0);
+    // 6 ms lands in a bucket starting just below it; the top of the range is the exact maximum.
+    assert!((5.95..=6.0).contains(&summary.latency.p50_ms), "{}", summary.latency.p50_ms);
+    assert_eq!((summary.latency.p99_ms, summary.latency.p999_ms, summary.latency.max_ms), (10.0, 10.0, 10.0));
+    assert_eq!(summary.latency.mean_ms, 5.5);
+    assert_eq!(summary.latency.buckets.len(), 10);
+    assert_eq!(summary.latency.buckets.iter().map(|b| b.count).sum::<u64>(), 10);
+    assert!(summary.latency.buckets.iter().all(|b| b.low_ns <= b.high_ns));
+    assert_eq!(summary.response_latency, None);
+    assert_eq!((summary.errors.server, summary.errors.injected, summary.errors.multi_cas_conflicts), (2, 1, 4));
+    assert_eq!(summary.error_rate, 0.3);
+}
+
+#[test]
+fn test_latency_summary_empty_is_zero() {
+    let summary = LatencySummary::new(&Histogram::default());
+    assert_eq!((summary.p50_ms, summary.p999_ms, summary.max_ms, summary.mean_ms), (0.0, 0.0, 0.0, 0.0));
+    assert!(summary.buckets.is_empty());
+}
+
+#[test]
+fn test_violation_summaries() {
+    let stale = Violation {
+        key: "k".to_string(),
+        version: Version(3),
+        kind: ViolationKind::StaleDataReturned { latest_known_version: Version(5) },
+    };
+    assert_eq!(
+        ViolationSummary::new(&stale),
+        ViolationSummary {
+            key: "k".to_string(),
+            version: 3,
+            kind: "StaleDataReturned".to_string(),
+            detail: "v5 was already acked".to_string(),
+        }
+    );
+
+    let skew = WriteSkew { key: "a".to_string(), read: Version(1), latest: Version(2) };
+    let summary = ViolationSummary::write_skew(&skew);
+    assert_eq!((summary.kind.as_str(), summary.detail.as_str()), ("WriteSkew", "read after v2 was committed"));
+}
+
+#[test]
+fn test_verdict_reports_the_first_failed_threshold() {
+    assert_eq!(Verdict::new(vec![]), Verdict { pass: true, exit_code: 0, failed: vec![] });
+
+    let verdict = Verdict::new(vec![Threshold::OpMix, Threshold::Violations, Threshold::OpMix]);
+    assert!(!verdict.pass);
+    assert_eq!(verdict.exit_code, 2);
+    assert_eq!(verdict.failed, [Threshold::Violations, Threshold::OpMix]);
+}
+
+#[test]
+fn test_report_round_trips_through_json() {
+    let violation = Violation {
+        key: "k".to_string(),
+        version: Version(7),
+        kind: ViolationKind::UnexpectedNotFound { expected_version: Version(7) },
+    };
+    let report = make_report(vec![ViolationSummary::new(&violation)], Verdict::new(vec![Threshold::Violations]));
+
+    let json = serde_json::to_string_pretty(&report).unwrap();
+    let parsed: Report = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, report);
+
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["config"]["workload"], "read-heavy");
+    assert_eq!(value["config"]["duration_secs"], 2);
+    assert_eq!(value["config"]["key_space"], 100);
+    assert_eq!(value["config"]["seed"], 42);
+    assert_eq!(value["config"]["value_size_distribution"], "uniform");
+    assert!(value["config"]["concurrency"].is_null());
+    assert_eq!(value["metrics"]["requests_total"], 10);
+    assert_eq!(value["metrics"]["throughput_rps"], 5.0);
+    for p in ["p50_ms", "p90_ms", "p95_ms", "p99_ms", "p999_ms", "max_ms", "mean_ms"] {
+        assert!(value["metrics"]["latency"][p].is_f64(), "missing {p}");
+    }
+    let buckets = value["metrics"]["latency"]["buckets"].as_array().unwrap();
+    assert_eq!(buckets.iter().map(|b| b["count"].as_u64().unwrap()).sum::<u64>(), 10);
+    assert_eq!(value["metrics"]["errors"]["server"], 2);
+    assert_eq!(value["metrics"]["errors"]["injected"], 1);
+    assert_eq!(value["violation_count"], 1);
+    assert_eq!(value["violations"][0]["kind"], "UnexpectedNotFound");
+    assert_eq!(value["violations"][0]["detail"], "v7 was acked and never deleted");
+    assert_eq!(value["verdict"]["pass"], false);
+    assert_eq!(value["verdict"]["exit_code"], 2);
+    assert_eq!(value["verdict"]["failed"], serde_json::json!(["violations"]));
+}
+
+#[test]
+fn test_stress_test_result_round_trips_through_json() {
+    let report = make_report(vec![], Verdict::new(vec![Threshold::ErrorRate]));
+    let result = StressTestResult {
+        run_id: "67e55044-10b1-426f-9247-bb680e5fe0c8".to_string(),
+        timestamp: "2025-01-01T00:00:00Z".to_string(),
+        config: serde_json::json!({ "workload": "read-heavy", "seed": 42 }),
+        metrics: report.metrics,
+        violations: report.violations,
+        verdict: report.verdict,
+    };
+
+    let json = serde_json::to_string(&result).unwrap();
+    assert_eq!(serde_json::from_str::<StressTestResult>(&json).unwrap(), result);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["run_id"], "67e55044-10b1-426f-9247-bb680e5fe0c8");
+    assert_eq!(value["config"]["workload"], "read-heavy");
+    assert_eq!(value["metrics"]["requests_total"], 10);
+    assert_eq!(value["verdict"]["exit_code"], 1);
+}
+
+#[test]
+fn test_rfc3339_utc() {
+    let at = |secs| rfc3339_utc(UNIX_EPOCH + Duration::from_secs(secs));
+    assert_eq!(at(0), "1970-01-01T00:00:00Z");
+    assert_eq!(at(1_735_689_600), "2025-01-01T00:00:00Z");
+    assert_eq!(at(951_782_400 + 3_661), "2000-02-29T01:01:01Z");
+    assert_eq!(at(4_107_542_399), "2100-02-28T23:59:59Z");
+    assert_eq!(at(4_107_542_400), "2100-03-01T00:00:00Z");
+}
+
+#[test]
+fn test_write_atomically_replaces_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("result.json");
+    write_atomically(&path, "first").unwrap();
+    write_atomically(&path, "second").unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "second");
+    assert!(!dir.path().join("result.json.tmp").exists());
+}
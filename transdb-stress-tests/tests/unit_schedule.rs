@@ -0,0 +1,28 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::time::{Duration, Instant};
+use transdb_stress_tests::schedule::{Schedule, JITTER};
+
+#[test]
+fn test_due_times_stay_near_their_slots_and_never_decrease() {
+    let start = Instant::now();
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut schedule = Schedule::new(start, 250.0);
+    let interval = Duration::from_millis(4);
+    let mut previous = start;
+    for i in 0..10_000u32 {
+        let due = schedule.next_due(&mut rng);
+        assert!(due >= previous, "slot {i} is due before slot {}", i - 1);
+        let offset = due.duration_since(start).as_secs_f64() - (interval * i).as_secs_f64();
+        assert!(offset.abs() <= JITTER * interval.as_secs_f64() + 1e-9, "slot {i} is {offset}s off");
+        previous = due;
+    }
+    // 10,000 operations at 250 per second take 40 seconds.
+    assert!(previous.duration_since(start).as_secs_f64() > 39.99);
+}
+
+#[test]
+#[should_panic(expected = "rate must be a positive number")]
+fn test_zero_rate_is_rejected() {
+    Schedule::new(Instant::now(), 0.0);
+}
@@ -1,9 +1,18 @@
+use bytes::Bytes;
 use rand::{rngs::StdRng, SeedableRng};
+use std::time::Duration;
+use transdb_client::ClientConfig;
+use transdb_common::{Topology, Version};
 use transdb_stress_tests::history::OpOutcome;
-use transdb_stress_tests::worker::{generate_value, is_error};
+use transdb_stress_tests::metrics::{CounterSnapshot, LiveCounters};
+use transdb_stress_tests::worker::{
+    self, generate_value, is_error, plan_op, worker_seed, RunConfig, PUT_TTL_SECS, TTL_OFFSET_SECS,
+};
+use transdb_stress_tests::workload::{Op, ValueSizeDistribution, WorkloadProfile};
 
-// `worker::run` requires a live HTTP server and is inherently integration-level.
-// The two helpers exposed by worker.rs cover all of the pure, testable logic.
+// `worker::run` normally requires a live HTTP server and is inherently integration-level.
+// The two helpers exposed by worker.rs cover the pure, testable logic; a 100% fault rate is the
+// one configuration of `run` that never touches the network.
 
 #[test]
 fn test_generate_value_and_is_error() {
@@ -13,7 +22,7 @@ fn test_generate_value_and_is_error() {
     let mut all_same = true;
     let mut prev: Option<Vec<u8>> = None;
     for _ in 0..50 {
-        let v = generate_value(&mut rng);
+        let v = generate_value(&mut rng, &ValueSizeDistribution::default());
         assert!(v.len() >= 8, "value too short: {}", v.len());
         assert!(v.len() <= 1024, "value too long: {}", v.len());
         if let Some(ref p) = prev {
@@ -28,7 +37,161 @@ fn test_generate_value_and_is_error() {
     // is_error: only OpOutcome::Error should return true.
     assert!(is_error(&OpOutcome::Error));
     assert!(!is_error(&OpOutcome::NotFound));
-    assert!(!is_error(&OpOutcome::DeleteOk { version: 1 }));
-    assert!(!is_error(&OpOutcome::GetOk { version: 1, value: vec![1] }));
-    assert!(!is_error(&OpOutcome::PutOk { version: 1, value: vec![1] }));
+    assert!(!is_error(&OpOutcome::DeleteOk { version: Version(1) }));
+    assert!(!is_error(&OpOutcome::GetOk { version: Version(1), value: Bytes::from_static(&[1]), expired: false }));
+    assert!(!is_error(&OpOutcome::PutOk { version: Version(1), value: Bytes::from_static(&[1]), expires_at: None }));
+}
+
+#[tokio::test]
+async fn test_full_fault_rate_fails_every_op_without_violations() {
+    // Nothing listens here; every op must be short-circuited before the client dials out.
+    let topology = Topology {
+        primary_addr: "127.0.0.1:1".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
+    let config = ClientConfig::new(topology);
+    let run_config = RunConfig {
+        profile: WorkloadProfile::Balanced,
+        key_space: 10,
+        duration: Duration::from_millis(10),
+        fault_rate: 1.0,
+        max_history_records: Some(5),
+        adaptive_key_space: false,
+        rate: None,
+        concurrency: 1,
+        seed: 1,
+        value_size: ValueSizeDistribution::default(),
+        ttl_fraction: 0.0,
+    };
+    let live = LiveCounters::default();
+    let (metrics, history) = worker::run(&config, &run_config, &live).await;
+
+    assert!(metrics.requests_total > 0);
+    assert_eq!(metrics.errors_5xx, metrics.requests_total);
+    assert_eq!(metrics.faults_injected, metrics.requests_total);
+    assert_eq!(live.snapshot(), CounterSnapshot { requests: metrics.requests_total, errors: metrics.errors_5xx });
+    assert_eq!(history.iter().count() as u64, metrics.requests_total.min(5));
+    assert_eq!(metrics.records_dropped, metrics.requests_total.saturating_sub(5));
+    assert!(history.iter().all(|r| matches!(r.outcome, OpOutcome::Error)));
+    assert!(history.check_correctness().is_empty());
+}
+
+#[tokio::test]
+async fn test_open_loop_launches_ops_at_the_target_rate() {
+    let topology = Topology {
+        primary_addr: "127.0.0.1:1".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
+    let config = ClientConfig::new(topology);
+    let run_config = RunConfig {
+        profile: WorkloadProfile::Balanced,
+        key_space: 10,
+        duration: Duration::from_millis(200),
+        fault_rate: 1.0,
+        max_history_records: None,
+        adaptive_key_space: false,
+        rate: Some(1000.0),
+        concurrency: 4,
+        seed: 1,
+        value_size: ValueSizeDistribution::default(),
+        ttl_fraction: 0.0,
+    };
+    let live = LiveCounters::default();
+    let (metrics, history) = worker::run(&config, &run_config, &live).await;
+
+    // 200 slots fall due within 200 ms; jitter can pull the next one in before the end.
+    assert!((200..=201).contains(&metrics.requests_total), "{} ops", metrics.requests_total);
+    assert_eq!(metrics.op_counts.total(), metrics.requests_total);
+    assert_eq!(metrics.faults_injected, metrics.requests_total);
+    assert_eq!(history.iter().count() as u64, metrics.requests_total);
+    let (response, service) = (&metrics.response_latency_ns, &metrics.latency_ns);
+    assert_eq!(response.len(), service.len());
+    // Latency from the due time covers the time to start as well as the service time, so every
+    // point of its distribution is at least as high.
+    for p in [0.0, 0.5, 0.9, 0.99, 1.0] {
+        assert!(response.percentile(p) >= service.percentile(p), "p{p}");
+    }
+    assert!(response.mean() >= service.mean());
+}
+
+#[test]
+fn test_plan_op_is_reproducible_from_a_seed() {
+    let plan = |seed: u64| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let value_size = ValueSizeDistribution::default();
+        (0..100).map(|_| plan_op(WorkloadProfile::Balanced, 50, &value_size, 0.0, &mut rng)).collect::<Vec<_>>()
+    };
+    let ops = plan(42);
+    assert_eq!(ops, plan(42));
+    assert_ne!(ops, plan(43));
+    for planned in &ops {
+        let is_write = matches!(planned.op, Op::Put | Op::PutWithTtl);
+        assert_eq!(planned.value.is_some(), is_write, "{planned:?}");
+    }
+    assert_eq!((worker_seed(42, 0), worker_seed(42, 3), worker_seed(u64::MAX, 1)), (42, 45, 0));
+}
+
+#[test]
+fn test_plan_op_gives_a_ttl_fraction_of_puts_a_ttl() {
+    let value_size = ValueSizeDistribution::default();
+    let plan = |profile: WorkloadProfile, ttl_fraction: f64| {
+        let mut rng = StdRng::seed_from_u64(42);
+        (0..1000).map(|_| plan_op(profile, 50, &value_size, ttl_fraction, &mut rng)).collect::<Vec<_>>()
+    };
+    assert!(plan(WorkloadProfile::PutOnly, 0.0).iter().all(|p| p.op == Op::Put && p.expires_in_secs.is_none()));
+    assert!(plan(WorkloadProfile::PutOnly, 1.0).iter().all(|p| p.op == Op::PutWithTtl && p.value.is_some()));
+
+    let with_ttl: Vec<_> =
+        plan(WorkloadProfile::PutOnly, 0.5).into_iter().filter(|p| p.op == Op::PutWithTtl).collect();
+    assert!((400..=600).contains(&with_ttl.len()), "{} of 1000", with_ttl.len());
+    assert!(with_ttl.iter().all(|p| p.expires_in_secs.is_some_and(|secs| TTL_OFFSET_SECS.contains(&secs))));
+    assert!(with_ttl.iter().any(|p| p.expires_in_secs < Some(0)), "some are written already expired");
+
+    // The ttl-reads workload's own TTL'd PUTs keep their short, fixed lifetime.
+    let ttl_reads = plan(WorkloadProfile::TtlReads, 0.0);
+    let ttl_puts: Vec<_> = ttl_reads.iter().filter(|p| p.op == Op::PutWithTtl).collect();
+    assert!(!ttl_puts.is_empty());
+    assert!(ttl_puts.iter().all(|p| p.expires_in_secs == Some(PUT_TTL_SECS)));
+}
+
+#[tokio::test]
+async fn test_runs_with_the_same_seed_issue_the_same_ops() {
+    // As above, every op is failed locally; what is compared is the sequence the worker issued.
+    let topology = Topology {
+        primary_addr: "127.0.0.1:1".to_string(),
+        replica_addr: None,
+        primary_id: None,
+        replica_id: None,
+    };
+    let config = ClientConfig::new(topology);
+    let first_ops = |seed: u64| {
+        let run_config = RunConfig {
+            profile: WorkloadProfile::Balanced,
+            key_space: 1000,
+            duration: Duration::from_millis(20),
+            fault_rate: 1.0,
+            max_history_records: None,
+            adaptive_key_space: false,
+            rate: None,
+            concurrency: 1,
+            seed,
+            value_size: ValueSizeDistribution::default(),
+        ttl_fraction: 0.0,
+        };
+        let config = config.clone();
+        async move {
+            let (_, history) = worker::run(&config, &run_config, &LiveCounters::default()).await;
+            let ops: Vec<_> = history.iter().take(100).map(|r| (r.kind, r.key.clone())).collect();
+            assert_eq!(ops.len(), 100, "the run is long enough to record 100 ops");
+            ops
+        }
+    };
+
+    let ops = first_ops(7).await;
+    assert_eq!(ops, first_ops(7).await);
+    assert_ne!(ops, first_ops(8).await);
 }
@@ -1,4 +1,8 @@
-use transdb_stress_tests::workload::{Op, WorkloadProfile};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use transdb_stress_tests::workload::{
+    Op, OpCounts, OpMix, ValueSizeDistribution, WarmupMode, WorkloadProfile, MAX_VALUE_SIZE,
+};
 
 #[test]
 fn test_from_name_roundtrip() {
@@ -7,6 +11,8 @@ fn test_from_name_roundtrip() {
         ("balanced", WorkloadProfile::Balanced),
         ("write-heavy", WorkloadProfile::WriteHeavy),
         ("put-only", WorkloadProfile::PutOnly),
+        ("delete-only", WorkloadProfile::DeleteOnly),
+        ("ttl-reads", WorkloadProfile::TtlReads),
     ] {
         let parsed = WorkloadProfile::from_name(name);
         assert_eq!(parsed, Some(expected), "from_name({name:?}) failed");
@@ -42,4 +48,92 @@ fn test_profile_boundaries() {
     // PutOnly: every roll is a PUT
     assert_eq!(WorkloadProfile::PutOnly.op_for_roll(0), Op::Put);
     assert_eq!(WorkloadProfile::PutOnly.op_for_roll(99), Op::Put);
+
+    // DeleteOnly: every roll is a DELETE
+    assert_eq!(WorkloadProfile::DeleteOnly.op_for_roll(0), Op::Delete);
+    assert_eq!(WorkloadProfile::DeleteOnly.op_for_roll(99), Op::Delete);
+
+    // TtlReads: [0,20) → GET, [20,70) → GET allowing expired, [70,100) → PUT with TTL
+    assert_eq!(WorkloadProfile::TtlReads.op_for_roll(19), Op::Get);
+    assert_eq!(WorkloadProfile::TtlReads.op_for_roll(20), Op::GetAllowingExpired);
+    assert_eq!(WorkloadProfile::TtlReads.op_for_roll(69), Op::GetAllowingExpired);
+    assert_eq!(WorkloadProfile::TtlReads.op_for_roll(70), Op::PutWithTtl);
+    assert_eq!(WorkloadProfile::TtlReads.op_for_roll(99), Op::PutWithTtl);
+}
+
+#[test]
+fn test_sampled_mix_matches_expected_mix() {
+    for profile in [
+        WorkloadProfile::ReadHeavy,
+        WorkloadProfile::Balanced,
+        WorkloadProfile::WriteHeavy,
+        WorkloadProfile::PutOnly,
+        WorkloadProfile::DeleteOnly,
+        WorkloadProfile::TtlReads,
+    ] {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut counts = OpCounts::default();
+        for _ in 0..200_000 {
+            counts.record(profile.sample(&mut rng));
+        }
+        let deviation = counts.mix().max_deviation(&profile.expected_mix());
+        assert!(deviation < 0.5, "{}: {:?} is {deviation:.2} pts off", profile.as_name(), counts.mix());
+    }
+}
+
+#[test]
+fn test_op_counts_group_variants_and_measure_deviation() {
+    let mut counts = OpCounts::default();
+    assert_eq!(counts.mix(), OpMix::default());
+    for op in [Op::Get, Op::GetAllowingExpired, Op::Put, Op::PutWithTtl, Op::Put, Op::Delete, Op::Get, Op::Get] {
+        counts.record(op);
+    }
+    assert_eq!(counts, OpCounts { get: 4, put: 3, delete: 1 });
+    assert_eq!(counts.mix(), OpMix { get: 50.0, put: 37.5, delete: 12.5 });
+    assert_eq!(counts.mix().max_deviation(&WorkloadProfile::Balanced.expected_mix()), 7.5);
+}
+
+#[test]
+fn test_warmup_mode_from_name() {
+    assert_eq!(WarmupMode::from_name("none"), Some(WarmupMode::None));
+    assert_eq!(WarmupMode::from_name("populate"), Some(WarmupMode::Populate));
+    assert!(WarmupMode::from_name("prefill").is_none());
+}
+
+#[test]
+fn test_value_size_distribution_from_flags() {
+    let dist = ValueSizeDistribution::from_flags;
+    assert_eq!(dist("uniform", 8, 1024, 1.0), Ok(ValueSizeDistribution::Uniform { min: 8, max: 1024 }));
+    assert_eq!(dist("uniform", 8, 1024, 1.0), Ok(ValueSizeDistribution::default()));
+    assert_eq!(dist("lognormal", 16, 1024, 1.5), Ok(ValueSizeDistribution::LogNormal { median: 128, sigma: 1.5 }));
+    assert_eq!(dist("fixed", 8, 512, 1.0), Ok(ValueSizeDistribution::Fixed(512)));
+    for (name, d) in [("uniform", dist("uniform", 1, 1, 1.0)), ("lognormal", dist("lognormal", 1, 9, 0.5))] {
+        assert_eq!(d.unwrap().as_name(), name);
+    }
+
+    assert!(dist("zipf", 8, 1024, 1.0).is_err());
+    assert!(dist("lognormal", 8, 1024, 0.0).is_err());
+    assert!(dist("uniform", 0, 1024, 1.0).is_err());
+    assert!(dist("uniform", 100, 10, 1.0).is_err());
+    assert!(dist("fixed", 8, MAX_VALUE_SIZE + 1, 1.0).is_err());
+}
+
+#[test]
+fn test_value_size_distribution_sample() {
+    let mut rng = StdRng::seed_from_u64(7);
+
+    let uniform = ValueSizeDistribution::Uniform { min: 10, max: 20 };
+    assert!((0..1_000).map(|_| uniform.sample(&mut rng)).all(|size| (10..=20).contains(&size)));
+    assert!((0..100).all(|_| ValueSizeDistribution::Fixed(300).sample(&mut rng) == 300));
+
+    // Half the draws fall below the median; the tail reaches far above it but stops at the limit.
+    let lognormal = ValueSizeDistribution::LogNormal { median: 1_000, sigma: 2.0 };
+    let mut sizes: Vec<usize> = (0..10_000).map(|_| lognormal.sample(&mut rng)).collect();
+    sizes.sort_unstable();
+    assert!((900..=1_100).contains(&sizes[5_000]), "median {}", sizes[5_000]);
+    assert!(sizes[9_990] > 50_000, "p99.9 {}", sizes[9_990]);
+    assert!(sizes[0] >= 1 && sizes[9_999] <= MAX_VALUE_SIZE);
+
+    let huge = ValueSizeDistribution::LogNormal { median: MAX_VALUE_SIZE, sigma: 3.0 };
+    assert!((0..1_000).any(|_| huge.sample(&mut rng) == MAX_VALUE_SIZE));
 }